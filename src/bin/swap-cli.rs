@@ -11,7 +11,7 @@ extern crate log;
 
 use clap::Parser;
 
-use farcaster_node::cli::Opts;
+use farcaster_node::cli::{CommandAuditLog, Opts, OutputFormat};
 use farcaster_node::client::Client;
 use farcaster_node::LogStyle;
 use farcaster_node::ServiceConfig;
@@ -29,10 +29,24 @@ fn main() {
     debug!("CTL RPC socket {}", &service_config.ctl_endpoint);
 
     let mut client = Client::with(service_config).expect("Error initializing client");
+    client.set_json_output(opts.output_format == OutputFormat::Json);
+    client.set_node_config(
+        opts.node_config()
+            .expect("Error loading the node configuration"),
+    );
 
     trace!("Executing command: {:?}", opts.command);
 
-    if let Err(err) = opts.command.exec(&mut client) {
+    let audit_log = opts.audit_log_path.clone().map(CommandAuditLog::new);
+    let command = opts.command.clone();
+    let res = opts.command.exec(&mut client);
+    if let Some(audit_log) = audit_log {
+        let args: Vec<String> = std::env::args().collect();
+        if let Err(err) = audit_log.record(&command, &args, &res) {
+            eprintln!("{} {}", "failed to write audit log:".err(), err.err());
+        }
+    }
+    if let Err(err) = res {
         eprintln!("{} {}", "error:".err(), err.err());
         std::process::exit(1);
     }
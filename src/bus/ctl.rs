@@ -8,7 +8,8 @@ use std::fmt::{self, Debug};
 use std::io;
 use std::str::FromStr;
 
-use farcaster_core::blockchain::Network;
+use farcaster_core::bitcoin::fee::SatPerKvB;
+use farcaster_core::blockchain::{FeeStrategy, Network};
 use farcaster_core::consensus::{self, Decodable, Encodable};
 use farcaster_core::impl_strict_encoding;
 use farcaster_core::swap::btcxmr::KeyManager;
@@ -25,10 +26,11 @@ use strict_encoding::{NetworkDecode, NetworkEncode};
 
 use crate::bus::p2p::{PeerMsg, TakerCommit};
 use crate::bus::{
-    AddressSecretKey, CheckpointEntry, DealInfo, Failure, OptionDetails, Outcome, Progress,
+    AbortReason, AddressSecretKey, CheckpointEntry, DealInfo, Failure, List, OptionDetails,
+    Outcome, PeerOutcome, Progress, SwapStateExport,
 };
-use crate::swapd::CheckpointSwapd;
-use crate::syncerd::{Health, SweepAddressAddendum};
+use crate::swapd::{CheckpointSwapd, ConfirmationBounds};
+use crate::syncerd::{SweepAddressAddendum, SyncerHealth};
 use crate::{Error, ServiceId};
 
 use super::p2p::Commit;
@@ -103,6 +105,11 @@ pub enum CtlMsg {
     #[display("make_deal({0})")]
     MakeDeal(ProtoDeal),
 
+    /// Validates the parameters of a deal against the configuration and the current fee
+    /// estimates, without making the deal.
+    #[display("validate_make_params({0})")]
+    ValidateMakeParams(ProtoDeal),
+
     /// A message sent from farcaster to wallet to trigger the creation of the taker wallet.
     #[display("take_deal({0}))")]
     TakeDeal(PubDeal),
@@ -116,10 +123,39 @@ pub enum CtlMsg {
     GetKeys(GetKeys),
 
     #[display("revoke_deal({0})")]
-    RevokeDeal(Deal),
+    RevokeDeal(DealRevocation),
+
+    /// Revoke every expired open deal at once, optionally forcing the revocations
+    #[display("revoke_expired_deals({0})")]
+    RevokeExpiredDeals(bool),
+
+    /// Revoke every listed deal at once, the deals with a swap in progress are not revoked
+    #[display("revoke_deals(..)")]
+    RevokeDeals(List<Deal>),
 
     #[display("abort_swap()")]
-    AbortSwap,
+    AbortSwap(Option<AbortReason>),
+
+    /// Ends a swap that has not locked yet without waiting for the counterparty or the funding
+    /// sweep, the local swap state is cleaned up as for an abort
+    #[display("force_abort_swap()")]
+    ForceAbortSwap(Option<AbortReason>),
+
+    /// Replaces the fee strategy used by a running swap for its punish transaction
+    #[display("set_fee_strategy({0})")]
+    SetFeeStrategy(FeeStrategy<SatPerKvB>),
+
+    /// Signs a message with the Bitcoin lock key of a running swap, sent from a client to swapd
+    #[display("sign_message({0})")]
+    SignMessage(String),
+
+    /// Verifies a message signature against the Bitcoin lock key of a running swap
+    #[display("verify_message({0})")]
+    VerifyMessage(VerifyMessage),
+
+    /// Records why a swap was aborted in the deal history, sent from swapd to databased
+    #[display("set_deal_abort_reason({0}, {1})")]
+    SetDealAbortReason(Deal, AbortReason),
 
     #[display("get_sweep_bitcoin_address({0})")]
     GetSweepBitcoinAddress(bitcoin::Address),
@@ -134,6 +170,9 @@ pub enum CtlMsg {
     #[display("get_balance")]
     GetBalance(AddressSecretKey),
 
+    #[display("estimate_swap_cost({0})")]
+    EstimateSwapCost(Deal),
+
     #[display("funding_updated()")]
     FundingUpdated,
 
@@ -148,6 +187,15 @@ pub enum CtlMsg {
     #[display("remove_checkpoint")]
     RemoveCheckpoint(SwapId),
 
+    /// Stores a swap checkpoint exported from another node, sent from a client to databased
+    #[display("import_swap_state({0})")]
+    ImportSwapState(SwapStateExport),
+
+    /// Records the outcome of a swap in the counterparty reputation, sent from farcasterd to
+    /// databased
+    #[display("set_peer_outcome({0})")]
+    SetPeerOutcome(PeerOutcome),
+
     #[display("set_deal_history({0})")]
     SetDealInfo(DealInfo),
 
@@ -176,11 +224,82 @@ pub enum CtlMsg {
     #[display("connect failed")]
     ConnectFailed,
 
+    #[display("reconnect_attempt({0})")]
+    ReconnectAttempt(ReconnectAttempt),
+
+    #[display("reconnect_attempt_result({0})")]
+    ReconnectAttemptResult(ReconnectAttemptResult),
+
     #[display("health_check({0} {1})")]
     HealthCheck(Blockchain, Network),
 
     #[display("health_result({0})")]
-    HealthResult(Health),
+    HealthResult(SyncerHealth),
+}
+
+impl CtlMsg {
+    /// Name of the message variant
+    pub fn name(&self) -> &'static str {
+        match self {
+            CtlMsg::Hello => "Hello",
+            CtlMsg::Terminate => "Terminate",
+            CtlMsg::Success(..) => "Success",
+            CtlMsg::Failure(..) => "Failure",
+            CtlMsg::Progress(..) => "Progress",
+            CtlMsg::CleanDanglingDeals => "CleanDanglingDeals",
+            CtlMsg::MakeSwap(..) => "MakeSwap",
+            CtlMsg::TakeSwap(..) => "TakeSwap",
+            CtlMsg::CreateSwapKeys(..) => "CreateSwapKeys",
+            CtlMsg::SwapKeys(..) => "SwapKeys",
+            CtlMsg::Params(..) => "Params",
+            CtlMsg::PeerdUnreachable(..) => "PeerdUnreachable",
+            CtlMsg::PeerdReconnected(..) => "PeerdReconnected",
+            CtlMsg::PeerdTerminated => "PeerdTerminated",
+            CtlMsg::Disconnected => "Disconnected",
+            CtlMsg::Reconnected => "Reconnected",
+            CtlMsg::Connect(..) => "Connect",
+            CtlMsg::ConnectSuccess => "ConnectSuccess",
+            CtlMsg::RestoreCheckpoint(..) => "RestoreCheckpoint",
+            CtlMsg::MakeDeal(..) => "MakeDeal",
+            CtlMsg::ValidateMakeParams(..) => "ValidateMakeParams",
+            CtlMsg::TakeDeal(..) => "TakeDeal",
+            CtlMsg::TakerCommitted(..) => "TakerCommitted",
+            CtlMsg::GetKeys(..) => "GetKeys",
+            CtlMsg::RevokeDeal(..) => "RevokeDeal",
+            CtlMsg::RevokeExpiredDeals(..) => "RevokeExpiredDeals",
+            CtlMsg::RevokeDeals(..) => "RevokeDeals",
+            CtlMsg::AbortSwap(..) => "AbortSwap",
+            CtlMsg::ForceAbortSwap(..) => "ForceAbortSwap",
+            CtlMsg::SetFeeStrategy(..) => "SetFeeStrategy",
+            CtlMsg::SignMessage(..) => "SignMessage",
+            CtlMsg::VerifyMessage(..) => "VerifyMessage",
+            CtlMsg::SetDealAbortReason(..) => "SetDealAbortReason",
+            CtlMsg::GetSweepBitcoinAddress(..) => "GetSweepBitcoinAddress",
+            CtlMsg::SweepAddress(..) => "SweepAddress",
+            CtlMsg::SetAddressSecretKey(..) => "SetAddressSecretKey",
+            CtlMsg::GetBalance(..) => "GetBalance",
+            CtlMsg::EstimateSwapCost(..) => "EstimateSwapCost",
+            CtlMsg::FundingUpdated => "FundingUpdated",
+            CtlMsg::SwapOutcome(..) => "SwapOutcome",
+            CtlMsg::Checkpoint(..) => "Checkpoint",
+            CtlMsg::RemoveCheckpoint(..) => "RemoveCheckpoint",
+            CtlMsg::ImportSwapState(..) => "ImportSwapState",
+            CtlMsg::SetPeerOutcome(..) => "SetPeerOutcome",
+            CtlMsg::SetDealInfo(..) => "SetDealInfo",
+            CtlMsg::Keys(..) => "Keys",
+            CtlMsg::FundingInfo(..) => "FundingInfo",
+            CtlMsg::BitcoinAddress(..) => "BitcoinAddress",
+            CtlMsg::MoneroAddress(..) => "MoneroAddress",
+            CtlMsg::FundingCompleted(..) => "FundingCompleted",
+            CtlMsg::FundingCanceled(..) => "FundingCanceled",
+            CtlMsg::FailedPeerMessage(..) => "FailedPeerMessage",
+            CtlMsg::ConnectFailed => "ConnectFailed",
+            CtlMsg::ReconnectAttempt(..) => "ReconnectAttempt",
+            CtlMsg::ReconnectAttemptResult(..) => "ReconnectAttemptResult",
+            CtlMsg::HealthCheck(..) => "HealthCheck",
+            CtlMsg::HealthResult(..) => "HealthResult",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
@@ -198,6 +317,16 @@ pub struct ProtoDeal {
     pub public_addr: InetSocketAddr,
     pub arbitrating_addr: bitcoin::Address,
     pub accordant_addr: monero::Address,
+    pub confirmation_bounds: ConfirmationBounds,
+    pub deal_expiry: Option<u32>,
+}
+
+#[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
+#[display("{deal}, force: {force}")]
+pub struct DealRevocation {
+    pub deal: Deal,
+    /// Revoke the deal even if takers are connected and may be about to take it
+    pub force: bool,
 }
 
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
@@ -206,12 +335,47 @@ pub struct PubDeal {
     pub deal: Deal,
     pub bitcoin_address: bitcoin::Address,
     pub monero_address: monero::Address,
+    /// Expiry advertised by the maker, checked before connecting to the maker
+    pub expiry: Option<DealExpiry>,
+}
+
+/// Block at which the maker revokes a deal, with the Bitcoin height known to the taker
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[display("expires at block {expires_at_block}, current height {bitcoin_height}")]
+pub struct DealExpiry {
+    pub expires_at_block: u64,
+    pub bitcoin_height: u64,
 }
 
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
 #[display("{0}, ..")]
 pub struct ReconnectPeer(pub NodeAddr, pub Option<SecretKey>);
 
+/// A client attempt to reconnect a running swap to its counterparty, numbered by the client
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[display("{swap_id}, attempt {attempt_number}")]
+pub struct ReconnectAttempt {
+    pub swap_id: SwapId,
+    pub attempt_number: u32,
+}
+
+/// Whether the swap is connected to its counterparty after a reconnect attempt
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[display("attempt {attempt_number} to {peer_addr}, success: {success}")]
+pub struct ReconnectAttemptResult {
+    pub success: bool,
+    pub peer_addr: String,
+    pub attempt_number: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[display("{message}, {signature}")]
+pub struct VerifyMessage {
+    pub message: String,
+    /// Base64 encoded Bitcoin message signature
+    pub signature: String,
+}
+
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode, PartialEq, Eq)]
 #[display("{0}")]
 pub struct Token(pub String);
@@ -316,6 +480,9 @@ pub struct BitcoinFundingInfo {
     pub address: bitcoin::Address,
     #[serde(with = "bitcoin::util::amount::serde::as_btc")]
     pub amount: bitcoin::Amount,
+    /// Last block in which the lock transaction can be mined for the swap to be safe, unknown if
+    /// the swap did not know the Bitcoin height when requesting the funding
+    pub max_safe_lock_mined_block: Option<u64>,
 }
 
 impl FromStr for BitcoinFundingInfo {
@@ -327,6 +494,7 @@ impl FromStr for BitcoinFundingInfo {
             swap_id: SwapId::from_str(content[0])?,
             amount: bitcoin::Amount::from_str(&format!("{} {}", content[2], content[3]))?,
             address: bitcoin::Address::from_str(content[5])?,
+            max_safe_lock_mined_block: None,
         })
     }
 }
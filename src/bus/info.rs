@@ -4,12 +4,14 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::time::Duration;
 
 use amplify::ToYamlString;
 use farcaster_core::role::{SwapRole, TradeRole};
 use farcaster_core::trade::DealId;
+use farcaster_core::transaction::TxLabel;
 use farcaster_core::{blockchain::Blockchain, swap::btcxmr::Deal, swap::SwapId};
 use internet2::addr::{InetSocketAddr, NodeAddr, NodeId};
 #[cfg(feature = "serde")]
@@ -17,12 +19,14 @@ use serde_with::{DisplayFromStr, DurationSeconds};
 use strict_encoding::{NetworkDecode, NetworkEncode};
 
 use crate::bus::{
-    AddressSecretKey, CheckpointEntry, DealInfo, Failure, List, OptionDetails, Progress,
+    AddressSecretKey, CheckpointEntry, DealInfo, Failure, List, OptionDetails, PeerReputationScore,
+    Progress, SwapStateExport,
 };
 use crate::cli::DealSelector;
 use crate::farcasterd::stats::Stats;
-use crate::swapd::StateReport;
+use crate::swapd::{StateReport, SwapEvent, SwapSummary, TaskStats};
 use crate::syncerd::runtime::SyncerdTask;
+use crate::syncerd::{TaskId, TaskKind};
 use crate::Error;
 
 use super::ctl::FundingInfo;
@@ -67,6 +71,21 @@ pub enum InfoMsg {
     #[display("get_checkpoint_entry({0})")]
     GetCheckpointEntry(SwapId),
 
+    #[display("get_swap_state({0})")]
+    GetSwapState(SwapId),
+
+    #[display("get_swap_history({0})")]
+    GetSwapHistory(SwapId),
+
+    #[display("get_node_pubkey()")]
+    GetNodePubkey,
+
+    #[display("get_peer_reputation({0})")]
+    GetPeerReputation(NodeId),
+
+    #[display("get_peer_reputations(..)")]
+    GetPeerReputations(List<NodeId>),
+
     // Progress functionalities
     // ----------------
     // Returns a SwapProgress message
@@ -104,6 +123,12 @@ pub enum InfoMsg {
     #[display(inner)]
     TookDeal(TookDeal),
 
+    #[display(inner)]
+    RevokedDeals(RevokedDeals),
+
+    #[display(inner)]
+    BatchRevokeResult(BatchRevokeResult),
+
     // - GetInfo section
     #[display("syncer_info(..)")]
     #[from]
@@ -125,7 +150,7 @@ pub enum InfoMsg {
     // - ListPeers section
     #[display(inner)]
     #[from]
-    PeerList(List<NodeAddr>),
+    PeerList(List<PeerReputationInfo>),
     // - End ListPeers section
 
     // - ListSwap section
@@ -137,7 +162,7 @@ pub enum InfoMsg {
     // - ListTasks section
     #[display(inner)]
     #[from]
-    TaskList(List<SyncerdTask>),
+    TaskList(List<TaskInfo>),
     // - End ListTasks section
 
     // - ListDeals section
@@ -174,11 +199,86 @@ pub enum InfoMsg {
     #[display("checkpoint_entry({0})")]
     CheckpointEntry(CheckpointEntry),
     // - End GetCheckpointEntry section
+    #[display(inner)]
+    SwapState(SwapStateExport),
+    #[display(inner)]
+    SwapHistory(SwapHistory),
     #[display("{0}")]
     FundingInfos(FundingInfos),
 
     #[display("{0}")]
     AddressBalance(AddressBalance),
+
+    #[display(inner)]
+    SwapCostEstimate(SwapCostEstimate),
+
+    #[display("{0}")]
+    NodePubkey(bitcoin::secp256k1::PublicKey),
+
+    #[display("{0}")]
+    PeerReputation(PeerReputationScore),
+
+    #[display(inner)]
+    PeerReputations(List<PeerReputationScore>),
+}
+
+impl InfoMsg {
+    /// Name of the message variant
+    pub fn name(&self) -> &'static str {
+        match self {
+            InfoMsg::GetInfo => "GetInfo",
+            InfoMsg::ListPeers => "ListPeers",
+            InfoMsg::ListSwaps => "ListSwaps",
+            InfoMsg::ListTasks => "ListTasks",
+            InfoMsg::ListDeals(..) => "ListDeals",
+            InfoMsg::ListListens => "ListListens",
+            InfoMsg::RetrieveAllCheckpointInfo => "RetrieveAllCheckpointInfo",
+            InfoMsg::GetAddressSecretKey(..) => "GetAddressSecretKey",
+            InfoMsg::GetAddresses(..) => "GetAddresses",
+            InfoMsg::NeedsFunding(..) => "NeedsFunding",
+            InfoMsg::GetCheckpointEntry(..) => "GetCheckpointEntry",
+            InfoMsg::GetSwapState(..) => "GetSwapState",
+            InfoMsg::GetSwapHistory(..) => "GetSwapHistory",
+            InfoMsg::GetNodePubkey => "GetNodePubkey",
+            InfoMsg::GetPeerReputation(..) => "GetPeerReputation",
+            InfoMsg::GetPeerReputations(..) => "GetPeerReputations",
+            InfoMsg::ReadProgress(..) => "ReadProgress",
+            InfoMsg::SwapProgress(..) => "SwapProgress",
+            InfoMsg::SubscribeProgress(..) => "SubscribeProgress",
+            InfoMsg::UnsubscribeProgress(..) => "UnsubscribeProgress",
+            InfoMsg::Progress(..) => "Progress",
+            InfoMsg::Success(..) => "Success",
+            InfoMsg::Failure(..) => "Failure",
+            InfoMsg::String(..) => "String",
+            InfoMsg::MadeDeal(..) => "MadeDeal",
+            InfoMsg::TookDeal(..) => "TookDeal",
+            InfoMsg::RevokedDeals(..) => "RevokedDeals",
+            InfoMsg::BatchRevokeResult(..) => "BatchRevokeResult",
+            InfoMsg::SyncerInfo(..) => "SyncerInfo",
+            InfoMsg::NodeInfo(..) => "NodeInfo",
+            InfoMsg::PeerInfo(..) => "PeerInfo",
+            InfoMsg::SwapInfo(..) => "SwapInfo",
+            InfoMsg::PeerList(..) => "PeerList",
+            InfoMsg::SwapList(..) => "SwapList",
+            InfoMsg::TaskList(..) => "TaskList",
+            InfoMsg::DealList(..) => "DealList",
+            InfoMsg::DealInfoList(..) => "DealInfoList",
+            InfoMsg::ListenList(..) => "ListenList",
+            InfoMsg::CheckpointList(..) => "CheckpointList",
+            InfoMsg::AddressSecretKey(..) => "AddressSecretKey",
+            InfoMsg::BitcoinAddressList(..) => "BitcoinAddressList",
+            InfoMsg::MoneroAddressList(..) => "MoneroAddressList",
+            InfoMsg::CheckpointEntry(..) => "CheckpointEntry",
+            InfoMsg::SwapState(..) => "SwapState",
+            InfoMsg::SwapHistory(..) => "SwapHistory",
+            InfoMsg::FundingInfos(..) => "FundingInfos",
+            InfoMsg::AddressBalance(..) => "AddressBalance",
+            InfoMsg::SwapCostEstimate(..) => "SwapCostEstimate",
+            InfoMsg::NodePubkey(..) => "NodePubkey",
+            InfoMsg::PeerReputation(..) => "PeerReputation",
+            InfoMsg::PeerReputations(..) => "PeerReputations",
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
@@ -191,6 +291,7 @@ pub enum InfoMsg {
 pub struct BitcoinAddressSwapIdPair {
     pub address: bitcoin::Address,
     pub swap_id: Option<SwapId>,
+    pub balance_sat: Option<u64>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
@@ -203,6 +304,58 @@ pub struct BitcoinAddressSwapIdPair {
 pub struct MoneroAddressSwapIdPair {
     pub address: monero::Address,
     pub swap_id: Option<SwapId>,
+    pub balance_piconero: Option<u64>,
+}
+
+/// Funding state of an address, comparing its balance with the amount expected by its swap
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "kebab-case")
+)]
+pub enum FundingStatus {
+    #[display("funded")]
+    Funded,
+    #[display("partially-funded")]
+    PartiallyFunded,
+    #[display("empty")]
+    Empty,
+    #[display("overfunded")]
+    Overfunded,
+}
+
+impl FundingStatus {
+    /// Status of an address holding `balance`, an address without expected amount is funded as
+    /// soon as it holds something
+    pub fn new(balance: u64, expected_amount: Option<u64>) -> Self {
+        match expected_amount {
+            _ if balance == 0 => FundingStatus::Empty,
+            Some(expected) if balance < expected => FundingStatus::PartiallyFunded,
+            Some(expected) if balance > expected => FundingStatus::Overfunded,
+            _ => FundingStatus::Funded,
+        }
+    }
+}
+
+/// Funding address with its current balance and the amount its swap waits for, amounts are in
+/// satoshis or piconeros depending on the blockchain
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(FundingAddressWithBalance::to_yaml_string)]
+pub struct FundingAddressWithBalance {
+    pub address: String,
+    pub blockchain: Blockchain,
+    pub swap_id: Option<SwapId>,
+    /// Amount the swap waits for, None if the swap does not need funding
+    pub expected_amount: Option<u64>,
+    /// None if the balance could not be queried
+    pub current_balance: Option<u64>,
+    pub status: Option<FundingStatus>,
 }
 
 #[cfg_attr(feature = "serde", serde_as)]
@@ -218,6 +371,52 @@ pub struct MadeDeal {
     pub viewable_deal: ViewableDeal,
 }
 
+/// Summary of a batch revocation of the expired deals
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(RevokedDeals::to_yaml_string)]
+pub struct RevokedDeals {
+    pub revoked: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Summary of a batch revocation of deals, with the reason each failed revocation failed
+#[cfg_attr(feature = "serde", serde_as)]
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(BatchRevokeResult::to_yaml_string)]
+pub struct BatchRevokeResult {
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    pub revoked: Vec<Deal>,
+    #[serde_as(as = "Vec<(DisplayFromStr, _)>")]
+    pub failed: Vec<(Deal, String)>,
+}
+
+impl From<BatchRevokeResult> for RevokedDeals {
+    fn from(result: BatchRevokeResult) -> RevokedDeals {
+        RevokedDeals {
+            revoked: result
+                .revoked
+                .iter()
+                .map(|deal| deal.id().to_string())
+                .collect(),
+            errors: result
+                .failed
+                .iter()
+                .map(|(deal, err)| format!("{}: {}", deal.id(), err))
+                .collect(),
+        }
+    }
+}
+
 #[cfg_attr(feature = "serde", serde_as)]
 #[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
 #[cfg_attr(
@@ -245,6 +444,27 @@ pub struct SyncerInfo {
     pub uptime: Duration,
     pub since: u64,
     pub tasks: Vec<SyncerdTask>,
+    /// Number of tasks held by the syncer, per task type
+    pub task_counts: BTreeMap<TaskKind, usize>,
+    pub total_tasks: usize,
+}
+
+/// A task registered in a syncer
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display("{id}: {kind} on {blockchain} since {created_at_height} until {expires_at_height}")]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub kind: TaskKind,
+    pub blockchain: Blockchain,
+    /// Syncer height when the task was received
+    pub created_at_height: u64,
+    /// Height after which the syncer drops the task, `u64::MAX` if the task does not expire
+    pub expires_at_height: u64,
 }
 
 #[cfg_attr(feature = "serde", serde_as)]
@@ -291,6 +511,26 @@ pub struct PeerInfo {
     pub messages_received: usize,
     pub forked_from_listener: bool,
     pub awaits_pong: bool,
+    /// Round trip time of the last completed ping
+    pub rtt_ms: Option<u32>,
+}
+
+#[cfg_attr(feature = "serde", serde_as)]
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(PeerReputationInfo::to_yaml_string)]
+pub struct PeerReputationInfo {
+    #[serde_as(as = "DisplayFromStr")]
+    pub peer: NodeAddr,
+    pub reputation: PeerReputationScore,
+    /// Number of running swaps with the peer
+    pub active_swap_count: u32,
+    /// Number of swaps with the peer recorded in its reputation, successful or not
+    pub completed_swap_count: u32,
 }
 
 #[cfg_attr(feature = "serde", serde_as)]
@@ -314,6 +554,52 @@ pub struct SwapInfo {
     pub local_swap_role: SwapRole,
     pub connected_counterparty_node_id: Option<NodeId>,
     pub state: StateReport,
+    /// Age of the syncer tasks still active in the swap
+    pub task_stats: TaskStats,
+}
+
+/// State transitions recorded by a swap, oldest first
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(SwapHistory::to_yaml_string)]
+pub struct SwapHistory {
+    pub swap_id: SwapId,
+    pub events: Vec<SwapEvent>,
+}
+
+/// Bitcoin fees paid by a swap over a deal, computed before the swap starts
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(SwapCostEstimate::to_yaml_string)]
+pub struct SwapCostEstimate {
+    /// Current fee estimate used for the lock transaction
+    pub lock_fee_rate_sat_per_kvb: u64,
+    /// Fee rate of the deal fee strategy used for the other transactions
+    pub deal_fee_rate_sat_per_kvb: u64,
+    pub fees: Vec<TxFeeEstimate>,
+    /// Fees of the most expensive path, i.e. lock, cancel and the costlier of refund and punish
+    pub worst_case_total: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display("{label}: {fee} sat")]
+pub struct TxFeeEstimate {
+    pub label: TxLabel,
+    pub vsize: u64,
+    pub fee: u64,
 }
 
 #[cfg_attr(feature = "serde", serde_as)]
@@ -326,6 +612,8 @@ pub struct SwapInfo {
 #[display(SwapProgress::to_yaml_string)]
 pub struct SwapProgress {
     pub progress: Vec<ProgressEvent>,
+    /// Summary of the swap in its latest state
+    pub summary: Option<SwapSummary>,
 }
 
 #[cfg_attr(feature = "serde", serde_as)]
@@ -455,10 +743,20 @@ impl ToYamlString for BitcoinAddressSwapIdPair {}
 #[cfg(feature = "serde")]
 impl ToYamlString for MoneroAddressSwapIdPair {}
 #[cfg(feature = "serde")]
+impl ToYamlString for FundingAddressWithBalance {}
+#[cfg(feature = "serde")]
 impl ToYamlString for ViewableDeal {}
 #[cfg(feature = "serde")]
 impl ToYamlString for MadeDeal {}
 #[cfg(feature = "serde")]
+impl ToYamlString for RevokedDeals {}
+#[cfg(feature = "serde")]
+impl ToYamlString for BatchRevokeResult {}
+#[cfg(feature = "serde")]
+impl ToYamlString for SwapHistory {}
+#[cfg(feature = "serde")]
+impl ToYamlString for SwapCostEstimate {}
+#[cfg(feature = "serde")]
 impl ToYamlString for TookDeal {}
 #[cfg(feature = "serde")]
 impl ToYamlString for CheckpointEntry {}
@@ -469,6 +767,8 @@ impl ToYamlString for NodeInfo {}
 #[cfg(feature = "serde")]
 impl ToYamlString for PeerInfo {}
 #[cfg(feature = "serde")]
+impl ToYamlString for PeerReputationInfo {}
+#[cfg(feature = "serde")]
 impl ToYamlString for SwapInfo {}
 #[cfg(feature = "serde")]
 impl ToYamlString for SyncerInfo {}
@@ -104,6 +104,59 @@ impl From<crate::Error> for BusMsg {
     }
 }
 
+#[cfg(feature = "serde")]
+impl BusMsg {
+    /// Machine readable representation of a response received by a client, messages without a
+    /// structured content are reported as their display string
+    pub fn to_json(&self) -> serde_json::Value {
+        fn value<T: serde::Serialize>(v: &T) -> serde_json::Value {
+            serde_json::to_value(v).expect("internal JSON serialization error")
+        }
+        match self {
+            BusMsg::Info(InfoMsg::MadeDeal(v)) => value(v),
+            BusMsg::Info(InfoMsg::TookDeal(v)) => value(v),
+            BusMsg::Info(InfoMsg::SyncerInfo(v)) => value(v),
+            BusMsg::Info(InfoMsg::NodeInfo(v)) => value(v),
+            BusMsg::Info(InfoMsg::PeerInfo(v)) => value(v),
+            BusMsg::Info(InfoMsg::SwapInfo(v)) => value(v),
+            BusMsg::Info(InfoMsg::PeerList(v)) => value(v),
+            BusMsg::Info(InfoMsg::SwapList(v)) => value(v),
+            BusMsg::Info(InfoMsg::TaskList(v)) => value(v),
+            BusMsg::Info(InfoMsg::DealList(v)) | BusMsg::Info(InfoMsg::DealInfoList(v)) => value(v),
+            BusMsg::Info(InfoMsg::ListenList(v)) => value(v),
+            BusMsg::Info(InfoMsg::CheckpointList(v)) => value(v),
+            BusMsg::Info(InfoMsg::BitcoinAddressList(v)) => value(v),
+            BusMsg::Info(InfoMsg::MoneroAddressList(v)) => value(v),
+            BusMsg::Info(InfoMsg::CheckpointEntry(v)) => value(v),
+            BusMsg::Info(InfoMsg::FundingInfos(v)) => value(v),
+            BusMsg::Info(InfoMsg::AddressBalance(v)) => value(v),
+            BusMsg::Info(InfoMsg::NodePubkey(v)) => value(v),
+            BusMsg::Info(InfoMsg::PeerReputation(v)) => value(v),
+            BusMsg::Info(InfoMsg::PeerReputations(v)) => value(v),
+            BusMsg::Info(InfoMsg::SwapProgress(v)) => value(v),
+            BusMsg::Info(InfoMsg::Progress(Progress::Message(v))) => {
+                serde_json::json!({ "message": v })
+            }
+            BusMsg::Info(InfoMsg::Progress(Progress::StateUpdate(v))) => {
+                serde_json::json!({ "update": v })
+            }
+            BusMsg::Info(InfoMsg::Progress(Progress::StateTransition(v))) => {
+                serde_json::json!({ "transition": v })
+            }
+            BusMsg::Info(InfoMsg::Progress(Progress::Summary(v))) => {
+                serde_json::json!({ "summary": v })
+            }
+            BusMsg::Info(InfoMsg::Success(v)) | BusMsg::Ctl(CtlMsg::Success(v)) => {
+                serde_json::json!({ "success": v })
+            }
+            BusMsg::Info(InfoMsg::Failure(v)) | BusMsg::Ctl(CtlMsg::Failure(v)) => {
+                serde_json::json!({ "failure": v })
+            }
+            msg => serde_json::json!({ "message": msg.to_string() }),
+        }
+    }
+}
+
 /// An encodable list that is serializable in yaml
 #[derive(Wrapper, Clone, PartialEq, Eq, Debug, From, StrictEncode, StrictDecode)]
 #[wrapper(IndexRange)]
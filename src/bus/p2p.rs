@@ -79,6 +79,26 @@ pub enum PeerMsg {
 }
 
 impl PeerMsg {
+    /// Name of the message variant
+    pub fn name(&self) -> &'static str {
+        match self {
+            PeerMsg::MakerCommit(..) => "MakerCommit",
+            PeerMsg::TakerCommit(..) => "TakerCommit",
+            PeerMsg::DealNotFound(..) => "DealNotFound",
+            PeerMsg::Reveal(..) => "Reveal",
+            PeerMsg::RefundProcedureSignatures(..) => "RefundProcedureSignatures",
+            PeerMsg::CoreArbitratingSetup(..) => "CoreArbitratingSetup",
+            PeerMsg::BuyProcedureSignature(..) => "BuyProcedureSignature",
+            PeerMsg::Ping(..) => "Ping",
+            PeerMsg::Pong(..) => "Pong",
+            PeerMsg::Identity(..) => "Identity",
+            PeerMsg::Abort(..) => "Abort",
+            PeerMsg::PingPeer => "PingPeer",
+            PeerMsg::PeerReceiverRuntimeShutdown => "PeerReceiverRuntimeShutdown",
+            PeerMsg::MsgReceipt(..) => "MsgReceipt",
+        }
+    }
+
     pub fn swap_id(&self) -> SwapId {
         match self {
             PeerMsg::MakerCommit(c) => c.swap_id(),
@@ -25,6 +25,17 @@ pub enum SyncMsg {
     BridgeEvent(BridgeEvent),
 }
 
+impl SyncMsg {
+    /// Name of the message variant
+    pub fn name(&self) -> &'static str {
+        match self {
+            SyncMsg::Task(..) => "Task",
+            SyncMsg::Event(..) => "Event",
+            SyncMsg::BridgeEvent(..) => "BridgeEvent",
+        }
+    }
+}
+
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
 #[display("{source}, {event}")]
 pub struct BridgeEvent {
@@ -11,7 +11,7 @@ use std::{
 
 use farcaster_core::{
     blockchain::Network,
-    role::TradeRole,
+    role::{SwapRole, TradeRole},
     swap::{btcxmr::Deal, SwapId},
 };
 
@@ -21,8 +21,8 @@ use microservices::rpc;
 use serde_with::DisplayFromStr;
 use strict_encoding::{NetworkDecode, NetworkEncode};
 
-use crate::swapd::StateReport;
-use crate::syncerd::Health;
+use crate::swapd::{StateReport, SwapSummary};
+use crate::syncerd::SyncerHealth;
 
 #[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
 #[display("{swap_id}, {deal}")]
@@ -39,6 +39,31 @@ pub struct CheckpointEntry {
     pub expected_counterparty_node_id: Option<NodeId>,
 }
 
+/// The raw strict encoded checkpoint state of a swap, exported to restore the swap on another
+/// node
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[display("swap_state({swap_id}, ..)")]
+pub struct SwapStateExport {
+    pub swap_id: SwapId,
+    pub state: Vec<u8>,
+}
+
+impl SwapStateExport {
+    /// Base64 encoding of the strict encoded export, suitable for cold storage
+    pub fn to_base64(&self) -> String {
+        base64::encode(
+            strict_encoding::strict_serialize(self).expect("Memory-based encoding does not fail"),
+        )
+    }
+
+    pub fn from_base64(s: &str) -> Result<Self, crate::Error> {
+        let data = base64::decode(s.trim()).map_err(|err| {
+            crate::Error::Farcaster(format!("Invalid base64 swap state: {}", err))
+        })?;
+        Ok(strict_encoding::strict_deserialize(&data)?)
+    }
+}
+
 #[derive(Clone, Debug, Display, Eq, PartialEq, Hash, NetworkDecode, NetworkEncode)]
 #[cfg_attr(
     feature = "serde",
@@ -117,11 +142,30 @@ pub struct DealInfo {
     pub serialized_deal: String,
     pub status: DealStatus,
     pub local_trade_role: TradeRole,
+    /// Swap role we play in the deal
+    pub local_swap_role: SwapRole,
+    /// Reason given when the swap was aborted, only set when read from the deal history
+    pub failure_reason: Option<String>,
+    /// Bitcoin height at which the open deal is automatically revoked, if made with an expiry
+    pub expires_at_block: Option<u64>,
+    /// Blocks and estimated time left before the open deal expires
+    pub expires_in: Option<String>,
 }
 
 #[cfg(feature = "serde")]
 impl ToYamlString for DealInfo {}
 
+#[derive(Clone, Debug, Eq, PartialEq, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum AbortReason {
+    #[display("user requested: {0}")]
+    UserRequested(String),
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Display, NetworkEncode, NetworkDecode)]
 #[cfg_attr(
     feature = "serde",
@@ -145,6 +189,7 @@ pub enum Progress {
     Message(String),
     StateUpdate(StateReport),
     StateTransition(StateTransition),
+    Summary(SwapSummary),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Display, NetworkEncode, NetworkDecode)]
@@ -275,10 +320,10 @@ impl FromStr for HealthCheckSelector {
 )]
 #[display(DefaultHealthReport::to_yaml_string)]
 pub struct DefaultHealthReport {
-    pub bitcoin_mainnet_health: Health,
-    pub bitcoin_testnet_health: Health,
-    pub monero_mainnet_health: Health,
-    pub monero_testnet_health: Health,
+    pub bitcoin_mainnet_health: SyncerHealth,
+    pub bitcoin_testnet_health: SyncerHealth,
+    pub monero_mainnet_health: SyncerHealth,
+    pub monero_testnet_health: SyncerHealth,
 }
 
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
@@ -289,12 +334,12 @@ pub struct DefaultHealthReport {
 )]
 #[display(CompleteHealthReport::to_yaml_string)]
 pub struct CompleteHealthReport {
-    pub bitcoin_mainnet_health: Health,
-    pub bitcoin_testnet_health: Health,
-    pub bitcoin_local_health: Health,
-    pub monero_mainnet_health: Health,
-    pub monero_testnet_health: Health,
-    pub monero_local_health: Health,
+    pub bitcoin_mainnet_health: SyncerHealth,
+    pub bitcoin_testnet_health: SyncerHealth,
+    pub bitcoin_local_health: SyncerHealth,
+    pub monero_mainnet_health: SyncerHealth,
+    pub monero_testnet_health: SyncerHealth,
+    pub monero_local_health: SyncerHealth,
 }
 
 #[derive(Clone, Debug, Display, NetworkEncode, NetworkDecode)]
@@ -305,8 +350,8 @@ pub struct CompleteHealthReport {
 )]
 #[display(ReducedHealthReport::to_yaml_string)]
 pub struct ReducedHealthReport {
-    pub bitcoin_health: Health,
-    pub monero_health: Health,
+    pub bitcoin_health: SyncerHealth,
+    pub monero_health: SyncerHealth,
 }
 
 #[cfg(feature = "serde")]
@@ -315,3 +360,112 @@ impl ToYamlString for DefaultHealthReport {}
 impl ToYamlString for CompleteHealthReport {}
 #[cfg(feature = "serde")]
 impl ToYamlString for ReducedHealthReport {}
+
+/// Swap history of a counterparty, persisted by databased
+#[derive(Clone, Debug, Default, Eq, PartialEq, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(PeerReputation::to_yaml_string)]
+pub struct PeerReputation {
+    pub successful_swaps: u32,
+    pub failed_swaps: u32,
+    pub protocol_violations: u32,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+impl PeerReputation {
+    /// Score given to peers without history
+    pub const NEUTRAL_SCORE: u8 = 50;
+
+    /// Update the history with a swap outcome. Only the failures attributable to the peer, i.e.
+    /// its protocol violations, count as failed swaps and are penalized twice in the score. An
+    /// aborted or refunded swap, or a swap where the local node was punished, does not count
+    /// against the peer.
+    pub fn record_outcome(&mut self, outcome: &Outcome, protocol_violation: bool, now: u64) {
+        if self.first_seen == 0 {
+            self.first_seen = now;
+        }
+        self.last_seen = now;
+        if *outcome == Outcome::SuccessSwap {
+            self.successful_swaps += 1;
+        }
+        if protocol_violation {
+            self.failed_swaps += 1;
+            self.protocol_violations += 1;
+        }
+    }
+
+    pub fn score(&self) -> PeerReputationScore {
+        let penalties = self.failed_swaps as u64 + self.protocol_violations as u64;
+        let total = self.successful_swaps as u64 + penalties;
+        let score = if total == 0 {
+            Self::NEUTRAL_SCORE
+        } else {
+            (self.successful_swaps as u64 * 100 / total) as u8
+        };
+        PeerReputationScore {
+            score,
+            successful: self.successful_swaps,
+            failed: self.failed_swaps,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(PeerReputationScore::to_yaml_string)]
+pub struct PeerReputationScore {
+    /// Score between 0 and 100, peers without history are neutral (50)
+    pub score: u8,
+    pub successful: u32,
+    pub failed: u32,
+}
+
+/// Outcome of a swap for a counterparty, sent to databased to update the peer reputation
+#[derive(Clone, Debug, Eq, PartialEq, Display, NetworkEncode, NetworkDecode)]
+#[display("peer_outcome({node_id}, {outcome})")]
+pub struct PeerOutcome {
+    pub node_id: NodeId,
+    pub outcome: Outcome,
+    pub protocol_violation: bool,
+}
+
+#[cfg(feature = "serde")]
+impl ToYamlString for PeerReputation {}
+#[cfg(feature = "serde")]
+impl ToYamlString for PeerReputationScore {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reputation_counts_only_failures_of_the_peer() {
+        let mut reputation = PeerReputation::default();
+        reputation.record_outcome(&Outcome::SuccessSwap, false, 10);
+        reputation.record_outcome(&Outcome::FailureAbort, false, 11);
+        reputation.record_outcome(&Outcome::FailureRefund, false, 12);
+        reputation.record_outcome(&Outcome::FailurePunish, false, 13);
+        assert_eq!(reputation.score().score, 100);
+        assert_eq!(reputation.failed_swaps, 0);
+
+        reputation.record_outcome(&Outcome::FailurePunish, true, 14);
+        assert_eq!(
+            reputation.score(),
+            PeerReputationScore {
+                score: 33,
+                successful: 1,
+                failed: 1,
+            }
+        );
+        assert_eq!((reputation.first_seen, reputation.last_seen), (10, 14));
+    }
+}
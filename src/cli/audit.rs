@@ -0,0 +1,104 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde_json::{json, Map, Value};
+
+use super::Command;
+use crate::Error;
+
+/// Arguments whose value is never written to the audit log
+const REDACTED_ARGS: [&str; 3] = ["key", "secret", "token"];
+
+/// Command name recorded for `abort-swap --force`
+const FORCED_ABORT: &str = "FORCED_ABORT";
+
+/// Appends a JSON line to the audit log for every command executed by the cli
+pub struct CommandAuditLog {
+    path: PathBuf,
+}
+
+impl CommandAuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        CommandAuditLog { path }
+    }
+
+    /// Records the command, the raw command-line arguments and the outcome of the command
+    pub fn record(
+        &self,
+        command: &Command,
+        args: &[String],
+        result: &Result<(), Error>,
+    ) -> Result<(), io::Error> {
+        let ts = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        // the variant name is the first word of the debug representation, forced aborts are
+        // singled out as they skip the protocol safeguards
+        let debug = format!("{:?}", command);
+        let name = match command {
+            Command::AbortSwap { force: true, .. } => FORCED_ABORT,
+            _ => debug
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or_default(),
+        };
+        let result = match result {
+            Ok(()) => "ok".to_string(),
+            Err(err) => format!("err: {}", err),
+        };
+        let entry = json!({
+            "ts": ts,
+            "command": name,
+            "args": parse_args(args),
+            "result": result,
+        });
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", entry)
+    }
+}
+
+// Map long options to their values, flags without value are set to true and positional arguments
+// are listed under `positional`
+fn parse_args(args: &[String]) -> Value {
+    let mut map = Map::new();
+    let mut positional = vec![];
+    // skip the binary name
+    let mut iter = args.iter().skip(1).peekable();
+    while let Some(arg) = iter.next() {
+        if let Some(opt) = arg.strip_prefix("--") {
+            let (key, value) = match opt.split_once('=') {
+                Some((key, value)) => (key, Value::from(value)),
+                None => match iter.peek() {
+                    Some(next) if !next.starts_with('-') => {
+                        (opt, Value::from(iter.next().expect("peeked").as_str()))
+                    }
+                    _ => (opt, Value::Bool(true)),
+                },
+            };
+            let value = if REDACTED_ARGS.iter().any(|r| key.contains(r)) {
+                Value::from("<redacted>")
+            } else {
+                value
+            };
+            map.insert(key.replace('-', "_"), value);
+        } else if !arg.starts_with('-') {
+            positional.push(Value::from(arg.as_str()));
+        }
+    }
+    if !positional.is_empty() {
+        map.insert("positional".to_string(), Value::Array(positional));
+    }
+    Value::Object(map)
+}
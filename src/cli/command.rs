@@ -6,35 +6,79 @@
 
 use farcaster_core::swap::btcxmr::{Deal, DealParameters};
 use farcaster_core::Uuid;
-use std::io::{self, Read};
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::iter::FromIterator;
+use std::path::Path;
+use std::process;
 use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
 
 use internet2::addr::{InetSocketAddr, NodeAddr};
 use microservices::shell::Exec;
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
 
 use clap::IntoApp;
 use clap_complete::generate;
 use clap_complete::shells::*;
 use farcaster_core::{
-    blockchain::{Blockchain, Network},
+    blockchain::{Blockchain, FeeStrategy, Network},
+    consensus,
     role::SwapRole,
     swap::SwapId,
 };
 
 use super::Command;
+use crate::bus::sync::SyncMsg;
 use crate::bus::{
-    ctl::{self, CtlMsg},
-    info::{Address, InfoMsg},
-    AddressSecretKey,
+    ctl::{
+        self, BitcoinFundingInfo, CtlMsg, FundingInfo, ReconnectAttempt, ReconnectAttemptResult,
+        VerifyMessage,
+    },
+    info::{
+        Address, AddressBalance, DealStatusSelector, FundingAddressWithBalance, FundingInfos,
+        FundingStatus, InfoMsg, PeerReputationInfo, ProgressEvent, SwapHistory, SwapProgress,
+        TaskInfo,
+    },
+    AbortReason, AddressSecretKey, DealInfo, DealStatus, List, Outcome, Progress, SwapStateExport,
 };
 use crate::bus::{
     BusMsg, CompleteHealthReport, DefaultHealthReport, Failure, FailureCode, HealthCheckSelector,
     ReducedHealthReport,
 };
-use crate::cli::opts::CheckpointSelector;
+use crate::cli::opts::{
+    CheckpointSelector, DealSelector, DealSource, HistoryFormat, PeerSortKey, QrFormat,
+    SwapRoleFilter,
+};
+use crate::cli::{monitor, qr};
 use crate::client::Client;
-use crate::syncerd::{Health, SweepAddressAddendum, SweepBitcoinAddress, SweepMoneroAddress};
-use crate::{Error, LogStyle, ServiceId};
+use crate::config::Config;
+use crate::farcasterd::DealPreValidation;
+use crate::swapd::{
+    simulate_swap, swap_state_diagram, ConfirmationBounds, DeadlineReport, SimulationPath,
+    StateReport, TemporalSafety, BITCOIN_BLOCK_TIME_SECS,
+};
+use crate::syncerd::{
+    Abort, AddressAddendum, BtcAddressAddendum, Event, GetTx, GetXmrTx, HeightChanged,
+    SweepAddressAddendum, SweepBitcoinAddress, SweepMoneroAddress, SyncerHealth, Task, TaskId,
+    TaskTarget, TxFilter, Txid, WatchAddress, WatchHeight, XmrAddressAddendum,
+    XmrTransactionRetrieved,
+};
+use crate::{AccordantBlockchain, ArbitratingBlockchain};
+use crate::{Error, LogStyle, ServiceConfig, ServiceId};
+
+const TAKE_FROM_FILE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+const TAKE_FROM_FILE_MAX_FAILED_POLLS: u32 = 30;
+const FUNDING_BALANCE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Number of blocks before the last safe lock block from which funding a swap is warned against
+const FUNDING_DEADLINE_WARNING_BLOCKS: u64 = 3;
+/// Wait before the second reconnect attempt, doubled after each failed attempt
+const RECONNECT_INITIAL_WAIT_SECS: u64 = 2;
 
 impl Exec for Command {
     type Client = Client;
@@ -82,10 +126,12 @@ impl Exec for Command {
                     }
                 };
                 match runtime.response()? {
-                    BusMsg::Info(InfoMsg::NodeInfo(info)) => println!("{}", info),
-                    BusMsg::Info(InfoMsg::PeerInfo(info)) => println!("{}", info),
-                    BusMsg::Info(InfoMsg::SwapInfo(info)) => println!("{}", info),
-                    BusMsg::Info(InfoMsg::SyncerInfo(info)) => println!("{}", info),
+                    resp @ BusMsg::Info(
+                        InfoMsg::NodeInfo(_)
+                        | InfoMsg::PeerInfo(_)
+                        | InfoMsg::SwapInfo(_)
+                        | InfoMsg::SyncerInfo(_),
+                    ) => runtime.print_response(&resp),
                     BusMsg::Ctl(CtlMsg::Failure(Failure { code, .. }))
                         if code == FailureCode::TargetServiceNotFound =>
                     {
@@ -124,27 +170,220 @@ impl Exec for Command {
                 }
             }
 
-            Command::Peers => {
-                runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListPeers)?;
+            Command::ExportNodePubkey => {
+                runtime.request_info(ServiceId::Farcasterd, InfoMsg::GetNodePubkey)?;
                 runtime.report_response_or_fail()?;
             }
 
+            Command::Peers {
+                latency,
+                with_swap_count,
+                with_reputation,
+                sort_by,
+            } => {
+                runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListPeers)?;
+                if let BusMsg::Info(InfoMsg::PeerList(peers)) = runtime.report_failure()? {
+                    let reputations: Vec<PeerReputationInfo> = peers.iter().cloned().collect();
+                    if latency {
+                        print_peers_latency(runtime, reputations)?;
+                    } else if with_swap_count {
+                        print_peers_swap_count(
+                            runtime,
+                            reputations,
+                            sort_by.unwrap_or(PeerSortKey::Swaps),
+                        );
+                    } else if with_reputation {
+                        print_output(runtime, &List::from_iter(reputations));
+                    } else {
+                        print_output(
+                            runtime,
+                            &List::from_iter(reputations.into_iter().map(|info| info.peer)),
+                        );
+                    }
+                } else {
+                    return Err(Error::Farcaster("Received unexpected response".to_string()));
+                }
+            }
+
             Command::ListSwaps => {
                 runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListSwaps)?;
                 runtime.report_response_or_fail()?;
             }
 
-            Command::ListDeals { select } => {
+            Command::Monitor => monitor::run(runtime)?,
+
+            Command::ListDeals {
+                select,
+                maker_role,
+                network,
+            } => {
                 runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListDeals(select.into()))?;
-                runtime.report_response_or_fail()?;
+                let resp = match runtime.report_failure()? {
+                    BusMsg::Info(InfoMsg::DealList(deals)) => {
+                        BusMsg::Info(InfoMsg::DealList(filter_deals(&deals, maker_role, network)))
+                    }
+                    BusMsg::Info(InfoMsg::DealInfoList(deals)) => BusMsg::Info(
+                        InfoMsg::DealInfoList(filter_deals(&deals, maker_role, network)),
+                    ),
+                    resp => resp,
+                };
+                runtime.print_response(&resp);
+            }
+
+            Command::Stats { role } => {
+                runtime.request_info(
+                    ServiceId::Database,
+                    InfoMsg::ListDeals(DealStatusSelector::All),
+                )?;
+                if let BusMsg::Info(InfoMsg::DealInfoList(deals)) = runtime.report_failure()? {
+                    let stats = SwapStats::from_deals(deals.iter(), role);
+                    if runtime.json_output() {
+                        println!("{}", stats.to_json());
+                    } else {
+                        stats.print_table();
+                    }
+                } else {
+                    return Err(Error::Farcaster("Received unexpected response".to_string()));
+                }
             }
 
             Command::ListTasks {
                 blockchain,
                 network,
+                verbose,
             } => {
                 runtime.request_info(ServiceId::Syncer(blockchain, network), InfoMsg::ListTasks)?;
-                runtime.report_response_or_fail()?;
+                match runtime.report_failure()? {
+                    BusMsg::Info(InfoMsg::TaskList(tasks)) if !runtime.json_output() => {
+                        if verbose {
+                            print_task_table(&tasks);
+                        } else {
+                            let ids: List<TaskId> = tasks.iter().map(|task| task.id).collect();
+                            print!("{}", ids);
+                        }
+                    }
+                    resp => runtime.print_response(&resp),
+                }
+            }
+
+            Command::WatchAddress {
+                blockchain,
+                network,
+                address,
+                from_height,
+                view_key,
+            } => {
+                let addendum = match (blockchain, address) {
+                    (Blockchain::Bitcoin, Address::Bitcoin(address))
+                        if address.is_valid_for_network(network.into()) =>
+                    {
+                        AddressAddendum::Bitcoin(BtcAddressAddendum {
+                            address,
+                            from_height,
+                        })
+                    }
+                    (Blockchain::Monero, Address::Monero(address))
+                        if address.network == network.into() =>
+                    {
+                        let view_key = view_key.ok_or_else(|| {
+                            Error::Farcaster(
+                                "Watching a Monero address requires its private view key"
+                                    .to_string(),
+                            )
+                        })?;
+                        AddressAddendum::Monero(XmrAddressAddendum {
+                            address,
+                            view_key,
+                            from_height: from_height.unwrap_or_default(),
+                        })
+                    }
+                    (blockchain, address) => {
+                        return Err(Error::Farcaster(format!(
+                            "{} is not a {} {} address",
+                            address, blockchain, network
+                        )));
+                    }
+                };
+                // the task is sent straight to the syncer, its events are routed back to this
+                // client until it exits
+                runtime.request_sync(
+                    ServiceId::Syncer(blockchain, network),
+                    SyncMsg::Task(Task::WatchAddress(WatchAddress {
+                        id: TaskId(0),
+                        lifetime: u64::MAX,
+                        addendum,
+                        include_tx: false,
+                        filter: TxFilter::All,
+                    })),
+                )?;
+                loop {
+                    if let resp @ BusMsg::Sync(SyncMsg::Event(Event::AddressTransaction(_))) =
+                        runtime.report_failure()?
+                    {
+                        runtime.print_response(&resp);
+                    }
+                }
+            }
+
+            Command::GetRawTransaction {
+                blockchain,
+                network,
+                hash,
+            } => {
+                let syncer = ServiceId::Syncer(blockchain, network);
+                match blockchain {
+                    Blockchain::Bitcoin => {
+                        let txid = bitcoin::Txid::from_str(&hash).map_err(|err| {
+                            Error::Farcaster(format!("Invalid Bitcoin txid {}: {}", hash, err))
+                        })?;
+                        let tx = retrieve_bitcoin_tx(runtime, &syncer, TaskId(0), txid)?
+                            .ok_or_else(|| {
+                                Error::Farcaster(format!("Transaction {} not found", txid))
+                            })?;
+                        println!("{}", bitcoin::consensus::encode::serialize_hex(&tx));
+                        // the fee is only known if every spent output can be retrieved
+                        let mut spent_outputs = vec![];
+                        for (index, input) in tx.input.iter().enumerate() {
+                            let prev_tx = retrieve_bitcoin_tx(
+                                runtime,
+                                &syncer,
+                                TaskId(index as u32 + 1),
+                                input.previous_output.txid,
+                            )?;
+                            spent_outputs.push(prev_tx.and_then(|prev_tx| {
+                                prev_tx
+                                    .output
+                                    .get(input.previous_output.vout as usize)
+                                    .cloned()
+                            }));
+                        }
+                        print_bitcoin_tx(&tx, &spent_outputs, network);
+                    }
+                    Blockchain::Monero => {
+                        let txid = monero::Hash::from_str(&hash).map_err(|err| {
+                            Error::Farcaster(format!("Invalid Monero txid {}: {}", hash, err))
+                        })?;
+                        runtime.request_sync(
+                            syncer,
+                            SyncMsg::Task(Task::GetXmrTx(GetXmrTx {
+                                id: TaskId(0),
+                                hash: txid.as_bytes().to_vec(),
+                            })),
+                        )?;
+                        let tx = loop {
+                            if let BusMsg::Sync(SyncMsg::Event(Event::XmrTransactionRetrieved(
+                                XmrTransactionRetrieved { tx, .. },
+                            ))) = runtime.report_failure()?
+                            {
+                                break tx;
+                            }
+                        };
+                        let tx = tx.ok_or_else(|| {
+                            Error::Farcaster(format!("Transaction {} not found", txid))
+                        })?;
+                        println!("{}", hex::encode(tx));
+                    }
+                }
             }
 
             Command::ListListens => {
@@ -192,9 +431,91 @@ impl Exec for Command {
                 }
             }
 
-            Command::Connect { swap_id } => {
-                runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::Connect(swap_id))?;
+            Command::ExportSwapState {
+                swap_id,
+                output_path,
+            } => {
+                runtime.request_info(ServiceId::Database, InfoMsg::GetSwapState(swap_id))?;
+                if let BusMsg::Info(InfoMsg::SwapState(export)) = runtime.report_failure()? {
+                    let encoded = export.to_base64();
+                    match output_path {
+                        Some(path) => {
+                            std::fs::write(&path, encoded)?;
+                            println!("Exported swap {} state to {}", swap_id, path.display());
+                        }
+                        None => println!("{}", encoded),
+                    }
+                } else {
+                    return Err(Error::Farcaster("Received unexpected response".to_string()));
+                }
+            }
+
+            Command::ImportSwapState { input_path } => {
+                let encoded = match input_path {
+                    Some(path) => std::fs::read_to_string(path)?,
+                    None => {
+                        let mut encoded = String::new();
+                        io::stdin().read_to_string(&mut encoded)?;
+                        encoded
+                    }
+                };
+                let export = SwapStateExport::from_base64(&encoded)?;
+                let swap_id = export.swap_id;
+                runtime.request_ctl(ServiceId::Database, CtlMsg::ImportSwapState(export))?;
                 runtime.report_response_or_fail()?;
+                // feed the imported checkpoint to the restore path
+                runtime.request_info(ServiceId::Database, InfoMsg::GetCheckpointEntry(swap_id))?;
+                if let BusMsg::Info(InfoMsg::CheckpointEntry(entry)) = runtime.report_failure()? {
+                    runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::RestoreCheckpoint(entry))?;
+                    runtime.report_response_or_fail()?;
+                } else {
+                    return Err(Error::Farcaster("Received unexpected response".to_string()));
+                }
+            }
+
+            Command::Connect { swap_id, max_wait } => {
+                let mut wait = RECONNECT_INITIAL_WAIT_SECS.min(max_wait);
+                for attempt_number in 1.. {
+                    runtime.request_ctl(
+                        ServiceId::Farcasterd,
+                        CtlMsg::ReconnectAttempt(ReconnectAttempt {
+                            swap_id,
+                            attempt_number,
+                        }),
+                    )?;
+                    match runtime.report_failure()? {
+                        BusMsg::Ctl(CtlMsg::ReconnectAttemptResult(ReconnectAttemptResult {
+                            success: true,
+                            peer_addr,
+                            ..
+                        })) => {
+                            println!(
+                                "Attempt {}: {}",
+                                attempt_number,
+                                format!("connected to {}", peer_addr).bright_green_bold()
+                            );
+                            break;
+                        }
+                        BusMsg::Ctl(CtlMsg::ReconnectAttemptResult(ReconnectAttemptResult {
+                            peer_addr,
+                            ..
+                        })) => {
+                            println!(
+                                "Attempt {}: {}, retrying in {} seconds",
+                                attempt_number,
+                                format!("{} not connected yet", peer_addr).err(),
+                                wait
+                            );
+                        }
+                        _ => {
+                            return Err(Error::Farcaster(
+                                "Received unexpected response".to_string(),
+                            ))
+                        }
+                    }
+                    thread::sleep(std::time::Duration::from_secs(wait));
+                    wait = (wait * 2).min(max_wait);
+                }
             }
 
             Command::HealthCheck { ref selector } => match selector {
@@ -209,14 +530,21 @@ impl Exec for Command {
                     let monero_testnet_health = self.check_health(runtime, Monero, Testnet)?;
                     let monero_mainnet_health = self.check_health(runtime, Monero, Mainnet)?;
 
-                    println!(
-                        "{}",
-                        DefaultHealthReport {
-                            bitcoin_testnet_health,
-                            bitcoin_mainnet_health,
-                            monero_testnet_health,
-                            monero_mainnet_health,
-                        }
+                    let report = DefaultHealthReport {
+                        bitcoin_testnet_health,
+                        bitcoin_mainnet_health,
+                        monero_testnet_health,
+                        monero_mainnet_health,
+                    };
+                    print_health_report(
+                        runtime,
+                        &report,
+                        &[
+                            ("bitcoin testnet", &report.bitcoin_testnet_health),
+                            ("bitcoin mainnet", &report.bitcoin_mainnet_health),
+                            ("monero testnet", &report.monero_testnet_health),
+                            ("monero mainnet", &report.monero_mainnet_health),
+                        ],
                     );
                 }
                 // user selected a specific network
@@ -225,12 +553,19 @@ impl Exec for Command {
 
                     let bitcoin_health = self.check_health(runtime, Bitcoin, *network)?;
                     let monero_health = self.check_health(runtime, Monero, *network)?;
-                    println!(
-                        "{}",
-                        ReducedHealthReport {
-                            bitcoin_health,
-                            monero_health,
-                        }
+                    let report = ReducedHealthReport {
+                        bitcoin_health,
+                        monero_health,
+                    };
+                    let bitcoin = format!("bitcoin {}", network);
+                    let monero = format!("monero {}", network);
+                    print_health_report(
+                        runtime,
+                        &report,
+                        &[
+                            (&bitcoin, &report.bitcoin_health),
+                            (&monero, &report.monero_health),
+                        ],
                     );
                 }
                 // check all networks
@@ -246,16 +581,25 @@ impl Exec for Command {
                     let monero_mainnet_health = self.check_health(runtime, Monero, Mainnet)?;
                     let monero_local_health = self.check_health(runtime, Monero, Local)?;
 
-                    println!(
-                        "{}",
-                        CompleteHealthReport {
-                            bitcoin_testnet_health,
-                            bitcoin_mainnet_health,
-                            bitcoin_local_health,
-                            monero_testnet_health,
-                            monero_mainnet_health,
-                            monero_local_health,
-                        }
+                    let report = CompleteHealthReport {
+                        bitcoin_testnet_health,
+                        bitcoin_mainnet_health,
+                        bitcoin_local_health,
+                        monero_testnet_health,
+                        monero_mainnet_health,
+                        monero_local_health,
+                    };
+                    print_health_report(
+                        runtime,
+                        &report,
+                        &[
+                            ("bitcoin testnet", &report.bitcoin_testnet_health),
+                            ("bitcoin mainnet", &report.bitcoin_mainnet_health),
+                            ("bitcoin local", &report.bitcoin_local_health),
+                            ("monero testnet", &report.monero_testnet_health),
+                            ("monero mainnet", &report.monero_mainnet_health),
+                            ("monero local", &report.monero_local_health),
+                        ],
                     );
                 }
             },
@@ -274,7 +618,25 @@ impl Exec for Command {
                 maker_role,
                 public_ip_addr,
                 public_port,
+                suggest_amounts,
+                lock_confs,
+                cancel_confs,
+                buy_confs,
+                refund_confs,
+                punish_confs,
+                deal_expiry,
+                dry_run,
             } => {
+                let accordant_amount = match suggest_amounts {
+                    Some(rate) => match suggest_accordant_amount(arbitrating_amount, rate) {
+                        Some(amount) => amount,
+                        None => {
+                            println!("Deal not made");
+                            return Ok(());
+                        }
+                    },
+                    None => accordant_amount.expect("required without suggest-amounts"),
+                };
                 let deal_parameters = DealParameters {
                     uuid: Uuid::new().into(),
                     network,
@@ -293,28 +655,133 @@ impl Exec for Command {
                     public_addr,
                     arbitrating_addr,
                     accordant_addr,
+                    confirmation_bounds: ConfirmationBounds {
+                        lock: lock_confs,
+                        cancel: cancel_confs,
+                        buy: buy_confs,
+                        refund: refund_confs,
+                        punish: punish_confs,
+                    },
+                    deal_expiry,
                 };
-                runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::MakeDeal(proto_deal))?;
+                if dry_run {
+                    runtime.request_ctl(
+                        ServiceId::Farcasterd,
+                        CtlMsg::ValidateMakeParams(proto_deal),
+                    )?;
+                } else {
+                    runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::MakeDeal(proto_deal))?;
+                }
                 // report success or failure of the request to cli
                 runtime.report_response_or_fail()?;
             }
 
-            Command::DealInfo { deal } => {
+            Command::DealInfo {
+                deal,
+                compare_market,
+            } => {
+                if runtime.json_output() {
+                    print_output(runtime, &deal);
+                    return Ok(());
+                }
                 println!("\n Trading {}\n", deal_buy_information(&deal.parameters));
+                if let Some(market_rate) = compare_market {
+                    println!(
+                        " {}\n",
+                        deal_market_comparison(&deal.parameters, market_rate.as_xmr())
+                    );
+                }
                 println!("{}", serde_yaml::to_string(&deal).expect("already parsed"));
             }
 
+            Command::EstimateSwapCost { deal } => {
+                runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::EstimateSwapCost(deal))?;
+                runtime.report_response_or_fail()?;
+            }
+
+            Command::GetDealQr { deal, format } => {
+                let code = qr::deal_qr_code(&deal)?;
+                match format.unwrap_or(QrFormat::Text) {
+                    QrFormat::Text => println!("{}", qr::render_text(&code)),
+                    QrFormat::Png { output } => {
+                        qr::save_png(&code, &output)?;
+                        println!("QR code saved to {}", output.display());
+                    }
+                }
+            }
+
+            Command::ConfirmDealParams { deal } => {
+                print_deal_summary(&deal);
+                match deal_temporal_safety(&deal, runtime.node_config()) {
+                    Ok(temporal_safety) => println!(
+                        "\n {}\n",
+                        format!(
+                            "Timelocks are safe with a finality of {} blocks and a safety margin of {} blocks",
+                            temporal_safety.arb_finality, temporal_safety.safety
+                        )
+                        .bright_green_bold()
+                    ),
+                    Err(err) => {
+                        println!("\n {}\n", format!("Risky deal: {}", err).red_bold());
+                        std::process::exit(1);
+                    }
+                }
+            }
+
             Command::Take {
                 deal,
                 bitcoin_address,
                 monero_address,
                 without_validation,
+                confirm_amounts,
+                min_reputation,
+                expires_at_block,
             } => {
                 let Deal {
                     node_id,
                     peer_address,
                     ..
                 } = deal;
+                let expiry = match expires_at_block {
+                    Some(expires_at_block) => {
+                        let bitcoin_height =
+                            query_bitcoin_height(&runtime.config(), deal.parameters.network)
+                                .ok_or_else(|| {
+                                    Error::Farcaster(
+                                        "Bitcoin height unknown, cannot check the deal expiry"
+                                            .to_string(),
+                                    )
+                                })?;
+                        Some(ctl::DealExpiry {
+                            expires_at_block,
+                            bitcoin_height,
+                        })
+                    }
+                    None => None,
+                };
+                if confirm_amounts && !confirm_deal_amounts(&deal.parameters) {
+                    return Err(Error::Farcaster(
+                        "Typed amount does not match the deal, refusing to take it".to_string(),
+                    ));
+                }
+                if let Some(min_reputation) = min_reputation {
+                    runtime.request_info(
+                        ServiceId::Database,
+                        InfoMsg::GetPeerReputation(node_id.into()),
+                    )?;
+                    if let BusMsg::Info(InfoMsg::PeerReputation(reputation)) =
+                        runtime.report_failure()?
+                    {
+                        if reputation.score < min_reputation {
+                            return Err(Error::Farcaster(format!(
+                                "Counterparty {} has a reputation score of {}, below the required {}",
+                                node_id, reputation.score, min_reputation
+                            )));
+                        }
+                    } else {
+                        return Err(Error::Farcaster("Received unexpected response".to_string()));
+                    }
+                }
                 if !without_validation {
                     println!(
                         "\nWant to buy {}?\n\nCarefully validate the deal!\n",
@@ -331,6 +798,7 @@ impl Exec for Command {
                             deal,
                             bitcoin_address,
                             monero_address,
+                            expiry,
                         }),
                     )?;
                     // report success of failure of the request to cli
@@ -338,23 +806,223 @@ impl Exec for Command {
                 }
             }
 
-            Command::RevokeDeal { deal } => {
-                runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::RevokeDeal(deal))?;
+            Command::ImportDeal {
+                source,
+                take_immediately,
+                bitcoin_address,
+                monero_address,
+            } => {
+                let encoded_deal = read_deal_source(&source)?;
+                let deal = Deal::from_str(encoded_deal.trim()).map_err(|err| {
+                    Error::Farcaster(format!("Invalid deal read from {}: {}", source, err))
+                })?;
+                let FeeStrategy::Fixed(fee_rate) = deal.parameters.fee_strategy;
+                if fee_rate.as_native_unit() == bitcoin::Amount::ZERO {
+                    return Err(Error::Farcaster(
+                        "The deal fee strategy pays no fee, its transactions would not confirm"
+                            .to_string(),
+                    ));
+                }
+                let temporal_safety = deal_temporal_safety(&deal, runtime.node_config())
+                    .map_err(|err| Error::Farcaster(format!("Risky deal: {}", err)))?;
+                print_deal_summary(&deal);
+                println!(
+                    "\n {}\n",
+                    format!(
+                        "Timelocks are safe with a finality of {} blocks and a safety margin of {} blocks",
+                        temporal_safety.arb_finality, temporal_safety.safety
+                    )
+                    .bright_green_bold()
+                );
+                match (take_immediately, bitcoin_address, monero_address) {
+                    (true, Some(bitcoin_address), Some(monero_address)) => {
+                        runtime.request_ctl(
+                            ServiceId::Farcasterd,
+                            CtlMsg::TakeDeal(ctl::PubDeal {
+                                deal,
+                                bitcoin_address,
+                                monero_address,
+                                expiry: None,
+                            }),
+                        )?;
+                        runtime.report_response_or_fail()?;
+                    }
+                    (true, ..) => {
+                        return Err(Error::Farcaster(
+                            "Taking the deal requires --btc-addr and --xmr-addr".to_string(),
+                        ));
+                    }
+                    (false, ..) => println!("{}", deal),
+                }
+            }
+
+            Command::TakeFromFile {
+                path,
+                bitcoin_address,
+                monero_address,
+                max_deals,
+                without_validation,
+                max_concurrent,
+            } => {
+                let content = std::fs::read_to_string(&path)?;
+                let entries = parse_deals_file(
+                    runtime.node_config(),
+                    &content,
+                    &bitcoin_address,
+                    monero_address,
+                    without_validation,
+                )
+                .map_err(|err| {
+                    Error::Farcaster(format!("Invalid deal file {}: {}", path.display(), err))
+                })?;
+                take_deals(
+                    runtime,
+                    entries,
+                    bitcoin_address,
+                    monero_address,
+                    max_deals,
+                    max_concurrent,
+                );
+            }
+
+            Command::BatchTake {
+                deals_file,
+                bitcoin_address,
+                monero_address,
+                without_validation,
+                max_concurrent,
+            } => {
+                let content = std::fs::read_to_string(&deals_file)?;
+                let entries = parse_deal_lines(
+                    runtime.node_config(),
+                    &content,
+                    &bitcoin_address,
+                    monero_address,
+                    without_validation,
+                );
+                take_deals(
+                    runtime,
+                    entries,
+                    bitcoin_address,
+                    monero_address,
+                    usize::MAX,
+                    max_concurrent,
+                );
+            }
+
+            Command::RevokeDeal { deal, force } => {
+                runtime.request_ctl(
+                    ServiceId::Farcasterd,
+                    CtlMsg::RevokeDeal(ctl::DealRevocation { deal, force }),
+                )?;
                 runtime.report_response_or_fail()?;
             }
 
-            Command::AbortSwap { swap_id } => {
-                runtime.request_ctl(ServiceId::Swap(swap_id), CtlMsg::AbortSwap)?;
+            Command::RevokeExpiredDeals { force } => {
+                runtime.request_ctl(ServiceId::Farcasterd, CtlMsg::RevokeExpiredDeals(force))?;
                 runtime.report_response_or_fail()?;
             }
 
-            Command::Progress { swapid, follow } => {
-                if follow {
+            Command::RevokeAllDeals { selector, yes } => {
+                if selector == DealSelector::InProgress {
+                    return Err(Error::Farcaster(
+                        "Deals with a swap in progress cannot be revoked, abort the swaps instead"
+                            .to_string(),
+                    ));
+                }
+                runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListDeals(selector.into()))?;
+                let deals: Vec<Deal> = match runtime.report_failure()? {
+                    BusMsg::Info(InfoMsg::DealList(deals))
+                    | BusMsg::Info(InfoMsg::DealInfoList(deals)) => deals
+                        .iter()
+                        .filter(|info| {
+                            matches!(info.status, DealStatus::Open | DealStatus::InProgress)
+                        })
+                        .map(|info| info.deal.clone())
+                        .collect(),
+                    _ => {
+                        return Err(Error::Farcaster("Received unexpected response".to_string()));
+                    }
+                };
+                if deals.is_empty() {
+                    println!("No deal to revoke");
+                    return Ok(());
+                }
+                if !yes && !confirm_revoke_all_deals(&deals) {
+                    return Err(Error::Farcaster("Revocation not confirmed".to_string()));
+                }
+                runtime.request_ctl(
+                    ServiceId::Farcasterd,
+                    CtlMsg::RevokeDeals(deals.into_iter().collect()),
+                )?;
+                let resp = runtime.report_failure()?;
+                runtime.print_response(&resp);
+            }
+
+            Command::AbortSwap {
+                swap_id,
+                reason,
+                force,
+            } => {
+                let reason = reason.map(AbortReason::UserRequested);
+                let request = if force {
+                    if !confirm_force_abort(swap_id) {
+                        return Err(Error::Farcaster("Force abort not confirmed".to_string()));
+                    }
+                    CtlMsg::ForceAbortSwap(reason)
+                } else {
+                    CtlMsg::AbortSwap(reason)
+                };
+                runtime.request_ctl(ServiceId::Swap(swap_id), request)?;
+                runtime.report_response_or_fail()?;
+            }
+
+            Command::SetFeeStrategy {
+                swap_id,
+                fee_strategy,
+            } => {
+                runtime.request_ctl(
+                    ServiceId::Swap(swap_id),
+                    CtlMsg::SetFeeStrategy(fee_strategy),
+                )?;
+                runtime.report_response_or_fail()?;
+            }
+
+            Command::SignMessage { swap_id, message } => {
+                runtime.request_ctl(ServiceId::Swap(swap_id), CtlMsg::SignMessage(message))?;
+                runtime.report_response_or_fail()?;
+            }
+
+            Command::VerifyMessage {
+                message,
+                signature,
+                swap_id,
+            } => {
+                runtime.request_ctl(
+                    ServiceId::Swap(swap_id),
+                    CtlMsg::VerifyMessage(VerifyMessage { message, signature }),
+                )?;
+                runtime.report_response_or_fail()?;
+            }
+
+            Command::Progress {
+                swapid,
+                follow,
+                verbose,
+                watch_only,
+                log_file,
+            } => {
+                if watch_only {
+                    println!("{}", spawn_progress_watcher()?);
+                } else if follow {
                     // subscribe to progress event and loop until Finish event is received or user
                     // ctrl-c the cli. Expect to recieve a stream of event responses
                     runtime
                         .request_info(ServiceId::Farcasterd, InfoMsg::SubscribeProgress(swapid))?;
-                    let res = runtime.report_progress();
+                    let res = match log_file {
+                        Some(path) => log_progress(runtime, &path),
+                        None => runtime.report_progress(),
+                    };
                     // if user didn't ctrl-c before that point we can cleanly unsubscribe the
                     // client from the notification stream and then return the result from report
                     // progress
@@ -367,18 +1035,134 @@ impl Exec for Command {
                     // request a read progress response. Expect to recieve only one response and
                     // quit
                     runtime.request_info(ServiceId::Farcasterd, InfoMsg::ReadProgress(swapid))?;
-                    runtime.report_response_or_fail()?;
+                    if runtime.json_output() || verbose {
+                        let resp = runtime.report_failure()?;
+                        let progress = match resp {
+                            BusMsg::Info(InfoMsg::SwapProgress(ref progress)) => progress,
+                            resp => {
+                                runtime.print_response(&resp);
+                                return Ok(());
+                            }
+                        };
+                        let deadline = if verbose {
+                            progress_deadline(runtime, swapid, progress)?
+                        } else {
+                            None
+                        };
+                        if runtime.json_output() {
+                            let mut value = progress_to_json(swapid, progress);
+                            if verbose {
+                                value["deadline"] = deadline_to_json(deadline);
+                            }
+                            println!("{}", value);
+                        } else {
+                            runtime.print_response(&resp);
+                            match deadline {
+                                Some(deadline) => print_deadline(&deadline),
+                                None => println!("Arbitrating lock not confirmed yet"),
+                            }
+                        }
+                    } else {
+                        runtime.report_response_or_fail()?;
+                    }
+                }
+            }
+
+            Command::StopWatch { pid } => {
+                signal::kill(Pid::from_raw(pid), Signal::SIGTERM).map_err(|err| {
+                    Error::Farcaster(format!("Failed to stop the watcher {}: {}", pid, err))
+                })?;
+                println!("Progress watcher {} stopped", pid);
+            }
+
+            Command::History { swap_id, format } => {
+                // a running swap reports its live history, otherwise read it from its checkpoint
+                runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListSwaps)?;
+                let running = match runtime.report_failure()? {
+                    BusMsg::Info(InfoMsg::SwapList(swaps)) => swaps.contains(&swap_id),
+                    _ => return Err(Error::Farcaster("Received unexpected response".to_string())),
+                };
+                let target = if running {
+                    ServiceId::Swap(swap_id)
+                } else {
+                    ServiceId::Database
+                };
+                runtime.request_info(target, InfoMsg::GetSwapHistory(swap_id))?;
+                if let BusMsg::Info(InfoMsg::SwapHistory(history)) = runtime.report_failure()? {
+                    print_history(&history, format);
+                } else {
+                    return Err(Error::Farcaster("Received unexpected response".to_string()));
                 }
             }
 
             Command::NeedsFunding { blockchain } => {
                 runtime.request_info(ServiceId::Farcasterd, InfoMsg::NeedsFunding(blockchain))?;
-                runtime.report_response_or_fail()?;
+                let resp = runtime.report_failure()?;
+                runtime.print_response(&resp);
+                if let BusMsg::Info(InfoMsg::FundingInfos(funding_infos)) = resp {
+                    warn_funding_deadlines(&runtime.config(), &funding_infos);
+                }
+            }
+
+            Command::ListFundingAddresses {
+                blockchain,
+                with_balance: true,
+            } => {
+                let addresses = funding_addresses_with_balance(runtime, blockchain)?;
+                print_funding_addresses_with_balance(runtime.json_output(), &addresses);
             }
 
-            Command::ListFundingAddresses { blockchain } => {
+            Command::ListFundingAddresses { blockchain, .. } => {
                 runtime.request_info(ServiceId::Database, InfoMsg::GetAddresses(blockchain))?;
-                runtime.report_response_or_fail()?;
+                match runtime.report_failure()? {
+                    BusMsg::Info(InfoMsg::BitcoinAddressList(mut addresses)) => {
+                        let balances = query_balances(
+                            &runtime.config(),
+                            addresses
+                                .iter()
+                                .map(|a| Address::Bitcoin(a.address.clone()))
+                                .collect(),
+                        );
+                        for (pair, balance) in addresses.iter_mut().zip(balances) {
+                            pair.balance_sat = balance;
+                        }
+                        print_funding_addresses(
+                            runtime.json_output(),
+                            addresses.iter().map(|a| {
+                                (
+                                    a.address.to_string(),
+                                    a.swap_id,
+                                    a.balance_sat
+                                        .map(|sat| bitcoin::Amount::from_sat(sat).to_string()),
+                                )
+                            }),
+                        );
+                    }
+                    BusMsg::Info(InfoMsg::MoneroAddressList(mut addresses)) => {
+                        let balances = query_balances(
+                            &runtime.config(),
+                            addresses
+                                .iter()
+                                .map(|a| Address::Monero(a.address))
+                                .collect(),
+                        );
+                        for (pair, balance) in addresses.iter_mut().zip(balances) {
+                            pair.balance_piconero = balance;
+                        }
+                        print_funding_addresses(
+                            runtime.json_output(),
+                            addresses.iter().map(|a| {
+                                (
+                                    a.address.to_string(),
+                                    a.swap_id,
+                                    a.balance_piconero
+                                        .map(|pico| monero::Amount::from_pico(pico).to_string()),
+                                )
+                            }),
+                        );
+                    }
+                    _ => return Err(Error::Farcaster("Received unexpected response".to_string())),
+                }
             }
 
             Command::SweepBitcoinAddress {
@@ -471,6 +1255,45 @@ impl Exec for Command {
                     }
                 }
             }
+
+            Command::ExportStateDiagram { output } => {
+                let diagram = swap_state_diagram();
+                match output {
+                    Some(path) => std::fs::write(&path, diagram)?,
+                    None => print!("{}", diagram),
+                }
+            }
+
+            Command::SimulateSwap {
+                role,
+                confirmations_per_event,
+                cancel_timelock,
+                punish_timelock,
+                safety,
+                btc_finality,
+                xmr_finality,
+            } => {
+                let temporal_safety = TemporalSafety {
+                    cancel_timelock,
+                    punish_timelock,
+                    safety,
+                    arb_finality: btc_finality,
+                    acc_finality: xmr_finality,
+                };
+                temporal_safety.valid_params()?;
+                let mut safe = true;
+                for path in [SimulationPath::Happy, SimulationPath::CancelRefund] {
+                    let simulation =
+                        simulate_swap(role, path, &temporal_safety, confirmations_per_event);
+                    safe &= simulation.is_safe();
+                    println!("{}\n", simulation);
+                }
+                if !safe {
+                    return Err(Error::Farcaster(s!(
+                        "The simulated swap violated temporal safety"
+                    )));
+                }
+            }
         }
 
         Ok(())
@@ -478,13 +1301,13 @@ impl Exec for Command {
 }
 
 impl Command {
-    /// Check syncer (coin, net) health via farcasterd and return a [`Health`] result
+    /// Check syncer (coin, net) health via farcasterd and return a [`SyncerHealth`] result
     fn check_health(
         &self,
         runtime: &mut Client,
         blockchain: Blockchain,
         network: Network,
-    ) -> Result<Health, Error> {
+    ) -> Result<SyncerHealth, Error> {
         runtime.request_ctl(
             ServiceId::Farcasterd,
             CtlMsg::HealthCheck(blockchain, network),
@@ -498,6 +1321,1028 @@ impl Command {
     }
 }
 
+// Print the health report as a table of the syncers with their measurements, or as JSON
+fn print_health_report<T: serde::Serialize>(
+    runtime: &Client,
+    report: &T,
+    syncers: &[(&str, &SyncerHealth)],
+) {
+    if runtime.json_output() {
+        println!(
+            "{}",
+            serde_json::to_value(report).expect("internal JSON serialization error")
+        );
+        return;
+    }
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "--".to_string());
+    println!(
+        "{:<16} {:>12} {:>14} {:>6}  {}",
+        "SYNCER", "LATENCY (ms)", "BLOCK AGE (s)", "PEERS", "HEALTH"
+    );
+    for (syncer, health) in syncers {
+        println!(
+            "{:<16} {:>12} {:>14} {:>6}  {}",
+            syncer,
+            health.latency_ms,
+            or_dash(health.last_block_age_secs.map(|age| age.to_string())),
+            or_dash(health.peer_count.map(|count| count.to_string())),
+            health.health
+        );
+    }
+}
+
+// Print a table of the connected peers sorted by round trip time, peers without a completed ping
+// come first
+fn print_peers_latency(
+    runtime: &mut Client,
+    reputations: Vec<PeerReputationInfo>,
+) -> Result<(), Error> {
+    let mut rows = vec![];
+    for PeerReputationInfo {
+        peer,
+        completed_swap_count,
+        ..
+    } in reputations
+    {
+        runtime.request_info(ServiceId::Peer(0, peer), InfoMsg::GetInfo)?;
+        if let BusMsg::Info(InfoMsg::PeerInfo(info)) = runtime.report_failure()? {
+            rows.push((peer, info, completed_swap_count));
+        } else {
+            return Err(Error::Farcaster("Received unexpected response".to_string()));
+        }
+    }
+    rows.sort_by(|(_, a, _), (_, b, _)| match (a.rtt_ms, b.rtt_ms) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    });
+    if runtime.json_output() {
+        let rows: Vec<_> = rows
+            .into_iter()
+            .map(|(peer, info, swaps)| {
+                serde_json::json!({
+                    "peer": peer.id.to_string(),
+                    "address": peer.addr.to_string(),
+                    "connected_secs": info.uptime.as_secs(),
+                    "swaps": swaps,
+                    "rtt_ms": info.rtt_ms,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(rows));
+        return Ok(());
+    }
+    println!(
+        "{:<18} {:<24} {:>10} {:>6} {:>8}",
+        "PEER", "ADDRESS", "CONNECTED", "SWAPS", "RTT (ms)"
+    );
+    for (peer, info, swaps) in rows {
+        println!(
+            "{:<18} {:<24} {:>9}s {:>6} {:>8}",
+            hex::encode(&peer.id.public_key().serialize()[..8]),
+            peer.addr.to_string(),
+            info.uptime.as_secs(),
+            swaps,
+            info.rtt_ms
+                .map(|rtt| rtt.to_string())
+                .unwrap_or_else(|| "--".to_string())
+        );
+    }
+    Ok(())
+}
+
+/// Prints the active and completed swaps with each peer, the most active peers first unless
+/// sorted by node id
+fn print_peers_swap_count(
+    runtime: &Client,
+    mut peers: Vec<PeerReputationInfo>,
+    sort_by: PeerSortKey,
+) {
+    match sort_by {
+        PeerSortKey::Swaps => peers.sort_by(|a, b| {
+            (b.active_swap_count, b.completed_swap_count)
+                .cmp(&(a.active_swap_count, a.completed_swap_count))
+        }),
+        PeerSortKey::NodeId => peers.sort_by_key(|info| info.peer.id.to_string()),
+    }
+    if runtime.json_output() {
+        print_output(runtime, &List::from_iter(peers));
+        return;
+    }
+    println!(
+        "{:<18} {:<24} {:>7} {:>10}",
+        "PEER", "ADDRESS", "ACTIVE", "COMPLETED"
+    );
+    for info in peers {
+        println!(
+            "{:<18} {:<24} {:>7} {:>10}",
+            hex::encode(&info.peer.id.public_key().serialize()[..8]),
+            info.peer.addr.to_string(),
+            info.active_swap_count,
+            info.completed_swap_count
+        );
+    }
+}
+
+fn print_history(history: &SwapHistory, format: HistoryFormat) {
+    match format {
+        HistoryFormat::Text => {
+            println!("Swap {}", history.swap_id);
+            for event in history.events.iter() {
+                println!(
+                    "{}  {} -> {}  ({})",
+                    format_timestamp(event.timestamp),
+                    event.old_state,
+                    event.new_state,
+                    event.trigger
+                );
+            }
+        }
+        HistoryFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_value(history).expect("internal JSON serialization error")
+            );
+        }
+        HistoryFormat::Csv => {
+            println!("timestamp,old_state,new_state,trigger");
+            for event in history.events.iter() {
+                println!(
+                    "{},{},{},{}",
+                    event.timestamp,
+                    csv_field(&event.old_state),
+                    csv_field(&event.new_state),
+                    csv_field(&event.trigger)
+                );
+            }
+        }
+    }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    chrono::NaiveDateTime::from_timestamp_opt(timestamp as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+// Quote a CSV field if it contains a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Print a value in the output format selected on the client
+fn print_output<T: std::fmt::Display + serde::Serialize>(runtime: &Client, value: &T) {
+    if runtime.json_output() {
+        println!(
+            "{}",
+            serde_json::to_value(value).expect("internal JSON serialization error")
+        );
+    } else {
+        println!("{}", value);
+    }
+}
+
+// Summarize the progress of a swap with its latest state and transaction confirmations
+fn latest_state_report(progress: &SwapProgress) -> Option<&StateReport> {
+    progress
+        .progress
+        .iter()
+        .rev()
+        .find_map(|event| match event {
+            ProgressEvent::StateUpdate(report) => Some(report),
+            ProgressEvent::StateTransition(transition) => Some(&transition.new_state),
+            _ => None,
+        })
+}
+
+fn progress_to_json(swap_id: SwapId, progress: &SwapProgress) -> serde_json::Value {
+    let latest = latest_state_report(progress);
+    serde_json::json!({
+        "swap_id": swap_id.to_string(),
+        "state": latest.map(|report| report.state.clone()),
+        "confirmations": {
+            "arb_lock": latest.and_then(|report| report.arb_lock_confirmations),
+            "acc_lock": latest.and_then(|report| report.acc_lock_confirmations),
+            "cancel": latest.and_then(|report| report.cancel_confirmations),
+        },
+        "progress": progress.progress,
+        "summary": progress.summary,
+    })
+}
+
+fn deadline_to_json(deadline: Option<DeadlineReport>) -> serde_json::Value {
+    deadline.map_or(serde_json::Value::Null, |deadline| {
+        serde_json::json!({
+            "blocks_until_cancel": deadline.blocks_until_cancel,
+            "blocks_until_punish": deadline.blocks_until_punish,
+            "seconds_until_cancel": deadline.seconds_until_cancel,
+            "seconds_until_punish": deadline.seconds_until_punish,
+        })
+    })
+}
+
+/// Estimate the time left until the cancel and punish deadlines of a running swap, None until
+/// its arbitrating lock is confirmed
+fn progress_deadline(
+    runtime: &mut Client,
+    swap_id: SwapId,
+    progress: &SwapProgress,
+) -> Result<Option<DeadlineReport>, Error> {
+    let lock_confirmations =
+        match latest_state_report(progress).and_then(|report| report.arb_lock_confirmations) {
+            Some(confirmations) => confirmations,
+            None => return Ok(None),
+        };
+    runtime.request_info(ServiceId::Swap(swap_id), InfoMsg::GetInfo)?;
+    match runtime.report_failure()? {
+        BusMsg::Info(InfoMsg::SwapInfo(info)) => Ok(Some(
+            deal_temporal_safety(&info.deal, runtime.node_config())?
+                .time_to_deadline(lock_confirmations, BITCOIN_BLOCK_TIME_SECS),
+        )),
+        _ => Err(Error::Farcaster("Received unexpected response".to_string())),
+    }
+}
+
+fn print_deadline(deadline: &DeadlineReport) {
+    for (name, blocks, seconds) in [
+        (
+            "Cancel",
+            deadline.blocks_until_cancel,
+            deadline.seconds_until_cancel,
+        ),
+        (
+            "Punish",
+            deadline.blocks_until_punish,
+            deadline.seconds_until_punish,
+        ),
+    ] {
+        if blocks > 0 {
+            println!(
+                "{} possible in {} blocks (~{})",
+                name,
+                blocks,
+                format_duration(seconds as u64)
+            );
+        } else {
+            println!(
+                "{} possible since {} blocks (~{} ago)",
+                name,
+                -blocks,
+                format_duration(seconds.unsigned_abs())
+            );
+        }
+    }
+}
+
+// Query the balance of every address in parallel, each with its own client, and give up on the
+// balances not received before the timeout
+fn query_balances(config: &ServiceConfig, addresses: Vec<Address>) -> Vec<Option<u64>> {
+    let mut balances = vec![None; addresses.len()];
+    let (tx, rx) = mpsc::channel();
+    for (index, address) in addresses.into_iter().enumerate() {
+        let tx = tx.clone();
+        let config = config.clone();
+        thread::spawn(move || {
+            let balance = query_balance(config, address)
+                .map_err(|err| debug!("Failed to query address balance: {}", err))
+                .ok();
+            // the receiver is gone if the timeout elapsed
+            let _ = tx.send((index, balance));
+        });
+    }
+    drop(tx);
+    let deadline = Instant::now() + FUNDING_BALANCE_TIMEOUT;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(remaining) {
+            Ok((index, balance)) => balances[index] = balance,
+            Err(_) => break,
+        }
+    }
+    balances
+}
+
+/// Warns on stderr about the Bitcoin swaps close to or past the last block in which their lock
+/// can be mined safely, the funding infos themselves are printed on stdout
+fn warn_funding_deadlines(config: &ServiceConfig, funding_infos: &FundingInfos) {
+    let mut heights: HashMap<bitcoin::Network, Option<u64>> = HashMap::new();
+    for info in funding_infos.swaps_need_funding.iter() {
+        if let FundingInfo::Bitcoin(BitcoinFundingInfo {
+            swap_id,
+            address,
+            max_safe_lock_mined_block: Some(deadline),
+            ..
+        }) = info
+        {
+            let height = *heights
+                .entry(address.network)
+                .or_insert_with(|| query_bitcoin_height(config, address.network.into()));
+            match height {
+                Some(height) if height > *deadline => eprintln!(
+                    "{}",
+                    format!(
+                        "Too late to fund swap {} safely, its deadline block {} has passed",
+                        swap_id, deadline
+                    )
+                    .err()
+                ),
+                Some(height) if height + FUNDING_DEADLINE_WARNING_BLOCKS >= *deadline => {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Do not fund swap {} after block {}, the current height is {}",
+                            swap_id, deadline, height
+                        )
+                        .err()
+                    )
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Current height known by the Bitcoin syncer, None if it does not answer in time
+fn query_bitcoin_height(config: &ServiceConfig, network: Network) -> Option<u64> {
+    let (tx, rx) = mpsc::channel();
+    let config = config.clone();
+    thread::spawn(move || {
+        let height = watch_bitcoin_height(config, network)
+            .map_err(|err| debug!("Failed to query the Bitcoin height: {}", err))
+            .ok();
+        // the receiver is gone if the timeout elapsed
+        let _ = tx.send(height);
+    });
+    rx.recv_timeout(FUNDING_BALANCE_TIMEOUT).ok().flatten()
+}
+
+fn watch_bitcoin_height(config: ServiceConfig, network: Network) -> Result<u64, Error> {
+    let mut runtime = Client::with(config)?;
+    let syncer = ServiceId::Syncer(Blockchain::Bitcoin, network);
+    runtime.request_sync(
+        syncer.clone(),
+        SyncMsg::Task(Task::WatchHeight(WatchHeight {
+            id: TaskId(0),
+            lifetime: u64::MAX,
+        })),
+    )?;
+    loop {
+        if let BusMsg::Sync(SyncMsg::Event(Event::HeightChanged(HeightChanged {
+            height, ..
+        }))) = runtime.report_failure()?
+        {
+            runtime.request_sync(
+                syncer,
+                SyncMsg::Task(Task::Abort(Abort {
+                    task_target: TaskTarget::TaskId(TaskId(0)),
+                    respond: false,
+                })),
+            )?;
+            break Ok(height);
+        }
+    }
+}
+
+fn query_balance(config: ServiceConfig, address: Address) -> Result<u64, Error> {
+    let mut runtime = Client::with(config)?;
+    runtime.request_info(ServiceId::Database, InfoMsg::GetAddressSecretKey(address))?;
+    if let BusMsg::Info(InfoMsg::AddressSecretKey(address_secret_key)) = runtime.report_failure()? {
+        runtime.request_ctl(
+            ServiceId::Farcasterd,
+            CtlMsg::GetBalance(address_secret_key),
+        )?;
+        if let BusMsg::Info(InfoMsg::AddressBalance(AddressBalance { balance, .. })) =
+            runtime.report_failure()?
+        {
+            return Ok(balance);
+        }
+    }
+    Err(Error::Farcaster("Received unexpected response".to_string()))
+}
+
+fn print_funding_addresses(
+    json_output: bool,
+    rows: impl Iterator<Item = (String, Option<SwapId>, Option<String>)>,
+) {
+    if json_output {
+        let rows: Vec<_> = rows
+            .map(|(address, swap_id, balance)| {
+                serde_json::json!({
+                    "address": address,
+                    "swap_id": swap_id.map(|id| id.to_string()),
+                    "balance": balance,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(rows));
+        return;
+    }
+    println!("{:<96} {:<66} {:>20}", "ADDRESS", "SWAP ID", "BALANCE");
+    for (address, swap_id, balance) in rows {
+        println!(
+            "{:<96} {:<66} {:>20}",
+            address,
+            swap_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "--".to_string()),
+            balance.unwrap_or_else(|| "--".to_string())
+        );
+    }
+}
+
+/// Funding addresses of the blockchain with their balance, and the amount expected for those of
+/// the swaps waiting for funding
+fn funding_addresses_with_balance(
+    runtime: &mut Client,
+    blockchain: Blockchain,
+) -> Result<Vec<FundingAddressWithBalance>, Error> {
+    runtime.request_info(ServiceId::Database, InfoMsg::GetAddresses(blockchain))?;
+    let addresses: Vec<(Address, Option<SwapId>)> = match runtime.report_failure()? {
+        BusMsg::Info(InfoMsg::BitcoinAddressList(addresses)) => addresses
+            .iter()
+            .map(|a| (Address::Bitcoin(a.address.clone()), a.swap_id))
+            .collect(),
+        BusMsg::Info(InfoMsg::MoneroAddressList(addresses)) => addresses
+            .iter()
+            .map(|a| (Address::Monero(a.address), a.swap_id))
+            .collect(),
+        _ => return Err(Error::Farcaster("Received unexpected response".to_string())),
+    };
+    runtime.request_info(ServiceId::Farcasterd, InfoMsg::NeedsFunding(blockchain))?;
+    let expected_amounts: HashMap<String, u64> = match runtime.report_failure()? {
+        BusMsg::Info(InfoMsg::FundingInfos(funding_infos)) => funding_infos
+            .swaps_need_funding
+            .iter()
+            .map(|info| match info {
+                FundingInfo::Bitcoin(info) => (info.address.to_string(), info.amount.as_sat()),
+                FundingInfo::Monero(info) => (info.address.to_string(), info.amount.as_pico()),
+            })
+            .collect(),
+        _ => return Err(Error::Farcaster("Received unexpected response".to_string())),
+    };
+    let balances = query_balances(
+        &runtime.config(),
+        addresses
+            .iter()
+            .map(|(address, _)| address.clone())
+            .collect(),
+    );
+    Ok(addresses
+        .into_iter()
+        .zip(balances)
+        .map(|((address, swap_id), current_balance)| {
+            let address = address.to_string();
+            let expected_amount = expected_amounts.get(&address).copied();
+            FundingAddressWithBalance {
+                address,
+                blockchain,
+                swap_id,
+                expected_amount,
+                current_balance,
+                status: current_balance.map(|balance| FundingStatus::new(balance, expected_amount)),
+            }
+        })
+        .collect())
+}
+
+fn print_funding_addresses_with_balance(
+    json_output: bool,
+    addresses: &[FundingAddressWithBalance],
+) {
+    if json_output {
+        println!(
+            "{}",
+            serde_json::to_string(addresses).expect("funding addresses serialize to json")
+        );
+        return;
+    }
+    let format_amount = |blockchain: Blockchain, amount: Option<u64>| match (blockchain, amount) {
+        (Blockchain::Bitcoin, Some(sat)) => bitcoin::Amount::from_sat(sat).to_string(),
+        (Blockchain::Monero, Some(pico)) => monero::Amount::from_pico(pico).to_string(),
+        (_, None) => "--".to_string(),
+    };
+    println!(
+        "{:<96} {:<10} {:>20} {:>20} {:<16}",
+        "ADDRESS", "BLOCKCHAIN", "EXPECTED AMOUNT", "CURRENT BALANCE", "STATUS"
+    );
+    for address in addresses {
+        println!(
+            "{:<96} {:<10} {:>20} {:>20} {:<16}",
+            address.address,
+            address.blockchain,
+            format_amount(address.blockchain, address.expected_amount),
+            format_amount(address.blockchain, address.current_balance),
+            address
+                .status
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "--".to_string())
+        );
+    }
+}
+
+const STATS_TOP_ABORT_REASONS: usize = 5;
+
+/// Aggregate statistics over the deal history
+#[derive(Default)]
+struct SwapStats {
+    role: Option<SwapRole>,
+    started: usize,
+    in_progress: usize,
+    completed: usize,
+    refunded: usize,
+    punished: usize,
+    aborted: usize,
+    btc_sent: bitcoin::Amount,
+    btc_received: bitcoin::Amount,
+    xmr_sent: monero::Amount,
+    xmr_received: monero::Amount,
+    abort_reasons: Vec<(String, usize)>,
+}
+
+/// Keeps the deals where we play the swap role of the filter, on the given network if any
+fn filter_deals(
+    deals: &List<DealInfo>,
+    role: SwapRoleFilter,
+    network: Option<Network>,
+) -> List<DealInfo> {
+    deals
+        .iter()
+        .filter(|deal_info| {
+            role.matches(deal_info.local_swap_role)
+                && network.map_or(true, |network| deal_info.deal.parameters.network == network)
+        })
+        .cloned()
+        .collect()
+}
+
+impl SwapStats {
+    fn from_deals<'a>(deals: impl Iterator<Item = &'a DealInfo>, role: Option<SwapRole>) -> Self {
+        let mut stats = SwapStats {
+            role,
+            ..Default::default()
+        };
+        let mut abort_reasons: HashMap<String, usize> = HashMap::new();
+        for deal_info in deals {
+            let swap_role = deal_info.deal.swap_role(&deal_info.local_trade_role);
+            if role.map_or(false, |role| role != swap_role) {
+                continue;
+            }
+            let outcome = match &deal_info.status {
+                DealStatus::Open | DealStatus::Revoked => continue,
+                DealStatus::InProgress => {
+                    stats.started += 1;
+                    stats.in_progress += 1;
+                    continue;
+                }
+                DealStatus::Ended(outcome) => outcome,
+            };
+            stats.started += 1;
+            match outcome {
+                Outcome::SuccessSwap => {
+                    stats.completed += 1;
+                    let parameters = &deal_info.deal.parameters;
+                    // Bob sells the arbitrating asset, Alice the accordant asset
+                    match swap_role {
+                        SwapRole::Bob => {
+                            stats.btc_sent += parameters.arbitrating_amount;
+                            stats.xmr_received += parameters.accordant_amount;
+                        }
+                        SwapRole::Alice => {
+                            stats.btc_received += parameters.arbitrating_amount;
+                            stats.xmr_sent += parameters.accordant_amount;
+                        }
+                    }
+                }
+                Outcome::FailureRefund => stats.refunded += 1,
+                Outcome::FailurePunish => stats.punished += 1,
+                Outcome::FailureAbort => {
+                    stats.aborted += 1;
+                    if let Some(reason) = &deal_info.failure_reason {
+                        *abort_reasons.entry(reason.clone()).or_default() += 1;
+                    }
+                }
+            }
+        }
+        stats.abort_reasons = abort_reasons.into_iter().collect();
+        stats
+            .abort_reasons
+            .sort_by(|(a_reason, a), (b_reason, b)| b.cmp(a).then(a_reason.cmp(b_reason)));
+        stats.abort_reasons.truncate(STATS_TOP_ABORT_REASONS);
+        stats
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "role": self.role.map(|role| role.to_string()),
+            "swaps": {
+                "started": self.started,
+                "in_progress": self.in_progress,
+                "completed": self.completed,
+                "refunded": self.refunded,
+                "punished": self.punished,
+                "aborted": self.aborted,
+            },
+            "volume": {
+                "btc_sent_sat": self.btc_sent.as_sat(),
+                "btc_received_sat": self.btc_received.as_sat(),
+                "xmr_sent_piconero": self.xmr_sent.as_pico(),
+                "xmr_received_piconero": self.xmr_received.as_pico(),
+            },
+            "top_abort_reasons": self
+                .abort_reasons
+                .iter()
+                .map(|(reason, count)| serde_json::json!({ "reason": reason, "count": count }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn print_table(&self) {
+        if let Some(role) = self.role {
+            println!("Swaps as {}", role);
+        }
+        for (label, value) in [
+            ("Started", self.started.to_string()),
+            ("In progress", self.in_progress.to_string()),
+            ("Completed", self.completed.to_string()),
+            ("Refunded", self.refunded.to_string()),
+            ("Punished", self.punished.to_string()),
+            ("Aborted", self.aborted.to_string()),
+            ("BTC sent", self.btc_sent.to_string()),
+            ("BTC received", self.btc_received.to_string()),
+            ("XMR sent", self.xmr_sent.to_string()),
+            ("XMR received", self.xmr_received.to_string()),
+        ] {
+            println!("{:<14} {:>20}", label, value);
+        }
+        if !self.abort_reasons.is_empty() {
+            println!("\n{:<50} {:>6}", "ABORT REASON", "COUNT");
+            for (reason, count) in &self.abort_reasons {
+                println!("{:<50} {:>6}", reason, count);
+            }
+        }
+    }
+}
+
+/// Requests a Bitcoin transaction from the syncer and waits for the matching retrieval event
+fn retrieve_bitcoin_tx(
+    runtime: &mut Client,
+    syncer: &ServiceId,
+    id: TaskId,
+    txid: bitcoin::Txid,
+) -> Result<Option<bitcoin::Transaction>, Error> {
+    runtime.request_sync(
+        syncer.clone(),
+        SyncMsg::Task(Task::GetTx(GetTx {
+            id,
+            hash: Txid::Bitcoin(txid),
+        })),
+    )?;
+    loop {
+        if let BusMsg::Sync(SyncMsg::Event(Event::TransactionRetrieved(retrieved))) =
+            runtime.report_failure()?
+        {
+            if retrieved.id == id {
+                break Ok(retrieved.tx);
+            }
+        }
+    }
+}
+
+/// Prints the inputs and outputs of a Bitcoin transaction, with its fee when the outputs spent
+/// by all its inputs are known
+fn print_bitcoin_tx(
+    tx: &bitcoin::Transaction,
+    spent_outputs: &[Option<bitcoin::TxOut>],
+    network: Network,
+) {
+    println!("\n Txid:     {}", tx.txid());
+    println!(" Version:  {}", tx.version);
+    println!(" Locktime: {}", tx.lock_time);
+    println!(" Size:     {} vB", tx.vsize());
+    println!("\n Inputs:");
+    for (index, (input, spent)) in tx.input.iter().zip(spent_outputs).enumerate() {
+        let amount = spent.as_ref().map_or_else(
+            || "unknown amount".to_string(),
+            |spent| bitcoin::Amount::from_sat(spent.value).to_string(),
+        );
+        println!(
+            "  {:>3}: {} (sequence {:#x}, {} witness items) {}",
+            index,
+            input.previous_output,
+            input.sequence,
+            input.witness.len(),
+            amount
+        );
+    }
+    println!("\n Outputs:");
+    for (index, output) in tx.output.iter().enumerate() {
+        let destination = bitcoin::Address::from_script(&output.script_pubkey, network.into())
+            .map_or_else(|| output.script_pubkey.to_string(), |addr| addr.to_string());
+        println!(
+            "  {:>3}: {} {}",
+            index,
+            destination,
+            bitcoin::Amount::from_sat(output.value)
+        );
+    }
+    let input_value: Option<u64> = spent_outputs
+        .iter()
+        .map(|spent| spent.as_ref().map(|spent| spent.value))
+        .sum();
+    let output_value: u64 = tx.output.iter().map(|output| output.value).sum();
+    match input_value.and_then(|input_value| input_value.checked_sub(output_value)) {
+        Some(fee) => println!(
+            "\n Fee:      {} ({:.1} sat/vB)",
+            bitcoin::Amount::from_sat(fee),
+            fee as f64 / tx.vsize() as f64
+        ),
+        None => println!("\n Fee:      unknown, the spent outputs could not be retrieved"),
+    }
+}
+
+fn print_task_table(tasks: &[TaskInfo]) {
+    println!(
+        "{:<14} {:<22} {:<10} {:>10} {:>10}",
+        "ID", "KIND", "BLOCKCHAIN", "CREATED", "EXPIRES"
+    );
+    for task in tasks {
+        println!(
+            "{:<14} {:<22} {:<10} {:>10} {:>10}",
+            task.id.to_string(),
+            task.kind.to_string(),
+            task.blockchain.to_string(),
+            task.created_at_height,
+            if task.expires_at_height == u64::MAX {
+                "never".to_string()
+            } else {
+                task.expires_at_height.to_string()
+            }
+        );
+    }
+}
+
+/// Parses the JSON array of encoded deals of a deal file, with their entry number. The deals
+/// failing validation come with the reason they are skipped.
+fn parse_deals_file(
+    config: &Config,
+    content: &str,
+    bitcoin_address: &bitcoin::Address,
+    monero_address: monero::Address,
+    without_validation: bool,
+) -> Result<Vec<(usize, Result<Deal, String>)>, serde_json::Error> {
+    let encoded_deals: Vec<String> = serde_json::from_str(content)?;
+    Ok(encoded_deals
+        .iter()
+        .enumerate()
+        .map(|(index, encoded_deal)| {
+            (
+                index + 1,
+                validate_deal_entry(
+                    config,
+                    encoded_deal.trim(),
+                    bitcoin_address,
+                    monero_address,
+                    without_validation,
+                ),
+            )
+        })
+        .collect())
+}
+
+/// Takes the valid deals of the entries, at most max_deals, and reports the status of every
+/// entry. Deals are taken one after the other, the next once the swap of the previous one is
+/// funded, unless max_concurrent is set.
+fn take_deals(
+    runtime: &mut Client,
+    entries: Vec<(usize, Result<Deal, String>)>,
+    bitcoin_address: bitcoin::Address,
+    monero_address: monero::Address,
+    max_deals: usize,
+    max_concurrent: Option<usize>,
+) {
+    let mut statuses = vec![];
+    let mut deals = vec![];
+    for (entry, deal) in entries {
+        match deal {
+            Ok(deal) if deals.len() < max_deals => deals.push((entry, deal)),
+            Ok(deal) => statuses.push((
+                entry,
+                Some(deal),
+                Err("Maximum number of deals reached".to_string()),
+            )),
+            Err(err) => statuses.push((entry, None, Err(err))),
+        }
+    }
+    let results: Vec<Result<(), String>> = match max_concurrent {
+        Some(max_concurrent) => submit_deals(
+            &runtime.config(),
+            deals.iter().map(|(_, deal)| deal.clone()).collect(),
+            &bitcoin_address,
+            monero_address,
+            max_concurrent,
+        ),
+        None => {
+            let mut results = Vec::with_capacity(deals.len());
+            for (index, (_, deal)) in deals.iter().enumerate() {
+                let result = submit_deal(
+                    runtime,
+                    deal.clone(),
+                    bitcoin_address.clone(),
+                    monero_address,
+                )
+                .map_err(|err| err.to_string());
+                let taken = result.is_ok();
+                results.push(result);
+                if taken && index + 1 < deals.len() {
+                    // stop taking deals if the swap does not progress
+                    if let Err(err) = wait_swap_funded(runtime, deal) {
+                        results.resize(deals.len(), Err(err.to_string()));
+                        break;
+                    }
+                }
+            }
+            results
+        }
+    };
+    statuses.extend(
+        deals
+            .into_iter()
+            .zip(results)
+            .map(|((entry, deal), result)| (entry, Some(deal), result)),
+    );
+    statuses.sort_by_key(|(entry, ..)| *entry);
+    print_take_statuses(runtime, &statuses);
+}
+
+/// Parses the deals of a batch file, one per line, with their line number. Blank lines are
+/// ignored and the deals failing validation come with the reason they are skipped.
+fn parse_deal_lines(
+    config: &Config,
+    content: &str,
+    bitcoin_address: &bitcoin::Address,
+    monero_address: monero::Address,
+    without_validation: bool,
+) -> Vec<(usize, Result<Deal, String>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            (
+                index + 1,
+                validate_deal_entry(
+                    config,
+                    line.trim(),
+                    bitcoin_address,
+                    monero_address,
+                    without_validation,
+                ),
+            )
+        })
+        .collect()
+}
+
+fn validate_deal_entry(
+    config: &Config,
+    encoded_deal: &str,
+    bitcoin_address: &bitcoin::Address,
+    monero_address: monero::Address,
+    without_validation: bool,
+) -> Result<Deal, String> {
+    let deal = Deal::from_str(encoded_deal).map_err(|err| format!("Invalid deal: {}", err))?;
+    if without_validation {
+        return Ok(deal);
+    }
+    deal.pre_validate(config, bitcoin_address, &monero_address, None)
+        .map_err(|err| err.to_string())?;
+    Ok(deal)
+}
+
+fn submit_deal(
+    runtime: &mut Client,
+    deal: Deal,
+    bitcoin_address: bitcoin::Address,
+    monero_address: monero::Address,
+) -> Result<(), Error> {
+    runtime.request_ctl(
+        ServiceId::Farcasterd,
+        CtlMsg::TakeDeal(ctl::PubDeal {
+            deal,
+            bitcoin_address,
+            monero_address,
+            expiry: None,
+        }),
+    )?;
+    runtime.report_failure().map(|_| ())
+}
+
+// Submit the deals at most max_concurrent at a time, each with its own client, the results are
+// in the order of the deals
+fn submit_deals(
+    config: &ServiceConfig,
+    deals: Vec<Deal>,
+    bitcoin_address: &bitcoin::Address,
+    monero_address: monero::Address,
+    max_concurrent: usize,
+) -> Vec<Result<(), String>> {
+    let mut results = Vec::with_capacity(deals.len());
+    for chunk in deals.chunks(max_concurrent.max(1)) {
+        let handles: Vec<_> = chunk
+            .iter()
+            .cloned()
+            .map(|deal| {
+                let config = config.clone();
+                let bitcoin_address = bitcoin_address.clone();
+                thread::spawn(move || {
+                    Client::with(config)
+                        .and_then(|mut runtime| {
+                            submit_deal(&mut runtime, deal, bitcoin_address, monero_address)
+                        })
+                        .map_err(|err| err.to_string())
+                })
+            })
+            .collect();
+        results.extend(handles.into_iter().map(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err("Deal submission panicked".to_string()))
+        }));
+    }
+    results
+}
+
+fn print_take_statuses(runtime: &Client, statuses: &[(usize, Option<Deal>, Result<(), String>)]) {
+    if runtime.json_output() {
+        let rows: Vec<serde_json::Value> = statuses
+            .iter()
+            .map(|(entry, deal, result)| {
+                serde_json::json!({
+                    "entry": entry,
+                    "deal_id": deal.as_ref().map(|deal| deal.id().to_string()),
+                    "taken": result.is_ok(),
+                    "error": result.as_ref().err(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(rows));
+        return;
+    }
+    println!("{:<6} {:<38} {:<8} {}", "ENTRY", "DEAL", "STATUS", "ERROR");
+    for (entry, deal, result) in statuses {
+        println!(
+            "{:<6} {:<38} {:<8} {}",
+            entry,
+            deal.as_ref()
+                .map(|deal| deal.id().to_string())
+                .unwrap_or_else(|| "--".to_string()),
+            if result.is_ok() { "taken" } else { "failed" },
+            result.as_ref().err().map(String::as_str).unwrap_or("")
+        );
+    }
+    let taken = statuses
+        .iter()
+        .filter(|(.., result)| result.is_ok())
+        .count();
+    println!("Took {} of {} deal(s)", taken, statuses.len());
+}
+
+// Poll the swap progress until the local funding is done or the swap ended
+fn wait_swap_funded(runtime: &mut Client, deal: &Deal) -> Result<(), Error> {
+    let swap_id: SwapId = deal.id().into();
+    let taker_role = deal.parameters.maker_role.other();
+    let mut failed_attempts = 0;
+    loop {
+        std::thread::sleep(TAKE_FROM_FILE_POLL_INTERVAL);
+        runtime.request_info(ServiceId::Farcasterd, InfoMsg::ReadProgress(swap_id))?;
+        match runtime.report_failure() {
+            Ok(BusMsg::Info(InfoMsg::SwapProgress(SwapProgress { progress, .. }))) => {
+                failed_attempts = 0;
+                let past_funding = progress.iter().any(|event| match event {
+                    ProgressEvent::StateUpdate(report) => match taker_role {
+                        SwapRole::Alice => report.acc_locked,
+                        SwapRole::Bob => report.arb_locked,
+                    },
+                    ProgressEvent::Success(_) | ProgressEvent::Failure(_) => true,
+                    _ => false,
+                });
+                if past_funding {
+                    return Ok(());
+                }
+            }
+            // the swap may not be launched yet
+            _ if failed_attempts < TAKE_FROM_FILE_MAX_FAILED_POLLS => failed_attempts += 1,
+            _ => {
+                return Err(Error::Farcaster(format!(
+                    "Swap {} did not start, stop taking deals",
+                    swap_id
+                )));
+            }
+        }
+    }
+}
+
 fn take_deal() -> bool {
     println!("Deal or No Deal? [y/n]");
     let mut input = [0u8; 1];
@@ -515,6 +2360,262 @@ fn take_deal() -> bool {
     }
 }
 
+// Ask the user to type the amount received in the swap and compare it with the deal, allowing
+// one atomic unit of difference
+fn confirm_deal_amounts(deal_parameters: &DealParameters) -> bool {
+    let btc = deal_parameters.arbitrating_amount;
+    let xmr = deal_parameters.accordant_amount;
+    let rate = xmr.as_xmr() / btc.as_btc();
+    let prompt = |asset: &str| -> Option<String> {
+        println!("Type the {} amount you will receive to confirm: ", asset);
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).ok()?;
+        Some(input.trim().trim_end_matches(asset).trim().to_string())
+    };
+    match deal_parameters.maker_role.other() {
+        SwapRole::Bob => {
+            println!(
+                "Selling: {} BTC | Receiving: {} XMR | Rate: {} XMR/BTC",
+                btc.as_btc(),
+                xmr.as_xmr(),
+                rate
+            );
+            prompt("XMR")
+                .and_then(|input| {
+                    monero::Amount::from_str_in(&input, monero::Denomination::Monero).ok()
+                })
+                .map_or(false, |typed| {
+                    typed.as_pico().max(xmr.as_pico()) - typed.as_pico().min(xmr.as_pico()) <= 1
+                })
+        }
+        SwapRole::Alice => {
+            println!(
+                "Selling: {} XMR | Receiving: {} BTC | Rate: {} XMR/BTC",
+                xmr.as_xmr(),
+                btc.as_btc(),
+                rate
+            );
+            prompt("BTC")
+                .and_then(|input| {
+                    bitcoin::Amount::from_str_in(&input, bitcoin::Denomination::Bitcoin).ok()
+                })
+                .map_or(false, |typed| {
+                    typed.as_sat().max(btc.as_sat()) - typed.as_sat().min(btc.as_sat()) <= 1
+                })
+        }
+    }
+}
+
+// Warn that a forced abort only cleans up the local state and ask the user to type yes
+fn confirm_force_abort(swap_id: SwapId) -> bool {
+    println!(
+        "{}",
+        "Warning: force aborting only cleans up the local state of the swap. It does not \
+         protect you if the counterparty comes back online, and funds already sent to the \
+         funding address are not swept back, use sweep-bitcoin-address to recover them."
+            .err()
+    );
+    println!("Type yes to force abort swap {}: ", swap_id);
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .map_or(false, |_| input.trim() == "yes")
+}
+
+fn confirm_revoke_all_deals(deals: &[Deal]) -> bool {
+    for deal in deals {
+        println!(
+            "{}  {} for {}",
+            deal.id(),
+            deal.parameters.arbitrating_amount,
+            deal.parameters.accordant_amount
+        );
+    }
+    println!("Type yes to revoke these {} deal(s): ", deals.len());
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .map_or(false, |_| input.trim() == "yes")
+}
+
+// Suggest the monero amount of a deal from the exchange rate and let the user accept, adjust or
+// refuse it, returns None if refused
+fn suggest_accordant_amount(
+    btc: bitcoin::Amount,
+    xmr_per_btc: monero::Amount,
+) -> Option<monero::Amount> {
+    let pico = btc.as_sat() as u128 * xmr_per_btc.as_pico() as u128
+        / bitcoin::Amount::ONE_BTC.as_sat() as u128;
+    let suggested = monero::Amount::from_pico(u64::try_from(pico).ok()?);
+    println!(
+        "Suggested amounts: {} BTC / {} XMR (rate: {} XMR/BTC)",
+        btc.as_btc(),
+        suggested.as_xmr(),
+        xmr_per_btc.as_xmr()
+    );
+    loop {
+        println!("Accept [y], refuse [n] or type the XMR amount to use instead: ");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).ok()?;
+        match input.trim() {
+            "y" | "Y" | "yes" | "" => return Some(suggested),
+            "n" | "N" | "no" => return None,
+            amount => {
+                match monero::Amount::from_str_in(
+                    amount.trim_end_matches("XMR").trim(),
+                    monero::Denomination::Monero,
+                ) {
+                    Ok(amount) => return Some(amount),
+                    Err(err) => println!("Invalid amount {}: {}", amount, err),
+                }
+            }
+        }
+    }
+}
+
+// Compare the deal rate with the market rate from the taker point of view: Bob buys monero and
+// wants more XMR per BTC, Alice buys bitcoin and wants less
+fn deal_market_comparison(deal_parameters: &DealParameters, market_rate: f64) -> String {
+    let deal_rate =
+        deal_parameters.accordant_amount.as_xmr() / deal_parameters.arbitrating_amount.as_btc();
+    let diff = (deal_rate - market_rate) / market_rate * 100.0;
+    let advantage = match deal_parameters.maker_role.other() {
+        SwapRole::Bob => diff,
+        SwapRole::Alice => -diff,
+    };
+    let msg = format!(
+        "Deal rate: {} XMR/BTC ({:.2}% {} market)",
+        deal_rate,
+        diff.abs(),
+        if diff >= 0.0 { "above" } else { "below" }
+    );
+    if advantage >= 0.0 {
+        msg.bright_green_bold().to_string()
+    } else if advantage >= -5.0 {
+        msg.bright_yellow_bold().to_string()
+    } else {
+        msg.red_bold().to_string()
+    }
+}
+
+fn bitcoin_blocks_duration(blocks: u32) -> String {
+    format_duration(blocks as u64 * BITCOIN_BLOCK_TIME_SECS)
+}
+
+fn format_duration(secs: u64) -> String {
+    let minutes = secs / 60;
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
+
+/// Prints the amounts, counterparty, fee strategy and timelocks of a deal
+fn print_deal_summary(deal: &Deal) {
+    let parameters = &deal.parameters;
+    println!("\n Trading {}\n", deal_buy_information(parameters));
+    println!(" Maker:           {}@{}", deal.node_id, deal.peer_address);
+    println!(" Maker role:      {}", parameters.maker_role);
+    println!(" Network:         {}", parameters.network);
+    println!(" Fee strategy:    {}", parameters.fee_strategy);
+    for (name, timelock) in [
+        ("Cancel timelock", parameters.cancel_timelock.as_u32()),
+        ("Punish timelock", parameters.punish_timelock.as_u32()),
+    ] {
+        println!(
+            " {}: {} blocks (~{})",
+            name,
+            timelock,
+            bitcoin_blocks_duration(timelock)
+        );
+    }
+}
+
+/// Start this command again in a detached process following the progress, in place of
+/// `--watch-only`, and return the PID of that process
+fn spawn_progress_watcher() -> Result<u32, Error> {
+    let args = std::env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--watch-only");
+    let watcher = process::Command::new(std::env::current_exe()?)
+        .args(args)
+        .arg("--follow")
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .spawn()
+        .map_err(|err| {
+            Error::Farcaster(format!("Failed to start the progress watcher: {}", err))
+        })?;
+    Ok(watcher.id())
+}
+
+/// Append the swap summaries received from the progress subscription to a file, one JSON object
+/// per line, until the swap ends
+fn log_progress(runtime: &mut Client, path: &Path) -> Result<(), Error> {
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| Error::Farcaster(format!("Failed to open {}: {}", path.display(), err)))?;
+    loop {
+        match runtime.report_failure()? {
+            BusMsg::Ctl(CtlMsg::Success(_)) => break Ok(()),
+            BusMsg::Info(InfoMsg::Progress(Progress::Summary(summary))) => {
+                let line = serde_json::to_string(&summary)
+                    .map_err(|err| Error::Farcaster(err.to_string()))?;
+                writeln!(log, "{}", line)?;
+                log.flush()?;
+                if summary.is_terminal() {
+                    break Ok(());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads an encoded deal from a file or downloads it from an HTTP(S) URL
+fn read_deal_source(source: &DealSource) -> Result<String, Error> {
+    match source {
+        DealSource::File(path) => std::fs::read_to_string(path).map_err(|err| {
+            Error::Farcaster(format!(
+                "Failed to read the deal from {}: {}",
+                path.display(),
+                err
+            ))
+        }),
+        DealSource::Url(url) => tokio::runtime::Runtime::new()?
+            .block_on(async {
+                reqwest::get(url.clone())
+                    .await?
+                    .error_for_status()?
+                    .text()
+                    .await
+            })
+            .map_err(|err| Error::Farcaster(format!("Failed to download the deal: {}", err))),
+    }
+}
+
+/// Temporal safety of a deal checked against the swap configuration of its network
+fn deal_temporal_safety(deal: &Deal, config: &Config) -> Result<TemporalSafety, Error> {
+    let parameters = &deal.parameters;
+    let arb: ArbitratingBlockchain = parameters
+        .arbitrating_blockchain
+        .try_into()
+        .map_err(|err: consensus::Error| Error::Farcaster(err.to_string()))?;
+    let acc: AccordantBlockchain = parameters
+        .accordant_blockchain
+        .try_into()
+        .map_err(|err: consensus::Error| Error::Farcaster(err.to_string()))?;
+    let swap_config = config
+        .get_swap_config(arb, acc, parameters.network)
+        .map_err(|err| Error::Farcaster(err.to_string()))?;
+    TemporalSafety::from_deal(
+        deal,
+        swap_config.arbitrating.finality.into(),
+        swap_config.accordant.finality.into(),
+        swap_config.arbitrating.safety.into(),
+    )
+}
+
 fn deal_buy_information(deal_parameters: &DealParameters) -> String {
     match deal_parameters.maker_role.other() {
         SwapRole::Alice => format!(
@@ -531,3 +2632,133 @@ fn deal_buy_information(deal_parameters: &DealParameters) -> String {
         ),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use farcaster_core::role::TradeRole;
+
+    fn deal_info(local_swap_role: SwapRole, network: Network) -> DealInfo {
+        let mut deal = fixtures::deal();
+        deal.parameters.maker_role = local_swap_role;
+        deal.parameters.network = network;
+        DealInfo {
+            serialized_deal: deal.to_string(),
+            status: DealStatus::Open,
+            local_trade_role: TradeRole::Maker,
+            local_swap_role,
+            failure_reason: None,
+            expires_at_block: None,
+            expires_in: None,
+            deal,
+        }
+    }
+
+    #[test]
+    fn filter_deals_by_role_and_network() {
+        let deals: List<DealInfo> = vec![
+            deal_info(SwapRole::Alice, Network::Testnet),
+            deal_info(SwapRole::Bob, Network::Testnet),
+            deal_info(SwapRole::Alice, Network::Mainnet),
+            deal_info(SwapRole::Bob, Network::Local),
+        ]
+        .into_iter()
+        .collect();
+        let filtered = |role, network| -> Vec<(SwapRole, Network)> {
+            filter_deals(&deals, role, network)
+                .iter()
+                .map(|d| (d.local_swap_role, d.deal.parameters.network))
+                .collect()
+        };
+
+        assert_eq!(filtered(SwapRoleFilter::All, None).len(), 4);
+        assert_eq!(
+            filtered(SwapRoleFilter::Alice, None),
+            vec![
+                (SwapRole::Alice, Network::Testnet),
+                (SwapRole::Alice, Network::Mainnet)
+            ]
+        );
+        assert_eq!(
+            filtered(SwapRoleFilter::Bob, None),
+            vec![
+                (SwapRole::Bob, Network::Testnet),
+                (SwapRole::Bob, Network::Local)
+            ]
+        );
+        assert_eq!(
+            filtered(SwapRoleFilter::All, Some(Network::Testnet)),
+            vec![
+                (SwapRole::Alice, Network::Testnet),
+                (SwapRole::Bob, Network::Testnet)
+            ]
+        );
+        assert_eq!(
+            filtered(SwapRoleFilter::Alice, Some(Network::Mainnet)),
+            vec![(SwapRole::Alice, Network::Mainnet)]
+        );
+        assert_eq!(
+            filtered(SwapRoleFilter::Bob, Some(Network::Mainnet)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn parse_deals_file_reports_invalid_entries() {
+        let encoded_deal = fixtures::DEAL;
+        let content = serde_json::json!([encoded_deal, "Deal:invalid", encoded_deal]).to_string();
+        let bitcoin_address =
+            bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let monero_address = monero::Address::from_str("43qHP7gSJJf8HZw1G3ZmpWVyYnbxkKdfta34Qj2nuRENjAsXBtj9JcMWcYMeT3n4NyTZqxhUkKgsTS6P2TNgM6ksM32czSp").unwrap();
+        let deals = parse_deals_file(
+            &Config::default(),
+            &content,
+            &bitcoin_address,
+            monero_address,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            deals.iter().map(|(entry, _)| *entry).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(deals[0].1.as_ref().unwrap(), &fixtures::deal());
+        assert!(deals[1].1.is_err());
+        assert!(deals[2].1.is_ok());
+        assert!(parse_deals_file(
+            &Config::default(),
+            encoded_deal,
+            &bitcoin_address,
+            monero_address,
+            true
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_deal_lines_skips_blank_lines() {
+        let content = format!(
+            "{}\n\nDeal:invalid\n  {}  \n",
+            fixtures::DEAL,
+            fixtures::DEAL
+        );
+        let bitcoin_address =
+            bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let monero_address = monero::Address::from_str("43qHP7gSJJf8HZw1G3ZmpWVyYnbxkKdfta34Qj2nuRENjAsXBtj9JcMWcYMeT3n4NyTZqxhUkKgsTS6P2TNgM6ksM32czSp").unwrap();
+        let deals = parse_deal_lines(
+            &Config::default(),
+            &content,
+            &bitcoin_address,
+            monero_address,
+            true,
+        );
+        assert_eq!(
+            deals.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+        assert_eq!(deals[0].1.as_ref().unwrap(), &fixtures::deal());
+        assert!(deals[1].1.is_err());
+        assert!(deals[2].1.is_ok());
+    }
+}
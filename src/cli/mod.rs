@@ -4,7 +4,11 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+mod audit;
 mod command;
+mod monitor;
 mod opts;
+mod qr;
 
-pub use opts::{Command, DealSelector, Opts};
+pub use audit::CommandAuditLog;
+pub use opts::{Command, DealSelector, Opts, OutputFormat};
@@ -0,0 +1,359 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::fmt::Display;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use farcaster_core::role::SwapRole;
+use farcaster_core::swap::SwapId;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Spans;
+use ratatui::widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, TableState};
+use ratatui::{Frame, Terminal};
+
+use crate::bus::info::{InfoMsg, ProgressEvent, SwapInfo, SwapProgress};
+use crate::bus::BusMsg;
+use crate::client::Client;
+use crate::swapd::StateReport;
+use crate::{Error, ServiceId};
+
+/// Delay between two polls of the daemon
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Number of characters of the swap id displayed in the table
+const SWAP_ID_DISPLAY_LEN: usize = 8;
+
+const COLUMNS: [&str; 8] = [
+    "Swap",
+    "Role",
+    "State",
+    "BTC confs",
+    "XMR confs",
+    "Until cancel",
+    "Until punish",
+    "Funding",
+];
+
+/// Row of the dashboard, one per running swap
+#[derive(Clone, Debug)]
+struct SwapRow {
+    swap_id: SwapId,
+    role: SwapRole,
+    report: StateReport,
+    funding: &'static str,
+    progress: Vec<ProgressEvent>,
+}
+
+impl SwapRow {
+    fn new(info: SwapInfo, progress: Option<SwapProgress>) -> Self {
+        let awaiting_funding = progress
+            .as_ref()
+            .and_then(|progress| progress.summary.as_ref())
+            .map_or(false, |summary| summary.awaiting_funding);
+        SwapRow {
+            swap_id: info.swap_id,
+            role: info.local_swap_role,
+            funding: funding_status(info.local_swap_role, &info.state, awaiting_funding),
+            report: info.state,
+            progress: progress
+                .map(|progress| progress.progress)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.swap_id
+                .to_string()
+                .chars()
+                .take(SWAP_ID_DISPLAY_LEN)
+                .collect(),
+            self.role.to_string(),
+            self.report.state.clone(),
+            or_dash(self.report.arb_lock_confirmations),
+            or_dash(self.report.acc_lock_confirmations),
+            or_dash(self.report.blocks_until_cancel_possible),
+            or_dash(self.report.blocks_until_punish_possible),
+            self.funding.to_string(),
+        ]
+    }
+}
+
+/// Funding state of the lock we are responsible for: Bob funds the Bitcoin lock and Alice the
+/// Monero lock
+fn funding_status(role: SwapRole, report: &StateReport, awaiting_funding: bool) -> &'static str {
+    let locked = match role {
+        SwapRole::Alice => report.acc_locked,
+        SwapRole::Bob => report.arb_locked,
+    };
+    if locked {
+        "funded"
+    } else if awaiting_funding {
+        "awaiting"
+    } else {
+        "-"
+    }
+}
+
+fn or_dash<T: Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// One line per progress event, state updates are already displayed in the table
+fn progress_line(event: &ProgressEvent) -> Option<String> {
+    match event {
+        ProgressEvent::Message(msg) => Some(msg.clone()),
+        ProgressEvent::StateUpdate(_) => None,
+        ProgressEvent::StateTransition(transition) => Some(format!(
+            "{} -> {}",
+            transition.old_state.state, transition.new_state.state
+        )),
+        ProgressEvent::Success(details) => Some(format!("Success: {}", details)),
+        ProgressEvent::Failure(failure) => Some(format!("Failure: {}", failure)),
+    }
+}
+
+#[derive(Default)]
+struct Monitor {
+    rows: Vec<SwapRow>,
+    table: TableState,
+    /// Swap whose progress is displayed in the detail pane
+    followed: Option<SwapId>,
+    /// Error of the last poll of the daemon
+    error: Option<String>,
+}
+
+impl Monitor {
+    /// Polls the daemon for the running swaps, the swaps ending while polling are skipped
+    fn refresh(&mut self, runtime: &mut Client) {
+        match fetch_rows(runtime) {
+            Ok(rows) => {
+                self.rows = rows;
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+        let selected = match self.table.selected() {
+            _ if self.rows.is_empty() => None,
+            Some(selected) => Some(selected.min(self.rows.len() - 1)),
+            None => Some(0),
+        };
+        self.table.select(selected);
+    }
+
+    fn select_next(&mut self) {
+        if let Some(selected) = self.table.selected() {
+            self.table
+                .select(Some((selected + 1).min(self.rows.len().saturating_sub(1))));
+        }
+    }
+
+    fn select_previous(&mut self) {
+        if let Some(selected) = self.table.selected() {
+            self.table.select(Some(selected.saturating_sub(1)));
+        }
+    }
+
+    fn follow_selected(&mut self) {
+        self.followed = self
+            .table
+            .selected()
+            .and_then(|selected| self.rows.get(selected))
+            .map(|row| row.swap_id);
+    }
+
+    fn run<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        runtime: &mut Client,
+    ) -> Result<(), Error> {
+        let mut last_refresh: Option<Instant> = None;
+        loop {
+            if last_refresh.map_or(true, |at| at.elapsed() >= REFRESH_INTERVAL) {
+                self.refresh(runtime);
+                last_refresh = Some(Instant::now());
+            }
+            terminal.draw(|f| self.draw(f))?;
+            let timeout = last_refresh.map_or(Duration::ZERO, |at| {
+                REFRESH_INTERVAL.saturating_sub(at.elapsed())
+            });
+            if !event::poll(timeout)? {
+                continue;
+            }
+            if let TermEvent::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Char('Q') => return Ok(()),
+                    KeyCode::Down => self.select_next(),
+                    KeyCode::Up => self.select_previous(),
+                    KeyCode::Enter => self.follow_selected(),
+                    KeyCode::Esc => self.followed = None,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>) {
+        let followed = self
+            .followed
+            .and_then(|swap_id| self.rows.iter().find(|row| row.swap_id == swap_id))
+            .cloned();
+        let constraints = if followed.is_some() {
+            vec![
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+                Constraint::Length(1),
+            ]
+        } else {
+            vec![Constraint::Min(0), Constraint::Length(1)]
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(f.size());
+
+        let header = Row::new(COLUMNS.iter().map(|column| Cell::from(*column)))
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.rows.iter().map(|row| Row::new(row.cells()));
+        let table = Table::new(rows)
+            .header(header)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Running swaps ({})", self.rows.len())),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .widths(&[
+                Constraint::Length(SWAP_ID_DISPLAY_LEN as u16 + 1),
+                Constraint::Length(6),
+                Constraint::Min(20),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(13),
+                Constraint::Length(13),
+                Constraint::Length(9),
+            ]);
+        f.render_stateful_widget(table, chunks[0], &mut self.table);
+
+        if let Some(row) = &followed {
+            let items: Vec<ListItem> = row
+                .progress
+                .iter()
+                .filter_map(progress_line)
+                .map(ListItem::new)
+                .collect();
+            let details = List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Swap {} progress", row.swap_id)),
+            );
+            f.render_widget(details, chunks[1]);
+        }
+
+        let footer = match &self.error {
+            Some(err) => format!("Failed to poll the daemon: {}", err),
+            None => "Up/Down: select  Enter: follow  Esc: unfollow  Q: quit".to_string(),
+        };
+        f.render_widget(
+            Paragraph::new(Spans::from(footer)),
+            chunks[chunks.len() - 1],
+        );
+    }
+}
+
+fn fetch_rows(runtime: &mut Client) -> Result<Vec<SwapRow>, Error> {
+    runtime.request_info(ServiceId::Farcasterd, InfoMsg::ListSwaps)?;
+    let swap_ids = match runtime.report_failure()? {
+        BusMsg::Info(InfoMsg::SwapList(swap_ids)) => swap_ids,
+        _ => return Err(Error::Farcaster("Received unexpected response".to_string())),
+    };
+    let mut rows = vec![];
+    for swap_id in swap_ids.iter() {
+        runtime.request_info(ServiceId::Swap(*swap_id), InfoMsg::GetInfo)?;
+        let info = match runtime.report_failure() {
+            Ok(BusMsg::Info(InfoMsg::SwapInfo(info))) => info,
+            _ => continue,
+        };
+        runtime.request_info(ServiceId::Farcasterd, InfoMsg::ReadProgress(*swap_id))?;
+        let progress = match runtime.report_failure() {
+            Ok(BusMsg::Info(InfoMsg::SwapProgress(progress))) => Some(progress),
+            _ => None,
+        };
+        rows.push(SwapRow::new(info, progress));
+    }
+    Ok(rows)
+}
+
+/// Runs the dashboard of the running swaps until the user quits, the terminal is restored even
+/// if polling the daemon failed
+pub fn run(runtime: &mut Client) -> Result<(), Error> {
+    terminal::enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    let res = Monitor::default().run(&mut terminal, runtime);
+    terminal::disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(arb_locked: bool, acc_locked: bool) -> StateReport {
+        StateReport {
+            state: "Bob Funded".to_string(),
+            arb_block_height: 0,
+            acc_block_height: 0,
+            arb_locked,
+            acc_locked,
+            canceled: false,
+            buy_seen: false,
+            refund_seen: false,
+            overfunded: false,
+            arb_lock_confirmations: None,
+            acc_lock_confirmations: None,
+            cancel_confirmations: None,
+            blocks_until_cancel_possible: None,
+            blocks_until_punish_possible: None,
+            blocks_until_safe_buy: None,
+            blocks_until_safe_monero_buy_sweep: None,
+            final_tx_count: 0,
+            required_final_tx_count: 0,
+        }
+    }
+
+    #[test]
+    fn funding_status_follows_the_local_lock() {
+        assert_eq!(
+            funding_status(SwapRole::Bob, &report(false, false), true),
+            "awaiting"
+        );
+        assert_eq!(
+            funding_status(SwapRole::Bob, &report(true, false), false),
+            "funded"
+        );
+        assert_eq!(
+            funding_status(SwapRole::Alice, &report(true, false), false),
+            "-"
+        );
+        assert_eq!(
+            funding_status(SwapRole::Alice, &report(true, true), false),
+            "funded"
+        );
+    }
+}
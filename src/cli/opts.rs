@@ -5,9 +5,12 @@
 // https://opensource.org/licenses/MIT.
 
 use bitcoin::Address as BtcAddress;
+use clap::ValueHint;
 use clap_complete::shells::Shell;
 use monero::Address as XmrAddress;
+use reqwest::Url;
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use farcaster_core::{
@@ -19,6 +22,9 @@ use farcaster_core::{
 
 use crate::bus::info::Address;
 use crate::bus::HealthCheckSelector;
+use crate::config::{parse_config, Config};
+use crate::farcasterd::FARCASTER_CONFIG;
+use crate::Error;
 
 /// Command-line tool for working with Farcaster node
 #[derive(Parser, Clone, PartialEq, Eq, Debug)]
@@ -29,6 +35,30 @@ pub struct Opts {
     #[clap(flatten)]
     pub shared: crate::opts::Opts,
 
+    /// Append every executed command and its result to this JSON Lines file
+    #[clap(long, global = true, env = "FARCASTER_AUDIT_LOG", value_hint = ValueHint::FilePath)]
+    pub audit_log_path: Option<PathBuf>,
+
+    /// Path to the configuration file of the node, the deals are validated against its swap
+    /// limits
+    #[clap(
+        long,
+        global = true,
+        env = "FARCASTER_CONFIG",
+        default_value = FARCASTER_CONFIG,
+        value_hint = ValueHint::FilePath
+    )]
+    pub config: String,
+
+    /// Print command responses as human readable text or as JSON
+    #[clap(
+        long,
+        global = true,
+        default_value = "text",
+        possible_values = &["text", "json"]
+    )]
+    pub output_format: OutputFormat,
+
     /// Command to execute
     #[clap(subcommand)]
     pub command: Command,
@@ -37,6 +67,17 @@ pub struct Opts {
 impl Opts {
     pub fn process(&mut self) {
         self.shared.process();
+        self.shared.process_dir(&mut self.config);
+    }
+
+    /// The configuration of the node, the default configuration if the node has not written
+    /// its configuration file yet
+    pub fn node_config(&self) -> Result<Config, Error> {
+        if std::path::Path::new(&self.config).exists() {
+            parse_config(&self.config)
+        } else {
+            Ok(Config::default())
+        }
     }
 }
 
@@ -51,13 +92,36 @@ pub enum Command {
         subject: Vec<String>,
     },
 
+    /// Returns the node public key, the key of the node id advertised in deals, hex encoded
+    ExportNodePubkey,
+
     /// Lists existing peer connections
-    Peers,
+    Peers {
+        /// Display the round trip time to each peer, sorted from the slowest
+        #[clap(long)]
+        latency: bool,
+
+        /// Display the number of active and completed swaps with each peer
+        #[clap(long, conflicts_with = "latency")]
+        with_swap_count: bool,
+
+        /// Display the reputation score of each peer
+        #[clap(long, conflicts_with_all = &["latency", "with-swap-count"])]
+        with_reputation: bool,
+
+        /// Sort the peers by swaps, the most active first, or by node id; defaults to swaps
+        #[clap(long, value_name = "swaps|node_id", requires = "with-swap-count")]
+        sort_by: Option<PeerSortKey>,
+    },
 
     /// Lists running swaps
     #[clap(aliases = &["ls"])]
     ListSwaps,
 
+    /// Opens a dashboard of the running swaps refreshed every 5 seconds. Select a swap with the
+    /// arrow keys and press Enter to follow its progress, press Q to quit.
+    Monitor,
+
     /// Lists deals created by daemon
     #[clap(aliases = &["ld"])]
     ListDeals {
@@ -68,6 +132,28 @@ pub enum Command {
             possible_values = &["open", "Open", "inprogress", "in_progress", "ended", "Ended", "all", "All"],
         )]
         select: DealSelector,
+
+        /// Only list the deals where we play this swap role
+        #[clap(
+            long,
+            default_value = "all",
+            possible_values = &["alice", "Alice", "bob", "Bob", "all", "All"],
+        )]
+        maker_role: SwapRoleFilter,
+
+        /// Only list the deals on this network
+        #[clap(
+            long,
+            possible_values = &["Testnet", "testnet", "Mainnet", "mainnet", "Local", "local"]
+        )]
+        network: Option<Network>,
+    },
+
+    /// Shows aggregate statistics over the swaps recorded in the deal history
+    Stats {
+        /// Only account for the swaps where we played this role
+        #[clap(long, possible_values = &["Alice", "Bob"])]
+        role: Option<SwapRole>,
     },
 
     /// Gives information on an open deal
@@ -76,6 +162,32 @@ pub enum Command {
     DealInfo {
         /// The deal to be canceled.
         deal: Deal,
+
+        /// Compare the deal rate with the market rate, given as the monero amount of one bitcoin,
+        /// e.g. "150 XMR".
+        #[clap(long = "compare-market", value_name = "XMR_PER_BTC")]
+        compare_market: Option<monero::Amount>,
+    },
+
+    /// Encodes a deal in a QR code for scanning it from a mobile device, printed in the terminal
+    /// unless saved as a PNG image
+    #[clap(aliases = &["qr"])]
+    #[display("get-deal-qr<{deal}>")]
+    GetDealQr {
+        /// The deal to encode.
+        deal: Deal,
+
+        #[clap(subcommand)]
+        format: Option<QrFormat>,
+    },
+
+    /// Reviews the parameters of a deal before taking it: amounts, timelocks and their safety
+    /// against the swap configuration of the node. Exits with code 1 if the deal looks risky.
+    #[clap(aliases = &["confirm-deal-parameters"])]
+    #[display("confirm-deal-params<{deal}>")]
+    ConfirmDealParams {
+        /// The deal to review.
+        deal: Deal,
     },
 
     /// Lists listeners created by daemon
@@ -90,6 +202,47 @@ pub enum Command {
 
         /// The network for which we want to list the tasks
         network: Network,
+
+        /// Display the type, blockchain, creation and expiration heights of each task
+        #[clap(short, long)]
+        verbose: bool,
+    },
+
+    /// Watch an arbitrary address with a syncer and print the transactions it receives until
+    /// interrupted, the swaps are not involved
+    #[display("watch-address<{blockchain}, {network}, {address}>")]
+    WatchAddress {
+        /// The blockchain of the address
+        blockchain: Blockchain,
+
+        /// The network of the address, selects the syncer to use
+        network: Network,
+
+        /// The address to watch
+        address: Address,
+
+        /// Ignore the transactions confirmed below this height. The whole Bitcoin address history
+        /// is scanned if absent, the Monero one from the genesis block.
+        #[clap(long)]
+        from_height: Option<u64>,
+
+        /// The private view key of the Monero address, required to watch a Monero address
+        #[clap(long)]
+        view_key: Option<monero::PrivateKey>,
+    },
+
+    /// Fetches a transaction from the syncer of the blockchain and prints it as hex. Bitcoin
+    /// transactions are also decoded, with their fee if the spent outputs can be retrieved.
+    #[display("get-raw-transaction<{blockchain}, {network}, {hash}>")]
+    GetRawTransaction {
+        /// The blockchain of the transaction
+        blockchain: Blockchain,
+
+        /// The network of the transaction, selects the syncer to use
+        network: Network,
+
+        /// The hex encoded transaction id
+        hash: String,
     },
 
     /// Lists saved checkpoints of the swaps
@@ -123,11 +276,33 @@ pub enum Command {
         swap_id: SwapId,
     },
 
+    /// Exports the checkpoint of a swap, base64 encoded, to restore it on another node
+    ExportSwapState {
+        /// The swap id of the swap to export
+        swap_id: SwapId,
+
+        /// Write the exported state to this file instead of the standard output
+        #[clap(short, long, value_hint = ValueHint::FilePath)]
+        output_path: Option<PathBuf>,
+    },
+
+    /// Imports a swap checkpoint exported with export-swap-state and restores the swap
+    ImportSwapState {
+        /// Read the exported state from this file instead of the standard input
+        #[clap(short, long, value_hint = ValueHint::FilePath)]
+        input_path: Option<PathBuf>,
+    },
+
     /// Connects a running swap to its counterparty
     #[clap(aliases = &["c"])]
     Connect {
         // The swap id of the swap we wish to connect again
         swap_id: SwapId,
+
+        /// Longest wait between two attempts in seconds, the wait starts at 2 seconds and
+        /// doubles after each failed attempt. Retries until connected or interrupted.
+        #[clap(long, default_value = "120")]
+        max_wait: u64,
     },
 
     /// Maker creates deal and start listening for incoming connections. Command used to to print
@@ -178,8 +353,14 @@ pub enum Command {
         arbitrating_amount: bitcoin::Amount,
 
         /// Amount of accordant assets to exchanged.
-        #[clap(long = "xmr-amount")]
-        accordant_amount: monero::Amount,
+        #[clap(long = "xmr-amount", required_unless_present = "suggest-amounts")]
+        accordant_amount: Option<monero::Amount>,
+
+        /// Suggest the amount of accordant assets from the given exchange rate, as the monero
+        /// amount of one bitcoin, e.g. "150 XMR". The suggestion is confirmed or adjusted
+        /// interactively before the deal is made.
+        #[clap(long = "suggest-amounts", value_name = "XMR_PER_BTC")]
+        suggest_amounts: Option<monero::Amount>,
 
         /// The future maker swap role, either Alice of Bob. This will dictate with asset will be
         /// exchanged for which asset. Alice will sell accordant assets for arbitrating ones and
@@ -200,7 +381,8 @@ pub enum Command {
         fee_strategy: FeeStrategy<SatPerKvB>,
 
         /// Public IPv4 or IPv6 address to advertise in the deal. This allows taker to
-        /// connect; defaults to 127.0.0.1.
+        /// connect; defaults to 127.0.0.1. When left to the default and 'farcasterd.stun_server' is
+        /// set, the daemon advertises the external address discovered with that STUN server.
         #[clap(short = 'I', long, default_value = "127.0.0.1")]
         public_ip_addr: IpAddr,
 
@@ -211,6 +393,36 @@ pub enum Command {
         /// {farcasterd.bind_ip}:{farcasterd.bind_port}
         #[clap(short = 'p', long, default_value = "7067")]
         public_port: u16,
+
+        /// Number of confirmations after which the lock transactions stop being watched.
+        #[clap(long, default_value = "50000")]
+        lock_confs: u32,
+
+        /// Number of confirmations after which the cancel transaction stops being watched.
+        #[clap(long, default_value = "50000")]
+        cancel_confs: u32,
+
+        /// Number of confirmations after which the buy transaction stops being watched.
+        #[clap(long, default_value = "50000")]
+        buy_confs: u32,
+
+        /// Number of confirmations after which the refund transaction stops being watched.
+        #[clap(long, default_value = "50000")]
+        refund_confs: u32,
+
+        /// Number of confirmations after which the punish transaction stops being watched.
+        #[clap(long, default_value = "50000")]
+        punish_confs: u32,
+
+        /// Number of Bitcoin blocks after which the deal is automatically revoked, counted from
+        /// the current height. Defaults to no expiry.
+        #[clap(long, value_name = "BLOCKS")]
+        deal_expiry: Option<u32>,
+
+        /// Only validate the deal parameters against the configuration and the current fee
+        /// estimates, the deal is not made.
+        #[clap(long)]
+        dry_run: bool,
     },
 
     /// Taker accepts deal and connects to maker's daemon to start the trade.
@@ -230,6 +442,104 @@ pub enum Command {
         /// Accept the deal without validation.
         #[clap(short, long)]
         without_validation: bool,
+
+        /// Require typing the amount to receive before taking the deal.
+        #[clap(long = "confirm-amounts")]
+        confirm_amounts: bool,
+
+        /// Refuse the deal if the counterparty reputation score (0-100) is below this value.
+        #[clap(long = "min-reputation")]
+        min_reputation: Option<u8>,
+
+        /// Block at which the maker revokes the deal, refuse the deal if it is already reached.
+        #[clap(long = "expires-at-block")]
+        expires_at_block: Option<u64>,
+    },
+
+    /// Estimates the Bitcoin fees paid by each transaction of a swap over the deal, and the
+    /// worst-case total, using the current fee estimate of the Bitcoin syncer.
+    #[display("estimate-swap-cost<{deal}>")]
+    EstimateSwapCost {
+        /// An encoded deal.
+        deal: Deal,
+    },
+
+    /// Reads an encoded deal from a file or an HTTP(S) URL, validates its fee strategy and
+    /// timelocks and prints a summary. The encoded deal is then printed for use with take, or
+    /// taken right away with --take-immediately.
+    #[display("import-deal<{source}>")]
+    ImportDeal {
+        /// Path to a file containing the encoded deal, or an http:// or https:// URL serving it.
+        source: DealSource,
+
+        /// Take the deal once it is validated, requires --btc-addr and --xmr-addr.
+        #[clap(long, requires_all = &["bitcoin-address", "monero-address"])]
+        take_immediately: bool,
+
+        /// Bitcoin address used as destination or refund address.
+        #[clap(long = "btc-addr")]
+        bitcoin_address: Option<BtcAddress>,
+
+        /// Monero address used as destination or refund address.
+        #[clap(long = "xmr-addr")]
+        monero_address: Option<XmrAddress>,
+    },
+
+    /// Takes deals listed in a JSON file without prompting. The deals failing pre-validation are
+    /// skipped and the status of every deal is reported once done. Deals are taken one after the
+    /// other, the next once the swap of the previous one is funded, unless --max-concurrent is
+    /// set.
+    #[display("take-from-file<{path:?}>")]
+    TakeFromFile {
+        /// Path to a JSON array of encoded deals.
+        path: PathBuf,
+
+        /// Bitcoin address used as destination or refund address.
+        #[clap(long = "btc-addr")]
+        bitcoin_address: BtcAddress,
+
+        /// Monero address used as destination or refund address.
+        #[clap(long = "xmr-addr")]
+        monero_address: XmrAddress,
+
+        /// Maximum number of deals to take.
+        #[clap(long = "max-deals", default_value = "1")]
+        max_deals: usize,
+
+        /// Take the deals without pre-validating them, the deals failing pre-validation are
+        /// skipped otherwise.
+        #[clap(short, long)]
+        without_validation: bool,
+
+        /// Maximum number of deals submitted to the daemon in parallel, without waiting for the
+        /// previous swaps to be funded.
+        #[clap(long = "max-concurrent")]
+        max_concurrent: Option<usize>,
+    },
+
+    /// Takes all the deals listed in a file, one encoded deal per line, like take-from-file.
+    #[display("batch-take<{deals_file:?}>")]
+    BatchTake {
+        /// Path to a file with one encoded deal per line.
+        deals_file: PathBuf,
+
+        /// Bitcoin address used as destination or refund address.
+        #[clap(long = "btc-addr")]
+        bitcoin_address: BtcAddress,
+
+        /// Monero address used as destination or refund address.
+        #[clap(long = "xmr-addr")]
+        monero_address: XmrAddress,
+
+        /// Take the deals without pre-validating them, the deals failing pre-validation are
+        /// skipped otherwise.
+        #[clap(short, long)]
+        without_validation: bool,
+
+        /// Maximum number of deals submitted to the daemon in parallel, without waiting for the
+        /// previous swaps to be funded.
+        #[clap(long = "max-concurrent")]
+        max_concurrent: Option<usize>,
     },
 
     /// Revoke deal accepts a deal and revokes it within the runtime.
@@ -237,6 +547,37 @@ pub enum Command {
     RevokeDeal {
         /// The deal to be canceled.
         deal: Deal,
+
+        /// Revoke the deal even if takers are connected and may be about to take it.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Revoke all open deals whose punish timelock elapsed since they were made, according to the
+    /// current Bitcoin height asked to the syncers when the command runs. Reports how many deals
+    /// were revoked and every revocation that failed.
+    #[display("revoke-expired-deals")]
+    RevokeExpiredDeals {
+        /// Revoke the deals even if takers are connected and may be about to take them.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Revoke every deal matching the selector. The deals are listed and revoking them must be
+    /// confirmed, the deals with a swap in progress are never revoked.
+    #[display("revoke-all-deals<{selector}>")]
+    RevokeAllDeals {
+        #[clap(
+            short,
+            long = "select",
+            default_value = "open",
+            possible_values = &["open", "Open", "inprogress", "in_progress", "ended", "Ended", "all", "All"],
+        )]
+        selector: DealSelector,
+
+        /// Revoke the deals without asking for confirmation.
+        #[clap(short, long)]
+        yes: bool,
     },
 
     /// Abort a swap if it has not locked yet.
@@ -244,6 +585,53 @@ pub enum Command {
     AbortSwap {
         /// The swap to be aborted
         swap_id: SwapId,
+
+        /// Reason for aborting the swap, stored in the deal history
+        #[clap(long)]
+        reason: Option<String>,
+
+        /// Clean up the local swap state right away, without waiting for the counterparty. Refused
+        /// once Bob's funding is seen, the funding must then be swept by a regular abort. Asks for
+        /// confirmation first.
+        #[clap(long)]
+        force: bool,
+    },
+
+    /// Raise the fee strategy of the punish transaction of a running swap, the other Bitcoin
+    /// transactions are co-signed with the deal fee strategy. Only accepted while Alice has not
+    /// signed the punish transaction yet, and the fee must not be below the current Bitcoin fee
+    /// estimate of the swap.
+    #[display("set-fee-strategy<{swap_id}, {fee_strategy}>")]
+    SetFeeStrategy {
+        /// The swap to update
+        swap_id: SwapId,
+
+        /// The new fee strategy, e.g. "2000 satoshi/kvB"
+        fee_strategy: FeeStrategy<SatPerKvB>,
+    },
+
+    /// Sign a message with the Bitcoin key that signs the lock transaction of a running swap, to
+    /// prove participation in the swap. Prints the base64 encoded Bitcoin message signature.
+    #[display("sign-message<{swap_id}>")]
+    SignMessage {
+        /// The swap whose lock key signs the message
+        swap_id: SwapId,
+
+        /// The message to sign
+        message: String,
+    },
+
+    /// Verify that a message signature was made with the Bitcoin lock key of a running swap.
+    #[display("verify-message<{swap_id}>")]
+    VerifyMessage {
+        /// The signed message
+        message: String,
+
+        /// The base64 encoded Bitcoin message signature
+        signature: String,
+
+        /// The swap whose lock key is expected to have signed the message
+        swap_id: SwapId,
     },
 
     /// Request swap progress report.
@@ -255,6 +643,38 @@ pub enum Command {
         /// Subscribe to progress and only return when progress is finished.
         #[clap(short, long)]
         follow: bool,
+
+        /// Include the estimated time remaining until the cancel and punish deadlines.
+        #[clap(short, long)]
+        verbose: bool,
+
+        /// Follow the progress in a background process and return immediately with its PID,
+        /// stop it with `stop-watch`.
+        #[clap(long, requires = "log-file", conflicts_with_all = &["follow", "verbose"])]
+        watch_only: bool,
+
+        /// Append each swap summary received while following the progress to this file, as one
+        /// JSON object per line.
+        #[clap(long, parse(from_os_str))]
+        log_file: Option<PathBuf>,
+    },
+
+    /// Stop a progress watcher started with `progress --watch-only`.
+    #[display("stop-watch<{pid}>")]
+    StopWatch {
+        /// The PID of the watcher process.
+        pid: i32,
+    },
+
+    /// Replays the state transitions recorded by a swap, with their time and trigger.
+    #[display("history<{swap_id}>")]
+    History {
+        /// The swap id requested.
+        swap_id: SwapId,
+
+        /// Output format of the history, one of text, json or csv.
+        #[clap(short, long, default_value = "text")]
+        format: HistoryFormat,
     },
 
     /// Returns addresses and amounts that require funding for blockchain.
@@ -269,6 +689,11 @@ pub enum Command {
     ListFundingAddresses {
         /// Retrieve funding addresses for a particular blockchain.
         blockchain: Blockchain,
+
+        /// Compare the balance of each address with the amount its swap waits for, and report
+        /// whether it is funded, partially funded, empty or overfunded.
+        #[clap(long)]
+        with_balance: bool,
     },
 
     /// Attempts to sweep any funds on a given bitcoin funding address
@@ -312,6 +737,65 @@ pub enum Command {
         #[clap(value_parser = clap::builder::EnumValueParser::<Shell>::new())]
         shell: Shell,
     },
+
+    /// Export the Alice and Bob swap state machines as a Graphviz DOT graph, e.g. to render it
+    /// with `dot -Tpng`. Does not contact the daemon.
+    #[display("export-state-diagram")]
+    ExportStateDiagram {
+        /// File to write the graph to, printed on the standard output if absent
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Model the timeline of the happy path and the cancel and refund path of a swap against
+    /// in-memory chains, and print the modelled transitions with the temporal safety checks. The
+    /// swap state machine itself is not run. Does not contact the daemon.
+    #[display("simulate-swap<{role}>")]
+    SimulateSwap {
+        /// The swap role to simulate
+        #[clap(short, long, possible_values = &["Alice", "Bob"])]
+        role: SwapRole,
+
+        /// Number of blocks mined by the mock chains on each event
+        #[clap(long, default_value = "1")]
+        confirmations_per_event: u32,
+
+        /// The cancel timelock of the simulated deal
+        #[clap(long, default_value = "10")]
+        cancel_timelock: u32,
+
+        /// The punish timelock of the simulated deal
+        #[clap(long, default_value = "30")]
+        punish_timelock: u32,
+
+        /// Number of blocks before a timelock expires in which a transaction is not broadcast
+        #[clap(long, default_value = "3")]
+        safety: u32,
+
+        /// Number of confirmations for a bitcoin transaction to be final
+        #[clap(long, default_value = "1")]
+        btc_finality: u32,
+
+        /// Number of confirmations for a monero transaction to be final
+        #[clap(long, default_value = "1")]
+        xmr_finality: u32,
+    },
+}
+
+/// Output of the deal QR code
+#[derive(Subcommand, Clone, PartialEq, Eq, Debug, Display)]
+pub enum QrFormat {
+    /// Print the QR code in the terminal with unicode block characters
+    #[display("text")]
+    Text,
+
+    /// Save the QR code as a PNG image
+    #[display("png")]
+    Png {
+        /// Path of the PNG image to write.
+        #[clap(short, long, value_hint = ValueHint::FilePath)]
+        output: PathBuf,
+    },
 }
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, From)]
@@ -345,6 +829,126 @@ pub enum DealSelectorParseError {
     Invalid,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum SwapRoleFilter {
+    #[display("alice")]
+    Alice,
+    #[display("bob")]
+    Bob,
+    #[display("all")]
+    All,
+}
+
+impl SwapRoleFilter {
+    pub fn matches(&self, swap_role: SwapRole) -> bool {
+        match self {
+            SwapRoleFilter::Alice => swap_role == SwapRole::Alice,
+            SwapRoleFilter::Bob => swap_role == SwapRole::Bob,
+            SwapRoleFilter::All => true,
+        }
+    }
+}
+
+impl FromStr for SwapRoleFilter {
+    type Err = SwapRoleFilterParseError;
+    fn from_str(input: &str) -> Result<SwapRoleFilter, Self::Err> {
+        match input {
+            "alice" | "Alice" => Ok(SwapRoleFilter::Alice),
+            "bob" | "Bob" => Ok(SwapRoleFilter::Bob),
+            "all" | "All" => Ok(SwapRoleFilter::All),
+            _ => Err(SwapRoleFilterParseError::Invalid),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum SwapRoleFilterParseError {
+    /// The provided value can't be parsed as a swap role filter, use alice, bob or all
+    Invalid,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum OutputFormat {
+    #[display("text")]
+    Text,
+    #[display("json")]
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = OutputFormatParseError;
+    fn from_str(input: &str) -> Result<OutputFormat, Self::Err> {
+        match input {
+            "text" | "Text" => Ok(OutputFormat::Text),
+            "json" | "Json" | "JSON" => Ok(OutputFormat::Json),
+            _ => Err(OutputFormatParseError::Invalid),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum OutputFormatParseError {
+    /// The provided value can't be parsed as an output format, use text or json
+    Invalid,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum PeerSortKey {
+    #[display("swaps")]
+    Swaps,
+    #[display("node_id")]
+    NodeId,
+}
+
+impl FromStr for PeerSortKey {
+    type Err = PeerSortKeyParseError;
+    fn from_str(input: &str) -> Result<PeerSortKey, Self::Err> {
+        match input {
+            "swaps" | "Swaps" => Ok(PeerSortKey::Swaps),
+            "node_id" | "node-id" | "NodeId" => Ok(PeerSortKey::NodeId),
+            _ => Err(PeerSortKeyParseError::Invalid),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PeerSortKeyParseError {
+    /// The provided value can't be parsed as a peer sort key, use swaps or node_id
+    Invalid,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum HistoryFormat {
+    #[display("text")]
+    Text,
+    #[display("json")]
+    Json,
+    #[display("csv")]
+    Csv,
+}
+
+impl FromStr for HistoryFormat {
+    type Err = HistoryFormatParseError;
+    fn from_str(input: &str) -> Result<HistoryFormat, Self::Err> {
+        match input {
+            "text" | "Text" => Ok(HistoryFormat::Text),
+            "json" | "Json" | "JSON" => Ok(HistoryFormat::Json),
+            "csv" | "Csv" | "CSV" => Ok(HistoryFormat::Csv),
+            _ => Err(HistoryFormatParseError::Invalid),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum HistoryFormatParseError {
+    /// The provided value can't be parsed as a history format, use text, json or csv
+    Invalid,
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, From)]
 #[display(Debug)]
 pub enum CheckpointSelector {
@@ -372,29 +976,103 @@ pub enum CheckpointSelectorParseError {
     Invalid,
 }
 
+/// Where an encoded deal is read from, URLs are recognized by their http or https scheme
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+pub enum DealSource {
+    #[display("{0:?}")]
+    File(PathBuf),
+    #[display(inner)]
+    Url(Url),
+}
+
+impl FromStr for DealSource {
+    type Err = DealSourceParseError;
+    fn from_str(input: &str) -> Result<DealSource, Self::Err> {
+        if input.starts_with("http://") || input.starts_with("https://") {
+            Url::parse(input)
+                .map(DealSource::Url)
+                .map_err(|_| DealSourceParseError::InvalidUrl)
+        } else {
+            Ok(DealSource::File(PathBuf::from(input)))
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error, From)]
 #[display(doc_comments)]
+pub enum DealSourceParseError {
+    /// The provided value starts like an HTTP(S) URL but can't be parsed as one
+    InvalidUrl,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
 pub enum AmountOfAssetParseError {
     /// The provided value can't be parsed as a pair of asset name/ticker and
     /// asset amount; use <asset>:<amount> or '<amount> <asset>' form and do
     /// not forget about quotation marks in the second case
     NeedsValuePair,
 
-    /// The provided amount can't be interpreted; please use unsigned integer
-    #[from(std::num::ParseIntError)]
-    InvalidAmount,
+    /// Unknown currency ticker {0}; use BTC or XMR
+    UnknownCurrency(String),
+
+    /// The provided amount can't be interpreted as a decimal {0} amount
+    InvalidAmount(Currency),
+}
+
+/// Currency of an amount given on the command-line
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+pub enum Currency {
+    #[display("BTC")]
+    Btc,
+    #[display("XMR")]
+    Xmr,
+}
+
+impl FromStr for Currency {
+    type Err = AmountOfAssetParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "BTC" => Ok(Currency::Btc),
+            "XMR" => Ok(Currency::Xmr),
+            _ => Err(AmountOfAssetParseError::UnknownCurrency(s.to_owned())),
+        }
+    }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
-#[display("{amount} {asset}", alt = "{asset}:{amount}")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct AmountOfAsset {
-    /// Asset ticker
-    asset: String,
+    /// Currency of the amount
+    currency: Currency,
 
-    /// Amount of the asset in atomic units
+    /// Amount of the asset in atomic units, i.e. satoshi or piconero
     amount: u64,
 }
 
+impl AmountOfAsset {
+    fn decimal(&self) -> String {
+        match self.currency {
+            Currency::Btc => {
+                bitcoin::Amount::from_sat(self.amount).to_string_in(bitcoin::Denomination::Bitcoin)
+            }
+            Currency::Xmr => {
+                monero::Amount::from_pico(self.amount).to_string_in(monero::Denomination::Monero)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AmountOfAsset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            write!(f, "{}:{}", self.currency, self.decimal())
+        } else {
+            write!(f, "{} {}", self.decimal(), self.currency)
+        }
+    }
+}
+
 impl FromStr for AmountOfAsset {
     type Err = AmountOfAssetParseError;
 
@@ -426,9 +1104,53 @@ impl FromStr for AmountOfAsset {
             return Err(AmountOfAssetParseError::NeedsValuePair);
         }
 
-        let amount = u64::from_str(amount)?;
-        let asset = asset.to_owned();
+        let currency = Currency::from_str(asset)?;
+        let amount = match currency {
+            Currency::Btc => bitcoin::Amount::from_str_in(amount, bitcoin::Denomination::Bitcoin)
+                .map(|amount| amount.as_sat())
+                .ok(),
+            Currency::Xmr => monero::Amount::from_str_in(amount, monero::Denomination::Monero)
+                .map(|amount| amount.as_pico())
+                .ok(),
+        }
+        .ok_or(AmountOfAssetParseError::InvalidAmount(currency))?;
+
+        Ok(AmountOfAsset { currency, amount })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amount_of_asset_round_trip() {
+        let xmr = AmountOfAsset::from_str("0.001 XMR").unwrap();
+        assert_eq!(xmr.currency, Currency::Xmr);
+        assert_eq!(xmr.amount, 1_000_000_000);
+        assert_eq!(AmountOfAsset::from_str(&xmr.to_string()).unwrap(), xmr);
+
+        let btc = AmountOfAsset::from_str("btc:0.001").unwrap();
+        assert_eq!(btc.currency, Currency::Btc);
+        assert_eq!(btc.amount, 100_000);
+        assert_eq!(AmountOfAsset::from_str(&format!("{:#}", btc)).unwrap(), btc);
+
+        assert!(AmountOfAsset::from_str("1 ETH").is_err());
+    }
 
-        Ok(AmountOfAsset { asset, amount })
+    #[test]
+    fn deal_source_from_path_or_url() {
+        assert_eq!(
+            DealSource::from_str("deals/deal.txt").unwrap(),
+            DealSource::File(PathBuf::from("deals/deal.txt"))
+        );
+        assert!(matches!(
+            DealSource::from_str("https://example.com/deal").unwrap(),
+            DealSource::Url(url) if url.host_str() == Some("example.com")
+        ));
+        assert_eq!(
+            DealSource::from_str("http://"),
+            Err(DealSourceParseError::InvalidUrl)
+        );
     }
 }
@@ -0,0 +1,72 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use farcaster_core::swap::btcxmr::Deal;
+use image::Luma;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::types::QrError;
+use qrcode::QrCode;
+
+use crate::Error;
+
+/// Encodes the deal string in a QR code, fails if the deal does not fit in the largest QR code
+/// version (40)
+pub fn deal_qr_code(deal: &Deal) -> Result<QrCode, Error> {
+    QrCode::new(deal.to_string().as_bytes()).map_err(|err| match err {
+        QrError::DataTooLong => Error::Farcaster(
+            "The deal is too long to fit in a QR code, make the deal with a shorter public address"
+                .to_string(),
+        ),
+        err => Error::Farcaster(format!("Failed to encode the deal in a QR code: {}", err)),
+    })
+}
+
+/// Renders the QR code with unicode half blocks, two modules per character, with light modules
+/// drawn so the code scans on a dark terminal background
+pub fn render_text(code: &QrCode) -> String {
+    code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build()
+}
+
+/// Writes the QR code to a PNG image
+pub fn save_png(code: &QrCode, output: &Path) -> Result<(), Error> {
+    code.render::<Luma<u8>>()
+        .build()
+        .save(output)
+        .map_err(|err| {
+            Error::Farcaster(format!(
+                "Failed to write the QR code to {}: {}",
+                output.display(),
+                err
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::str::FromStr;
+
+    #[test]
+    fn deal_qr_code_round_trip() {
+        let deal = fixtures::deal();
+        let image = deal_qr_code(&deal).unwrap().render::<Luma<u8>>().build();
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+            image.width() as usize,
+            image.height() as usize,
+            |x, y| image.get_pixel(x as u32, y as u32)[0],
+        );
+        let grids = prepared.detect_grids();
+        assert_eq!(grids.len(), 1);
+        let (_, content) = grids[0].decode().unwrap();
+        assert_eq!(Deal::from_str(&content).unwrap(), deal);
+    }
+}
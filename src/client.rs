@@ -10,15 +10,19 @@ use std::time::Duration;
 use internet2::ZmqSocketType;
 use microservices::esb;
 
-use crate::bus::{ctl::CtlMsg, info::InfoMsg, BusMsg, ServiceBus};
+use crate::bus::{ctl::CtlMsg, info::InfoMsg, sync::SyncMsg, BusMsg, Progress, ServiceBus};
 use crate::service::Endpoints;
 use crate::service::ServiceConfig;
+use crate::Config;
 use crate::{Error, LogStyle, ServiceId};
 
 #[repr(C)]
 pub struct Client {
     identity: ServiceId,
+    config: ServiceConfig,
     response_queue: std::collections::VecDeque<BusMsg>,
+    json_output: bool,
+    node_config: Config,
     esb: esb::Controller<ServiceBus, BusMsg, Handler>,
 }
 
@@ -29,12 +33,17 @@ impl Client {
         let esb = esb::Controller::with(
             map! {
                 ServiceBus::Ctl => esb::BusConfig::with_addr(
-                    config.ctl_endpoint,
+                    config.ctl_endpoint.clone(),
                     ZmqSocketType::RouterConnect,
                     Some(ServiceId::router())
                 ),
                 ServiceBus::Info => esb::BusConfig::with_addr(
-                    config.info_endpoint,
+                    config.info_endpoint.clone(),
+                    ZmqSocketType::RouterConnect,
+                    Some(ServiceId::router()),
+                ),
+                ServiceBus::Sync => esb::BusConfig::with_addr(
+                    config.sync_endpoint.clone(),
                     ZmqSocketType::RouterConnect,
                     Some(ServiceId::router()),
                 )
@@ -49,7 +58,10 @@ impl Client {
 
         Ok(Self {
             identity,
+            config,
             response_queue: empty!(),
+            json_output: false,
+            node_config: Config::default(),
             esb,
         })
     }
@@ -58,6 +70,38 @@ impl Client {
         self.identity.clone()
     }
 
+    pub fn config(&self) -> ServiceConfig {
+        self.config.clone()
+    }
+
+    /// Print the responses as JSON instead of human readable text
+    pub fn set_json_output(&mut self, json_output: bool) {
+        self.json_output = json_output;
+    }
+
+    pub fn json_output(&self) -> bool {
+        self.json_output
+    }
+
+    /// Validate the deals against the swap limits of the node configuration
+    pub fn set_node_config(&mut self, node_config: Config) {
+        self.node_config = node_config;
+    }
+
+    pub fn node_config(&self) -> &Config {
+        &self.node_config
+    }
+
+    /// Print a response in the selected output format
+    pub fn print_response(&self, resp: &BusMsg) {
+        #[cfg(feature = "serde")]
+        if self.json_output {
+            println!("{}", resp.to_json());
+            return;
+        }
+        println!("{}", resp);
+    }
+
     pub fn request_info(&mut self, daemon: ServiceId, req: InfoMsg) -> Result<(), Error> {
         debug!("Executing {}", req);
         self.esb
@@ -84,6 +128,13 @@ impl Client {
             .expect("We always have at least one element"))
     }
 
+    pub fn request_sync(&mut self, syncer: ServiceId, req: SyncMsg) -> Result<(), Error> {
+        debug!("Executing {}", req);
+        self.esb
+            .send_to(ServiceBus::Sync, syncer, BusMsg::Sync(req))?;
+        Ok(())
+    }
+
     pub fn report_failure(&mut self) -> Result<BusMsg, Error> {
         match self.response()? {
             BusMsg::Ctl(CtlMsg::Failure(fail)) => Err(Error::Farcaster(fail.info)),
@@ -94,11 +145,13 @@ impl Client {
     pub fn report_response_or_fail(&mut self) -> Result<(), Error> {
         let resp = self.report_failure()?;
         // note: this triggers the yaml formatting when implemented
-        println!("{}", resp);
+        self.print_response(&resp);
         Ok(())
     }
 
-    /// Print the stream of received requests until progress fails or succeed
+    /// Print the stream of received requests until progress fails or succeed, or until the swap
+    /// summary reports a terminal state. In text mode only the summaries are printed for state
+    /// changes.
     pub fn report_progress(&mut self) -> Result<(), Error> {
         // loop on all requests received until a progress termination condition is recieved
         // report failure transform BusMsg::Failure in error already, terminate on error or on
@@ -111,11 +164,31 @@ impl Client {
                     break Err(e)
                 }
                 Ok(BusMsg::Ctl(CtlMsg::Success(s))) => {
-                    println!("{}", s.bright_green_bold());
+                    if self.json_output {
+                        self.print_response(&BusMsg::Ctl(CtlMsg::Success(s)));
+                    } else {
+                        println!("{}", s.bright_green_bold());
+                    }
                     // terminate on success
                     break Ok(());
                 }
-                Ok(req) => println!("{}", req),
+                Ok(BusMsg::Info(InfoMsg::Progress(Progress::Summary(summary)))) => {
+                    if self.json_output {
+                        self.print_response(&BusMsg::Info(InfoMsg::Progress(Progress::Summary(
+                            summary.clone(),
+                        ))));
+                    } else {
+                        println!("{}", summary);
+                    }
+                    // terminate once the swap ended
+                    if summary.is_terminal() {
+                        break Ok(());
+                    }
+                }
+                Ok(BusMsg::Info(InfoMsg::Progress(
+                    Progress::StateUpdate(_) | Progress::StateTransition(_),
+                ))) if !self.json_output => {}
+                Ok(req) => self.print_response(&req),
             }
         }
     }
@@ -49,6 +49,9 @@ pub const SWAP_TESTNET_MONERO_FINALITY: u8 = 1;
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(crate = "serde_crate")]
 pub struct Config {
+    /// High-water mark of the syncers internal ZMQ sockets, the number of messages queued before
+    /// new ones are dropped, 0 for unlimited. Defaults to unlimited
+    pub zmq_hwm: Option<i32>,
     /// Farcasterd configuration
     pub farcasterd: Option<FarcasterdConfig>,
     /// Swap configuration, applies to all swaps launched by this node
@@ -57,9 +60,86 @@ pub struct Config {
     pub grpc: Option<GrpcConfig>,
     /// Syncer configuration
     pub syncers: Option<Networked<Option<SyncerServers>>>,
+    /// Per-network profiles overriding the global settings
+    pub network: Option<Networked<Option<NetworkProfile>>>,
 }
 
 impl Config {
+    /// Returns the configuration with the settings of the network profile, if any, merged into
+    /// the global settings for that network
+    pub fn for_network(&self, network: Network) -> Self {
+        let mut config = self.clone();
+        let profile = match self
+            .network
+            .as_ref()
+            .and_then(|profiles| profiles.get_for_network(network))
+        {
+            Some(profile) => profile,
+            None => return config,
+        };
+
+        // only complete syncer sections can be overridden, urls alone are not enough to launch
+        // syncers
+        if let Some(servers) = config
+            .syncers
+            .as_mut()
+            .and_then(|syncers| syncers.get_mut_for_network(network).as_mut())
+        {
+            if let Some(url) = profile.bitcoin_rpc_url {
+                servers.electrum_server = url;
+            }
+            if let Some(url) = profile.monero_rpc_url {
+                servers.monero_daemon = url;
+            }
+        }
+
+        let swap = config.swap.get_or_insert_with(SwapConfig::default);
+        if let Some(finality) = profile.btc_finality_thr {
+            let bitcoin = swap.bitcoin.get_mut_for_network(network);
+            if bitcoin.is_none() {
+                *bitcoin =
+                    ArbConfig::get(ArbitratingBlockchain::Bitcoin, network).map(|temporality| {
+                        ChainSwapConfig {
+                            temporality,
+                            amounts: TradeableAmounts::none(),
+                        }
+                    });
+            }
+            if let Some(bitcoin) = bitcoin {
+                bitcoin.temporality.finality = finality;
+            }
+        }
+        if let Some(finality) = profile.xmr_finality_thr {
+            let monero = swap.monero.get_mut_for_network(network);
+            match monero {
+                Some(monero) => monero.temporality.finality = finality,
+                None => {
+                    *monero = Some(ChainSwapConfig {
+                        temporality: AccConfig { finality },
+                        amounts: TradeableAmounts::none(),
+                    })
+                }
+            }
+        }
+
+        config
+    }
+
+    /// Returns the default cancel and punish timelocks of the network profile, if any
+    pub fn get_default_timelocks(&self, network: Network) -> (Option<u32>, Option<u32>) {
+        match self
+            .network
+            .as_ref()
+            .and_then(|profiles| profiles.get_for_network(network))
+        {
+            Some(profile) => (
+                profile.cancel_timelock_default,
+                profile.punish_timelock_default,
+            ),
+            None => (None, None),
+        }
+    }
+
     /// Returns if auto-funding functionality is enabled
     pub fn is_auto_funding_enable(&self) -> bool {
         match &self.farcasterd {
@@ -141,6 +221,14 @@ impl Config {
         Ok(InetSocketAddr::from_str(&addr)?)
     }
 
+    /// Returns the STUN server used to discover the node's external ip address if one is set in
+    /// the config file, the lookup is disabled otherwise
+    pub fn get_stun_server(&self) -> Option<String> {
+        self.farcasterd
+            .as_ref()
+            .and_then(|farcasterd| farcasterd.stun_server.clone())
+    }
+
     /// Returns a syncer configuration, if found in config, for the specified network
     pub fn get_syncer_servers(&self, network: Network) -> Option<SyncerServers> {
         match network {
@@ -326,12 +414,14 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Config {
+            zmq_hwm: None,
             farcasterd: Some(FarcasterdConfig::default()),
             swap: Some(SwapConfig::default()),
             grpc: None,
             syncers: Some(Networked {
                 mainnet: Some(SyncerServers {
                     electrum_server: FARCASTER_MAINNET_ELECTRUM_SERVER.into(),
+                    bitcoin_zmq_rawtx: None,
                     monero_daemon: FARCASTER_MAINNET_MONERO_DAEMON.into(),
                     monero_rpc_wallet: FARCASTER_MAINNET_MONERO_RPC_WALLET.into(),
                     monero_lws: None,
@@ -339,6 +429,7 @@ impl Default for Config {
                 }),
                 testnet: Some(SyncerServers {
                     electrum_server: FARCASTER_TESTNET_ELECTRUM_SERVER.into(),
+                    bitcoin_zmq_rawtx: None,
                     monero_daemon: FARCASTER_TESTNET_MONERO_DAEMON.into(),
                     monero_rpc_wallet: FARCASTER_TESTNET_MONERO_RPC_WALLET.into(),
                     monero_lws: None,
@@ -346,6 +437,7 @@ impl Default for Config {
                 }),
                 local: None,
             }),
+            network: None,
         }
     }
 }
@@ -361,6 +453,9 @@ pub struct FarcasterdConfig {
     pub bind_ip: Option<String>,
     /// Whether checkpoints should be auto restored at start-up, or not
     pub auto_restore: Option<bool>,
+    /// Sets the STUN server used to discover the public ip address advertised in deals, default to
+    /// no lookup
+    pub stun_server: Option<String>,
 }
 
 /// This struct holds all swap config, for all chains and all networks
@@ -590,6 +685,8 @@ pub struct AutoFundingServers {
 pub struct SyncerServers {
     /// Electrum server to use
     pub electrum_server: String,
+    /// Bitcoin node ZMQ rawtx endpoint used to watch the mempool
+    pub bitcoin_zmq_rawtx: Option<String>,
     /// Monero daemon to use
     pub monero_daemon: String,
     /// Monero rpc wallet to use
@@ -600,6 +697,24 @@ pub struct SyncerServers {
     pub monero_wallet_dir: Option<String>,
 }
 
+/// Network profile, each value set overrides the corresponding global setting for the network
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[serde(crate = "serde_crate")]
+pub struct NetworkProfile {
+    /// Overrides the electrum server used by the Bitcoin syncer
+    pub bitcoin_rpc_url: Option<String>,
+    /// Overrides the Monero daemon used by the Monero syncer
+    pub monero_rpc_url: Option<String>,
+    /// Overrides the number of confirmations required to consider a Bitcoin transaction final
+    pub btc_finality_thr: Option<u8>,
+    /// Overrides the number of confirmations required to consider a Monero transaction final
+    pub xmr_finality_thr: Option<u8>,
+    /// Default cancel timelock for deals made on this network
+    pub cancel_timelock_default: Option<u32>,
+    /// Default punish timelock for deals made on this network
+    pub punish_timelock_default: Option<u32>,
+}
+
 #[derive(Deserialize, Serialize, Default, Debug, Clone)]
 #[serde(crate = "serde_crate")]
 pub struct Networked<T> {
@@ -622,6 +737,14 @@ where
             Network::Local => self.local.clone(),
         }
     }
+
+    fn get_mut_for_network(&mut self, network: Network) -> &mut T {
+        match network {
+            Network::Mainnet => &mut self.mainnet,
+            Network::Testnet => &mut self.testnet,
+            Network::Local => &mut self.local,
+        }
+    }
 }
 
 // Default implementation is used to generate the config file on disk if not found
@@ -664,6 +787,7 @@ impl Default for FarcasterdConfig {
             // write the default port and ip in the generated config
             bind_port: Some(FARCASTER_BIND_PORT),
             bind_ip: Some(FARCASTER_BIND_IP.to_string()),
+            stun_server: None,
         }
     }
 }
@@ -675,7 +799,13 @@ pub fn parse_config(path: &str) -> Result<Config, Error> {
         info!("Loading config file at: {}", &config_file);
         let mut settings = config::Config::default();
         settings.merge(config::File::with_name(config_file).required(true))?;
-        let conf = settings.try_into::<Config>().map_err(Into::into);
+        let conf: Result<Config, Error> = settings.try_into::<Config>().map_err(Into::into);
+        // merge the network profiles into the global settings
+        let conf = conf.map(|conf| {
+            conf.for_network(Network::Mainnet)
+                .for_network(Network::Testnet)
+                .for_network(Network::Local)
+        });
         trace!("{:#?}", conf);
         conf
     } else {
@@ -689,11 +819,49 @@ pub fn parse_config(path: &str) -> Result<Config, Error> {
 
 #[cfg(test)]
 mod tests {
-    use super::parse_config;
+    use super::*;
 
     #[test]
     fn config_example_parse() {
         let config = parse_config("./farcasterd.toml").expect("correct config example");
         dbg!(config);
     }
+
+    #[test]
+    fn config_network_profile_merge() {
+        let mut config = Config::default();
+        config.network = Some(Networked {
+            mainnet: None,
+            testnet: Some(NetworkProfile {
+                bitcoin_rpc_url: Some(s!("tcp://localhost:50001")),
+                btc_finality_thr: Some(2),
+                xmr_finality_thr: Some(3),
+                ..Default::default()
+            }),
+            local: None,
+        });
+        let merged = config.for_network(Network::Testnet);
+        let servers = merged.get_syncer_servers(Network::Testnet).unwrap();
+        assert_eq!(servers.electrum_server, "tcp://localhost:50001");
+        assert_eq!(servers.monero_daemon, FARCASTER_TESTNET_MONERO_DAEMON);
+        let swap = merged
+            .get_swap_config(
+                ArbitratingBlockchain::Bitcoin,
+                AccordantBlockchain::Monero,
+                Network::Testnet,
+            )
+            .unwrap();
+        assert_eq!(swap.arbitrating.finality, 2);
+        assert_eq!(swap.arbitrating.safety, SWAP_TESTNET_BITCOIN_SAFETY);
+        assert_eq!(swap.accordant.finality, 3);
+        // other networks are left untouched
+        let mainnet = merged
+            .get_swap_config(
+                ArbitratingBlockchain::Bitcoin,
+                AccordantBlockchain::Monero,
+                Network::Mainnet,
+            )
+            .unwrap();
+        assert_eq!(mainnet.arbitrating.finality, SWAP_MAINNET_BITCOIN_FINALITY);
+    }
 }
@@ -0,0 +1,122 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Migrations of the checkpointed swap state between format versions. Changing a checkpointed
+//! structure requires bumping [`CheckpointVersion::CURRENT`] and appending to [`MIGRATIONS`] the
+//! function rewriting a state of the previous version into the new format.
+
+use std::io::Cursor as IoCursor;
+
+use bitcoin::Txid;
+use farcaster_core::role::TradeRole;
+use farcaster_core::swap::btcxmr::Deal;
+use farcaster_core::transaction::TxLabel;
+use internet2::addr::NodeId;
+use strict_encoding::{StrictDecode, StrictEncode};
+
+use crate::bus::p2p::PeerMsg;
+use crate::swapd::{CheckpointSwapd, SwapStateMachine, TemporalSafety};
+use crate::syncerd::XmrAddressAddendum;
+use crate::ServiceId;
+
+/// Version of the format of a checkpointed swap state
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display("v{0}")]
+pub struct CheckpointVersion(pub u16);
+
+impl CheckpointVersion {
+    /// Version of the checkpoints written by this node
+    pub const CURRENT: CheckpointVersion = CheckpointVersion(2);
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum MigrationError {
+    /// Checkpoint version {0} does not exist, versions start at 1
+    UnknownVersion(CheckpointVersion),
+
+    /// Checkpoint version {0} is newer than the {1} supported by this node
+    UnsupportedVersion(CheckpointVersion, CheckpointVersion),
+
+    /// Checkpoint migration from {0} failed: {1}
+    Failed(CheckpointVersion, String),
+}
+
+/// Rewrites a state of version `n` into version `n + 1`, found at index `n - 1`
+type Migration = fn(&[u8]) -> Result<Vec<u8>, MigrationError>;
+
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+/// Checkpointed swap state of version 1, the layout of the checkpoints written before the syncer
+/// task counter, the swap history and the fee strategy were added
+#[derive(StrictEncode, StrictDecode)]
+pub struct CheckpointSwapdV1 {
+    pub state: SwapStateMachine,
+    pub pending_msg: Option<PeerMsg>,
+    pub enquirer: Option<ServiceId>,
+    pub xmr_addr_addendum: Option<XmrAddressAddendum>,
+    pub temporal_safety: TemporalSafety,
+    pub txids: Vec<(TxLabel, Txid)>,
+    pub pending_broadcasts: Vec<(bitcoin::Transaction, TxLabel)>,
+    pub local_trade_role: TradeRole,
+    pub connected_counterparty_node_id: Option<NodeId>,
+    pub deal: Deal,
+}
+
+/// Starts the task counter from zero, swapd bumps it past the restored tasks anyway, with an
+/// empty history and the fee strategy of the deal
+fn migrate_v1_to_v2(bytes: &[u8]) -> Result<Vec<u8>, MigrationError> {
+    let failed =
+        |err: strict_encoding::Error| MigrationError::Failed(CheckpointVersion(1), err.to_string());
+    let CheckpointSwapdV1 {
+        state,
+        pending_msg,
+        enquirer,
+        xmr_addr_addendum,
+        temporal_safety,
+        txids,
+        pending_broadcasts,
+        local_trade_role,
+        connected_counterparty_node_id,
+        deal,
+    } = CheckpointSwapdV1::strict_decode(IoCursor::new(bytes)).map_err(failed)?;
+    let checkpoint = CheckpointSwapd {
+        state,
+        pending_msg,
+        enquirer,
+        xmr_addr_addendum,
+        temporal_safety,
+        txids,
+        pending_broadcasts,
+        local_trade_role,
+        connected_counterparty_node_id,
+        task_counter: 0,
+        history: vec![],
+        fee_strategy: deal.parameters.fee_strategy,
+        deal,
+    };
+    let mut encoded = vec![];
+    checkpoint.strict_encode(&mut encoded).map_err(failed)?;
+    Ok(encoded)
+}
+
+/// Applies the successive migrations from `from_version` to the current version on the encoded
+/// swap state, without its version header
+pub fn migrate(from_version: CheckpointVersion, bytes: &[u8]) -> Result<Vec<u8>, MigrationError> {
+    if from_version.0 == 0 {
+        return Err(MigrationError::UnknownVersion(from_version));
+    }
+    if from_version > CheckpointVersion::CURRENT {
+        return Err(MigrationError::UnsupportedVersion(
+            from_version,
+            CheckpointVersion::CURRENT,
+        ));
+    }
+    debug_assert_eq!(MIGRATIONS.len() + 1, CheckpointVersion::CURRENT.0 as usize);
+    MIGRATIONS[from_version.0 as usize - 1..]
+        .iter()
+        .try_fold(bytes.to_vec(), |state, migration| migration(&state))
+}
@@ -0,0 +1,155 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Storage format of the swap checkpoints. Every checkpoint starts with a four bytes header, a
+//! magic marker followed by the [`CheckpointVersion`] of the encoded state, so that checkpoints
+//! written by an older node are migrated before being decoded.
+
+pub mod migration;
+
+use std::io::Cursor as IoCursor;
+
+use strict_encoding::{StrictDecode, StrictEncode};
+
+pub use migration::{migrate, CheckpointSwapdV1, CheckpointVersion, MigrationError};
+
+use crate::swapd::CheckpointSwapd;
+use crate::Error;
+
+/// Marker preceding the checkpoint version. A checkpoint written before versioning starts with
+/// the tag of its swap state instead, which is never this large.
+pub const CHECKPOINT_MAGIC: [u8; 2] = [0xfc, 0x5d];
+
+/// Encodes the checkpoint with the current version header
+pub fn encode_checkpoint(checkpoint: &CheckpointSwapd) -> Result<Vec<u8>, Error> {
+    let mut encoded = CHECKPOINT_MAGIC.to_vec();
+    encoded.extend(CheckpointVersion::CURRENT.0.to_le_bytes());
+    checkpoint.strict_encode(&mut encoded)?;
+    Ok(encoded)
+}
+
+/// Decodes a checkpoint, migrating its state to the current version first. Checkpoints without
+/// header predate versioning and are read as version 1.
+pub fn decode_checkpoint(bytes: &[u8]) -> Result<CheckpointSwapd, Error> {
+    let (version, state) = match bytes {
+        [magic0, magic1, version0, version1, state @ ..]
+            if [*magic0, *magic1] == CHECKPOINT_MAGIC =>
+        {
+            (
+                CheckpointVersion(u16::from_le_bytes([*version0, *version1])),
+                state,
+            )
+        }
+        _ => (CheckpointVersion(1), bytes),
+    };
+    let state = migrate(version, state)?;
+    Ok(CheckpointSwapd::strict_decode(IoCursor::new(state))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::swapd::SwapEvent;
+    use farcaster_core::bitcoin::fee::SatPerKvB;
+    use farcaster_core::blockchain::FeeStrategy;
+
+    fn checkpoint() -> CheckpointSwapd {
+        let mut checkpoint = fixtures::checkpoint(fixtures::deal());
+        checkpoint.task_counter = 7;
+        checkpoint.history = vec![SwapEvent {
+            timestamp: 1_660_000_000,
+            old_state: "Bob Init Maker".to_string(),
+            new_state: "Bob Buy Sweeping".to_string(),
+            trigger: "Ctl::TakerCommit".to_string(),
+        }];
+        checkpoint.fee_strategy = FeeStrategy::Fixed(SatPerKvB::from_sat(2000));
+        checkpoint
+    }
+
+    fn strict_encoded(checkpoint: &CheckpointSwapd) -> Vec<u8> {
+        let mut encoded = vec![];
+        checkpoint.strict_encode(&mut encoded).unwrap();
+        encoded
+    }
+
+    fn v1_checkpoint(checkpoint: &CheckpointSwapd) -> CheckpointSwapdV1 {
+        let checkpoint = checkpoint.clone();
+        CheckpointSwapdV1 {
+            state: checkpoint.state,
+            pending_msg: checkpoint.pending_msg,
+            enquirer: checkpoint.enquirer,
+            xmr_addr_addendum: checkpoint.xmr_addr_addendum,
+            temporal_safety: checkpoint.temporal_safety,
+            txids: checkpoint.txids,
+            pending_broadcasts: checkpoint.pending_broadcasts,
+            local_trade_role: checkpoint.local_trade_role,
+            connected_counterparty_node_id: checkpoint.connected_counterparty_node_id,
+            deal: checkpoint.deal,
+        }
+    }
+
+    #[test]
+    fn current_checkpoint_roundtrips() {
+        let checkpoint = checkpoint();
+        let encoded = encode_checkpoint(&checkpoint).unwrap();
+        assert_eq!(encoded[..2], CHECKPOINT_MAGIC);
+        assert_eq!(
+            u16::from_le_bytes([encoded[2], encoded[3]]),
+            CheckpointVersion::CURRENT.0
+        );
+        assert_eq!(
+            migrate(CheckpointVersion::CURRENT, &encoded[4..]).unwrap(),
+            strict_encoded(&checkpoint)
+        );
+        let decoded = decode_checkpoint(&encoded).unwrap();
+        assert_eq!(strict_encoded(&decoded), strict_encoded(&checkpoint));
+
+        let mut future = encoded;
+        future[2..4].copy_from_slice(&(CheckpointVersion::CURRENT.0 + 1).to_le_bytes());
+        assert!(matches!(
+            decode_checkpoint(&future),
+            Err(Error::CheckpointMigration(
+                MigrationError::UnsupportedVersion(..)
+            ))
+        ));
+        assert_eq!(
+            migrate(CheckpointVersion(0), &[]),
+            Err(MigrationError::UnknownVersion(CheckpointVersion(0)))
+        );
+    }
+
+    #[test]
+    fn v1_checkpoint_migrates_to_current() {
+        let checkpoint = checkpoint();
+        let mut v1_encoded = vec![];
+        v1_checkpoint(&checkpoint)
+            .strict_encode(&mut v1_encoded)
+            .unwrap();
+        // the fields added since v1 are appended to the v1 layout
+        assert!(strict_encoded(&checkpoint).starts_with(&v1_encoded));
+
+        let mut migrated = checkpoint.clone();
+        migrated.task_counter = 0;
+        migrated.history = vec![];
+        migrated.fee_strategy = migrated.deal.parameters.fee_strategy;
+
+        // checkpoints stored before versioning have no header and the v1 layout
+        let legacy = decode_checkpoint(&v1_encoded).unwrap();
+        assert_eq!(strict_encoded(&legacy), strict_encoded(&migrated));
+
+        let mut versioned = CHECKPOINT_MAGIC.to_vec();
+        versioned.extend(1u16.to_le_bytes());
+        versioned.extend(&v1_encoded);
+        let decoded = decode_checkpoint(&versioned).unwrap();
+        assert_eq!(strict_encoded(&decoded), strict_encoded(&migrated));
+
+        assert!(matches!(
+            migrate(CheckpointVersion(1), &v1_encoded[..v1_encoded.len() - 1]),
+            Err(MigrationError::Failed(..))
+        ));
+    }
+}
@@ -7,19 +7,26 @@
 use farcaster_core::swap::btcxmr::Deal;
 use farcaster_core::swap::SwapId;
 use farcaster_core::{blockchain::Blockchain, role::TradeRole};
+use internet2::addr::NodeId;
 use lmdb::{Cursor, Transaction as LMDBTransaction};
 use std::io::Cursor as IoCursor;
 use std::path::PathBuf;
+use std::time::SystemTime;
 use strict_encoding::{StrictDecode, StrictEncode};
 
 use crate::bus::{
     ctl::{Checkpoint, CtlMsg},
-    info::{Address, InfoMsg},
+    info::{Address, InfoMsg, SwapHistory},
     info::{BitcoinAddressSwapIdPair, DealStatusSelector, MoneroAddressSwapIdPair},
-    AddressSecretKey, BitcoinSecretKeyInfo, BusMsg, CheckpointEntry, DealInfo, DealStatus, Failure,
-    FailureCode, MoneroSecretKeyInfo, Outcome, ServiceBus,
+    AbortReason, AddressSecretKey, BitcoinSecretKeyInfo, BusMsg, CheckpointEntry, DealInfo,
+    DealStatus, Failure, FailureCode, MoneroSecretKeyInfo, OptionDetails, Outcome, PeerOutcome,
+    PeerReputation, ServiceBus, SwapStateExport,
+};
+use crate::{
+    databased::checkpoint::{decode_checkpoint, encode_checkpoint},
+    swapd::{CheckpointSwapd, CheckpointValidator},
+    Endpoints,
 };
-use crate::{swapd::CheckpointSwapd, Endpoints};
 use crate::{CtlServer, Error, LogStyle, Service, ServiceConfig, ServiceId};
 use microservices::esb::{self, Handler};
 
@@ -101,35 +108,24 @@ impl Runtime {
                     swap_id,
                     service_id: source,
                 };
-                let mut state_encoded = vec![];
-                state.strict_encode(&mut state_encoded)?;
+                let state_encoded = encode_checkpoint(&state)?;
                 self.database.set_checkpoint_state(&key, &state_encoded)?;
                 debug!("{} | checkpoint set", swap_id.swap_id());
             }
 
             CtlMsg::RestoreCheckpoint(CheckpointEntry { swap_id, .. }) => {
-                match self.database.get_checkpoint_state(&CheckpointKey {
-                    swap_id,
-                    service_id: ServiceId::Swap(swap_id),
-                }) {
-                    Ok(raw_state) => {
-                        match CheckpointSwapd::strict_decode(IoCursor::new(raw_state)) {
-                            Ok(state) => {
-                                endpoints.send_to(
-                                    ServiceBus::Ctl,
-                                    self.identity(),
-                                    ServiceId::Swap(swap_id),
-                                    BusMsg::Ctl(CtlMsg::Checkpoint(Checkpoint { swap_id, state })),
-                                )?;
-                            }
-                            Err(err) => {
-                                error!("Decoding the checkpoint failed: {}", err);
-                            }
-                        }
+                match self.database.get_validated_checkpoint(&swap_id) {
+                    Ok(state) => {
+                        endpoints.send_to(
+                            ServiceBus::Ctl,
+                            self.identity(),
+                            ServiceId::Swap(swap_id),
+                            BusMsg::Ctl(CtlMsg::Checkpoint(Checkpoint { swap_id, state })),
+                        )?;
                     }
                     Err(err) => {
                         error!(
-                            "Failed to retrieve checkpointed state for swap {}: {}",
+                            "Failed to restore checkpointed state for swap {}: {}",
                             swap_id, err
                         );
                     }
@@ -151,6 +147,47 @@ impl Runtime {
                 }
             }
 
+            CtlMsg::ImportSwapState(SwapStateExport { swap_id, state }) => {
+                match decode_checkpoint(&state).and_then(|checkpoint| {
+                    let info = CheckpointEntry {
+                        swap_id,
+                        deal: checkpoint.deal.clone(),
+                        trade_role: checkpoint.local_trade_role,
+                        expected_counterparty_node_id: checkpoint.connected_counterparty_node_id,
+                    };
+                    CheckpointValidator::new(info.clone()).validate(&checkpoint)?;
+                    Ok(info)
+                }) {
+                    Ok(info) => {
+                        self.database.set_checkpoint_info(&swap_id, &info)?;
+                        let key = CheckpointKey {
+                            swap_id,
+                            service_id: ServiceId::Swap(swap_id),
+                        };
+                        self.database.set_checkpoint_state(&key, &state)?;
+                        debug!("{} | imported swap state", swap_id.swap_id());
+                        self.send_client_ctl(
+                            endpoints,
+                            source,
+                            CtlMsg::Success(OptionDetails(Some(format!(
+                                "Imported checkpoint of swap {}",
+                                swap_id
+                            )))),
+                        )?;
+                    }
+                    Err(err) => {
+                        self.send_client_ctl(
+                            endpoints,
+                            source,
+                            CtlMsg::Failure(Failure {
+                                code: FailureCode::Unknown,
+                                info: format!("Invalid checkpoint state for {}: {}", swap_id, err),
+                            }),
+                        )?;
+                    }
+                }
+            }
+
             CtlMsg::SetAddressSecretKey(AddressSecretKey::Bitcoin {
                 address,
                 secret_key_info,
@@ -182,6 +219,24 @@ impl Runtime {
                 )?;
             }
 
+            CtlMsg::SetDealAbortReason(deal, reason) => {
+                self.database.set_deal_abort_reason(&deal, &reason)?;
+            }
+
+            CtlMsg::SetPeerOutcome(PeerOutcome {
+                node_id,
+                outcome,
+                protocol_violation,
+            }) => {
+                let mut reputation = self.database.get_peer_reputation(&node_id)?;
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                reputation.record_outcome(&outcome, protocol_violation, now);
+                self.database.set_peer_reputation(&node_id, &reputation)?;
+            }
+
             CtlMsg::CleanDanglingDeals => {
                 let checkpointed_pub_deals: Vec<Deal> = self
                     .database
@@ -257,9 +312,27 @@ impl Runtime {
 
             InfoMsg::GetCheckpointEntry(swap_id) => {
                 match self.database.get_checkpoint_info(&swap_id) {
-                    Ok(entry) => {
-                        self.send_client_info(endpoints, source, InfoMsg::CheckpointEntry(entry))?;
-                    }
+                    // refuse to hand out the entry of a checkpoint that cannot be restored
+                    Ok(entry) => match self.database.get_validated_checkpoint(&swap_id) {
+                        Ok(_) => {
+                            self.send_client_info(
+                                endpoints,
+                                source,
+                                InfoMsg::CheckpointEntry(entry),
+                            )?;
+                        }
+                        Err(err) => {
+                            warn!("Invalid checkpoint for swap {}: {}", swap_id, err);
+                            self.send_client_ctl(
+                                endpoints,
+                                source,
+                                CtlMsg::Failure(Failure {
+                                    code: FailureCode::Unknown,
+                                    info: format!("Invalid checkpoint for {}: {}", swap_id, err),
+                                }),
+                            )?;
+                        }
+                    },
                     Err(err) => {
                         warn!("Failed to retrieve checkpoint entry: {}", err);
                         self.send_client_ctl(
@@ -277,6 +350,69 @@ impl Runtime {
                 }
             }
 
+            // history of a swap that is not running, as of its last checkpoint
+            InfoMsg::GetSwapHistory(swap_id) => {
+                match self
+                    .database
+                    .get_checkpoint_state(&CheckpointKey {
+                        swap_id,
+                        service_id: ServiceId::Swap(swap_id),
+                    })
+                    .and_then(|state| decode_checkpoint(&state))
+                {
+                    Ok(checkpoint) => {
+                        self.send_client_info(
+                            endpoints,
+                            source,
+                            InfoMsg::SwapHistory(SwapHistory {
+                                swap_id,
+                                events: checkpoint.history,
+                            }),
+                        )?;
+                    }
+                    Err(err) => {
+                        warn!("Failed to retrieve swap history: {}", err);
+                        self.send_client_ctl(
+                            endpoints,
+                            source,
+                            CtlMsg::Failure(Failure {
+                                code: FailureCode::Unknown,
+                                info: format!("Could not retrieve swap history for {}", swap_id),
+                            }),
+                        )?;
+                    }
+                }
+            }
+
+            InfoMsg::GetSwapState(swap_id) => {
+                match self.database.get_checkpoint_state(&CheckpointKey {
+                    swap_id,
+                    service_id: ServiceId::Swap(swap_id),
+                }) {
+                    Ok(state) => {
+                        self.send_client_info(
+                            endpoints,
+                            source,
+                            InfoMsg::SwapState(SwapStateExport { swap_id, state }),
+                        )?;
+                    }
+                    Err(err) => {
+                        warn!("Failed to retrieve checkpoint state: {}", err);
+                        self.send_client_ctl(
+                            endpoints,
+                            source,
+                            CtlMsg::Failure(Failure {
+                                code: FailureCode::Unknown,
+                                info: format!(
+                                    "Could not retrieve checkpoint state for {}",
+                                    swap_id
+                                ),
+                            }),
+                        )?;
+                    }
+                }
+            }
+
             InfoMsg::GetAddressSecretKey(Address::Monero(address)) => {
                 match self.database.get_monero_address_secret_key(&address) {
                     Err(_) => {
@@ -328,6 +464,27 @@ impl Runtime {
                 }
             }
 
+            InfoMsg::GetPeerReputation(node_id) => {
+                let reputation = self.database.get_peer_reputation(&node_id)?;
+                self.send_client_info(
+                    endpoints,
+                    source,
+                    InfoMsg::PeerReputation(reputation.score()),
+                )?;
+            }
+
+            InfoMsg::GetPeerReputations(node_ids) => {
+                let mut reputations = vec![];
+                for node_id in node_ids.iter() {
+                    reputations.push(self.database.get_peer_reputation(node_id)?.score());
+                }
+                self.send_client_info(
+                    endpoints,
+                    source,
+                    InfoMsg::PeerReputations(reputations.into()),
+                )?;
+            }
+
             InfoMsg::GetAddresses(Blockchain::Bitcoin) => {
                 let mut addresses = self.database.get_all_bitcoin_addresses()?;
                 self.send_client_info(
@@ -336,7 +493,11 @@ impl Runtime {
                     InfoMsg::BitcoinAddressList(
                         addresses
                             .drain(..)
-                            .map(|(address, swap_id)| BitcoinAddressSwapIdPair { address, swap_id })
+                            .map(|(address, swap_id)| BitcoinAddressSwapIdPair {
+                                address,
+                                swap_id,
+                                balance_sat: None,
+                            })
                             .collect(),
                     ),
                 )?;
@@ -350,7 +511,11 @@ impl Runtime {
                     InfoMsg::MoneroAddressList(
                         addresses
                             .drain(..)
-                            .map(|(address, swap_id)| MoneroAddressSwapIdPair { address, swap_id })
+                            .map(|(address, swap_id)| MoneroAddressSwapIdPair {
+                                address,
+                                swap_id,
+                                balance_piconero: None,
+                            })
                             .collect(),
                     ),
                 )?;
@@ -400,6 +565,8 @@ const LMDB_CHECKPOINT_INFOS: &str = "checkpoint_infos";
 const LMDB_BITCOIN_ADDRESSES: &str = "bitcoin_addresses";
 const LMDB_MONERO_ADDRESSES: &str = "monero_addresses";
 const LMDB_DEAL_HISTORY: &str = "deal_history";
+const LMDB_PEER_REPUTATION: &str = "peer_reputation";
+const LMDB_DEAL_ABORT_REASONS: &str = "deal_abort_reasons";
 
 impl Database {
     fn new(path: PathBuf) -> Result<Database, lmdb::Error> {
@@ -412,6 +579,8 @@ impl Database {
         env.create_db(Some(LMDB_BITCOIN_ADDRESSES), lmdb::DatabaseFlags::empty())?;
         env.create_db(Some(LMDB_DEAL_HISTORY), lmdb::DatabaseFlags::empty())?;
         env.create_db(Some(LMDB_MONERO_ADDRESSES), lmdb::DatabaseFlags::empty())?;
+        env.create_db(Some(LMDB_PEER_REPUTATION), lmdb::DatabaseFlags::empty())?;
+        env.create_db(Some(LMDB_DEAL_ABORT_REASONS), lmdb::DatabaseFlags::empty())?;
         Ok(Database(env))
     }
 
@@ -430,8 +599,21 @@ impl Database {
         Ok(())
     }
 
+    fn set_deal_abort_reason(&mut self, deal: &Deal, reason: &AbortReason) -> Result<(), Error> {
+        let db = self.0.open_db(Some(LMDB_DEAL_ABORT_REASONS))?;
+        let mut tx = self.0.begin_rw_txn()?;
+        let mut key = vec![];
+        deal.strict_encode(&mut key)?;
+        let mut val = vec![];
+        reason.strict_encode(&mut val)?;
+        tx.put(db, &key, &val, lmdb::WriteFlags::empty())?;
+        tx.commit()?;
+        Ok(())
+    }
+
     fn get_deals(&mut self, selector: DealStatusSelector) -> Result<Vec<DealInfo>, Error> {
         let db = self.0.open_db(Some(LMDB_DEAL_HISTORY))?;
+        let reasons_db = self.0.open_db(Some(LMDB_DEAL_ABORT_REASONS))?;
         let tx = self.0.begin_ro_txn()?;
         let mut cursor = tx.open_ro_cursor(db)?;
         cursor
@@ -457,13 +639,24 @@ impl Database {
                     _ if selector == DealStatusSelector::All => Some(status),
                     _ => None,
                 }?;
+                let failure_reason = match tx.get(reasons_db, &key) {
+                    Ok(val) => match AbortReason::strict_decode(IoCursor::new(val.to_vec())) {
+                        Ok(reason) => Some(reason.to_string()),
+                        Err(err) => return Some(Err(Error::from(err))),
+                    },
+                    Err(_) => None,
+                };
                 Some(
                     Deal::strict_decode(IoCursor::new(key.to_vec()))
                         .map(|deal| DealInfo {
                             serialized_deal: deal.to_string(),
-                            deal,
                             status: filtered_status,
                             local_trade_role,
+                            local_swap_role: deal.swap_role(&local_trade_role),
+                            failure_reason,
+                            expires_at_block: None,
+                            expires_in: None,
+                            deal,
                         })
                         .map_err(Error::from),
                 )
@@ -471,6 +664,35 @@ impl Database {
             .collect()
     }
 
+    fn set_peer_reputation(
+        &mut self,
+        node_id: &NodeId,
+        reputation: &PeerReputation,
+    ) -> Result<(), Error> {
+        let db = self.0.open_db(Some(LMDB_PEER_REPUTATION))?;
+        let mut tx = self.0.begin_rw_txn()?;
+        let key = node_id.public_key().serialize();
+        let mut val = vec![];
+        reputation.strict_encode(&mut val)?;
+        tx.put(db, &key, &val, lmdb::WriteFlags::empty())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns the reputation of the peer, or an empty history if the peer is unknown
+    fn get_peer_reputation(&mut self, node_id: &NodeId) -> Result<PeerReputation, Error> {
+        let db = self.0.open_db(Some(LMDB_PEER_REPUTATION))?;
+        let tx = self.0.begin_ro_txn()?;
+        let key = node_id.public_key().serialize();
+        let res = match tx.get(db, &key) {
+            Ok(val) => Ok(PeerReputation::strict_decode(IoCursor::new(val.to_vec()))?),
+            Err(lmdb::Error::NotFound) => Ok(PeerReputation::default()),
+            Err(err) => Err(err.into()),
+        };
+        tx.abort();
+        res
+    }
+
     fn set_bitcoin_address(
         &mut self,
         address: &bitcoin::Address,
@@ -644,6 +866,19 @@ impl Database {
         res
     }
 
+    /// Decode the checkpointed state of a swap and verify it is consistent with the swap
+    /// checkpoint entry
+    fn get_validated_checkpoint(&mut self, swap_id: &SwapId) -> Result<CheckpointSwapd, Error> {
+        let entry = self.get_checkpoint_info(swap_id)?;
+        let raw_state = self.get_checkpoint_state(&CheckpointKey {
+            swap_id: *swap_id,
+            service_id: ServiceId::Swap(*swap_id),
+        })?;
+        let checkpoint = decode_checkpoint(&raw_state)?;
+        CheckpointValidator::new(entry).validate(&checkpoint)?;
+        Ok(checkpoint)
+    }
+
     fn get_checkpoint_state(&mut self, checkpoint_key: &CheckpointKey) -> Result<Vec<u8>, Error> {
         let db = self.0.open_db(Some(LMDB_CHECKPOINTS))?;
         let tx = self.0.begin_ro_txn()?;
@@ -678,6 +913,7 @@ impl Database {
 #[test]
 fn test_lmdb_state() {
     use crate::bus::Outcome;
+    use crate::fixtures;
     use bitcoin::secp256k1::SecretKey;
     use farcaster_core::role::TradeRole;
     use farcaster_core::Uuid;
@@ -715,7 +951,7 @@ fn test_lmdb_state() {
     let val_info = CheckpointEntry {
         swap_id: key_info,
         trade_role: TradeRole::Maker,
-        deal: Deal::from_str("Deal:Cke4ftrP5A781Vq85dgBQJNwYgBS4nuUV1LQM2fvVdFMNR4h5TrWhRR11111uMFuZTAsNgpdK8DiK11111TB9zym113GTvtvqfD1111114A4TTfFfmZoWyvpcjDBtTZCdWFSUWcRKYfEC3Y17hqaXZ3dWz11111111111111111111111111111111111111111AfZ113SEBTEspU3a").unwrap(),
+        deal: fixtures::deal(),
         expected_counterparty_node_id: None,
     };
     database.set_checkpoint_info(&key_info, &val_info).unwrap();
@@ -815,17 +1051,75 @@ fn test_lmdb_state() {
     let deals_retrieved = database.get_deals(DealStatusSelector::All).unwrap();
     let status_1 = DealInfo {
         serialized_deal: deal_1.to_string(),
-        deal: deal_1,
         status: DealStatus::Ended(Outcome::SuccessSwap),
         local_trade_role: TradeRole::Maker,
+        local_swap_role: deal_1.swap_role(&TradeRole::Maker),
+        failure_reason: None,
+        expires_at_block: None,
+        expires_in: None,
+        deal: deal_1,
     };
     let status_2 = DealInfo {
         serialized_deal: deal_2.to_string(),
-        deal: deal_2,
         status: DealStatus::Open,
         local_trade_role: TradeRole::Maker,
+        local_swap_role: deal_2.swap_role(&TradeRole::Maker),
+        failure_reason: None,
+        expires_at_block: None,
+        expires_in: None,
+        deal: deal_2,
     };
     assert!(deals_retrieved.len() == 2);
     assert!(deals_retrieved.contains(&status_1));
     assert!(deals_retrieved.contains(&status_2));
+
+    database
+        .set_deal_abort_reason(
+            &status_1.deal,
+            &AbortReason::UserRequested("price moved".to_string()),
+        )
+        .unwrap();
+    let deals_retrieved = database.get_deals(DealStatusSelector::Ended).unwrap();
+    assert_eq!(
+        deals_retrieved[0].failure_reason,
+        Some("user requested: price moved".to_string())
+    );
+}
+
+#[test]
+fn test_swap_state_export_roundtrip() {
+    use farcaster_core::Uuid;
+
+    let path = std::env::temp_dir().join(format!("farcaster-swap-state-{}", Uuid::new()));
+    std::fs::create_dir_all(&path).unwrap();
+    let mut database = Database::new(path.clone()).unwrap();
+    let swap_id: SwapId = Uuid::new().into();
+    let key = CheckpointKey {
+        swap_id,
+        service_id: ServiceId::Swap(swap_id),
+    };
+    let state = vec![0, 1, 2, 3, 4];
+    database.set_checkpoint_state(&key, &state).unwrap();
+
+    let export = SwapStateExport {
+        swap_id,
+        state: database.get_checkpoint_state(&key).unwrap(),
+    };
+    let imported = SwapStateExport::from_base64(&export.to_base64()).unwrap();
+    assert_eq!(imported, export);
+
+    database.delete_checkpoint_state(key.clone()).unwrap();
+    database
+        .set_checkpoint_state(
+            &CheckpointKey {
+                swap_id: imported.swap_id,
+                service_id: ServiceId::Swap(imported.swap_id),
+            },
+            &imported.state,
+        )
+        .unwrap();
+    assert_eq!(database.get_checkpoint_state(&key).unwrap(), state);
+    assert!(SwapStateExport::from_base64("not a swap state").is_err());
+
+    std::fs::remove_dir_all(path).unwrap();
 }
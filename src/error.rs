@@ -74,6 +74,16 @@ pub enum Error {
     /// Invalid walletd token error
     InvalidToken,
 
+    /// Deal has {count} connected taker(s) that did not commit yet, force the revocation to revoke
+    /// it anyway
+    DealHasActiveConnections { count: usize },
+
+    /// Deal has a swap in progress, abort the swap instead of revoking the deal
+    DealSwapInProgress,
+
+    /// Deal is not open, it already ended or is unknown to the daemon
+    DealNotOpen,
+
     /// Syncer microservice errors
     #[display(inner)]
     #[from]
@@ -89,6 +99,24 @@ pub enum Error {
     #[from]
     Checkpoint(lmdb::Error),
 
+    /// Syncer task store errors
+    #[cfg(feature = "node")]
+    #[display(inner)]
+    #[from]
+    TaskStore(sled::Error),
+
+    /// Checkpoint consistency errors, detected before restoring a swap
+    #[cfg(feature = "node")]
+    #[display(inner)]
+    #[from]
+    CheckpointValidation(crate::swapd::CheckpointValidationError),
+
+    /// Checkpoint format migration errors, when restoring a checkpoint written by another version
+    #[cfg(feature = "node")]
+    #[display(inner)]
+    #[from]
+    CheckpointMigration(crate::databased::checkpoint::MigrationError),
+
     /// Bitcoin key errors
     #[display(inner)]
     #[from]
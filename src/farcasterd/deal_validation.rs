@@ -0,0 +1,242 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::convert::TryInto;
+
+use farcaster_core::bitcoin::fee::SatPerKvB;
+use farcaster_core::blockchain::FeeStrategy;
+use farcaster_core::consensus;
+use farcaster_core::role::TradeRole;
+use farcaster_core::swap::btcxmr::Deal;
+
+use crate::bus::ctl::{DealExpiry, ProtoDeal};
+use crate::config::Config;
+use crate::swapd::TemporalSafety;
+use crate::{AccordantBlockchain, ArbitratingBlockchain};
+
+#[derive(Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum DealPreValidationError {
+    /// No swap configuration for the deal: {0}
+    MissingSwapConfig(String),
+
+    /// Deal temporal parameters are unsafe: {0}
+    UnsafeTemporalParameters(String),
+
+    /// Deal amounts are outside of the configured limits: {0}
+    InvalidAmounts(String),
+
+    /// Provided addresses do not match the deal: {0}
+    InvalidAddresses(String),
+
+    /// Deal expired at block {expires_at_block}, the current Bitcoin height is {bitcoin_height}
+    Expired {
+        expires_at_block: u64,
+        bitcoin_height: u64,
+    },
+}
+
+#[derive(Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum MakeParamsValidationError {
+    /// No swap configuration for the deal: {0}
+    MissingSwapConfig(String),
+
+    /// Deal temporal parameters are unsafe: {0}
+    UnsafeTemporalParameters(String),
+
+    /// Deal amounts are outside of the configured limits: {0}
+    InvalidAmounts(String),
+
+    /// Provided addresses do not match the deal: {0}
+    InvalidAddresses(String),
+
+    /// Deal fee rate of {fee_rate} sat/kvB is below the current low priority estimate of {estimate} sat/kvB, the swap transactions may not confirm in time
+    FeeBelowEstimate { fee_rate: u64, estimate: u64 },
+}
+
+/// Checks the parameters of a deal about to be made against the configuration, without making
+/// the deal. Returns the temporal safety parameters the swap would run with.
+pub fn validate_make_params(
+    config: &Config,
+    proto_deal: &ProtoDeal,
+) -> Result<TemporalSafety, MakeParamsValidationError> {
+    let parameters = &proto_deal.deal_parameters;
+    let missing_config =
+        |err: consensus::Error| MakeParamsValidationError::MissingSwapConfig(err.to_string());
+    let arb: ArbitratingBlockchain = parameters
+        .arbitrating_blockchain
+        .try_into()
+        .map_err(missing_config)?;
+    let acc: AccordantBlockchain = parameters
+        .accordant_blockchain
+        .try_into()
+        .map_err(missing_config)?;
+    let swap_config = config
+        .get_swap_config(arb, acc, parameters.network)
+        .map_err(|err| MakeParamsValidationError::MissingSwapConfig(err.to_string()))?;
+    let temporal_safety = TemporalSafety::from_deal_parameters(
+        parameters,
+        swap_config.arbitrating.finality.into(),
+        swap_config.accordant.finality.into(),
+        swap_config.arbitrating.safety.into(),
+    )
+    .map_err(|err| MakeParamsValidationError::UnsafeTemporalParameters(err.to_string()))?;
+
+    config
+        .validate_deal_amounts(parameters, TradeRole::Maker)
+        .map_err(|err| MakeParamsValidationError::InvalidAmounts(err.to_string()))?;
+    config
+        .validate_deal_addresses(
+            parameters,
+            &proto_deal.arbitrating_addr,
+            &proto_deal.accordant_addr,
+        )
+        .map_err(|err| MakeParamsValidationError::InvalidAddresses(err.to_string()))?;
+    Ok(temporal_safety)
+}
+
+/// Checks the deal fee strategy against the current low priority fee estimate, the cancel,
+/// refund and punish transactions must confirm before the timelocks expire
+pub fn validate_fee_strategy(
+    fee_strategy: &FeeStrategy<SatPerKvB>,
+    low_priority_sat_per_kvb: u64,
+) -> Result<(), MakeParamsValidationError> {
+    let fee_rate = match fee_strategy {
+        FeeStrategy::Fixed(fee_rate) => fee_rate.as_native_unit().as_sat(),
+    };
+    if fee_rate < low_priority_sat_per_kvb {
+        return Err(MakeParamsValidationError::FeeBelowEstimate {
+            fee_rate,
+            estimate: low_priority_sat_per_kvb,
+        });
+    }
+    Ok(())
+}
+
+/// Checks run by a taker against a deal before connecting to the maker, so that an invalid deal
+/// is refused before revealing anything to the counterparty. Nothing is sent over the network.
+pub trait DealPreValidation {
+    fn pre_validate(
+        &self,
+        config: &Config,
+        arb_addr: &bitcoin::Address,
+        acc_addr: &monero::Address,
+        expiry: Option<&DealExpiry>,
+    ) -> Result<(), DealPreValidationError>;
+}
+
+impl DealPreValidation for Deal {
+    fn pre_validate(
+        &self,
+        config: &Config,
+        arb_addr: &bitcoin::Address,
+        acc_addr: &monero::Address,
+        expiry: Option<&DealExpiry>,
+    ) -> Result<(), DealPreValidationError> {
+        let parameters = &self.parameters;
+
+        if let Some(&DealExpiry {
+            expires_at_block,
+            bitcoin_height,
+        }) = expiry
+        {
+            if bitcoin_height >= expires_at_block {
+                return Err(DealPreValidationError::Expired {
+                    expires_at_block,
+                    bitcoin_height,
+                });
+            }
+        }
+
+        // the deal timelocks must be coherent with our finality and safety parameters
+        let missing_config =
+            |err: consensus::Error| DealPreValidationError::MissingSwapConfig(err.to_string());
+        let arb: ArbitratingBlockchain = parameters
+            .arbitrating_blockchain
+            .try_into()
+            .map_err(missing_config)?;
+        let acc: AccordantBlockchain = parameters
+            .accordant_blockchain
+            .try_into()
+            .map_err(missing_config)?;
+        let swap_config = config
+            .get_swap_config(arb, acc, parameters.network)
+            .map_err(|err| DealPreValidationError::MissingSwapConfig(err.to_string()))?;
+        TemporalSafety::from_deal_parameters(
+            parameters,
+            swap_config.arbitrating.finality.into(),
+            swap_config.accordant.finality.into(),
+            swap_config.arbitrating.safety.into(),
+        )
+        .map_err(|err| DealPreValidationError::UnsafeTemporalParameters(err.to_string()))?;
+
+        config
+            .validate_deal_amounts(parameters, TradeRole::Taker)
+            .map_err(|err| DealPreValidationError::InvalidAmounts(err.to_string()))?;
+        config
+            .validate_deal_addresses(parameters, arb_addr, acc_addr)
+            .map_err(|err| DealPreValidationError::InvalidAddresses(err.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::swapd::ConfirmationBounds;
+    use farcaster_core::bitcoin::timelock::CSVTimelock;
+    use std::str::FromStr;
+
+    #[test]
+    fn make_params_validation_failures() {
+        let deal = fixtures::deal();
+        let mut proto_deal = ProtoDeal {
+            deal_parameters: deal.parameters,
+            public_addr: deal.peer_address,
+            arbitrating_addr: bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov")
+                .unwrap(),
+            accordant_addr: monero::Address::from_str("43qHP7gSJJf8HZw1G3ZmpWVyYnbxkKdfta34Qj2nuRENjAsXBtj9JcMWcYMeT3n4NyTZqxhUkKgsTS6P2TNgM6ksM32czSp").unwrap(),
+            confirmation_bounds: ConfirmationBounds::default(),
+            deal_expiry: None,
+        };
+        proto_deal.deal_parameters.cancel_timelock = CSVTimelock::new(10);
+        proto_deal.deal_parameters.punish_timelock = CSVTimelock::new(5);
+        assert!(matches!(
+            validate_make_params(&Config::default(), &proto_deal),
+            Err(MakeParamsValidationError::UnsafeTemporalParameters(_))
+        ));
+
+        let fee_strategy = FeeStrategy::Fixed(SatPerKvB::from_sat(1000));
+        assert!(validate_fee_strategy(&fee_strategy, 1000).is_ok());
+        assert!(matches!(
+            validate_fee_strategy(&fee_strategy, 1001),
+            Err(MakeParamsValidationError::FeeBelowEstimate {
+                fee_rate: 1000,
+                estimate: 1001
+            })
+        ));
+    }
+
+    #[test]
+    fn pre_validation_refuses_expired_deals() {
+        let deal = fixtures::deal();
+        let arb_addr = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let acc_addr = monero::Address::from_str("43qHP7gSJJf8HZw1G3ZmpWVyYnbxkKdfta34Qj2nuRENjAsXBtj9JcMWcYMeT3n4NyTZqxhUkKgsTS6P2TNgM6ksM32czSp").unwrap();
+        let expiry = DealExpiry {
+            expires_at_block: 100,
+            bitcoin_height: 100,
+        };
+        assert!(matches!(
+            deal.pre_validate(&Config::default(), &arb_addr, &acc_addr, Some(&expiry)),
+            Err(DealPreValidationError::Expired {
+                expires_at_block: 100,
+                bitcoin_height: 100
+            })
+        ));
+    }
+}
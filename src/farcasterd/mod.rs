@@ -4,13 +4,17 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+mod deal_validation;
 #[cfg(feature = "shell")]
 mod opts;
 mod runtime;
 pub mod stats;
+mod stun;
+mod swap_cost;
 mod syncer_state_machine;
 mod trade_state_machine;
 
+pub use deal_validation::DealPreValidation;
 #[cfg(feature = "shell")]
-pub use opts::Opts;
+pub use opts::{Opts, FARCASTER_CONFIG};
 pub use runtime::run;
@@ -4,18 +4,25 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use crate::bus::ctl::{CtlMsg, FundingInfo, GetKeys, SwapKeys};
-use crate::bus::info::FundingInfos;
+use crate::bus::ctl::{CtlMsg, DealRevocation, FundingInfo, GetKeys, ReconnectAttempt, SwapKeys};
+use crate::bus::info::{BatchRevokeResult, FundingInfos, PeerReputationInfo, RevokedDeals};
 use crate::bus::p2p::{PeerMsg, TakerCommit};
 use crate::bus::sync::SyncMsg;
 use crate::bus::{BusMsg, DealInfo, DealStatus, List, ServiceBus};
 use crate::event::StateMachineExecutor;
 use crate::farcasterd::stats::Stats;
+use crate::farcasterd::stun::{ExternalIp, StunClient};
 use crate::farcasterd::syncer_state_machine::{SyncerStateMachine, SyncerStateMachineExecutor};
-use crate::farcasterd::trade_state_machine::{TradeStateMachine, TradeStateMachineExecutor};
+use crate::farcasterd::trade_state_machine::{
+    active_swap_count, TradeStateMachine, TradeStateMachineExecutor,
+};
 use crate::farcasterd::Opts;
-use crate::syncerd::{AddressBalance, TaskAborted};
-use crate::syncerd::{Event as SyncerEvent, HealthResult, SweepSuccess, TaskId};
+use crate::swapd::{ConfirmationBounds, BITCOIN_BLOCK_TIME_SECS};
+use crate::syncerd::{Abort, AddressBalance, TaskAborted, TaskTarget};
+use crate::syncerd::{
+    Event as SyncerEvent, FeeEstimation, HealthResult, HeightChanged, SweepSuccess, Task, TaskId,
+    WatchHeight,
+};
 use crate::{
     bus::ctl::{Keys, ProgressStack, Token},
     bus::info::{DealStatusSelector, InfoMsg, NodeInfo, ProgressEvent, SwapProgress},
@@ -32,9 +39,11 @@ use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::io;
 use std::iter::FromIterator;
+use std::net::IpAddr;
 use std::process;
 use std::time::{Duration, SystemTime};
 
+use bitcoin::hashes::hex::ToHex;
 use bitcoin::{secp256k1::PublicKey, secp256k1::SecretKey};
 use clap::IntoApp;
 use farcaster_core::{
@@ -76,6 +85,8 @@ pub fn run(
         );
     }
 
+    let external_ip = config.get_stun_server().map(StunClient::spawn_discovery);
+
     let runtime = Runtime {
         identity: ServiceId::Farcasterd,
         node_secret_key: None,
@@ -86,6 +97,16 @@ pub fn run(
         spawning_services: none!(),
         registered_services: none!(),
         deals: none!(),
+        confirmation_bounds: none!(),
+        deal_creation_heights: none!(),
+        deal_creation_times: none!(),
+        pending_deal_expiries: none!(),
+        deal_expiry_heights: none!(),
+        deal_expiry_watches: none!(),
+        bitcoin_heights: none!(),
+        expired_deals_revocations: vec![],
+        pending_peer_lists: none!(),
+        external_ip,
         wallet_token,
         progress: none!(),
         progress_subscriptions: none!(),
@@ -111,6 +132,16 @@ pub struct Runtime {
     pub spawning_services: HashSet<ServiceId>, // Services that have been launched, but have not replied with Hello yet
     pub registered_services: HashSet<ServiceId>, // Services that have announced themselves with Hello
     pub deals: HashSet<Deal>, // The set of all known deals. Includes open, consumed and ended deals includes open, consumed and ended deals
+    pub confirmation_bounds: HashMap<Deal, ConfirmationBounds>, // Set by MakeDeal, the confirmation bounds requested for the made deal, consumed when the swap is launched
+    deal_creation_heights: HashMap<Deal, u64>, // Set by MakeDeal if the Bitcoin height of the deal's network is watched, the height when the deal was made, cleared when the deal is consumed or revoked
+    deal_creation_times: HashMap<Deal, SystemTime>, // Set by MakeDeal, the time when the deal was made, cleared when the deal is consumed or revoked
+    pending_deal_expiries: HashMap<Deal, u32>, // Set by MakeDeal with an expiry, the expiry in blocks of deals made before the Bitcoin height was known
+    deal_expiry_heights: HashMap<Deal, u64>, // Set once the Bitcoin height is known, the height at which the open deal is revoked, cleared when the deal is consumed or revoked
+    deal_expiry_watches: HashMap<Network, TaskId>, // Set by the first MakeDeal on a network, the syncer task watching the Bitcoin height
+    bitcoin_heights: HashMap<Network, u64>, // Last Bitcoin height reported by the deal expiry watches
+    expired_deals_revocations: Vec<ExpiredDealsRevocation>, // Set by RevokeExpiredDeals, the requests waiting for the Bitcoin height of the networks of the open deals
+    pending_peer_lists: VecDeque<(ServiceId, Vec<NodeAddr>)>, // Set by ListPeers, the clients and their listed peers waiting for the peer reputations from databased
+    external_ip: Option<ExternalIp>, // Set on Runtime instantiation when a STUN server is configured, filled by the lookup thread
     progress: HashMap<ServiceId, VecDeque<ProgressStack>>, // A mapping from Swap ServiceId to its sent and received progress messages (Progress, Success, Failure)
     progress_subscriptions: HashMap<ServiceId, HashSet<ServiceId>>, // A mapping from a Client ServiceId to its subsribed swap progresses
     pub stats: Stats,             // Some stats about deals and swaps
@@ -120,6 +151,16 @@ pub struct Runtime {
     syncer_state_machines: HashMap<TaskId, SyncerStateMachine>, // New syncer state machines are inserted by their syncer task id when sending a syncer request and destroyed upon matching syncer request receival
 }
 
+/// A revoke-expired-deals request waiting for the Bitcoin height of the networks of the open
+/// deals, queried from the syncers on demand
+struct ExpiredDealsRevocation {
+    source: ServiceId,
+    force: bool,
+    height_queries: HashMap<TaskId, Network>,
+    heights: HashMap<Network, u64>,
+    errors: Vec<String>,
+}
+
 impl CtlServer for Runtime {}
 
 impl esb::Handler<ServiceBus> for Runtime {
@@ -198,6 +239,407 @@ impl esb::Handler<ServiceBus> for Runtime {
 }
 
 impl Runtime {
+    /// The external ip address discovered through STUN, if the lookup is enabled and finished
+    pub fn external_ip(&self) -> Option<IpAddr> {
+        self.external_ip
+            .as_ref()
+            .and_then(|external_ip| external_ip.lock().ok().and_then(|ip| *ip))
+    }
+
+    /// Whether the punish timelock of the deal elapsed at the Bitcoin height. The creation height
+    /// of a deal made while the height of its network was not watched is estimated from the age
+    /// of the deal.
+    fn deal_expired(&self, deal: &Deal, height: u64) -> bool {
+        let created = match self.deal_creation_heights.get(deal) {
+            Some(created) => *created,
+            None => match self
+                .deal_creation_times
+                .get(deal)
+                .and_then(|created| created.elapsed().ok())
+            {
+                Some(age) => height.saturating_sub(age.as_secs() / BITCOIN_BLOCK_TIME_SECS),
+                None => return false,
+            },
+        };
+        height >= created + deal.parameters.punish_timelock.as_u32() as u64
+    }
+
+    /// Revoke all the expired open deals in one go once the Bitcoin height of their networks is
+    /// known. The height is asked to the syncers on demand, unless it is already watched for the
+    /// deal expiries. Every revocation is attempted and the failures, including the networks
+    /// without a syncer, are collected in the summary instead of interrupting the batch.
+    fn revoke_expired_deals(
+        &mut self,
+        endpoints: &mut Endpoints,
+        source: ServiceId,
+        force: bool,
+    ) -> Result<(), Error> {
+        let networks: HashSet<Network> = self
+            .trade_state_machines
+            .iter()
+            .filter_map(|tsm| tsm.open_deal())
+            .map(|deal| deal.parameters.network)
+            .collect();
+        let mut revocation = ExpiredDealsRevocation {
+            source,
+            force,
+            height_queries: none!(),
+            heights: none!(),
+            errors: vec![],
+        };
+        for network in networks {
+            if let (true, Some(height)) = (
+                self.deal_expiry_watches.contains_key(&network),
+                self.bitcoin_heights.get(&network),
+            ) {
+                revocation.heights.insert(network, *height);
+                continue;
+            }
+            // if the syncer is not up the query is sent when it says hello
+            match syncer_up(
+                &mut self.spawning_services,
+                &mut self.registered_services,
+                Blockchain::Bitcoin,
+                network,
+                &self.config,
+            ) {
+                Ok(syncer) => {
+                    let task_id = TaskId(self.syncer_task_counter);
+                    self.syncer_task_counter += 1;
+                    revocation.height_queries.insert(task_id, network);
+                    if let Some(syncer) = syncer {
+                        self.send_height_watch(endpoints, syncer, task_id)?;
+                    }
+                }
+                Err(err) => revocation.errors.push(format!(
+                    "Cannot get the Bitcoin height of {}: {}",
+                    network, err
+                )),
+            }
+        }
+        if revocation.height_queries.is_empty() {
+            self.complete_expired_deals_revocation(endpoints, revocation)
+        } else {
+            self.expired_deals_revocations.push(revocation);
+            Ok(())
+        }
+    }
+
+    fn complete_expired_deals_revocation(
+        &mut self,
+        endpoints: &mut Endpoints,
+        revocation: ExpiredDealsRevocation,
+    ) -> Result<(), Error> {
+        let expired_deals: Vec<Deal> = self
+            .trade_state_machines
+            .iter()
+            .filter_map(|tsm| tsm.open_deal())
+            .filter(|deal| {
+                revocation
+                    .heights
+                    .get(&deal.parameters.network)
+                    .map_or(false, |height| self.deal_expired(deal, *height))
+            })
+            .collect();
+        let mut revoked_deals: RevokedDeals = self
+            .revoke_open_deals(endpoints, expired_deals, revocation.force)
+            .into();
+        revoked_deals.errors.extend(revocation.errors);
+        self.send_client_info(
+            endpoints,
+            revocation.source,
+            InfoMsg::RevokedDeals(revoked_deals),
+        )
+    }
+
+    /// Record the Bitcoin height answering an on-demand height query, and complete the
+    /// revoke-expired-deals request once all its heights are known. Returns false if the task is
+    /// not a height query.
+    fn handle_height_query(
+        &mut self,
+        endpoints: &mut Endpoints,
+        syncer: ServiceId,
+        task_id: TaskId,
+        height: u64,
+    ) -> Result<bool, Error> {
+        let position = match self
+            .expired_deals_revocations
+            .iter()
+            .position(|revocation| revocation.height_queries.contains_key(&task_id))
+        {
+            Some(position) => position,
+            None => return Ok(false),
+        };
+        // the height is watched, stop it now that a value was received
+        endpoints.send_to(
+            ServiceBus::Sync,
+            self.identity(),
+            syncer,
+            BusMsg::Sync(SyncMsg::Task(Task::Abort(Abort {
+                task_target: TaskTarget::TaskId(task_id),
+                respond: false,
+            }))),
+        )?;
+        let revocation = &mut self.expired_deals_revocations[position];
+        if let Some(network) = revocation.height_queries.remove(&task_id) {
+            revocation.heights.insert(network, height);
+        }
+        if revocation.height_queries.is_empty() {
+            let revocation = self.expired_deals_revocations.remove(position);
+            self.complete_expired_deals_revocation(endpoints, revocation)?;
+            self.clean_up_after_syncer_usage(endpoints)?;
+        }
+        Ok(true)
+    }
+
+    /// Whether an on-demand height query is pending on the network
+    fn has_height_query(&self, network: Network) -> bool {
+        self.expired_deals_revocations
+            .iter()
+            .flat_map(|revocation| revocation.height_queries.values())
+            .any(|query_network| *query_network == network)
+    }
+
+    /// Revoke the listed deals in one go. The deals with a swap in progress and the deals not
+    /// open anymore are reported as failed, they are never revoked.
+    fn revoke_deals(&mut self, endpoints: &mut Endpoints, deals: Vec<Deal>) -> BatchRevokeResult {
+        let mut open_deals = vec![];
+        let mut failed = vec![];
+        for deal in deals {
+            let tsm = self
+                .trade_state_machines
+                .iter()
+                .find(|tsm| tsm.deal() == Some(&deal));
+            match tsm {
+                Some(tsm) if tsm.open_deal().is_some() => open_deals.push(deal),
+                Some(_) => failed.push((deal, Error::DealSwapInProgress.to_string())),
+                None => failed.push((deal, Error::DealNotOpen.to_string())),
+            }
+        }
+        let mut result = self.revoke_open_deals(endpoints, open_deals, false);
+        result.failed.extend(failed);
+        result
+    }
+
+    fn revoke_open_deals(
+        &mut self,
+        endpoints: &mut Endpoints,
+        deals: Vec<Deal>,
+        force: bool,
+    ) -> BatchRevokeResult {
+        let count = self.pending_peer_connections();
+        let mut result = BatchRevokeResult {
+            revoked: vec![],
+            failed: vec![],
+        };
+        for deal in deals {
+            if count > 0 && !force {
+                let err = Error::DealHasActiveConnections { count };
+                result.failed.push((deal, err.to_string()));
+                continue;
+            }
+            if let Err(err) = endpoints.send_to(
+                ServiceBus::Ctl,
+                self.identity(),
+                ServiceId::Database,
+                BusMsg::Ctl(CtlMsg::SetDealInfo(DealInfo {
+                    deal: deal.clone(),
+                    serialized_deal: deal.to_string(),
+                    status: DealStatus::Revoked,
+                    local_trade_role: TradeRole::Maker,
+                    local_swap_role: deal.swap_role(&TradeRole::Maker),
+                    failure_reason: None,
+                    expires_at_block: None,
+                    expires_in: None,
+                })),
+            ) {
+                result.failed.push((deal, err.to_string()));
+                continue;
+            }
+            self.trade_state_machines
+                .retain(|tsm| tsm.open_deal().as_ref() != Some(&deal));
+            self.confirmation_bounds.remove(&deal);
+            self.clear_deal_heights(&deal);
+            info!("Revoked deal {}", deal.label());
+            result.revoked.push(deal);
+        }
+        result
+    }
+
+    /// Register the creation time of a newly made deal, and its creation height if the Bitcoin
+    /// height of the deal's network is watched. No syncer is needed to make a deal, the height
+    /// is only queried when the expired deals are revoked.
+    pub fn set_deal_creation(&mut self, deal: &Deal) {
+        let network = deal.parameters.network;
+        if self.deal_expiry_watches.contains_key(&network) {
+            if let Some(height) = self.bitcoin_heights.get(&network) {
+                self.deal_creation_heights.insert(deal.clone(), *height);
+            }
+        }
+        self.deal_creation_times
+            .insert(deal.clone(), SystemTime::now());
+    }
+
+    /// Register the expiry, in blocks from the current Bitcoin height, of a newly made deal. The
+    /// expiry height is fixed as soon as the Bitcoin height of the deal's network is known.
+    pub fn set_deal_expiry(
+        &mut self,
+        endpoints: &mut Endpoints,
+        deal: &Deal,
+        blocks: u32,
+    ) -> Result<(), Error> {
+        let network = deal.parameters.network;
+        if let Some(height) = self.bitcoin_heights.get(&network) {
+            self.deal_expiry_heights
+                .insert(deal.clone(), height + blocks as u64);
+        } else {
+            self.pending_deal_expiries.insert(deal.clone(), blocks);
+        }
+        self.watch_bitcoin_height(endpoints, network)
+    }
+
+    fn watch_bitcoin_height(
+        &mut self,
+        endpoints: &mut Endpoints,
+        network: Network,
+    ) -> Result<(), Error> {
+        // the syncer may have been terminated since the watch was registered, if it is not up
+        // the watch is sent when it says hello
+        let syncer = syncer_up(
+            &mut self.spawning_services,
+            &mut self.registered_services,
+            Blockchain::Bitcoin,
+            network,
+            &self.config,
+        )?;
+        if self.deal_expiry_watches.contains_key(&network) {
+            return Ok(());
+        }
+        let task_id = TaskId(self.syncer_task_counter);
+        self.syncer_task_counter += 1;
+        self.deal_expiry_watches.insert(network, task_id);
+        if let Some(syncer) = syncer {
+            self.send_height_watch(endpoints, syncer, task_id)?;
+        }
+        Ok(())
+    }
+
+    fn send_height_watch(
+        &mut self,
+        endpoints: &mut Endpoints,
+        syncer: ServiceId,
+        task_id: TaskId,
+    ) -> Result<(), Error> {
+        endpoints.send_to(
+            ServiceBus::Sync,
+            self.identity(),
+            syncer,
+            BusMsg::Sync(SyncMsg::Task(Task::WatchHeight(WatchHeight {
+                id: task_id,
+                lifetime: u64::MAX,
+            }))),
+        )?;
+        Ok(())
+    }
+
+    /// Forget the creation and the expiry of a consumed or revoked deal
+    pub fn clear_deal_heights(&mut self, deal: &Deal) {
+        self.deal_creation_heights.remove(deal);
+        self.deal_creation_times.remove(deal);
+        self.pending_deal_expiries.remove(deal);
+        self.deal_expiry_heights.remove(deal);
+    }
+
+    /// Whether a deal on the network still has a pending or active expiry, i.e. the Bitcoin
+    /// syncer of the network is needed to watch the height
+    fn has_deal_expiry(&self, network: Network) -> bool {
+        self.deal_expiry_watches.contains_key(&network)
+            && self
+                .pending_deal_expiries
+                .keys()
+                .chain(self.deal_expiry_heights.keys())
+                .any(|deal| deal.parameters.network == network)
+    }
+
+    /// Blocks and estimated time left before the deal expires, None if the deal has no expiry
+    /// height yet
+    fn deal_expires_in(&self, deal: &Deal) -> Option<String> {
+        let expiry = self.deal_expiry_heights.get(deal)?;
+        let height = self.bitcoin_heights.get(&deal.parameters.network)?;
+        let blocks = expiry.saturating_sub(*height);
+        let minutes = blocks * BITCOIN_BLOCK_TIME_SECS / 60;
+        Some(format!(
+            "{} blocks (~{}h{:02}m)",
+            blocks,
+            minutes / 60,
+            minutes % 60
+        ))
+    }
+
+    /// Open deals on the network whose expiry height is reached at the given height
+    fn deals_expired_at(&self, network: Network, height: u64) -> Vec<Deal> {
+        self.trade_state_machines
+            .iter()
+            .filter_map(|tsm| tsm.open_deal())
+            .filter(|deal| deal.parameters.network == network)
+            .filter(|deal| {
+                self.deal_expiry_heights
+                    .get(deal)
+                    .map_or(false, |expiry| height >= *expiry)
+            })
+            .collect()
+    }
+
+    /// Fix the expiry heights of the deals made before the height was known, then
+    /// revoke the deals that expired. Failed revocations are retried on the next block.
+    fn handle_deal_expiry_height(
+        &mut self,
+        endpoints: &mut Endpoints,
+        network: Network,
+        height: u64,
+    ) {
+        self.bitcoin_heights.insert(network, height);
+        let pending: Vec<Deal> = self
+            .pending_deal_expiries
+            .keys()
+            .filter(|deal| deal.parameters.network == network)
+            .cloned()
+            .collect();
+        for deal in pending {
+            if let Some(blocks) = self.pending_deal_expiries.remove(&deal) {
+                self.deal_expiry_heights
+                    .insert(deal, height + blocks as u64);
+            }
+        }
+        let expired_deals = self.deals_expired_at(network, height);
+        if expired_deals.is_empty() {
+            return;
+        }
+        let result = self.revoke_open_deals(endpoints, expired_deals, false);
+        for (deal, error) in result.failed {
+            warn!(
+                "Failed to revoke expired deal, retrying on the next block: {}: {}",
+                deal.id(),
+                error
+            );
+        }
+    }
+
+    /// Number of connected peers not yet bound to a trade, i.e. takers that may still be in the
+    /// handshake phase before committing to a deal
+    pub fn pending_peer_connections(&self) -> usize {
+        self.registered_services
+            .iter()
+            .filter(|service| matches!(service, ServiceId::Peer(..)))
+            .filter(|service| {
+                !self
+                    .trade_state_machines
+                    .iter()
+                    .any(|tsm| tsm.get_connection().as_ref() == Some(*service))
+            })
+            .count()
+    }
+
     fn handle_msg(
         &mut self,
         endpoints: &mut Endpoints,
@@ -272,7 +714,7 @@ impl Runtime {
                     ServiceId::Swap(_) => {
                         // nothing to do, we register swapd instances on a by-swap basis
                     }
-                    ServiceId::Syncer(_, _) => {
+                    ServiceId::Syncer(blockchain, network) => {
                         if self.spawning_services.remove(&source) {
                             self.registered_services.insert(source.clone());
                             info!(
@@ -280,6 +722,22 @@ impl Runtime {
                                 source,
                                 self.count_syncers().bright_blue_bold()
                             );
+                            if *blockchain == Blockchain::Bitcoin {
+                                if let Some(task_id) = self.deal_expiry_watches.get(network) {
+                                    let task_id = *task_id;
+                                    self.send_height_watch(endpoints, source.clone(), task_id)?;
+                                }
+                                let height_queries: Vec<TaskId> = self
+                                    .expired_deals_revocations
+                                    .iter()
+                                    .flat_map(|revocation| revocation.height_queries.iter())
+                                    .filter(|(_, query_network)| *query_network == network)
+                                    .map(|(task_id, _)| *task_id)
+                                    .collect();
+                                for task_id in height_queries {
+                                    self.send_height_watch(endpoints, source.clone(), task_id)?;
+                                }
+                            }
                         } else {
                             error!(
                                 "Syncer {} was already registered; the service probably was relaunched\\
@@ -327,8 +785,23 @@ impl Runtime {
                 }
             }
 
+            CtlMsg::RevokeExpiredDeals(force) => {
+                self.revoke_expired_deals(endpoints, source, force)?;
+            }
+
+            CtlMsg::RevokeDeals(deals) => {
+                let result = self.revoke_deals(endpoints, deals.iter().cloned().collect());
+                self.send_client_info(endpoints, source, InfoMsg::BatchRevokeResult(result))?;
+            }
+
             CtlMsg::Keys(Keys(sk, pk)) => {
                 debug!("received peerd keys {}", sk.display_secret());
+                if self.node_public_key.is_none() {
+                    info!(
+                        "Node fingerprint: {}",
+                        pk.serialize()[..8].to_hex().bright_yellow_bold()
+                    );
+                }
                 self.node_secret_key = Some(sk);
                 self.node_public_key = Some(pk);
                 self.handle_auto_restore(endpoints)?;
@@ -376,6 +849,12 @@ impl Runtime {
                                 queue.pop_back();
                             }
                         }
+                        // Only the latest summary is kept
+                        if let Progress::Summary(_) = p {
+                            queue.retain(|prog| {
+                                !matches!(prog, ProgressStack::Progress(Progress::Summary(_)))
+                            });
+                        }
                         (ProgressStack::Progress(p.clone()), InfoMsg::Progress(p))
                     }
                     CtlMsg::Success(s) => (ProgressStack::Success(s.clone()), InfoMsg::Success(s)),
@@ -435,12 +914,56 @@ impl Runtime {
                 )?;
             }
 
+            // the node key is the one of the node id used in deals and peer connections
+            InfoMsg::GetNodePubkey => match self.peer_keys_ready() {
+                Ok((_, pk)) => {
+                    self.send_client_info(endpoints, source, InfoMsg::NodePubkey(pk))?;
+                }
+                Err(err) => {
+                    self.send_client_ctl(
+                        endpoints,
+                        source,
+                        CtlMsg::Failure(Failure {
+                            code: FailureCode::Unknown,
+                            info: err.to_string(),
+                        }),
+                    )?;
+                }
+            },
+
+            // The reputations of the peers are retrieved from databased, the list is sent to
+            // the client once they are received
             InfoMsg::ListPeers => {
-                self.send_client_info(
-                    endpoints,
-                    source,
-                    InfoMsg::PeerList(self.get_open_connections().into()),
+                let peers = self.get_open_connections();
+                endpoints.send_to(
+                    ServiceBus::Info,
+                    self.identity(),
+                    ServiceId::Database,
+                    BusMsg::Info(InfoMsg::GetPeerReputations(
+                        peers.iter().map(|peer| peer.id).collect(),
+                    )),
                 )?;
+                self.pending_peer_lists.push_back((source, peers));
+            }
+
+            // databased answers the reputation requests in order
+            InfoMsg::PeerReputations(reputations) if source == ServiceId::Database => {
+                if let Some((client, peers)) = self.pending_peer_lists.pop_front() {
+                    let peer_list = peers
+                        .into_iter()
+                        .zip(reputations.iter().cloned())
+                        .map(|(peer, reputation)| PeerReputationInfo {
+                            active_swap_count: active_swap_count(
+                                &self.trade_state_machines,
+                                &peer.id,
+                            ),
+                            completed_swap_count: reputation.successful + reputation.failed,
+                            peer,
+                            reputation,
+                        })
+                        .collect();
+                    self.send_client_info(endpoints, client, InfoMsg::PeerList(peer_list))?;
+                }
             }
 
             InfoMsg::ListSwaps => {
@@ -465,9 +988,13 @@ impl Runtime {
                             .filter_map(|tsm| tsm.open_deal())
                             .map(|deal| DealInfo {
                                 serialized_deal: deal.to_string(),
-                                deal,
                                 status: DealStatus::Open,
                                 local_trade_role: TradeRole::Maker,
+                                local_swap_role: deal.swap_role(&TradeRole::Maker),
+                                failure_reason: None,
+                                expires_at_block: self.deal_expiry_heights.get(&deal).copied(),
+                                expires_in: self.deal_expires_in(&deal),
+                                deal,
                             })
                             .collect();
                         self.send_client_info(endpoints, source, InfoMsg::DealList(open_deals))?;
@@ -479,9 +1006,13 @@ impl Runtime {
                             .filter_map(|tsm| tsm.consumed_deal())
                             .map(|(deal, trade_role)| DealInfo {
                                 serialized_deal: deal.to_string(),
-                                deal,
                                 status: DealStatus::InProgress,
                                 local_trade_role: trade_role,
+                                local_swap_role: deal.swap_role(&trade_role),
+                                failure_reason: None,
+                                expires_at_block: None,
+                                expires_in: None,
+                                deal,
                             })
                             .collect();
                         self.send_client_info(endpoints, source, InfoMsg::DealList(pub_deals))?;
@@ -507,7 +1038,10 @@ impl Runtime {
             // Returns a unique response that contains the complete progress queue
             InfoMsg::ReadProgress(swap_id) => {
                 if let Some(queue) = self.progress.get_mut(&ServiceId::Swap(swap_id)) {
-                    let mut swap_progress = SwapProgress { progress: vec![] };
+                    let mut swap_progress = SwapProgress {
+                        progress: vec![],
+                        summary: None,
+                    };
                     for req in queue.iter() {
                         match req {
                             ProgressStack::Progress(Progress::Message(m)) => {
@@ -525,6 +1059,9 @@ impl Runtime {
                                     .progress
                                     .push(ProgressEvent::StateTransition(t.clone()));
                             }
+                            ProgressStack::Progress(Progress::Summary(s)) => {
+                                swap_progress.summary = Some(s.clone());
+                            }
                             ProgressStack::Success(s) => {
                                 swap_progress
                                     .progress
@@ -689,6 +1226,22 @@ impl Runtime {
         source: ServiceId,
         request: SyncMsg,
     ) -> Result<(), Error> {
+        if let SyncMsg::Event(SyncerEvent::HeightChanged(HeightChanged { id, height, .. })) =
+            &request
+        {
+            let network = self
+                .deal_expiry_watches
+                .iter()
+                .find(|(_, task_id)| *task_id == id)
+                .map(|(network, _)| *network);
+            if let Some(network) = network {
+                self.handle_deal_expiry_height(endpoints, network, *height);
+                return Ok(());
+            }
+            if self.handle_height_query(endpoints, source.clone(), *id, *height)? {
+                return Ok(());
+            }
+        }
         self.process_request_with_state_machines(BusMsg::Sync(request), source, endpoints)
     }
 
@@ -890,6 +1443,11 @@ impl Runtime {
             .values()
             .filter_map(|ssm| ssm.syncer())
             .any(|client_syncer| client_syncer == *syncerd)
+            || matches!(
+                syncerd,
+                ServiceId::Syncer(Blockchain::Bitcoin, network)
+                    if self.has_deal_expiry(*network) || self.has_height_query(*network)
+            )
     }
 
     fn count_syncers(&self) -> usize {
@@ -928,7 +1486,11 @@ impl Runtime {
         match (req, source) {
             (BusMsg::Ctl(CtlMsg::SweepAddress(..)), _)
             | (BusMsg::Ctl(CtlMsg::HealthCheck(..)), _)
-            | (BusMsg::Ctl(CtlMsg::GetBalance(..)), _) => Ok(Some(SyncerStateMachine::Start)),
+            | (BusMsg::Ctl(CtlMsg::GetBalance(..)), _)
+            | (BusMsg::Ctl(CtlMsg::EstimateSwapCost(..)), _)
+            | (BusMsg::Ctl(CtlMsg::ValidateMakeParams(..)), _) => {
+                Ok(Some(SyncerStateMachine::Start))
+            }
             (
                 BusMsg::Sync(SyncMsg::Event(SyncerEvent::SweepSuccess(SweepSuccess {
                     id, ..
@@ -947,6 +1509,12 @@ impl Runtime {
                     id, ..
                 }))),
                 _,
+            )
+            | (
+                BusMsg::Sync(SyncMsg::Event(SyncerEvent::FeeEstimation(FeeEstimation {
+                    id, ..
+                }))),
+                _,
             ) => Ok(self.syncer_state_machines.remove(id)),
             (BusMsg::Sync(SyncMsg::Event(SyncerEvent::TaskAborted(TaskAborted { id, .. }))), _) => {
                 // can only match to a syncer state machine if `id` vec is singleton, i.e. a single ssm.
@@ -994,7 +1562,7 @@ impl Runtime {
             (BusMsg::Ctl(CtlMsg::MakeDeal(..)), _) => Ok(Some(vec![TradeStateMachine::StartMaker])),
             (BusMsg::Ctl(CtlMsg::TakeDeal(..)), _) => Ok(Some(vec![TradeStateMachine::StartTaker])),
             (BusMsg::P2p(PeerMsg::TakerCommit(TakerCommit { deal, .. })), _)
-            | (BusMsg::Ctl(CtlMsg::RevokeDeal(deal)), _) => {
+            | (BusMsg::Ctl(CtlMsg::RevokeDeal(DealRevocation { deal, .. })), _) => {
                 Ok(dummy_drain_filter(&mut self.trade_state_machines, |tsm| {
                     if let Some(tsm_deal) = tsm.open_deal() {
                         tsm_deal == *deal
@@ -1027,6 +1595,7 @@ impl Runtime {
             | (BusMsg::Ctl(CtlMsg::FundingCanceled(..)), ServiceId::Swap(swap_id))
             | (BusMsg::Ctl(CtlMsg::FundingCompleted(..)), ServiceId::Swap(swap_id))
             | (BusMsg::Ctl(CtlMsg::Connect(swap_id)), _)
+            | (BusMsg::Ctl(CtlMsg::ReconnectAttempt(ReconnectAttempt { swap_id, .. })), _)
             | (BusMsg::Ctl(CtlMsg::SwapOutcome(..)), ServiceId::Swap(swap_id)) => {
                 Ok(dummy_drain_filter(&mut self.trade_state_machines, |tsm| {
                     if let Some(tsm_swap_id) = tsm.swap_id() {
@@ -1084,7 +1653,7 @@ impl Runtime {
                     )?;
                     Ok(())
                 }
-                BusMsg::Ctl(CtlMsg::Connect(..)) => {
+                BusMsg::Ctl(CtlMsg::Connect(..)) | BusMsg::Ctl(CtlMsg::ReconnectAttempt(..)) => {
                     endpoints.send_to(
                         ServiceBus::Ctl,
                         self.identity(),
@@ -1288,12 +1857,13 @@ pub fn syncer_up(
 }
 
 /// Launch a swapd instance with all the necessary paramters for: swap id, deal to use, trade role
-/// to execute, temporal safety arguments.
+/// to execute, temporal safety arguments and confirmation bounds.
 pub fn launch_swapd(
     local_trade_role: TradeRole,
     deal: Deal,
     swap_id: SwapId,
     swap_config: ParsedSwapConfig,
+    confirmation_bounds: ConfirmationBounds,
 ) -> Result<(), Error> {
     debug!("Instantiating swapd...");
     let child = launch(
@@ -1311,6 +1881,16 @@ pub fn launch_swapd(
             deal.to_string(),
             "--trade-role".to_string(),
             local_trade_role.to_string(),
+            "--lock-confs".to_string(),
+            confirmation_bounds.lock.to_string(),
+            "--cancel-confs".to_string(),
+            confirmation_bounds.cancel.to_string(),
+            "--buy-confs".to_string(),
+            confirmation_bounds.buy.to_string(),
+            "--refund-confs".to_string(),
+            confirmation_bounds.refund.to_string(),
+            "--punish-confs".to_string(),
+            confirmation_bounds.punish.to_string(),
         ],
     )?;
     debug!("New instance of swapd launched with PID {}", child.id());
@@ -1325,12 +1905,22 @@ fn syncer_servers_args(
     blockchain: Blockchain,
     net: Network,
 ) -> Result<Vec<String>, Error> {
+    let hwm_args = config
+        .zmq_hwm
+        .map_or(vec![], |hwm| vec!["--zmq-hwm".to_string(), hwm.to_string()]);
     match config.get_syncer_servers(net) {
         Some(servers) => match blockchain {
-            Blockchain::Bitcoin => Ok(vec![
-                "--electrum-server".to_string(),
-                servers.electrum_server,
-            ]),
+            Blockchain::Bitcoin => {
+                let mut args: Vec<String> =
+                    vec!["--electrum-server".to_string(), servers.electrum_server];
+                args.extend(
+                    servers
+                        .bitcoin_zmq_rawtx
+                        .map_or(vec![], |v| vec!["--bitcoin-zmq-rawtx".to_string(), v]),
+                );
+                args.extend(hwm_args);
+                Ok(args)
+            }
             Blockchain::Monero => {
                 let mut args: Vec<String> = vec![
                     "--monero-daemon".to_string(),
@@ -1348,6 +1938,7 @@ fn syncer_servers_args(
                         .monero_wallet_dir
                         .map_or(vec![], |v| vec!["--monero-wallet-dir-path".to_string(), v]),
                 );
+                args.extend(hwm_args);
                 Ok(args)
             }
         },
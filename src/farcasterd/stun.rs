@@ -0,0 +1,203 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Minimal STUN client (RFC 5389) used to discover the external ip address of the node. Only the
+//! binding request is implemented, this is enough to learn the reflexive transport address the
+//! STUN server sees.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+const STUN_BINDING_REQUEST: u16 = 0x0001;
+const STUN_BINDING_SUCCESS: u16 = 0x0101;
+const STUN_MAGIC_COOKIE: u32 = 0x2112_A442;
+const STUN_HEADER_LEN: usize = 20;
+const STUN_ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+const STUN_ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+const STUN_FAMILY_IPV4: u8 = 0x01;
+const STUN_FAMILY_IPV6: u8 = 0x02;
+const STUN_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Display, From, Error)]
+#[display(doc_comments)]
+pub enum StunError {
+    /// STUN server address {0} cannot be resolved
+    Resolve(String),
+
+    /// I/O error while talking to the STUN server: {0}
+    #[from]
+    Io(io::Error),
+
+    /// STUN server returned a malformed response
+    MalformedResponse,
+
+    /// STUN server response does not contain a mapped address
+    NoMappedAddress,
+}
+
+/// External ip address shared between the STUN lookup thread and the runtime, `None` until the
+/// lookup succeeds.
+pub type ExternalIp = Arc<Mutex<Option<IpAddr>>>;
+
+pub struct StunClient;
+
+impl StunClient {
+    /// Runs the binding request on a dedicated thread so the blocking socket exchange never
+    /// stalls the event loop. The discovered address is stored in the returned handle.
+    pub fn spawn_discovery(server: String) -> ExternalIp {
+        let external_ip: ExternalIp = Arc::new(Mutex::new(None));
+        let handle = Arc::clone(&external_ip);
+        thread::spawn(move || match Self::discover_external_ip(&server) {
+            Ok(ip) => {
+                info!("Discovered external IP {} with STUN server {}", ip, server);
+                if let Ok(mut external_ip) = handle.lock() {
+                    *external_ip = Some(ip);
+                }
+            }
+            Err(err) => warn!(
+                "Failed to discover external IP with STUN server {}: {}",
+                server, err
+            ),
+        });
+        external_ip
+    }
+
+    /// Sends a binding request to the STUN server and returns the external ip address reported
+    /// by the server.
+    pub fn discover_external_ip(server: &str) -> Result<IpAddr, StunError> {
+        let server_addr = server
+            .to_socket_addrs()
+            .map_err(|_| StunError::Resolve(server.to_string()))?
+            .next()
+            .ok_or_else(|| StunError::Resolve(server.to_string()))?;
+        let bind_addr: SocketAddr = match server_addr {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(bind_addr)?;
+        socket.set_read_timeout(Some(STUN_TIMEOUT))?;
+        socket.set_write_timeout(Some(STUN_TIMEOUT))?;
+
+        let mut transaction_id = [0u8; 12];
+        transaction_id.copy_from_slice(&Uuid::new_v4().as_bytes()[..12]);
+        socket.send_to(&binding_request(&transaction_id), server_addr)?;
+
+        let mut buf = [0u8; 512];
+        let (len, _) = socket.recv_from(&mut buf)?;
+        parse_binding_response(&buf[..len], &transaction_id)
+    }
+}
+
+fn binding_request(transaction_id: &[u8; 12]) -> Vec<u8> {
+    let mut req = Vec::with_capacity(STUN_HEADER_LEN);
+    req.extend_from_slice(&STUN_BINDING_REQUEST.to_be_bytes());
+    // no attributes, message length is zero
+    req.extend_from_slice(&0u16.to_be_bytes());
+    req.extend_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+    req.extend_from_slice(transaction_id);
+    req
+}
+
+fn parse_binding_response(msg: &[u8], transaction_id: &[u8; 12]) -> Result<IpAddr, StunError> {
+    if msg.len() < STUN_HEADER_LEN
+        || u16::from_be_bytes([msg[0], msg[1]]) != STUN_BINDING_SUCCESS
+        || msg[4..8] != STUN_MAGIC_COOKIE.to_be_bytes()
+        || &msg[8..20] != transaction_id
+    {
+        return Err(StunError::MalformedResponse);
+    }
+    let body_len = u16::from_be_bytes([msg[2], msg[3]]) as usize;
+    let body = msg
+        .get(STUN_HEADER_LEN..STUN_HEADER_LEN + body_len)
+        .ok_or(StunError::MalformedResponse)?;
+
+    let mut mapped = None;
+    let mut offset = 0;
+    while offset + 4 <= body.len() {
+        let attr_type = u16::from_be_bytes([body[offset], body[offset + 1]]);
+        let attr_len = u16::from_be_bytes([body[offset + 2], body[offset + 3]]) as usize;
+        let value = body
+            .get(offset + 4..offset + 4 + attr_len)
+            .ok_or(StunError::MalformedResponse)?;
+        match attr_type {
+            // prefer the xor-mapped address, some NATs rewrite plain addresses in payloads
+            STUN_ATTR_XOR_MAPPED_ADDRESS => {
+                return parse_address(value, Some(transaction_id));
+            }
+            STUN_ATTR_MAPPED_ADDRESS => {
+                mapped = Some(parse_address(value, None)?);
+            }
+            _ => {}
+        }
+        // attributes are padded to a multiple of 4 bytes
+        offset += 4 + ((attr_len + 3) & !3);
+    }
+    mapped.ok_or(StunError::NoMappedAddress)
+}
+
+fn parse_address(value: &[u8], xor_with: Option<&[u8; 12]>) -> Result<IpAddr, StunError> {
+    let family = *value.get(1).ok_or(StunError::MalformedResponse)?;
+    let mut mask = STUN_MAGIC_COOKIE.to_be_bytes().to_vec();
+    match xor_with {
+        Some(transaction_id) => mask.extend_from_slice(transaction_id),
+        None => mask = vec![0u8; 16],
+    }
+    match family {
+        STUN_FAMILY_IPV4 => {
+            let raw = value.get(4..8).ok_or(StunError::MalformedResponse)?;
+            let mut ip = [0u8; 4];
+            for (i, byte) in raw.iter().enumerate() {
+                ip[i] = byte ^ mask[i];
+            }
+            Ok(IpAddr::V4(Ipv4Addr::from(ip)))
+        }
+        STUN_FAMILY_IPV6 => {
+            let raw = value.get(4..20).ok_or(StunError::MalformedResponse)?;
+            let mut ip = [0u8; 16];
+            for (i, byte) in raw.iter().enumerate() {
+                ip[i] = byte ^ mask[i];
+            }
+            Ok(IpAddr::V6(Ipv6Addr::from(ip)))
+        }
+        _ => Err(StunError::MalformedResponse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stun_parse_xor_mapped_address() {
+        let transaction_id = [7u8; 12];
+        let ip = Ipv4Addr::new(203, 0, 113, 42);
+        let mut xored = ip.octets();
+        for (i, byte) in xored.iter_mut().enumerate() {
+            *byte ^= STUN_MAGIC_COOKIE.to_be_bytes()[i];
+        }
+
+        let mut msg = vec![];
+        msg.extend_from_slice(&STUN_BINDING_SUCCESS.to_be_bytes());
+        msg.extend_from_slice(&12u16.to_be_bytes());
+        msg.extend_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+        msg.extend_from_slice(&transaction_id);
+        msg.extend_from_slice(&STUN_ATTR_XOR_MAPPED_ADDRESS.to_be_bytes());
+        msg.extend_from_slice(&8u16.to_be_bytes());
+        msg.extend_from_slice(&[0, STUN_FAMILY_IPV4, 0, 0]);
+        msg.extend_from_slice(&xored);
+
+        assert_eq!(
+            parse_binding_response(&msg, &transaction_id).unwrap(),
+            IpAddr::V4(ip)
+        );
+        assert!(parse_binding_response(&msg, &[0u8; 12]).is_err());
+    }
+}
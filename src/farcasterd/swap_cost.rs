@@ -0,0 +1,104 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use farcaster_core::blockchain::FeeStrategy;
+use farcaster_core::swap::btcxmr::Deal;
+use farcaster_core::transaction::TxLabel;
+
+use crate::bus::info::{SwapCostEstimate, TxFeeEstimate};
+use crate::syncerd::bitcoin_syncer::p2wpkh_signed_tx_fee;
+
+/// Unsigned vsize of the 1-in-1-out lock transaction, see the funding amount computed by swapd
+const LOCK_UNSIGNED_TX_VSIZE: usize = 94;
+
+/// Witness vsize of the lock transaction p2wpkh input, as counted by `p2wpkh_signed_tx_fee`
+const P2WPKH_INPUT_WITNESS_VSIZE: u64 = 28;
+
+/// Upper bound of the vsize of the cancel, refund, buy and punish transactions. They spend a
+/// single output with the largest witness farcaster_core simulates when setting their fee (two
+/// signatures and the 111 bytes refund/punish script) and create a single output of at most 43
+/// bytes (p2wsh or p2tr).
+const PROTOCOL_TX_VSIZE: u64 = 160;
+
+/// Compute the Bitcoin fees of every transaction of a swap over the deal. The lock transaction
+/// pays the current fee estimate like the funding amount swapd requests, the other transactions
+/// pay the rate of the deal fee strategy.
+pub fn estimate_swap_cost(deal: &Deal, lock_fee_rate_sat_per_kvb: u64) -> SwapCostEstimate {
+    let deal_fee_rate_sat_per_kvb = match deal.parameters.fee_strategy {
+        FeeStrategy::Fixed(fee_rate) => fee_rate.as_native_unit().as_sat(),
+    };
+    let protocol_fee = (deal_fee_rate_sat_per_kvb as f64 * PROTOCOL_TX_VSIZE as f64 * 1e-3).round();
+    let fees: Vec<TxFeeEstimate> = [
+        TxLabel::Lock,
+        TxLabel::Cancel,
+        TxLabel::Refund,
+        TxLabel::Buy,
+        TxLabel::Punish,
+    ]
+    .iter()
+    .map(|label| match label {
+        TxLabel::Lock => TxFeeEstimate {
+            label: *label,
+            vsize: LOCK_UNSIGNED_TX_VSIZE as u64 + P2WPKH_INPUT_WITNESS_VSIZE,
+            fee: p2wpkh_signed_tx_fee(lock_fee_rate_sat_per_kvb, LOCK_UNSIGNED_TX_VSIZE, 1),
+        },
+        _ => TxFeeEstimate {
+            label: *label,
+            vsize: PROTOCOL_TX_VSIZE,
+            fee: protocol_fee as u64,
+        },
+    })
+    .collect();
+    let fee_of = |label: TxLabel| {
+        fees.iter()
+            .find(|estimate| estimate.label == label)
+            .map_or(0, |estimate| estimate.fee)
+    };
+    let worst_case_total = fee_of(TxLabel::Lock)
+        + std::cmp::max(
+            fee_of(TxLabel::Buy),
+            fee_of(TxLabel::Cancel)
+                + std::cmp::max(fee_of(TxLabel::Refund), fee_of(TxLabel::Punish)),
+        );
+    SwapCostEstimate {
+        lock_fee_rate_sat_per_kvb,
+        deal_fee_rate_sat_per_kvb,
+        fees,
+        worst_case_total,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn swap_cost_worst_case_total() {
+        let deal = fixtures::deal();
+        let FeeStrategy::Fixed(deal_fee_rate) = deal.parameters.fee_strategy;
+        let deal_fee_rate = deal_fee_rate.as_native_unit().as_sat();
+
+        let estimate = estimate_swap_cost(&deal, 2000);
+        assert_eq!(estimate.deal_fee_rate_sat_per_kvb, deal_fee_rate);
+        let labels: Vec<TxLabel> = estimate.fees.iter().map(|fee| fee.label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                TxLabel::Lock,
+                TxLabel::Cancel,
+                TxLabel::Refund,
+                TxLabel::Buy,
+                TxLabel::Punish
+            ]
+        );
+        // 122 vbytes at 2 sat/vB
+        assert_eq!(estimate.fees[0].fee, 244);
+        let protocol_fee = (deal_fee_rate as f64 * 0.16).round() as u64;
+        assert!(estimate.fees[1..].iter().all(|fee| fee.fee == protocol_fee));
+        assert_eq!(estimate.worst_case_total, 244 + 2 * protocol_fee);
+    }
+}
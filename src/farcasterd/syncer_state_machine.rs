@@ -5,22 +5,27 @@
 // https://opensource.org/licenses/MIT.
 
 use farcaster_core::blockchain::{Blockchain, Network};
+use farcaster_core::swap::btcxmr::{Deal, DealParameters};
 
 use crate::{
     bus::ctl::CtlMsg,
     bus::info::InfoMsg,
     bus::BusMsg,
-    bus::{sync::SyncMsg, AddressSecretKey, Failure, FailureCode},
+    bus::{sync::SyncMsg, AddressSecretKey, Failure, FailureCode, OptionDetails},
     error::Error,
     event::{Event, StateMachine, StateMachineExecutor},
+    swapd::TemporalSafety,
     syncerd::{
-        Event as SyncerEvent, GetAddressBalance, Health, HealthCheck, SweepAddress,
-        SweepAddressAddendum, Task, TaskAborted, TaskId,
+        Abort, Event as SyncerEvent, FeeEstimation, FeeEstimations, GetAddressBalance, Health,
+        HealthCheck, SweepAddress, SweepAddressAddendum, SyncerHealth, Task, TaskAborted, TaskId,
+        TaskTarget, WatchEstimateFee,
     },
     ServiceId,
 };
 
+use super::deal_validation::{validate_fee_strategy, validate_make_params};
 use super::runtime::{syncer_up, Runtime};
+use super::swap_cost::estimate_swap_cost;
 
 /// State machine for making a syncer request from and to a client.
 /// State machine automaton:
@@ -59,17 +64,29 @@ pub enum SyncerStateMachine {
     AwaitingSyncerRequest(AwaitingSyncerRequest),
 }
 
+/// Client request answered from the fee estimate of the Bitcoin syncer
+pub enum FeeRequest {
+    /// Estimate the fees of a swap over the deal
+    SwapCost(Deal),
+    /// Validate the parameters of a deal about to be made, checked against the configuration
+    /// into the temporal safety parameters
+    MakeParams(DealParameters, TemporalSafety),
+}
+
 pub struct AwaitingSyncer {
     source: ServiceId,
     syncer: ServiceId,
     syncer_task: Task,
     syncer_task_id: TaskId,
+    // the request answered with the fee estimate, if any
+    fee_request: Option<FeeRequest>,
 }
 
 pub struct AwaitingSyncerRequest {
     source: ServiceId,
     syncer: ServiceId,
     syncer_task_id: TaskId,
+    fee_request: Option<FeeRequest>,
 }
 
 impl StateMachine<Runtime, Error> for SyncerStateMachine {
@@ -178,6 +195,7 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
                         source,
                         syncer_task_id,
                         syncer: ServiceId::Syncer(blockchain, network),
+                        fee_request: None,
                     },
                 )))
             } else {
@@ -186,6 +204,7 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
                     syncer: ServiceId::Syncer(blockchain, network),
                     syncer_task,
                     syncer_task_id,
+                    fee_request: None,
                 })))
             }
         }
@@ -219,6 +238,7 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
                         source,
                         syncer_task_id,
                         syncer: ServiceId::Syncer(blockchain, network),
+                        fee_request: None,
                     },
                 )))
             } else {
@@ -227,6 +247,7 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
                     syncer: ServiceId::Syncer(blockchain, network),
                     syncer_task,
                     syncer_task_id,
+                    fee_request: None,
                 })))
             }
         }
@@ -250,6 +271,7 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
                             source,
                             syncer_task_id,
                             syncer: ServiceId::Syncer(blockchain, network),
+                            fee_request: None,
                         },
                     )))
                 }
@@ -258,16 +280,40 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
                     syncer: ServiceId::Syncer(blockchain, network),
                     syncer_task,
                     syncer_task_id,
+                    fee_request: None,
                 }))),
                 Err(err) => {
-                    event.complete_ctl(CtlMsg::HealthResult(Health::ConfigUnavailable(
-                        err.to_string(),
+                    event.complete_ctl(CtlMsg::HealthResult(SyncerHealth::unmeasured(
+                        Health::ConfigUnavailable(err.to_string()),
                     )))?;
                     Ok(None)
                 }
             }
         }
 
+        BusMsg::Ctl(CtlMsg::EstimateSwapCost(deal)) => {
+            let network = deal.parameters.network;
+            watch_fee_estimate(event, runtime, network, FeeRequest::SwapCost(deal))
+        }
+
+        BusMsg::Ctl(CtlMsg::ValidateMakeParams(proto_deal)) => {
+            match validate_make_params(&runtime.config, &proto_deal) {
+                Ok(temporal_safety) => {
+                    let network = proto_deal.deal_parameters.network;
+                    let request =
+                        FeeRequest::MakeParams(proto_deal.deal_parameters, temporal_safety);
+                    watch_fee_estimate(event, runtime, network, request)
+                }
+                Err(err) => {
+                    event.complete_client_ctl(CtlMsg::Failure(Failure {
+                        code: FailureCode::Unknown,
+                        info: err.to_string(),
+                    }))?;
+                    Ok(None)
+                }
+            }
+        }
+
         req => {
             warn!(
                 "Request {} from {} invalid for state start - invalidating.",
@@ -278,6 +324,49 @@ fn attempt_transition_to_awaiting_syncer_or_awaiting_syncer_request(
     }
 }
 
+/// Registers a fee estimation task on the Bitcoin syncer of the network, launching the syncer if
+/// needed, to answer the request once the estimate is received
+fn watch_fee_estimate(
+    event: Event,
+    runtime: &mut Runtime,
+    network: Network,
+    request: FeeRequest,
+) -> Result<Option<SyncerStateMachine>, Error> {
+    let source = event.source.clone();
+    let syncer_task_id = TaskId(runtime.syncer_task_counter);
+    runtime.syncer_task_counter += 1;
+    let blockchain = Blockchain::Bitcoin;
+    let syncer_task = Task::WatchEstimateFee(WatchEstimateFee {
+        id: syncer_task_id,
+        lifetime: u64::MAX,
+    });
+    if let Some(service_id) = syncer_up(
+        &mut runtime.spawning_services,
+        &mut runtime.registered_services,
+        blockchain,
+        network,
+        &runtime.config,
+    )? {
+        event.complete_sync_service(service_id, SyncMsg::Task(syncer_task))?;
+        Ok(Some(SyncerStateMachine::AwaitingSyncerRequest(
+            AwaitingSyncerRequest {
+                source,
+                syncer_task_id,
+                syncer: ServiceId::Syncer(blockchain, network),
+                fee_request: Some(request),
+            },
+        )))
+    } else {
+        Ok(Some(SyncerStateMachine::AwaitingSyncer(AwaitingSyncer {
+            source,
+            syncer: ServiceId::Syncer(blockchain, network),
+            syncer_task,
+            syncer_task_id,
+            fee_request: Some(request),
+        })))
+    }
+}
+
 fn attempt_transition_to_awaiting_syncer_request(
     event: Event,
     _runtime: &mut Runtime,
@@ -288,6 +377,7 @@ fn attempt_transition_to_awaiting_syncer_request(
         syncer,
         syncer_task,
         syncer_task_id,
+        fee_request,
     } = awaiting_syncer;
     match (event.request.clone(), event.source.clone()) {
         (BusMsg::Ctl(CtlMsg::Hello), syncer_id) if syncer == syncer_id => {
@@ -297,6 +387,7 @@ fn attempt_transition_to_awaiting_syncer_request(
                     source,
                     syncer_task_id,
                     syncer,
+                    fee_request,
                 },
             )))
         }
@@ -318,6 +409,7 @@ fn attempt_transition_to_awaiting_syncer_request(
                 syncer,
                 syncer_task,
                 syncer_task_id,
+                fee_request,
             })))
         }
     }
@@ -332,6 +424,7 @@ fn attempt_transition_to_end(
         syncer_task_id,
         source,
         syncer,
+        fee_request,
     } = awaiting_syncer_request;
     match (event.request.clone(), event.source.clone()) {
         (BusMsg::Sync(SyncMsg::Event(SyncerEvent::SweepSuccess(mut success))), syncer_id)
@@ -396,6 +489,70 @@ fn attempt_transition_to_end(
             Ok(None)
         }
 
+        (
+            BusMsg::Sync(SyncMsg::Event(SyncerEvent::FeeEstimation(FeeEstimation {
+                id,
+                fee_estimations:
+                    FeeEstimations::BitcoinFeeEstimation {
+                        high_priority_sats_per_kvbyte,
+                        low_priority_sats_per_kvbyte,
+                    },
+            }))),
+            syncer_id,
+        ) if syncer == syncer_id && id == syncer_task_id && fee_request.is_some() => {
+            // the fee estimation is watched, stop it now that a value was received
+            event.send_sync_service(
+                syncer,
+                SyncMsg::Task(Task::Abort(Abort {
+                    task_target: TaskTarget::TaskId(syncer_task_id),
+                    respond: false,
+                })),
+            )?;
+            match fee_request.expect("checked in match guard") {
+                FeeRequest::SwapCost(deal) => {
+                    event.send_client_info(
+                        source,
+                        InfoMsg::SwapCostEstimate(estimate_swap_cost(
+                            &deal,
+                            high_priority_sats_per_kvbyte,
+                        )),
+                    )?;
+                }
+                FeeRequest::MakeParams(deal_parameters, temporal_safety) => {
+                    match validate_fee_strategy(
+                        &deal_parameters.fee_strategy,
+                        low_priority_sats_per_kvbyte,
+                    ) {
+                        Ok(()) => event.send_client_ctl(
+                            source,
+                            CtlMsg::Success(OptionDetails::with(format!(
+                                "Deal parameters are valid, no deal was made. Timelocks: cancel {} \
+                                 blocks, punish {} blocks with a finality of {} blocks and a safety \
+                                 margin of {} blocks. Fee: {} (current estimates: {} to {} \
+                                 sat/kvB)",
+                                temporal_safety.cancel_timelock,
+                                temporal_safety.punish_timelock,
+                                temporal_safety.arb_finality,
+                                temporal_safety.safety,
+                                deal_parameters.fee_strategy,
+                                low_priority_sats_per_kvbyte,
+                                high_priority_sats_per_kvbyte,
+                            ))),
+                        )?,
+                        Err(err) => event.send_client_ctl(
+                            source,
+                            CtlMsg::Failure(Failure {
+                                code: FailureCode::Unknown,
+                                info: err.to_string(),
+                            }),
+                        )?,
+                    }
+                }
+            }
+            runtime.clean_up_after_syncer_usage(event.endpoints)?;
+            Ok(None)
+        }
+
         (BusMsg::Sync(SyncMsg::Event(SyncerEvent::HealthResult(res))), syncer_id)
             if syncer == syncer_id && res.id == syncer_task_id =>
         {
@@ -421,6 +578,7 @@ fn attempt_transition_to_end(
                     syncer_task_id,
                     source,
                     syncer,
+                    fee_request,
                 },
             )))
         }
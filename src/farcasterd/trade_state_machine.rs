@@ -5,14 +5,17 @@
 // https://opensource.org/licenses/MIT.
 
 use crate::bus::ctl::{
-    BitcoinFundingInfo, CtlMsg, FundingInfo, InitMakerSwap, InitTakerSwap, MoneroFundingInfo,
-    ProtoDeal, PubDeal, SwapKeys, WrappedKeyManager,
+    BitcoinFundingInfo, CtlMsg, DealRevocation, FundingInfo, InitMakerSwap, InitTakerSwap,
+    MoneroFundingInfo, ProtoDeal, PubDeal, ReconnectAttempt, ReconnectAttemptResult, SwapKeys,
+    WrappedKeyManager,
 };
 use crate::bus::info::{InfoMsg, MadeDeal, TookDeal, ViewableDeal};
 use crate::bus::p2p::{Commit, PeerMsg};
-use crate::bus::{CheckpointEntry, DealInfo, DealStatus, Failure, FailureCode};
+use crate::bus::{CheckpointEntry, DealInfo, DealStatus, Failure, FailureCode, PeerOutcome};
+use crate::farcasterd::deal_validation::DealPreValidation;
 use crate::farcasterd::runtime::{launch_swapd, syncer_up, Runtime};
 use crate::service::{SwapDetails, SwapLogging};
+use crate::swapd::ConfirmationBounds;
 use crate::LogStyle;
 use crate::{
     bus::{BusMsg, Outcome},
@@ -20,13 +23,14 @@ use crate::{
     event::{Event, StateMachine, StateMachineExecutor},
     ServiceId,
 };
-use farcaster_core::blockchain::Blockchain;
+use farcaster_core::blockchain::{Blockchain, Network};
 use farcaster_core::role::{SwapRole, TradeRole};
 use farcaster_core::swap::{btcxmr::Deal, SwapId};
 use farcaster_core::Uuid;
-use internet2::addr::{NodeAddr, NodeId};
+use internet2::addr::{InetSocketAddr, NodeAddr, NodeId};
 use microservices::esb::Handler;
 use std::convert::TryInto;
+use std::net::{IpAddr, Ipv4Addr};
 use std::str::FromStr;
 
 /// State machine for launching a swap and cleaning up once done.
@@ -422,6 +426,52 @@ impl TradeStateMachine {
         }
     }
 
+    /// Node id of the counterparty of a launched swap: the node of its connection, or for a
+    /// swap without connection the deal maker when taking and the expected counterparty of a
+    /// restored swap when making
+    pub fn counterparty_node_id(&self) -> Option<NodeId> {
+        let (peerd, deal, trade_role, expected_counterparty_node_id) = match self {
+            TradeStateMachine::SwapdLaunched(SwapdLaunched {
+                peerd,
+                deal,
+                consumed_deal_role,
+                ..
+            }) => (
+                Some(peerd),
+                deal,
+                TradeRole::from(consumed_deal_role.clone()),
+                None,
+            ),
+            TradeStateMachine::RestoringSwapd(RestoringSwapd {
+                peerd,
+                deal,
+                trade_role,
+                expected_counterparty_node_id,
+                ..
+            })
+            | TradeStateMachine::SwapdRunning(SwapdRunning {
+                peerd,
+                deal,
+                trade_role,
+                expected_counterparty_node_id,
+                ..
+            }) => (
+                peerd.as_ref(),
+                deal,
+                *trade_role,
+                *expected_counterparty_node_id,
+            ),
+            _ => return None,
+        };
+        peerd
+            .and_then(|peerd| peerd.node_addr())
+            .map(|node_addr| node_addr.id)
+            .or(match trade_role {
+                TradeRole::Taker => Some(NodeId::from(deal.node_id)),
+                TradeRole::Maker => expected_counterparty_node_id,
+            })
+    }
+
     pub fn get_swap_id_with_matching_connection(&self, source: &ServiceId) -> Option<SwapId> {
         if let Some(peer) = self.get_connection() {
             if peer == *source {
@@ -500,6 +550,34 @@ impl TradeStateMachine {
     }
 }
 
+/// Replace the default loopback address with the external ip address discovered through STUN,
+/// local network deals are kept untouched. Keep the provided address while no external ip address
+/// is known.
+fn discover_public_addr(
+    public_addr: InetSocketAddr,
+    network: Network,
+    external_ip: Option<IpAddr>,
+    log_helper: &LogHelper,
+) -> InetSocketAddr {
+    let port = match public_addr {
+        InetSocketAddr::IPv4(addr) if *addr.ip() == Ipv4Addr::LOCALHOST => addr.port(),
+        _ => return public_addr,
+    };
+    if network == Network::Local {
+        return public_addr;
+    }
+    match external_ip {
+        Some(ip) => {
+            log_helper.log_info(format!(
+                "Advertising external IP: {}",
+                ip.bright_white_bold()
+            ));
+            InetSocketAddr::socket(ip, port)
+        }
+        None => public_addr,
+    }
+}
+
 fn attempt_transition_to_make_deal(
     mut event: Event,
     runtime: &mut Runtime,
@@ -511,7 +589,8 @@ fn attempt_transition_to_make_deal(
             arbitrating_addr,
             accordant_addr,
             public_addr,
-            ..
+            confirmation_bounds,
+            deal_expiry,
         })) => {
             // validate deal parameters
             if let Err(e) = runtime.config.validate_deal_parameters(
@@ -527,6 +606,22 @@ fn attempt_transition_to_make_deal(
                 }))?;
                 return Ok(None);
             }
+            // an expiring deal needs a bitcoin syncer to watch the height, launch it early
+            if deal_expiry.is_some() {
+                if let Err(err) = syncer_up(
+                    &mut runtime.spawning_services,
+                    &mut runtime.registered_services,
+                    Blockchain::Bitcoin,
+                    deal_parameters.network,
+                    &runtime.config,
+                ) {
+                    event.complete_client_ctl(CtlMsg::Failure(Failure {
+                        code: FailureCode::Unknown,
+                        info: format!("Cannot watch the deal expiry: {}", err),
+                    }))?;
+                    return Ok(None);
+                }
+            }
             // start a listener on the bind_addr
             let bind_addr = match runtime.config.get_bind_addr() {
                 Err(err) => {
@@ -551,7 +646,17 @@ fn attempt_transition_to_make_deal(
                     Ok(None)
                 }
                 Ok(node_id) => {
+                    let public_addr = discover_public_addr(
+                        public_addr,
+                        deal_parameters.network,
+                        runtime.external_ip(),
+                        &log_helper,
+                    );
                     let deal = deal_parameters.to_v1(node_id.public_key(), public_addr);
+                    runtime.set_deal_creation(&deal);
+                    if let Some(blocks) = deal_expiry {
+                        runtime.set_deal_expiry(event.endpoints, &deal, blocks)?;
+                    }
                     let msg = s!("Deal registered, please share with taker.");
                     log_helper.log_info(format!(
                         "{}: {:#}",
@@ -565,6 +670,10 @@ fn attempt_transition_to_make_deal(
                             serialized_deal: deal.to_string(),
                             status: DealStatus::Open,
                             local_trade_role: TradeRole::Maker,
+                            local_swap_role: deal.swap_role(&TradeRole::Maker),
+                            failure_reason: None,
+                            expires_at_block: None,
+                            expires_in: None,
                         }),
                     )?;
                     event.complete_client_info(InfoMsg::MadeDeal(MadeDeal {
@@ -575,6 +684,9 @@ fn attempt_transition_to_make_deal(
                         },
                     }))?;
                     runtime.deals.insert(deal.clone());
+                    runtime
+                        .confirmation_bounds
+                        .insert(deal.clone(), confirmation_bounds);
                     Ok(Some(TradeStateMachine::MakeDeal(MakeDeal {
                         deal,
                         arb_addr: arbitrating_addr,
@@ -603,21 +715,8 @@ fn attempt_transition_to_taker_connect_or_take_deal(
             deal,
             bitcoin_address: arb_addr,
             monero_address: acc_addr,
+            expiry,
         })) => {
-            // validate deal parameters
-            if let Err(e) = runtime.config.validate_deal_parameters(
-                &deal.parameters,
-                &arb_addr,
-                &acc_addr,
-                TradeRole::Taker,
-            ) {
-                warn!("Deal parameters validation error: {}", e);
-                event.complete_client_ctl(CtlMsg::Failure(Failure {
-                    code: FailureCode::Unknown,
-                    info: e.to_string(),
-                }))?;
-                return Ok(None);
-            }
             if runtime.consumed_deals_contains(&deal) || runtime.deals.contains(&deal) {
                 let msg = format!(
                     "{} already exists or was already taken, ignoring request",
@@ -631,6 +730,18 @@ fn attempt_transition_to_taker_connect_or_take_deal(
                 return Ok(None);
             }
 
+            // validate the deal before establishing a connection with the maker
+            if let Err(e) =
+                deal.pre_validate(&runtime.config, &arb_addr, &acc_addr, expiry.as_ref())
+            {
+                warn!("Deal pre-validation error: {}", e);
+                event.complete_client_ctl(CtlMsg::Failure(Failure {
+                    code: FailureCode::Unknown,
+                    info: e.to_string(),
+                }))?;
+                return Ok(None);
+            }
+
             let peer_node_addr = node_addr_from_deal(&deal);
             // connect to the remote peer
             match runtime.connect_peer(&peer_node_addr) {
@@ -781,7 +892,13 @@ fn attempt_transition_to_restoring_swapd(
                 &runtime.config,
             )?;
 
-            launch_swapd(trade_role, deal.clone(), swap_id, swap_config)?;
+            launch_swapd(
+                trade_role,
+                deal.clone(),
+                swap_id,
+                swap_config,
+                ConfirmationBounds::default(),
+            )?;
             event.complete_client_info(InfoMsg::String("Restoring checkpoint.".to_string()))?;
 
             Ok(Some(TradeStateMachine::RestoringSwapd(RestoringSwapd {
@@ -836,6 +953,10 @@ fn attempt_transition_to_taker_committed(
                         serialized_deal: deal.to_string(),
                         status: DealStatus::InProgress,
                         local_trade_role: TradeRole::Maker,
+                        local_swap_role: deal.swap_role(&TradeRole::Maker),
+                        failure_reason: None,
+                        expires_at_block: None,
+                        expires_in: None,
                     }),
                 )?;
                 Ok(Some(TradeStateMachine::TakerCommit(TakerCommit {
@@ -857,10 +978,31 @@ fn attempt_transition_to_taker_committed(
                 })))
             }
         }
-        (BusMsg::Ctl(CtlMsg::RevokeDeal(revoke_deal)), _) => {
+        (
+            BusMsg::Ctl(CtlMsg::RevokeDeal(DealRevocation {
+                deal: revoke_deal,
+                force,
+            })),
+            _,
+        ) => {
             log_helper.log_debug(format!("attempting to revoke {}", deal));
-            if revoke_deal == deal {
+            let count = runtime.pending_peer_connections();
+            if revoke_deal == deal && count > 0 && !force {
+                let err = Error::DealHasActiveConnections { count };
+                log_helper.log_warn(err.to_string());
+                event.complete_client_ctl(CtlMsg::Failure(Failure {
+                    code: FailureCode::Unknown,
+                    info: err.to_string(),
+                }))?;
+                Ok(Some(TradeStateMachine::MakeDeal(MakeDeal {
+                    deal,
+                    arb_addr,
+                    acc_addr,
+                })))
+            } else if revoke_deal == deal {
                 log_helper.log_info(format!("Revoked deal {}", deal.label()));
+                runtime.confirmation_bounds.remove(&deal);
+                runtime.clear_deal_heights(&deal);
                 event.send_ctl_service(
                     ServiceId::Database,
                     CtlMsg::SetDealInfo(DealInfo {
@@ -868,6 +1010,10 @@ fn attempt_transition_to_taker_committed(
                         serialized_deal: deal.to_string(),
                         status: DealStatus::Revoked,
                         local_trade_role: TradeRole::Maker,
+                        local_swap_role: deal.swap_role(&TradeRole::Maker),
+                        failure_reason: None,
+                        expires_at_block: None,
+                        expires_in: None,
                     }),
                 )?;
                 event.complete_client_info(InfoMsg::String(
@@ -1067,9 +1213,13 @@ fn attempt_transition_from_take_deal_to_swapd_launched(
                 ServiceId::Database,
                 CtlMsg::SetDealInfo(DealInfo {
                     serialized_deal: deal.to_string(),
-                    deal,
                     status: DealStatus::InProgress,
                     local_trade_role: TradeRole::Taker,
+                    local_swap_role: deal.swap_role(&TradeRole::Taker),
+                    failure_reason: None,
+                    expires_at_block: None,
+                    expires_in: None,
+                    deal,
                 }),
             )?;
             Ok(Some(tsm))
@@ -1133,11 +1283,17 @@ fn transition_to_swapd_launched_tsm(
     ));
 
     runtime.stats.incr_initiated();
+    let confirmation_bounds = runtime
+        .confirmation_bounds
+        .remove(&deal)
+        .unwrap_or_default();
+    runtime.clear_deal_heights(&deal);
     launch_swapd(
         consumed_deal_role.clone().into(),
         deal.clone(),
         swap_id,
         swap_config,
+        confirmation_bounds,
     )?;
 
     Ok(TradeStateMachine::SwapdLaunched(SwapdLaunched {
@@ -1464,6 +1620,7 @@ fn attempt_transition_to_end(
                 swap_id,
                 ref address,
                 amount,
+                ..
             }) => {
                 runtime
                     .stats
@@ -1713,6 +1870,52 @@ fn attempt_transition_to_end(
             })))
         }
 
+        // Unlike Connect, an existing connection is a success and a connection still being
+        // established is reported as failed, so the client can retry later
+        (
+            BusMsg::Ctl(CtlMsg::ReconnectAttempt(ReconnectAttempt {
+                swap_id: attempt_swap_id,
+                attempt_number,
+            })),
+            _,
+        ) if attempt_swap_id == swap_id => {
+            let peer_node_addr = node_addr_from_deal(&deal);
+            let mut new_peerd = peerd.clone();
+            let success = match peerd {
+                Some(ref peerd) => runtime.registered_services.contains(peerd),
+                None => match runtime.connect_peer(&peer_node_addr) {
+                    Err(err) => {
+                        log_helper.log_debug(format!(
+                            "Reconnect attempt {} failed: {}",
+                            attempt_number, err
+                        ));
+                        false
+                    }
+                    Ok((connected, peerd)) => {
+                        new_peerd = Some(peerd);
+                        connected
+                    }
+                },
+            };
+            event.complete_client_ctl(CtlMsg::ReconnectAttemptResult(ReconnectAttemptResult {
+                success,
+                peer_addr: peer_node_addr.to_string(),
+                attempt_number,
+            }))?;
+            Ok(Some(TradeStateMachine::SwapdRunning(SwapdRunning {
+                peerd: new_peerd,
+                deal,
+                swap_id,
+                arbitrating_syncer,
+                accordant_syncer,
+                funding_info,
+                auto_funded,
+                clients_awaiting_connect_result,
+                trade_role,
+                expected_counterparty_node_id,
+            })))
+        }
+
         // A ConnectSuccess event can only come from a peerd connecting to a listener (maker)
         (BusMsg::Ctl(CtlMsg::ConnectSuccess), source)
             if source.node_addr() == Some(node_addr_from_deal(&deal)) =>
@@ -1793,13 +1996,38 @@ fn attempt_transition_to_end(
         (BusMsg::Ctl(CtlMsg::SwapOutcome(outcome)), source)
             if ServiceId::Swap(swap_id) == source =>
         {
+            // record the outcome against the counterparty for reputation scoring
+            let counterparty = match trade_role {
+                TradeRole::Taker => Some(node_addr_from_deal(&deal).id),
+                TradeRole::Maker => peerd
+                    .as_ref()
+                    .and_then(|peerd| peerd.node_id())
+                    .or(expected_counterparty_node_id),
+            };
+            if let Some(node_id) = counterparty {
+                // Alice punishing Bob means the counterparty broke the protocol
+                let protocol_violation = outcome == Outcome::FailurePunish
+                    && deal.swap_role(&trade_role) == SwapRole::Alice;
+                event.send_ctl_service(
+                    ServiceId::Database,
+                    CtlMsg::SetPeerOutcome(PeerOutcome {
+                        node_id,
+                        outcome: outcome.clone(),
+                        protocol_violation,
+                    }),
+                )?;
+            }
             event.send_ctl_service(
                 ServiceId::Database,
                 CtlMsg::SetDealInfo(DealInfo {
                     serialized_deal: deal.to_string(),
-                    deal,
                     status: DealStatus::Ended(outcome.clone()),
                     local_trade_role: trade_role,
+                    local_swap_role: deal.swap_role(&trade_role),
+                    failure_reason: None,
+                    expires_at_block: None,
+                    expires_in: None,
+                    deal,
                 }),
             )?;
             runtime.clean_up_after_swap(&swap_id, event.endpoints)?;
@@ -1856,3 +2084,64 @@ fn node_addr_from_deal(deal: &Deal) -> NodeAddr {
         addr: deal.peer_address,        // peer_address is InetSocketAddr
     }
 }
+
+/// Number of launched swaps whose counterparty is the node
+pub fn active_swap_count(trade_state_machines: &[TradeStateMachine], node_id: &NodeId) -> u32 {
+    trade_state_machines
+        .iter()
+        .filter(|tsm| tsm.counterparty_node_id().as_ref() == Some(node_id))
+        .count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use bitcoin::secp256k1::{SecretKey, SECP256K1};
+
+    fn swapd_running(
+        deal: &Deal,
+        peerd: Option<ServiceId>,
+        trade_role: TradeRole,
+    ) -> TradeStateMachine {
+        TradeStateMachine::SwapdRunning(SwapdRunning {
+            peerd,
+            deal: deal.clone(),
+            arbitrating_syncer: ServiceId::Syncer(Blockchain::Bitcoin, Network::Local),
+            accordant_syncer: ServiceId::Syncer(Blockchain::Monero, Network::Local),
+            swap_id: SwapId(Uuid::random()),
+            funding_info: None,
+            auto_funded: false,
+            clients_awaiting_connect_result: vec![],
+            trade_role,
+            expected_counterparty_node_id: None,
+        })
+    }
+
+    #[test]
+    fn active_swap_count_counts_every_swap_with_the_peer() {
+        let deal = fixtures::deal();
+        let maker = NodeId::from(deal.node_id);
+        let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let taker = NodeId::from(bitcoin::secp256k1::PublicKey::from_secret_key(
+            SECP256K1,
+            &secret_key,
+        ));
+        let taker_peerd = ServiceId::Peer(
+            0,
+            NodeAddr {
+                id: taker,
+                addr: deal.peer_address,
+            },
+        );
+        let trade_state_machines = vec![
+            // two swaps taking deals of the same maker, without a connection
+            swapd_running(&deal, None, TradeRole::Taker),
+            swapd_running(&deal, None, TradeRole::Taker),
+            swapd_running(&deal, Some(taker_peerd), TradeRole::Maker),
+            TradeStateMachine::StartMaker,
+        ];
+        assert_eq!(active_swap_count(&trade_state_machines, &maker), 2);
+        assert_eq!(active_swap_count(&trade_state_machines, &taker), 1);
+    }
+}
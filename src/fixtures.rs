@@ -0,0 +1,50 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+//! Fixtures shared by the unit tests
+
+use std::str::FromStr;
+
+use farcaster_core::swap::btcxmr::Deal;
+
+#[cfg(feature = "node")]
+use crate::swapd::{CheckpointSwapd, SwapStateMachine, TemporalSafety};
+#[cfg(feature = "node")]
+use farcaster_core::role::TradeRole;
+
+/// An encoded deal used across the unit tests
+pub const DEAL: &str = "Deal:Cke4ftrP5A781Vq85dgBQJNwYgBS4nuUV1LQM2fvVdFMNR4h5TrWhRR11111uMFuZTAsNgpdK8DiK11111TB9zym113GTvtvqfD1111114A4TTfFfmZoWyvpcjDBtTZCdWFSUWcRKYfEC3Y17hqaXZ3dWz11111111111111111111111111111111111111111AfZ113SEBTEspU3a";
+
+pub fn deal() -> Deal {
+    Deal::from_str(DEAL).expect("valid deal fixture")
+}
+
+/// A maker checkpoint of the deal in the Bob buy sweeping state, with no pending message,
+/// transaction nor history
+#[cfg(feature = "node")]
+pub fn checkpoint(deal: Deal) -> CheckpointSwapd {
+    CheckpointSwapd {
+        state: SwapStateMachine::BobBuySweeping,
+        pending_msg: None,
+        enquirer: None,
+        xmr_addr_addendum: None,
+        temporal_safety: TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        },
+        txids: vec![],
+        pending_broadcasts: vec![],
+        local_trade_role: TradeRole::Maker,
+        connected_counterparty_node_id: None,
+        task_counter: 0,
+        history: vec![],
+        fee_strategy: deal.parameters.fee_strategy,
+        deal,
+    }
+}
@@ -30,7 +30,7 @@ use tonic::{transport::Server, Request as GrpcRequest, Response as GrpcResponse,
 use uuid::Uuid;
 
 use crate::bus::bridge::BridgeMsg;
-use crate::bus::ctl::{FundingInfo, ProtoDeal, PubDeal};
+use crate::bus::ctl::{DealRevocation, FundingInfo, ProtoDeal, PubDeal};
 use crate::bus::info::AddressBalance;
 use crate::bus::info::{Address, DealStatusSelector, ProgressEvent};
 use crate::bus::{ctl::CtlMsg, info::InfoMsg, info::SwapInfo};
@@ -40,7 +40,7 @@ use crate::bus::{
 use crate::bus::{BusMsg, ServiceBus};
 use crate::grpcd::runtime::farcaster::NetworkSelector;
 use crate::service::Endpoints;
-use crate::swapd::StateReport;
+use crate::swapd::{ConfirmationBounds, StateReport};
 use crate::syncerd::{Health, SweepAddressAddendum, SweepBitcoinAddress, SweepMoneroAddress};
 use crate::{CtlServer, Error, Service, ServiceConfig, ServiceId};
 
@@ -52,6 +52,9 @@ pub mod farcaster {
     tonic::include_proto!("farcaster");
 }
 
+const PROGRESS_STREAM_BUFFER: usize = 16;
+const PROGRESS_STREAM_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 impl From<TradeRole> for farcaster::TradeRole {
     fn from(t: TradeRole) -> farcaster::TradeRole {
         match t {
@@ -250,6 +253,8 @@ impl From<StateReport> for farcaster::State {
             blocks_until_safe_monero_buy_sweep: state_report
                 .blocks_until_safe_monero_buy_sweep
                 .map(farcaster::state::BlocksUntilSafeMoneroBuySweep::BuyMoneroBlocks),
+            final_tx_count: state_report.final_tx_count,
+            required_final_tx_count: state_report.required_final_tx_count,
         }
     }
 }
@@ -297,6 +302,49 @@ impl From<crate::farcasterd::stats::Stats> for Stats {
     }
 }
 
+impl From<ProgressEvent> for farcaster::Progress {
+    fn from(p: ProgressEvent) -> farcaster::Progress {
+        match p {
+            ProgressEvent::Message(m) => farcaster::Progress {
+                progress: Some(farcaster::progress::Progress::Message(m)),
+            },
+            ProgressEvent::StateUpdate(su) => farcaster::Progress {
+                progress: Some(farcaster::progress::Progress::StateUpdate(su.into())),
+            },
+            ProgressEvent::StateTransition(st) => farcaster::Progress {
+                progress: Some(farcaster::progress::Progress::StateTransition(
+                    farcaster::StateTransition {
+                        old_state: Some(st.old_state.into()),
+                        new_state: Some(st.new_state.into()),
+                    },
+                )),
+            },
+            ProgressEvent::Failure(Failure { info, .. }) => farcaster::Progress {
+                progress: Some(farcaster::progress::Progress::Failure(info)),
+            },
+            ProgressEvent::Success(OptionDetails(s)) => farcaster::Progress {
+                progress: Some(farcaster::progress::Progress::Success(
+                    s.unwrap_or_default(),
+                )),
+            },
+        }
+    }
+}
+
+/// Stream of the progress events of a swap, ends when the swap succeeds or fails
+pub struct ProgressStream(tokio::sync::mpsc::Receiver<Result<farcaster::Progress, Status>>);
+
+impl tonic::codegen::futures_core::Stream for ProgressStream {
+    type Item = Result<farcaster::Progress, Status>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, PartialOrd, Hash, Display)]
 #[display(Debug)]
 pub struct IdCounter(u64);
@@ -308,6 +356,7 @@ impl IdCounter {
     }
 }
 
+#[derive(Clone)]
 pub struct FarcasterService {
     tokio_tx_request: tokio::sync::mpsc::Sender<(u64, BusMsg)>,
     pending_requests: Arc<Mutex<HashMap<u64, tokio::sync::oneshot::Sender<BusMsg>>>>,
@@ -347,7 +396,7 @@ impl FarcasterService {
             .await?;
 
         match oneshot_rx.await {
-            Ok(BusMsg::Ctl(CtlMsg::HealthResult(health))) => Ok(health),
+            Ok(BusMsg::Ctl(CtlMsg::HealthResult(res))) => Ok(res.health),
             _ => Err(Status::internal("Error during health check".to_string())),
         }
     }
@@ -412,7 +461,7 @@ impl Farcaster for FarcasterService {
             Ok(BusMsg::Info(InfoMsg::PeerList(peers))) => {
                 let reply = farcaster::PeersResponse {
                     id: request.into_inner().id,
-                    peers: peers.iter().map(|peer| format!("{}", peer)).collect(),
+                    peers: peers.iter().map(|info| format!("{}", info.peer)).collect(),
                 };
                 Ok(GrpcResponse::new(reply))
             }
@@ -449,6 +498,7 @@ impl Farcaster for FarcasterService {
                 local_trade_role,
                 local_swap_role,
                 connected_counterparty_node_id,
+                ..
             }))) => {
                 let reply = SwapInfoResponse {
                     id,
@@ -820,6 +870,8 @@ impl Farcaster for FarcasterService {
             public_addr,
             arbitrating_addr,
             accordant_addr,
+            confirmation_bounds: ConfirmationBounds::default(),
+            deal_expiry: None,
         };
 
         let oneshot_rx = self
@@ -852,7 +904,7 @@ impl Farcaster for FarcasterService {
 
         let oneshot_rx = self
             .process_request(BusMsg::Bridge(BridgeMsg::Ctl {
-                request: CtlMsg::RevokeDeal(deal),
+                request: CtlMsg::RevokeDeal(DealRevocation { deal, force: false }),
                 service_id: ServiceId::Farcasterd,
             }))
             .await?;
@@ -879,7 +931,7 @@ impl Farcaster for FarcasterService {
 
         let oneshot_rx = self
             .process_request(BusMsg::Bridge(BridgeMsg::Ctl {
-                request: CtlMsg::AbortSwap,
+                request: CtlMsg::AbortSwap(None),
                 service_id: ServiceId::Swap(swap_id),
             }))
             .await?;
@@ -918,32 +970,7 @@ impl Farcaster for FarcasterService {
                     progress: progress
                         .progress
                         .drain(..)
-                        .map(|p| match p {
-                            ProgressEvent::Message(m) => farcaster::Progress {
-                                progress: Some(farcaster::progress::Progress::Message(m)),
-                            },
-                            ProgressEvent::StateUpdate(su) => farcaster::Progress {
-                                progress: Some(farcaster::progress::Progress::StateUpdate(
-                                    su.into(),
-                                )),
-                            },
-                            ProgressEvent::StateTransition(st) => farcaster::Progress {
-                                progress: Some(farcaster::progress::Progress::StateTransition(
-                                    farcaster::StateTransition {
-                                        old_state: Some(st.old_state.into()),
-                                        new_state: Some(st.new_state.into()),
-                                    },
-                                )),
-                            },
-                            ProgressEvent::Failure(Failure { info, .. }) => farcaster::Progress {
-                                progress: Some(farcaster::progress::Progress::Failure(info)),
-                            },
-                            ProgressEvent::Success(OptionDetails(s)) => farcaster::Progress {
-                                progress: Some(farcaster::progress::Progress::Success(
-                                    s.unwrap_or_default(),
-                                )),
-                            },
-                        })
+                        .map(farcaster::Progress::from)
                         .collect(),
                 };
 
@@ -953,6 +980,64 @@ impl Farcaster for FarcasterService {
         }
     }
 
+    type SubscribeProgressStream = ProgressStream;
+
+    async fn subscribe_progress(
+        &self,
+        request: GrpcRequest<ProgressRequest>,
+    ) -> Result<GrpcResponse<Self::SubscribeProgressStream>, Status> {
+        debug!("Received a grpc subscribe progress request: {:?}", request);
+        let ProgressRequest {
+            swap_id: str_swap_id,
+            ..
+        } = request.into_inner();
+        let swap_id =
+            SwapId::from_str(&str_swap_id).map_err(|_| Status::invalid_argument("swap id"))?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(PROGRESS_STREAM_BUFFER);
+        let service = self.clone();
+        // farcasterd returns the complete progress of the swap, poll it and stream the new events
+        tokio::spawn(async move {
+            let mut sent = 0;
+            loop {
+                let res = match service
+                    .process_request(BusMsg::Bridge(BridgeMsg::Info {
+                        request: InfoMsg::ReadProgress(swap_id),
+                        service_id: ServiceId::Farcasterd,
+                    }))
+                    .await
+                {
+                    Ok(oneshot_rx) => oneshot_rx.await,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                };
+                let progress = match res {
+                    Ok(BusMsg::Info(InfoMsg::SwapProgress(progress))) => progress.progress,
+                    res => {
+                        if let Err(status) = process_error_response::<()>(res) {
+                            let _ = tx.send(Err(status)).await;
+                        }
+                        return;
+                    }
+                };
+                for event in progress.into_iter().skip(sent) {
+                    sent += 1;
+                    let end =
+                        matches!(event, ProgressEvent::Success(_) | ProgressEvent::Failure(_));
+                    // the client dropped the stream
+                    if tx.send(Ok(event.into())).await.is_err() || end {
+                        return;
+                    }
+                }
+                tokio::time::sleep(PROGRESS_STREAM_POLL_INTERVAL).await;
+            }
+        });
+
+        Ok(GrpcResponse::new(ProgressStream(rx)))
+    }
+
     async fn connect_swap(
         &self,
         request: GrpcRequest<ConnectSwapRequest>,
@@ -1224,6 +1309,7 @@ impl Farcaster for FarcasterService {
                     deal,
                     bitcoin_address,
                     monero_address,
+                    expiry: None,
                 }),
                 service_id: ServiceId::Farcasterd,
             }))
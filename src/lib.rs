@@ -45,6 +45,8 @@ pub mod client;
 pub mod config;
 pub mod error;
 pub mod event;
+#[cfg(test)]
+mod fixtures;
 #[cfg(feature = "shell")]
 pub mod opts;
 
@@ -138,6 +138,8 @@ pub fn run_from_connect(
         messages_sent: 0,
         messages_received: 0,
         awaited_pong: None,
+        ping_sent: None,
+        rtt: None,
         thread_flag_tx,
         unchecked_msg_cache: empty!(),
     };
@@ -243,6 +245,8 @@ pub fn run_from_listener(
         messages_sent: 0,
         messages_received: 0,
         awaited_pong: None,
+        ping_sent: None,
+        rtt: None,
         thread_flag_tx,
         unchecked_msg_cache: empty!(),
     };
@@ -381,6 +385,8 @@ pub struct Runtime {
     messages_sent: usize,
     messages_received: usize,
     awaited_pong: Option<u16>,
+    ping_sent: Option<SystemTime>,
+    rtt: Option<Duration>,
 
     unchecked_msg_cache: Vec<((SwapId, internet2::TypeId), PeerMsg)>,
 
@@ -638,6 +644,7 @@ impl Runtime {
                     messages_received: self.messages_received,
                     forked_from_listener: self.forked_from_listener,
                     awaits_pong: self.awaited_pong.is_some(),
+                    rtt_ms: self.rtt.map(|rtt| rtt.as_millis() as u32),
                 };
                 self.send_client_info(endpoints, source, InfoMsg::PeerInfo(info))?;
             }
@@ -737,10 +744,13 @@ impl Runtime {
                             self.identity()
                         );
                     }
-                    _ => trace!(
-                        "{} | Got pong reply, exiting pong await mode",
-                        self.identity()
-                    ),
+                    _ => {
+                        trace!(
+                            "{} | Got pong reply, exiting pong await mode",
+                            self.identity()
+                        );
+                        self.rtt = self.ping_sent.and_then(|sent| sent.elapsed().ok());
+                    }
                 }
                 self.awaited_pong = None;
             }
@@ -867,6 +877,7 @@ impl Runtime {
             .expect("should be connected")
             .send_message(PeerMsg::Ping(pong_size))?;
         self.awaited_pong = Some(pong_size);
+        self.ping_sent = Some(SystemTime::now());
         Ok(())
     }
 
@@ -0,0 +1,146 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashSet;
+
+use farcaster_core::swap::SwapId;
+use farcaster_core::transaction::TxLabel;
+
+use crate::bus::CheckpointEntry;
+
+use super::runtime::CheckpointSwapd;
+
+/// Inconsistencies found in a decoded swap checkpoint, any of them prevents the restore
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CheckpointValidationError {
+    /// Checkpoint does not belong to swap {0}, its deal or trade role differ from the swap entry
+    SwapMismatch(SwapId),
+
+    /// Checkpoint has invalid temporal safety parameters: {0}
+    InvalidTemporalSafety(String),
+
+    /// Checkpoint temporal safety timelocks differ from the timelocks of the deal of swap {0}
+    TemporalSafetyMismatch(SwapId),
+
+    /// Checkpoint watches the {0} transaction more than once
+    DuplicateTxLabel(TxLabel),
+
+    /// Checkpoint pending broadcast of the {0} transaction does not match its watched txid
+    PendingBroadcastMismatch(TxLabel),
+
+    /// Checkpoint syncer task counter overflowed
+    TaskCounterOverflow,
+}
+
+/// Verifies the internal consistency of a decoded swap checkpoint before it is applied
+pub struct CheckpointValidator {
+    /// Checkpoint entry recorded for the swap being restored
+    pub entry: CheckpointEntry,
+}
+
+impl CheckpointValidator {
+    pub fn new(entry: CheckpointEntry) -> Self {
+        CheckpointValidator { entry }
+    }
+
+    /// Run every check on the checkpoint and return the first inconsistency found
+    pub fn validate(&self, checkpoint: &CheckpointSwapd) -> Result<(), CheckpointValidationError> {
+        if checkpoint.deal != self.entry.deal
+            || checkpoint.local_trade_role != self.entry.trade_role
+        {
+            return Err(CheckpointValidationError::SwapMismatch(self.entry.swap_id));
+        }
+        checkpoint
+            .temporal_safety
+            .valid_params()
+            .map_err(|err| CheckpointValidationError::InvalidTemporalSafety(err.to_string()))?;
+        if !checkpoint.temporal_safety.matches_deal(&checkpoint.deal) {
+            return Err(CheckpointValidationError::TemporalSafetyMismatch(
+                self.entry.swap_id,
+            ));
+        }
+        let mut labels = HashSet::new();
+        for (label, _) in checkpoint.txids.iter() {
+            if !labels.insert(*label) {
+                return Err(CheckpointValidationError::DuplicateTxLabel(*label));
+            }
+        }
+        for (tx, label) in checkpoint.pending_broadcasts.iter() {
+            let watched = checkpoint
+                .txids
+                .iter()
+                .find_map(|(watched_label, txid)| (watched_label == label).then(|| txid));
+            if watched.map_or(false, |txid| *txid != tx.txid()) {
+                return Err(CheckpointValidationError::PendingBroadcastMismatch(*label));
+            }
+        }
+        if checkpoint.task_counter == u32::MAX {
+            return Err(CheckpointValidationError::TaskCounterOverflow);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::swapd::SwapStateMachine;
+    use farcaster_core::bitcoin::timelock::CSVTimelock;
+    use farcaster_core::role::{SwapRole, TradeRole};
+    use farcaster_core::Uuid;
+
+    #[test]
+    fn checkpoint_validation_failures() {
+        let mut deal = fixtures::deal();
+        deal.parameters.cancel_timelock = CSVTimelock::new(10);
+        deal.parameters.punish_timelock = CSVTimelock::new(30);
+        let swap_id = SwapId(Uuid::new());
+        let validator = CheckpointValidator::new(CheckpointEntry {
+            swap_id,
+            deal: deal.clone(),
+            trade_role: TradeRole::Maker,
+            expected_counterparty_node_id: None,
+        });
+        let mut checkpoint = fixtures::checkpoint(deal);
+        checkpoint.state = SwapStateMachine::StartMaker(SwapRole::Alice);
+        checkpoint.txids = vec![(TxLabel::Lock, bitcoin::Txid::default())];
+        assert_eq!(validator.validate(&checkpoint), Ok(()));
+
+        checkpoint.task_counter = u32::MAX;
+        assert_eq!(
+            validator.validate(&checkpoint),
+            Err(CheckpointValidationError::TaskCounterOverflow)
+        );
+
+        checkpoint
+            .txids
+            .push((TxLabel::Lock, bitcoin::Txid::default()));
+        assert_eq!(
+            validator.validate(&checkpoint),
+            Err(CheckpointValidationError::DuplicateTxLabel(TxLabel::Lock))
+        );
+
+        checkpoint.temporal_safety.punish_timelock = 31;
+        assert_eq!(
+            validator.validate(&checkpoint),
+            Err(CheckpointValidationError::TemporalSafetyMismatch(swap_id))
+        );
+
+        checkpoint.temporal_safety.safety = 20;
+        assert!(matches!(
+            validator.validate(&checkpoint),
+            Err(CheckpointValidationError::InvalidTemporalSafety(_))
+        ));
+
+        checkpoint.local_trade_role = TradeRole::Taker;
+        assert_eq!(
+            validator.validate(&checkpoint),
+            Err(CheckpointValidationError::SwapMismatch(swap_id))
+        );
+    }
+}
@@ -4,18 +4,30 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+mod checkpoint_validator;
 #[cfg(feature = "shell")]
 mod opts;
 mod runtime;
+mod simulation;
+mod state_diagram;
 mod state_report;
+mod swap_history;
 mod swap_key_manager;
 mod swap_state;
 mod syncer_client;
 mod temporal_safety;
 
+pub use checkpoint_validator::{CheckpointValidationError, CheckpointValidator};
 #[cfg(feature = "shell")]
 pub use opts::Opts;
 pub use runtime::run;
 pub use runtime::CheckpointSwapd;
-pub use state_report::StateReport;
+pub use simulation::{
+    simulate_swap, SafetyConstraint, SimulatedTransition, SimulationPath, SwapSimulation,
+};
+pub use state_diagram::swap_state_diagram;
+pub use state_report::{StateReport, SwapSummary};
+pub use swap_history::SwapEvent;
 pub use swap_state::SwapStateMachine;
+pub use syncer_client::{ConfirmationBounds, TaskStats};
+pub use temporal_safety::{DeadlineReport, ReorgImpact, TemporalSafety, BITCOIN_BLOCK_TIME_SECS};
@@ -48,6 +48,41 @@ pub struct Opts {
     #[clap(long = "acc-finality")]
     pub accordant_finality: u8,
 
+    /// Remaining lifetime, in blocks, below which the lifetime of a syncer watch task is
+    /// refreshed to keep observing the swap transactions
+    #[clap(long, default_value = "100")]
+    pub task_lifetime_refresh_threshold: u64,
+
+    /// Number of blocks without a broadcast result from the syncer after which the pending
+    /// transactions are broadcasted again
+    #[clap(long, default_value = "6")]
+    pub rebroadcast_threshold: u64,
+
+    /// Number of seconds without a broadcast result from the syncer after which a possible
+    /// network issue is reported
+    #[clap(long, default_value = "60")]
+    pub propagation_warning_secs: u64,
+
+    /// Confirmation bound used when watching the lock transactions
+    #[clap(long, default_value = "50000")]
+    pub lock_confs: u32,
+
+    /// Confirmation bound used when watching the cancel transaction
+    #[clap(long, default_value = "50000")]
+    pub cancel_confs: u32,
+
+    /// Confirmation bound used when watching the buy transaction
+    #[clap(long, default_value = "50000")]
+    pub buy_confs: u32,
+
+    /// Confirmation bound used when watching the refund transaction
+    #[clap(long, default_value = "50000")]
+    pub refund_confs: u32,
+
+    /// Confirmation bound used when watching the punish transaction
+    #[clap(long, default_value = "50000")]
+    pub punish_confs: u32,
+
     /// These params can be read also from the configuration file, not just
     /// Command-line args or environment variables
     #[clap(flatten)]
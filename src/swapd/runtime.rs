@@ -5,20 +5,25 @@
 // https://opensource.org/licenses/MIT.
 
 use super::{
+    swap_history::{trigger_name, SwapEvent},
+    swap_key_manager::{sign_message, verify_message},
     swap_state::{SwapStateMachine, SwapStateMachineExecutor},
-    syncer_client::{SyncerState, SyncerTasks},
-    temporal_safety::TemporalSafety,
-    StateReport,
+    syncer_client::{ConfirmationBounds, RemovedTask, SyncerState, SyncerTasks, TxConfsUpdate},
+    temporal_safety::{ReorgImpact, TemporalSafety},
+    StateReport, SwapSummary,
 };
 use crate::syncerd::types::{Event, TransactionConfirmations};
 use crate::syncerd::{Abort, Task, TaskTarget};
 use crate::{
-    bus::ctl::{Checkpoint, CtlMsg},
-    bus::info::{InfoMsg, SwapInfo},
+    bus::ctl::{Checkpoint, CtlMsg, VerifyMessage},
+    bus::info::{InfoMsg, SwapHistory, SwapInfo},
     bus::p2p::PeerMsg,
     bus::sync::SyncMsg,
-    bus::{BusMsg, ServiceBus},
-    syncerd::{HeightChanged, TransactionRetrieved, XmrAddressAddendum},
+    bus::{AbortReason, BusMsg, Failure, FailureCode, Outcome, Progress, ServiceBus},
+    syncerd::{
+        FeeEstimations, HeightChanged, TransactionRetrieved, TransactionSeen, XmrAddressAddendum,
+        XmrTransactionRetrieved,
+    },
 };
 use crate::{service::SwapDetails, swapd::Opts};
 use crate::{service::SwapLogging, swapd::temporal_safety::SWEEP_MONERO_THRESHOLD};
@@ -29,13 +34,15 @@ use crate::{
 use crate::{CtlServer, Error, LogStyle, Service, ServiceConfig, ServiceId};
 
 use std::any::Any;
+use std::convert::TryFrom;
 use std::time::{Duration, SystemTime};
 
 use bitcoin::Txid;
 use farcaster_core::{
-    blockchain::Blockchain,
+    bitcoin::fee::SatPerKvB,
+    blockchain::{Blockchain, FeeStrategy},
     role::{SwapRole, TradeRole},
-    swap::btcxmr::{Deal, DealParameters},
+    swap::btcxmr::{ArbitratingParameters, Deal, DealParameters},
     swap::SwapId,
     transaction::TxLabel,
 };
@@ -52,13 +59,20 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
         arbitrating_finality,
         arbitrating_safety,
         accordant_finality,
+        task_lifetime_refresh_threshold,
+        rebroadcast_threshold,
+        propagation_warning_secs,
+        lock_confs,
+        cancel_confs,
+        buy_confs,
+        refund_confs,
+        punish_confs,
         ..
     } = opts;
 
     let DealParameters {
-        cancel_timelock,
-        punish_timelock,
         network,
+        fee_strategy,
         ..
     } = deal.parameters;
 
@@ -76,18 +90,23 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
         swap_id.swap_id()
     );
 
-    let temporal_safety = TemporalSafety {
-        cancel_timelock: cancel_timelock.as_u32(),
-        punish_timelock: punish_timelock.as_u32(),
-        arb_finality: arbitrating_finality.into(),
-        safety: arbitrating_safety.into(),
-        acc_finality: accordant_finality.into(),
+    let temporal_safety = TemporalSafety::from_deal(
+        &deal,
+        arbitrating_finality.into(),
+        accordant_finality.into(),
+        arbitrating_safety.into(),
+    )?;
+    let confirmation_bounds = ConfirmationBounds {
+        lock: lock_confs,
+        cancel: cancel_confs,
+        buy: buy_confs,
+        refund: refund_confs,
+        punish: punish_confs,
     };
-
-    temporal_safety.valid_params()?;
     let tasks = SyncerTasks {
         counter: 0,
         watched_addrs: none!(),
+        watched_mempool: none!(),
         watched_txs: none!(),
         retrieving_txs: none!(),
         sweeping_addr: none!(),
@@ -95,6 +114,9 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
         txids: none!(),
         final_txs: none!(),
         tasks: none!(),
+        watched_heights: none!(),
+        registered_at: none!(),
+        broadcast_times: none!(),
     };
     let syncer_state = SyncerState {
         swap_id,
@@ -103,16 +125,25 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
         tasks,
         monero_height: 0,
         bitcoin_height: 0,
-        confirmation_bound: 50000,
+        confirmation_bounds,
         last_tx_event: none!(),
+        scan_progress: none!(),
+        btc_fee_estimate_sat_per_kvb: None,
+        mempool_congestion: None,
+        task_lifetime_refresh_threshold,
+        rebroadcast_threshold,
+        broadcast_heights: none!(),
+        propagation_warning_threshold: Duration::from_secs(propagation_warning_secs),
         network,
         bitcoin_syncer: ServiceId::Syncer(Blockchain::Bitcoin, network),
         monero_syncer: ServiceId::Syncer(Blockchain::Monero, network),
         awaiting_funding: false,
+        expected_funding_amounts: none!(),
         xmr_addr_addendum: None,
         confirmations: none!(),
         broadcasted_txs: none!(),
         failed_broadcasted_txs: none!(),
+        from_height_overrides: none!(),
     };
 
     let state_report = StateReport::new("Start".to_string(), &temporal_safety, &syncer_state);
@@ -131,11 +162,15 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
         enquirer: None,
         pending_peer_request: none!(),
         deal,
+        fee_strategy,
         local_trade_role,
         local_swap_role,
         latest_state_report: state_report,
         swap_state_machine,
         unhandled_peer_message: None, // The last message we received and was not handled by the state machine
+        abort_reason: None,
+        history: vec![],
+        trigger: String::new(),
     };
     let broker = false;
     Service::run(config, runtime, broker)
@@ -152,11 +187,15 @@ pub struct Runtime {
     pub temporal_safety: TemporalSafety,
     pub pending_peer_request: Vec<PeerMsg>, // Peer requests that failed and are waiting for reconnection
     pub deal: Deal,
+    pub fee_strategy: FeeStrategy<SatPerKvB>, // Starts as the deal fee strategy, can be raised by the user until the punish transaction is signed
     pub local_trade_role: TradeRole,
     pub local_swap_role: SwapRole,
     pub latest_state_report: StateReport,
     pub swap_state_machine: SwapStateMachine,
     pub unhandled_peer_message: Option<PeerMsg>,
+    pub abort_reason: Option<AbortReason>, // Reason given by the user when aborting the swap
+    pub history: Vec<SwapEvent>, // Append-only log of the state transitions, persisted in the checkpoints
+    pub trigger: String,         // Type of the message being processed by the state machine
 }
 
 #[derive(Debug, Clone, Display, StrictEncode, StrictDecode)]
@@ -172,6 +211,9 @@ pub struct CheckpointSwapd {
     pub local_trade_role: TradeRole,
     pub connected_counterparty_node_id: Option<NodeId>,
     pub deal: Deal,
+    pub task_counter: u32, // Syncer task id counter, task ids are not reused after restore
+    pub history: Vec<SwapEvent>,
+    pub fee_strategy: FeeStrategy<SatPerKvB>,
 }
 
 impl CtlServer for Runtime {}
@@ -283,11 +325,12 @@ impl Runtime {
         tx_label: TxLabel,
         endpoints: &mut Endpoints,
     ) -> Result<(), Error> {
-        self.log_info(format!(
-            "Broadcasting {} tx({})",
-            tx_label.label(),
-            tx.txid().tx_hash()
-        ));
+        let amount = tx.output.iter().map(|out| out.value).sum();
+        self.syncer_state.log_tx_created(
+            tx_label,
+            tx.txid(),
+            Some(bitcoin::Amount::from_sat(amount)),
+        );
         let task = self.syncer_state.broadcast(tx, tx_label);
         Ok(endpoints.send_to(
             ServiceBus::Sync,
@@ -401,10 +444,29 @@ impl Runtime {
                     xmr_addr_addendum,
                     local_trade_role,
                     state,
+                    task_counter,
+                    history,
+                    fee_strategy,
                     ..
                 } = state;
                 self.log_info("Restoring swap");
+                // Tasks of the swap before restore may still run on the syncers, they are aborted
+                // and registered again below
+                self.abort_all_syncer_tasks(endpoints)?;
+                let highest_task_id = self
+                    .syncer_state
+                    .tasks
+                    .tasks
+                    .keys()
+                    .map(|id| id.0)
+                    .max()
+                    .unwrap_or_default();
+                self.syncer_state.tasks.counter = u64::from(task_counter)
+                    .max(u64::from(highest_task_id))
+                    .max(self.syncer_state.tasks.counter);
                 self.swap_state_machine = state;
+                self.history = history;
+                self.fee_strategy = fee_strategy;
                 self.enquirer = enquirer;
                 self.temporal_safety = temporal_safety;
                 // We need to update the peerd for the pending requests in case of reconnect
@@ -442,12 +504,15 @@ impl Runtime {
                     from_height,
                 }) = xmr_addr_addendum
                 {
+                    // scan from the checkpointed height, the lock may predate the restore by far
+                    self.syncer_state
+                        .set_from_height_override(TxLabel::AccLock, from_height);
                     let task = self.syncer_state.watch_addr_xmr(
                         address,
                         view_key,
                         TxLabel::AccLock,
                         from_height,
-                    );
+                    )?;
                     endpoints.send_to(
                         ServiceBus::Sync,
                         self.identity(),
@@ -461,6 +526,95 @@ impl Runtime {
                 }
             }
 
+            CtlMsg::SetFeeStrategy(fee_strategy) => {
+                match self.validate_fee_strategy(&fee_strategy) {
+                    Ok(()) => {
+                        self.log_info(format!(
+                            "Fee strategy updated from {} to {}",
+                            self.fee_strategy,
+                            fee_strategy.bright_yellow_bold()
+                        ));
+                        self.fee_strategy = fee_strategy;
+                        self.send_client_info(
+                            endpoints,
+                            source,
+                            InfoMsg::String(format!("Fee strategy set to {}", fee_strategy)),
+                        )?;
+                    }
+                    Err(info) => {
+                        self.log_warn(&info);
+                        self.send_client_ctl(
+                            endpoints,
+                            source,
+                            CtlMsg::Failure(Failure {
+                                code: FailureCode::Unknown,
+                                info,
+                            }),
+                        )?;
+                    }
+                }
+            }
+
+            CtlMsg::ForceAbortSwap(reason) => {
+                if self.swap_state_machine.abortable() {
+                    self.log_warn(format!(
+                        "Force aborting swap in state {}, the counterparty is not waited for",
+                        self.swap_state_machine
+                    ));
+                    self.trigger =
+                        trigger_name(&BusMsg::Ctl(CtlMsg::ForceAbortSwap(reason.clone())));
+                    self.abort_reason = reason;
+                    self.send_client_info(
+                        endpoints,
+                        source,
+                        InfoMsg::String("Force aborted swap".to_string()),
+                    )?;
+                    let swap_end = SwapStateMachine::SwapEnd(Outcome::FailureAbort);
+                    self.history.push(SwapEvent::new(
+                        &self.swap_state_machine,
+                        &swap_end,
+                        &self.trigger,
+                    ));
+                    self.swap_state_machine = swap_end;
+                    self.end_swap(endpoints, Outcome::FailureAbort)?;
+                } else {
+                    self.send_client_result(
+                        endpoints,
+                        source,
+                        Err(format!(
+                            "Swap cannot be force aborted in state {}, funds are locked or the \
+                            funding is seen, abort without --force to sweep the funding",
+                            self.swap_state_machine
+                        )),
+                    )?;
+                }
+            }
+
+            CtlMsg::SignMessage(message) => {
+                let res = match self.swap_state_machine.key_manager() {
+                    Some(key_manager) => {
+                        sign_message(key_manager, &message).map_err(|err| err.to_string())
+                    }
+                    None => Err(self.keys_unavailable()),
+                };
+                self.send_client_result(endpoints, source, res)?;
+            }
+
+            CtlMsg::VerifyMessage(VerifyMessage { message, signature }) => {
+                let res = match self.swap_state_machine.key_manager() {
+                    Some(key_manager) => match verify_message(key_manager, &message, &signature) {
+                        Ok(true) => Ok(format!("Signature is valid for swap {}", self.swap_id)),
+                        Ok(false) => Err(format!(
+                            "Signature is not made with the lock key of swap {}",
+                            self.swap_id
+                        )),
+                        Err(err) => Err(err.to_string()),
+                    },
+                    None => Err(self.keys_unavailable()),
+                };
+                self.send_client_result(endpoints, source, res)?;
+            }
+
             req => {
                 self.execute_state_machine(endpoints, BusMsg::Ctl(req), source)?;
             }
@@ -469,6 +623,68 @@ impl Runtime {
         Ok(())
     }
 
+    fn keys_unavailable(&self) -> String {
+        format!(
+            "Swap {} no longer holds its keys in state {}",
+            self.swap_id, self.swap_state_machine
+        )
+    }
+
+    /// Replies to the client with the information or with a failure carrying the error
+    fn send_client_result(
+        &mut self,
+        endpoints: &mut Endpoints,
+        source: ServiceId,
+        res: Result<String, String>,
+    ) -> Result<(), Error> {
+        match res {
+            Ok(info) => self.send_client_info(endpoints, source, InfoMsg::String(info)),
+            Err(info) => {
+                self.log_warn(&info);
+                self.send_client_ctl(
+                    endpoints,
+                    source,
+                    CtlMsg::Failure(Failure {
+                        code: FailureCode::Unknown,
+                        info,
+                    }),
+                )
+            }
+        }
+    }
+
+    /// Refuses a fee strategy paying less than the latest Bitcoin fee estimate, the transactions
+    /// created with it could not confirm before the timelocks expire. The fee strategy only
+    /// applies to the punish transaction, so it is refused once the punish transaction is signed.
+    fn validate_fee_strategy(&self, fee_strategy: &FeeStrategy<SatPerKvB>) -> Result<(), String> {
+        if !self.swap_state_machine.punish_unsigned() {
+            return Err(format!(
+                "Fee strategy cannot be changed in state {}, it only applies to the punish \
+                 transaction which is signed by Alice on the core arbitrating setup",
+                self.swap_state_machine
+            ));
+        }
+        let fee_rate = match fee_strategy {
+            FeeStrategy::Fixed(fee_rate) => fee_rate.as_native_unit().as_sat(),
+        };
+        match self.syncer_state.bitcoin_fee_sat_per_kvb() {
+            Some(estimate) if fee_rate < estimate => Err(format!(
+                "Fee strategy {} is below the current Bitcoin fee estimate of {} satoshi/kvB",
+                fee_strategy, estimate
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The arbitrating parameters of the deal with the current fee strategy of the swap. Only used
+    /// for the punish transaction, the other transactions are co-signed or validated by the
+    /// counterparty against the deal fee strategy.
+    pub fn punish_arbitrating_params(&self) -> ArbitratingParameters {
+        let mut params = self.deal.to_arbitrating_params();
+        params.fee_strategy = self.fee_strategy;
+        params
+    }
+
     fn handle_info(
         &mut self,
         endpoints: &mut Endpoints,
@@ -495,10 +711,22 @@ impl Runtime {
                     local_trade_role: self.local_trade_role,
                     local_swap_role: self.deal.swap_role(&self.local_trade_role),
                     connected_counterparty_node_id: self.peer_service.node_id(),
+                    task_stats: self.syncer_state.tasks.stats(),
                 };
                 self.send_client_info(endpoints, source, InfoMsg::SwapInfo(info))?;
             }
 
+            InfoMsg::GetSwapHistory(_) => {
+                self.send_client_info(
+                    endpoints,
+                    source,
+                    InfoMsg::SwapHistory(SwapHistory {
+                        swap_id: self.swap_id,
+                        events: self.history.clone(),
+                    }),
+                )?;
+            }
+
             req => {
                 self.log_error(format!(
                     "BusMsg {} is not supported by the INFO interface",
@@ -516,6 +744,8 @@ impl Runtime {
         source: ServiceId,
         request: SyncMsg,
     ) -> Result<(), Error> {
+        let mut tx_confs_update = None;
+        let mut reorg_impact = ReorgImpact::NoImpact;
         match request {
             SyncMsg::Event(ref event) if source == self.syncer_state.monero_syncer => {
                 match &event {
@@ -523,6 +753,7 @@ impl Runtime {
                         self.syncer_state.handle_height_change(
                             *height,
                             Blockchain::Monero,
+                            &self.temporal_safety,
                             endpoints,
                         );
                     }
@@ -530,9 +761,10 @@ impl Runtime {
                     Event::TransactionConfirmations(TransactionConfirmations {
                         id,
                         confirmations,
+                        amount,
                         ..
                     }) => {
-                        self.syncer_state.handle_tx_confs(
+                        tx_confs_update = self.syncer_state.handle_tx_confs(
                             id,
                             confirmations,
                             self.swap_id(),
@@ -540,6 +772,21 @@ impl Runtime {
                             endpoints,
                         );
 
+                        if let Some(amount) = amount.map(monero::Amount::from_pico) {
+                            let expected = self.deal.parameters.accordant_amount;
+                            if amount < expected {
+                                self.log_warn(format!(
+                                    "Monero transaction decoded amount {} is lower than the expected {}",
+                                    amount, expected
+                                ));
+                            } else {
+                                self.log_debug(format!(
+                                    "Monero transaction decoded amount {}",
+                                    amount
+                                ));
+                            }
+                        }
+
                         // saving requests of interest for later replaying latest event
                         if let Some(txlabel) = self.syncer_state.tasks.watched_txs.get(id) {
                             self.syncer_state
@@ -557,12 +804,51 @@ impl Runtime {
                         }
                     }
 
+                    // This re-triggers the tx fetch event in case the transaction was not detected yet
+                    Event::XmrTransactionRetrieved(XmrTransactionRetrieved { id, tx: None })
+                        if self.syncer_state.tasks.retrieving_txs.contains_key(id)
+                            && self.syncer_state.tasks.tasks.contains_key(id) =>
+                    {
+                        let task = self.syncer_state.tasks.tasks.get(id).unwrap();
+                        std::thread::sleep(core::time::Duration::from_millis(500));
+                        endpoints.send_to(
+                            ServiceBus::Sync,
+                            self.identity(),
+                            self.syncer_state.monero_syncer(),
+                            BusMsg::Sync(SyncMsg::Task(task.clone())),
+                        )?;
+                    }
+
+                    Event::XmrTransactionRetrieved(XmrTransactionRetrieved {
+                        id,
+                        tx: Some(tx),
+                    }) => {
+                        if let RemovedTask::RetrievingTx(label) =
+                            self.syncer_state.tasks.remove_task(*id)
+                        {
+                            self.log_debug(format!(
+                                "Retrieved {} transaction ({} bytes)",
+                                label.label(),
+                                tx.len()
+                            ));
+                        }
+                    }
+
                     Event::SweepSuccess(_) => {}
 
                     Event::TaskAborted(_) => {}
 
                     Event::Empty(_) => {}
 
+                    Event::ScanProgress(progress) => {
+                        if self.syncer_state.handle_scan_progress(progress) {
+                            self.report_progress_message_log_fail(
+                                endpoints,
+                                format!("Scanning Monero blockchain: {}%", progress.percent),
+                            );
+                        }
+                    }
+
                     event => {
                         self.log_error(format!("event not handled {}", event));
                     }
@@ -572,9 +858,10 @@ impl Runtime {
             SyncMsg::Event(ref event) if source == self.syncer_state.bitcoin_syncer => {
                 match &event {
                     Event::HeightChanged(HeightChanged { height, .. }) => {
-                        self.syncer_state.handle_height_change(
+                        reorg_impact = self.syncer_state.handle_height_change(
                             *height,
                             Blockchain::Bitcoin,
+                            &self.temporal_safety,
                             endpoints,
                         );
                     }
@@ -603,7 +890,7 @@ impl Runtime {
                         .final_tx(*confirmations, Blockchain::Bitcoin)
                         && self.syncer_state.tasks.watched_txs.get(id).is_some() =>
                     {
-                        self.syncer_state.handle_tx_confs(
+                        tx_confs_update = self.syncer_state.handle_tx_confs(
                             id,
                             &Some(*confirmations),
                             self.swap_id(),
@@ -623,7 +910,7 @@ impl Runtime {
                         confirmations,
                         ..
                     }) => {
-                        self.syncer_state.handle_tx_confs(
+                        tx_confs_update = self.syncer_state.handle_tx_confs(
                             id,
                             confirmations,
                             self.swap_id(),
@@ -642,6 +929,17 @@ impl Runtime {
                         self.syncer_state.transaction_broadcasted(event);
                     }
 
+                    Event::TransactionSeen(TransactionSeen { id, hash, amount }) => {
+                        if let Some(txlabel) = self.syncer_state.tasks.watched_mempool.get(id) {
+                            self.log_info(format!(
+                                "{} transaction ({}) seen in mempool with {}, awaiting confirmation",
+                                txlabel.label(),
+                                hash,
+                                bitcoin::Amount::from_sat(*amount).bright_green_bold(),
+                            ));
+                        }
+                    }
+
                     Event::AddressTransaction(AddressTransaction { id, .. }) => {
                         // saving requests of interest for later replaying latest event
                         if let Some(txlabel) = self.syncer_state.tasks.watched_addrs.get(id) {
@@ -664,33 +962,110 @@ impl Runtime {
                         self.log_debug(event);
                     }
 
+                    Event::XmrTransactionRetrieved(event) => {
+                        self.log_debug(event);
+                    }
+
                     Event::AddressBalance(event) => {
                         self.log_debug(event);
                     }
 
                     Event::FeeEstimation(event) => {
+                        let FeeEstimations::BitcoinFeeEstimation {
+                            high_priority_sats_per_kvbyte,
+                            ..
+                        } = event.fee_estimations;
+                        self.syncer_state.btc_fee_estimate_sat_per_kvb =
+                            Some(high_priority_sats_per_kvbyte);
                         self.log_debug(event);
                     }
                     Event::Empty(_) => self.log_debug("empty event not handled for Bitcoin"),
 
+                    Event::MempoolInfo(info) => {
+                        let congestion = self.syncer_state.handle_mempool_info(info).to_string();
+                        self.log_debug(&congestion);
+                        self.report_progress_message_log_fail(endpoints, congestion);
+                    }
+
                     Event::HealthResult(_) => self.log_debug("ignoring health result in swapd"),
+
+                    Event::ScanProgress(event) => {
+                        self.log_debug(event);
+                    }
                 };
             }
             _ => {}
         }
-        self.execute_state_machine(endpoints, BusMsg::Sync(request), source)?;
+        self.execute_state_machine(endpoints, BusMsg::Sync(request), source.clone())?;
+
+        if reorg_impact != ReorgImpact::NoImpact {
+            self.handle_reorg_impact(endpoints, reorg_impact, source.clone())?;
+        }
+
+        // A transaction reaching finality may unlock a transition guarded by another transaction
+        // event already received, replay those now instead of waiting for the next event
+        if let Some(TxConfsUpdate {
+            label,
+            became_final: true,
+            ..
+        }) = tx_confs_update
+        {
+            for (txlabel, event) in self.syncer_state.last_tx_event.clone() {
+                if txlabel == label
+                    || matches!(self.swap_state_machine, SwapStateMachine::SwapEnd(_))
+                {
+                    continue;
+                }
+                self.execute_state_machine(endpoints, BusMsg::Sync(event), source.clone())?;
+            }
+        }
 
         Ok(())
     }
+
+    /// Reports the reorg impact and replays the last lock confirmations event with the
+    /// confirmations lowered by the reorg, so the state machine re-evaluates its guards before
+    /// the syncer reports the lock again
+    fn handle_reorg_impact(
+        &mut self,
+        endpoints: &mut Endpoints,
+        reorg_impact: ReorgImpact,
+        source: ServiceId,
+    ) -> Result<(), Error> {
+        let msg = match reorg_impact {
+            ReorgImpact::NoImpact => return Ok(()),
+            ReorgImpact::BuySafetyRestored => "Reorg: buy is safe to broadcast again".to_string(),
+            ReorgImpact::CancelValidityLost { .. } => {
+                "Reorg: cancel timelock is not reached anymore".to_string()
+            }
+            ReorgImpact::PunishValidityLost => {
+                "Reorg: punish timelock is not reached anymore".to_string()
+            }
+        };
+        self.log_warn(&msg);
+        self.report_progress_message_log_fail(endpoints, msg);
+        if let Some(SyncMsg::Event(Event::TransactionConfirmations(mut event))) =
+            self.syncer_state.last_tx_event.get(&TxLabel::Lock).cloned()
+        {
+            event.confirmations = self.syncer_state.get_confs(TxLabel::Lock);
+            let event = SyncMsg::Event(Event::TransactionConfirmations(event));
+            self.syncer_state
+                .last_tx_event
+                .insert(TxLabel::Lock, event.clone());
+            self.execute_state_machine(endpoints, BusMsg::Sync(event), source)?;
+        }
+        Ok(())
+    }
 }
 
 impl Runtime {
-    fn execute_state_machine(
+    pub fn execute_state_machine(
         &mut self,
         endpoints: &mut Endpoints,
         msg: BusMsg,
         source: ServiceId,
     ) -> Result<(), Error> {
+        self.trigger = trigger_name(&msg);
         if let Some(ssm) = SwapStateMachineExecutor::execute(
             self,
             endpoints,
@@ -698,20 +1073,15 @@ impl Runtime {
             msg.clone(),
             self.swap_state_machine.clone(),
         )? {
+            self.history.push(SwapEvent::new(
+                &self.swap_state_machine,
+                &ssm,
+                &self.trigger,
+            ));
             self.swap_state_machine = ssm;
-            // On SwapEnd, report immediately to ensure the progress message goes out before the swap is terminated, then let farcasterd know of the outcome.
             if let SwapStateMachine::SwapEnd(outcome) = &self.swap_state_machine {
                 let outcome = outcome.clone(); // so we don't borrow self anymore
-                self.abort_all_syncer_tasks(endpoints)?;
-                self.report_potential_state_change(endpoints)?;
-                self.send_ctl(
-                    endpoints,
-                    ServiceId::Farcasterd,
-                    BusMsg::Ctl(CtlMsg::SwapOutcome(outcome)),
-                )?;
-            }
-            if matches!(self.swap_state_machine, SwapStateMachine::SwapEnd(_)) {
-                self.report_potential_state_change(endpoints)?;
+                self.end_swap(endpoints, outcome)?;
                 return Ok(());
             }
             // Unset previously set unhandled peer message
@@ -736,6 +1106,26 @@ impl Runtime {
         Ok(())
     }
 
+    // On SwapEnd, report immediately to ensure the progress message goes out before the swap is
+    // terminated, then let farcasterd know of the outcome.
+    fn end_swap(&mut self, endpoints: &mut Endpoints, outcome: Outcome) -> Result<(), Error> {
+        self.syncer_state.cancel_all_tasks(endpoints)?;
+        self.report_potential_state_change(endpoints)?;
+        if let (Outcome::FailureAbort, Some(reason)) = (&outcome, self.abort_reason.take()) {
+            self.send_ctl(
+                endpoints,
+                ServiceId::Database,
+                BusMsg::Ctl(CtlMsg::SetDealAbortReason(self.deal.clone(), reason)),
+            )?;
+        }
+        self.send_ctl(
+            endpoints,
+            ServiceId::Farcasterd,
+            BusMsg::Ctl(CtlMsg::SwapOutcome(outcome)),
+        )?;
+        self.report_potential_state_change(endpoints)
+    }
+
     fn report_potential_state_change(&mut self, endpoints: &mut Endpoints) -> Result<(), Error> {
         // Generate a new state report for the clients
         let new_state_report = StateReport::new(
@@ -744,11 +1134,32 @@ impl Runtime {
             &self.syncer_state,
         );
         if self.latest_state_report != new_state_report {
+            if self.latest_state_report.final_tx_count != new_state_report.final_tx_count
+                || self.latest_state_report.required_final_tx_count
+                    != new_state_report.required_final_tx_count
+            {
+                self.report_progress_message_log_fail(
+                    endpoints,
+                    format!(
+                        "Finalized: {}/{} required transactions",
+                        new_state_report.final_tx_count, new_state_report.required_final_tx_count
+                    ),
+                );
+            }
             let progress = self
                 .latest_state_report
                 .generate_progress_update_or_transition(&new_state_report);
+            let state_changed = self.latest_state_report.state != new_state_report.state;
             self.latest_state_report = new_state_report;
             self.report_progress(endpoints, progress)?;
+            if state_changed {
+                let summary = SwapSummary::new(
+                    &self.latest_state_report,
+                    &self.deal.parameters,
+                    &self.syncer_state,
+                );
+                self.report_progress(endpoints, Progress::Summary(summary))?;
+            }
         }
         Ok(())
     }
@@ -759,6 +1170,20 @@ impl Runtime {
         pending_msg: Option<PeerMsg>,
         next_state: SwapStateMachine,
     ) -> Result<(), Error> {
+        // the transition to the checkpointed state is only recorded once the state machine returns
+        let mut history = self.history.clone();
+        history.push(SwapEvent::new(
+            &self.swap_state_machine,
+            &next_state,
+            &self.trigger,
+        ));
+        let mut pending_broadcasts = vec![];
+        for pending in self.syncer_state.pending_broadcast_txs() {
+            match pending {
+                Ok(pending) => pending_broadcasts.push(pending),
+                Err(err) => self.log_error(err),
+            }
+        }
         endpoints.send_to(
             ServiceBus::Ctl,
             self.identity(),
@@ -771,11 +1196,16 @@ impl Runtime {
                     enquirer: self.enquirer.clone(),
                     temporal_safety: self.temporal_safety.clone(),
                     txids: self.syncer_state.tasks.txids.clone().drain().collect(),
-                    pending_broadcasts: self.syncer_state.pending_broadcast_txs(),
+                    pending_broadcasts,
                     xmr_addr_addendum: self.syncer_state.xmr_addr_addendum.clone(),
                     local_trade_role: self.local_trade_role,
                     connected_counterparty_node_id: self.peer_service.node_id(),
                     deal: self.deal.clone(),
+                    // the counter stops at the recycle threshold, below u32::MAX
+                    task_counter: u32::try_from(self.syncer_state.tasks.counter)
+                        .unwrap_or(u32::MAX - 1),
+                    history,
+                    fee_strategy: self.fee_strategy,
                 },
             })),
         )?;
@@ -0,0 +1,498 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use farcaster_core::blockchain::Blockchain;
+use farcaster_core::role::SwapRole;
+use farcaster_core::transaction::TxLabel;
+
+use super::temporal_safety::TemporalSafety;
+
+/// Path followed by a simulated swap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+pub enum SimulationPath {
+    #[display("happy path")]
+    Happy,
+    #[display("cancel and refund path")]
+    CancelRefund,
+}
+
+/// Temporal safety constraint checked before a transaction is broadcast
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+pub enum SafetyConstraint {
+    #[display("safe buy")]
+    SafeBuy,
+    #[display("valid cancel")]
+    ValidCancel,
+    #[display("safe refund")]
+    SafeRefund,
+}
+
+/// Chain condition the simulation mines blocks for before a transition
+#[derive(Clone, Copy, Debug)]
+enum Await {
+    Nothing,
+    Final(TxLabel),
+    CancelTimelock,
+}
+
+/// A modelled transition of the swap state machine: the chain condition awaited, the constraint
+/// checked once the condition holds and the transaction broadcast when entering the new state
+struct Step {
+    new_state: &'static str,
+    trigger: &'static str,
+    awaiting: Await,
+    constraint: Option<SafetyConstraint>,
+    broadcast: Option<TxLabel>,
+}
+
+const fn step(
+    new_state: &'static str,
+    trigger: &'static str,
+    awaiting: Await,
+    constraint: Option<SafetyConstraint>,
+    broadcast: Option<TxLabel>,
+) -> Step {
+    Step {
+        new_state,
+        trigger,
+        awaiting,
+        constraint,
+        broadcast,
+    }
+}
+
+const ALICE_START: &str = "Start Alice Maker";
+
+const ALICE_LOCKED: &[Step] = &[
+    step(
+        "Alice Init Maker",
+        "Ctl::MakeSwap",
+        Await::Nothing,
+        None,
+        None,
+    ),
+    step("Alice Reveal", "P2p::Reveal", Await::Nothing, None, None),
+    step(
+        "Alice Core Arbitrating Setup",
+        "P2p::CoreArbitratingSetup",
+        Await::Nothing,
+        None,
+        Some(TxLabel::Lock),
+    ),
+    step(
+        "Alice Arbitrating Lock Final",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Lock),
+        None,
+        None,
+    ),
+    step(
+        "Alice Accordant Lock",
+        "Sync::Event::AddressTransaction",
+        Await::Nothing,
+        None,
+        Some(TxLabel::AccLock),
+    ),
+];
+
+const ALICE_HAPPY: &[Step] = &[
+    step(
+        "Alice Buy Procedure Signature",
+        "P2p::BuyProcedureSignature",
+        Await::Final(TxLabel::AccLock),
+        Some(SafetyConstraint::SafeBuy),
+        Some(TxLabel::Buy),
+    ),
+    step(
+        "Swap End: Success Swap",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Buy),
+        None,
+        None,
+    ),
+];
+
+const ALICE_CANCEL_REFUND: &[Step] = &[
+    step(
+        "Alice Cancel",
+        "Sync::Event::TransactionConfirmations",
+        Await::CancelTimelock,
+        Some(SafetyConstraint::ValidCancel),
+        Some(TxLabel::Cancel),
+    ),
+    step(
+        "Alice Refund",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Cancel),
+        Some(SafetyConstraint::SafeRefund),
+        Some(TxLabel::Refund),
+    ),
+    step(
+        "Alice Refund Sweeping",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Refund),
+        None,
+        None,
+    ),
+    step(
+        "Swap End: Failure Refund",
+        "Sync::Event::SweepSuccess",
+        Await::Nothing,
+        None,
+        None,
+    ),
+];
+
+const BOB_START: &str = "Start Bob Taker";
+
+const BOB_LOCKED: &[Step] = &[
+    step(
+        "Bob Init Taker",
+        "Ctl::TakeSwap",
+        Await::Nothing,
+        None,
+        None,
+    ),
+    step(
+        "Bob Taker Maker Commit",
+        "P2p::MakerCommit",
+        Await::Nothing,
+        None,
+        None,
+    ),
+    step("Bob Reveal", "P2p::Reveal", Await::Nothing, None, None),
+    step(
+        "Bob Fee Estimated",
+        "Sync::Event::FeeEstimation",
+        Await::Nothing,
+        None,
+        None,
+    ),
+    step(
+        "Bob Funded",
+        "Sync::Event::AddressTransaction",
+        Await::Nothing,
+        None,
+        Some(TxLabel::Funding),
+    ),
+    step(
+        "Bob Refund Procedure Signatures",
+        "P2p::RefundProcedureSignatures",
+        Await::Final(TxLabel::Funding),
+        None,
+        Some(TxLabel::Lock),
+    ),
+];
+
+const BOB_HAPPY: &[Step] = &[
+    step(
+        "Bob Accordant Lock",
+        "Sync::Event::AddressTransaction",
+        Await::Final(TxLabel::Lock),
+        None,
+        Some(TxLabel::AccLock),
+    ),
+    step(
+        "Bob Accordant Lock Final",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::AccLock),
+        Some(SafetyConstraint::SafeBuy),
+        Some(TxLabel::Buy),
+    ),
+    step(
+        "Bob Buy Seen",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Buy),
+        None,
+        None,
+    ),
+    step(
+        "Bob Buy Sweeping",
+        "Sync::Event::TransactionConfirmations",
+        Await::Nothing,
+        None,
+        None,
+    ),
+    step(
+        "Swap End: Success Swap",
+        "Sync::Event::SweepSuccess",
+        Await::Nothing,
+        None,
+        None,
+    ),
+];
+
+const BOB_CANCEL_REFUND: &[Step] = &[
+    step(
+        "Bob Cancel",
+        "Sync::Event::TransactionConfirmations",
+        Await::CancelTimelock,
+        Some(SafetyConstraint::ValidCancel),
+        Some(TxLabel::Cancel),
+    ),
+    step(
+        "Bob Cancel Final",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Cancel),
+        Some(SafetyConstraint::SafeRefund),
+        Some(TxLabel::Refund),
+    ),
+    step(
+        "Swap End: Failure Refund",
+        "Sync::Event::TransactionConfirmations",
+        Await::Final(TxLabel::Refund),
+        None,
+        None,
+    ),
+];
+
+/// A transition of the simulated swap, with the chain heights when it happened and the outcome
+/// of the temporal safety constraint checked, if any
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedTransition {
+    pub old_state: String,
+    pub new_state: String,
+    pub trigger: String,
+    pub bitcoin_height: u64,
+    pub monero_height: u64,
+    /// Constraint checked, its outcome and the confirmations it was checked against
+    pub constraint: Option<(SafetyConstraint, bool, u32)>,
+}
+
+impl fmt::Display for SimulatedTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} on {} (bitcoin height {}, monero height {})",
+            self.old_state, self.new_state, self.trigger, self.bitcoin_height, self.monero_height
+        )?;
+        if let Some((constraint, satisfied, confirmations)) = self.constraint {
+            write!(
+                f,
+                ", {} {} at {} confirmations",
+                constraint,
+                if satisfied { "satisfied" } else { "VIOLATED" },
+                confirmations
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Trace of a simulated swap from its start to its end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapSimulation {
+    pub role: SwapRole,
+    pub path: SimulationPath,
+    pub transitions: Vec<SimulatedTransition>,
+}
+
+impl SwapSimulation {
+    /// Whether every temporal safety constraint checked during the swap was satisfied
+    pub fn is_safe(&self) -> bool {
+        self.transitions
+            .iter()
+            .all(|transition| transition.constraint.map_or(true, |(_, ok, _)| ok))
+    }
+}
+
+impl fmt::Display for SwapSimulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} on the {}:", self.role, self.path)?;
+        for transition in self.transitions.iter() {
+            writeln!(f, "  {}", transition)?;
+        }
+        if self.is_safe() {
+            write!(f, "Temporal safety satisfied throughout")
+        } else {
+            write!(f, "Temporal safety VIOLATED")
+        }
+    }
+}
+
+/// In-memory Bitcoin and Monero chains standing in for the syncers: every event mines the same
+/// number of blocks on both chains, and broadcast transactions are mined in the next block
+struct MockChains {
+    heights: HashMap<Blockchain, u64>,
+    mempool: Vec<TxLabel>,
+    mined: HashMap<TxLabel, u64>,
+    blocks_per_event: u64,
+}
+
+impl MockChains {
+    fn new(blocks_per_event: u32) -> Self {
+        MockChains {
+            heights: [(Blockchain::Bitcoin, 0), (Blockchain::Monero, 0)]
+                .iter()
+                .cloned()
+                .collect(),
+            mempool: vec![],
+            mined: none!(),
+            blocks_per_event: blocks_per_event.max(1) as u64,
+        }
+    }
+
+    fn height(&self, blockchain: Blockchain) -> u64 {
+        self.heights[&blockchain]
+    }
+
+    fn confirmations(&self, label: TxLabel) -> u32 {
+        self.mined.get(&label).map_or(0, |mined_height| {
+            (self.height(tx_blockchain(label)) - mined_height + 1) as u32
+        })
+    }
+
+    fn mine(&mut self) {
+        for height in self.heights.values_mut() {
+            *height += self.blocks_per_event;
+        }
+        for label in self.mempool.drain(..) {
+            let mined_height = self.heights[&tx_blockchain(label)] - self.blocks_per_event + 1;
+            self.mined.insert(label, mined_height);
+        }
+    }
+}
+
+fn tx_blockchain(label: TxLabel) -> Blockchain {
+    match label {
+        TxLabel::AccLock => Blockchain::Monero,
+        _ => Blockchain::Bitcoin,
+    }
+}
+
+/// Replay the timeline of a swap from start to end against mock chains reporting
+/// `blocks_per_event` new confirmations per event, checking the temporal safety constraints
+/// before each broadcast. The transitions are a model of the swap state machine, the simulation
+/// does not run `SwapStateMachine` itself as its transitions require the counterparty and the
+/// wallet keys.
+pub fn simulate_swap(
+    role: SwapRole,
+    path: SimulationPath,
+    temporal_safety: &TemporalSafety,
+    blocks_per_event: u32,
+) -> SwapSimulation {
+    let (start, locked, end) = match (role, path) {
+        (SwapRole::Alice, SimulationPath::Happy) => (ALICE_START, ALICE_LOCKED, ALICE_HAPPY),
+        (SwapRole::Alice, SimulationPath::CancelRefund) => {
+            (ALICE_START, ALICE_LOCKED, ALICE_CANCEL_REFUND)
+        }
+        (SwapRole::Bob, SimulationPath::Happy) => (BOB_START, BOB_LOCKED, BOB_HAPPY),
+        (SwapRole::Bob, SimulationPath::CancelRefund) => (BOB_START, BOB_LOCKED, BOB_CANCEL_REFUND),
+    };
+    let mut chains = MockChains::new(blocks_per_event);
+    let mut state = start;
+    let mut transitions = vec![];
+    for step in locked.iter().chain(end.iter()) {
+        loop {
+            let ready = match step.awaiting {
+                Await::Nothing => true,
+                Await::Final(label) => {
+                    temporal_safety.final_tx(chains.confirmations(label), tx_blockchain(label))
+                }
+                Await::CancelTimelock => {
+                    temporal_safety.valid_cancel(chains.confirmations(TxLabel::Lock))
+                }
+            };
+            if ready {
+                break;
+            }
+            chains.mine();
+        }
+        let constraint = step.constraint.map(|constraint| {
+            let (satisfied, confirmations) = match constraint {
+                SafetyConstraint::SafeBuy => {
+                    let confs = chains.confirmations(TxLabel::Lock);
+                    (temporal_safety.safe_buy(confs), confs)
+                }
+                SafetyConstraint::ValidCancel => {
+                    let confs = chains.confirmations(TxLabel::Lock);
+                    (temporal_safety.valid_cancel(confs), confs)
+                }
+                SafetyConstraint::SafeRefund => {
+                    let confs = chains.confirmations(TxLabel::Cancel);
+                    (temporal_safety.safe_refund(confs), confs)
+                }
+            };
+            (constraint, satisfied, confirmations)
+        });
+        if let Some(label) = step.broadcast {
+            chains.mempool.push(label);
+        }
+        transitions.push(SimulatedTransition {
+            old_state: state.to_string(),
+            new_state: step.new_state.to_string(),
+            trigger: step.trigger.to_string(),
+            bitcoin_height: chains.height(Blockchain::Bitcoin),
+            monero_height: chains.height(Blockchain::Monero),
+            constraint,
+        });
+        state = step.new_state;
+    }
+    SwapSimulation {
+        role,
+        path,
+        transitions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::state_diagram::{ALICE_TRANSITIONS, BOB_TRANSITIONS};
+    use super::*;
+
+    fn temporal_safety() -> TemporalSafety {
+        TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        }
+    }
+
+    #[test]
+    fn simulated_transitions_follow_the_state_machine() {
+        for (role, transitions) in [
+            (SwapRole::Alice, ALICE_TRANSITIONS),
+            (SwapRole::Bob, BOB_TRANSITIONS),
+        ] {
+            for path in [SimulationPath::Happy, SimulationPath::CancelRefund] {
+                let simulation = simulate_swap(role, path, &temporal_safety(), 1);
+                assert!(simulation.is_safe(), "{}", simulation);
+                for transition in simulation.transitions.iter() {
+                    assert!(
+                        transitions.iter().any(|(old_state, new_state, triggers)| {
+                            *old_state == transition.old_state
+                                && *new_state == transition.new_state
+                                && triggers.contains(&transition.trigger.as_str())
+                        }),
+                        "{}",
+                        transition
+                    );
+                }
+                assert!(simulation
+                    .transitions
+                    .last()
+                    .unwrap()
+                    .new_state
+                    .starts_with("Swap End"));
+            }
+        }
+    }
+
+    #[test]
+    fn slow_confirmations_violate_safe_buy() {
+        let simulation = simulate_swap(SwapRole::Bob, SimulationPath::Happy, &temporal_safety(), 5);
+        assert!(!simulation.is_safe());
+        assert!(simulation.transitions.iter().any(|transition| matches!(
+            transition.constraint,
+            Some((SafetyConstraint::SafeBuy, false, _))
+        )));
+    }
+}
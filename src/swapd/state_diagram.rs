@@ -0,0 +1,464 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::fmt::Write;
+
+use farcaster_core::role::SwapRole;
+
+use crate::bus::Outcome;
+
+use super::swap_state::SwapStateMachine;
+
+/// Transitions of the swap state machine of a role: the old state, the new state and the types
+/// of the messages triggering the transition, named like in the swap history. The states are
+/// named after their display in `SwapStateMachine`.
+type Transitions = &'static [(&'static str, &'static str, &'static [&'static str])];
+
+pub(super) const ALICE_TRANSITIONS: Transitions = &[
+    ("Start Alice Taker", "Alice Init Taker", &["Ctl::TakeSwap"]),
+    (
+        "Start Alice Taker",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    ("Start Alice Maker", "Alice Init Maker", &["Ctl::MakeSwap"]),
+    (
+        "Start Alice Maker",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Alice Init Taker",
+        "Alice Taker Maker Commit",
+        &["P2p::MakerCommit"],
+    ),
+    (
+        "Alice Init Taker",
+        "Swap End: Failure Abort",
+        &["P2p::DealNotFound", "Ctl::AbortSwap"],
+    ),
+    ("Alice Taker Maker Commit", "Alice Reveal", &["P2p::Reveal"]),
+    (
+        "Alice Taker Maker Commit",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    ("Alice Init Maker", "Alice Reveal", &["P2p::Reveal"]),
+    (
+        "Alice Init Maker",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Alice Reveal",
+        "Alice Core Arbitrating Setup",
+        &["P2p::CoreArbitratingSetup"],
+    ),
+    (
+        "Alice Reveal",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Alice Core Arbitrating Setup",
+        "Alice Arbitrating Lock Final",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Core Arbitrating Setup",
+        "Alice Cancel",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Arbitrating Lock Final",
+        "Alice Arbitrating Lock Final",
+        &[
+            "Sync::Event::Empty",
+            "Sync::Event::TransactionConfirmations",
+        ],
+    ),
+    (
+        "Alice Arbitrating Lock Final",
+        "Alice Accordant Lock",
+        &["Sync::Event::AddressTransaction"],
+    ),
+    (
+        "Alice Arbitrating Lock Final",
+        "Alice Cancel",
+        &[
+            "Sync::Event::AddressTransaction",
+            "Sync::Event::TransactionConfirmations",
+        ],
+    ),
+    (
+        "Alice Accordant Lock",
+        "Alice Buy Procedure Signature",
+        &["P2p::BuyProcedureSignature"],
+    ),
+    (
+        "Alice Accordant Lock",
+        "Alice Cancel",
+        &[
+            "P2p::BuyProcedureSignature",
+            "Sync::Event::TransactionConfirmations",
+        ],
+    ),
+    (
+        "Alice Buy Procedure Signature",
+        "Swap End: Success Swap",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Cancel",
+        "Alice Refund",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Cancel",
+        "Swap End: Failure Refund",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Cancel",
+        "Swap End: Failure Punish",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Refund",
+        "Alice Refund Sweeping",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Alice Refund Sweeping",
+        "Swap End: Failure Refund",
+        &["Sync::Event::SweepSuccess"],
+    ),
+];
+
+pub(super) const BOB_TRANSITIONS: Transitions = &[
+    ("Start Bob Taker", "Bob Init Taker", &["Ctl::TakeSwap"]),
+    (
+        "Start Bob Taker",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    ("Start Bob Maker", "Bob Init Maker", &["Ctl::MakeSwap"]),
+    (
+        "Start Bob Maker",
+        "Swap End: Failure Abort",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Bob Init Taker",
+        "Bob Taker Maker Commit",
+        &["P2p::MakerCommit"],
+    ),
+    (
+        "Bob Init Taker",
+        "Bob Abort Awaiting Bitcoin Sweep",
+        &["P2p::DealNotFound", "Ctl::AbortSwap"],
+    ),
+    ("Bob Taker Maker Commit", "Bob Reveal", &["P2p::Reveal"]),
+    (
+        "Bob Taker Maker Commit",
+        "Bob Abort Awaiting Bitcoin Sweep",
+        &["Ctl::AbortSwap"],
+    ),
+    ("Bob Init Maker", "Bob Reveal", &["P2p::Reveal"]),
+    (
+        "Bob Init Maker",
+        "Bob Abort Awaiting Bitcoin Sweep",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Bob Reveal",
+        "Bob Fee Estimated",
+        &["Sync::Event::FeeEstimation"],
+    ),
+    (
+        "Bob Reveal",
+        "Bob Abort Awaiting Bitcoin Sweep",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Bob Fee Estimated",
+        "Bob Funded",
+        &["Sync::Event::AddressTransaction"],
+    ),
+    (
+        "Bob Fee Estimated",
+        "Bob Abort Awaiting Bitcoin Sweep",
+        &["Sync::Event::AddressTransaction", "Ctl::AbortSwap"],
+    ),
+    (
+        "Bob Funded",
+        "Bob Refund Procedure Signatures",
+        &["P2p::RefundProcedureSignatures"],
+    ),
+    (
+        "Bob Funded",
+        "Bob Abort Awaiting Bitcoin Sweep",
+        &["Ctl::AbortSwap"],
+    ),
+    (
+        "Bob Refund Procedure Signatures",
+        "Bob Accordant Lock",
+        &["Sync::Event::AddressTransaction"],
+    ),
+    (
+        "Bob Refund Procedure Signatures",
+        "Bob Cancel",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Accordant Lock",
+        "Bob Accordant Lock Final",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Accordant Lock",
+        "Bob Cancel",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Accordant Lock Final",
+        "Bob Buy Seen",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Accordant Lock Final",
+        "Bob Cancel",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Buy Seen",
+        "Bob Buy Sweeping",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Buy Sweeping",
+        "Swap End: Success Swap",
+        &["Sync::Event::SweepSuccess"],
+    ),
+    (
+        "Bob Cancel",
+        "Bob Cancel Final",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Cancel Final",
+        "Swap End: Failure Refund",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Cancel Final",
+        "Swap End: Failure Punish",
+        &["Sync::Event::TransactionConfirmations"],
+    ),
+    (
+        "Bob Abort Awaiting Bitcoin Sweep",
+        "Swap End: Failure Abort",
+        &["Sync::Event::TaskAborted", "Sync::Event::SweepSuccess"],
+    ),
+];
+
+/// Fill color of a state node: green for a successful end, red for a failed end and yellow for
+/// the intermediate states
+fn state_color(state: &str) -> &'static str {
+    if state == SwapStateMachine::SwapEnd(Outcome::SuccessSwap).to_string() {
+        "green"
+    } else if [
+        Outcome::FailureRefund,
+        Outcome::FailurePunish,
+        Outcome::FailureAbort,
+    ]
+    .iter()
+    .any(|outcome| state == SwapStateMachine::SwapEnd(outcome.clone()).to_string())
+    {
+        "red"
+    } else {
+        "yellow"
+    }
+}
+
+fn write_cluster(dot: &mut String, role: SwapRole, transitions: Transitions) -> std::fmt::Result {
+    // node ids are prefixed with the role, both roles share the start and end states names
+    let node_id = |state: &str| format!("\"{}: {}\"", role, state);
+    writeln!(
+        dot,
+        "    subgraph cluster_{} {{",
+        role.to_string().to_lowercase()
+    )?;
+    writeln!(dot, "        label=\"{}\";", role)?;
+    let mut states: Vec<&str> = vec![];
+    for (old_state, new_state, _) in transitions.iter() {
+        for state in [*old_state, *new_state] {
+            if !states.contains(&state) {
+                states.push(state);
+            }
+        }
+    }
+    for state in states {
+        writeln!(
+            dot,
+            "        {} [label=\"{}\", fillcolor={}];",
+            node_id(state),
+            state,
+            state_color(state)
+        )?;
+    }
+    for (old_state, new_state, triggers) in transitions.iter() {
+        writeln!(
+            dot,
+            "        {} -> {} [label=\"{}\"];",
+            node_id(old_state),
+            node_id(new_state),
+            triggers.join("\\n")
+        )?;
+    }
+    writeln!(dot, "    }}")
+}
+
+/// Graphviz DOT representation of the Alice and Bob swap state machines, each state is a node
+/// and each edge is labeled with the messages triggering the transition
+pub fn swap_state_diagram() -> String {
+    let mut dot = String::new();
+    writeln!(dot, "digraph swap_state_machine {{").expect("writing to a string");
+    writeln!(dot, "    node [shape=box, style=\"rounded,filled\"];").expect("writing to a string");
+    write_cluster(&mut dot, SwapRole::Alice, ALICE_TRANSITIONS).expect("writing to a string");
+    write_cluster(&mut dot, SwapRole::Bob, BOB_TRANSITIONS).expect("writing to a string");
+    writeln!(dot, "}}").expect("writing to a string");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_diagram_uses_the_state_machine_names() {
+        let named_states = [
+            SwapStateMachine::StartTaker(SwapRole::Alice),
+            SwapStateMachine::StartMaker(SwapRole::Alice),
+            SwapStateMachine::StartTaker(SwapRole::Bob),
+            SwapStateMachine::StartMaker(SwapRole::Bob),
+            SwapStateMachine::BobBuySweeping,
+            SwapStateMachine::BobCancelFinal,
+            SwapStateMachine::BobAbortAwaitingBitcoinSweep,
+            SwapStateMachine::AliceBuyProcedureSignature,
+            SwapStateMachine::AliceRefundSweeping,
+            SwapStateMachine::SwapEnd(Outcome::SuccessSwap),
+            SwapStateMachine::SwapEnd(Outcome::FailureRefund),
+            SwapStateMachine::SwapEnd(Outcome::FailurePunish),
+            SwapStateMachine::SwapEnd(Outcome::FailureAbort),
+        ];
+        let states: Vec<&str> = ALICE_TRANSITIONS
+            .iter()
+            .chain(BOB_TRANSITIONS.iter())
+            .flat_map(|(old_state, new_state, _)| [*old_state, *new_state])
+            .collect();
+        for state in named_states.iter() {
+            assert!(states.contains(&state.to_string().as_str()), "{}", state);
+        }
+
+        let dot = swap_state_diagram();
+        assert!(dot.starts_with("digraph swap_state_machine {"));
+        assert!(dot.trim_end().ends_with('}'));
+        assert!(dot.contains(
+            "\"Bob: Swap End: Success Swap\" [label=\"Swap End: Success Swap\", fillcolor=green];"
+        ));
+        assert!(dot.contains("\"Alice: Swap End: Failure Punish\" [label=\"Swap End: Failure Punish\", fillcolor=red];"));
+        assert!(dot.contains("\"Alice: Alice Reveal\" [label=\"Alice Reveal\", fillcolor=yellow];"));
+    }
+
+    #[test]
+    fn state_diagram_covers_every_state() {
+        // the match does not compile when a state is added to the state machine, list its name
+        // below and add its transitions to the diagram
+        let _exhaustive = |state: &SwapStateMachine| match state {
+            SwapStateMachine::StartTaker(_)
+            | SwapStateMachine::StartMaker(_)
+            | SwapStateMachine::BobInitMaker(_)
+            | SwapStateMachine::AliceInitMaker(_)
+            | SwapStateMachine::BobInitTaker(_)
+            | SwapStateMachine::AliceInitTaker(_)
+            | SwapStateMachine::BobTakerMakerCommit(_)
+            | SwapStateMachine::AliceTakerMakerCommit(_)
+            | SwapStateMachine::BobReveal(_)
+            | SwapStateMachine::BobFeeEstimated(_)
+            | SwapStateMachine::BobFunded(_)
+            | SwapStateMachine::BobRefundProcedureSignatures(_)
+            | SwapStateMachine::BobAccordantLock(_)
+            | SwapStateMachine::BobAccordantLockFinal(_)
+            | SwapStateMachine::BobBuySeen(_)
+            | SwapStateMachine::BobBuySweeping
+            | SwapStateMachine::BobCanceled(_)
+            | SwapStateMachine::BobCancelFinal
+            | SwapStateMachine::BobAbortAwaitingBitcoinSweep
+            | SwapStateMachine::AliceReveal(_)
+            | SwapStateMachine::AliceCoreArbitratingSetup(_)
+            | SwapStateMachine::AliceArbitratingLockFinal(_)
+            | SwapStateMachine::AliceAccordantLock(_)
+            | SwapStateMachine::AliceBuyProcedureSignature
+            | SwapStateMachine::AliceCanceled(_)
+            | SwapStateMachine::AliceRefund(_)
+            | SwapStateMachine::AliceRefundSweeping
+            | SwapStateMachine::SwapEnd(_) => (),
+        };
+        let alice_states = [
+            "Alice Init Maker",
+            "Alice Init Taker",
+            "Alice Taker Maker Commit",
+            "Alice Reveal",
+            "Alice Core Arbitrating Setup",
+            "Alice Arbitrating Lock Final",
+            "Alice Accordant Lock",
+            "Alice Buy Procedure Signature",
+            "Alice Cancel",
+            "Alice Refund",
+            "Alice Refund Sweeping",
+        ];
+        let bob_states = [
+            "Bob Init Maker",
+            "Bob Init Taker",
+            "Bob Taker Maker Commit",
+            "Bob Reveal",
+            "Bob Fee Estimated",
+            "Bob Funded",
+            "Bob Refund Procedure Signatures",
+            "Bob Accordant Lock",
+            "Bob Accordant Lock Final",
+            "Bob Buy Seen",
+            "Bob Buy Sweeping",
+            "Bob Cancel",
+            "Bob Cancel Final",
+            "Bob Abort Awaiting Bitcoin Sweep",
+        ];
+        for (role, transitions, role_states) in [
+            (SwapRole::Alice, ALICE_TRANSITIONS, &alice_states[..]),
+            (SwapRole::Bob, BOB_TRANSITIONS, &bob_states[..]),
+        ] {
+            let states: Vec<&str> = transitions
+                .iter()
+                .flat_map(|(old_state, new_state, _)| [*old_state, *new_state])
+                .collect();
+            let mut expected: Vec<String> = role_states.iter().map(|s| s.to_string()).collect();
+            expected.push(SwapStateMachine::StartTaker(role).to_string());
+            expected.push(SwapStateMachine::StartMaker(role).to_string());
+            for outcome in [
+                Outcome::SuccessSwap,
+                Outcome::FailureRefund,
+                Outcome::FailurePunish,
+                Outcome::FailureAbort,
+            ] {
+                expected.push(SwapStateMachine::SwapEnd(outcome).to_string());
+            }
+            for state in expected.iter() {
+                assert!(states.contains(&state.as_str()), "{}: {}", role, state);
+            }
+        }
+    }
+}
@@ -4,7 +4,14 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use farcaster_core::{blockchain::Blockchain, transaction::TxLabel};
+use std::fmt;
+
+use farcaster_core::{
+    blockchain::Blockchain,
+    role::SwapRole,
+    swap::{btcxmr::DealParameters, SwapId},
+    transaction::TxLabel,
+};
 use strict_encoding::{NetworkDecode, NetworkEncode};
 
 use crate::bus::{Progress, StateTransition};
@@ -36,6 +43,8 @@ pub struct StateReport {
     pub blocks_until_punish_possible: Option<i64>,
     pub blocks_until_safe_buy: Option<u32>,
     pub blocks_until_safe_monero_buy_sweep: Option<u32>,
+    pub final_tx_count: u32,
+    pub required_final_tx_count: u32,
 }
 
 impl StateReport {
@@ -48,14 +57,8 @@ impl StateReport {
             state,
             arb_block_height: syncer_state.bitcoin_height,
             acc_block_height: syncer_state.monero_height,
-            arb_locked: temp_safety.final_tx(
-                syncer_state.get_confs(TxLabel::Lock).unwrap_or(0),
-                Blockchain::Bitcoin,
-            ),
-            acc_locked: temp_safety.final_tx(
-                syncer_state.get_confs(TxLabel::AccLock).unwrap_or(0),
-                Blockchain::Monero,
-            ),
+            arb_locked: syncer_state.btc_lock_final(temp_safety),
+            acc_locked: syncer_state.xmr_lock_final(temp_safety),
             canceled: temp_safety.final_tx(
                 syncer_state.get_confs(TxLabel::Cancel).unwrap_or(0),
                 Blockchain::Bitcoin,
@@ -78,6 +81,8 @@ impl StateReport {
             blocks_until_safe_monero_buy_sweep: syncer_state
                 .get_confs(TxLabel::AccLock)
                 .map(|c| SWEEP_MONERO_THRESHOLD.saturating_sub(c)),
+            final_tx_count: syncer_state.final_tx_count(),
+            required_final_tx_count: syncer_state.required_final_tx_count(),
         }
     }
 
@@ -95,3 +100,102 @@ impl StateReport {
         }
     }
 }
+
+/// Condensed view of a running swap reported to the clients each time the swap state changes
+#[derive(Clone, Debug, Eq, PartialEq, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct SwapSummary {
+    pub swap_id: SwapId,
+    /// Name of the swap state, as displayed by the swap state machine
+    pub state: String,
+    pub role: SwapRole,
+    #[serde(with = "bitcoin::util::amount::serde::as_btc")]
+    pub btc_amount: bitcoin::Amount,
+    #[serde(with = "monero::util::amount::serde::as_xmr")]
+    pub xmr_amount: monero::Amount,
+    pub lock_confirmations: Option<u32>,
+    pub cancel_confirmations: Option<u32>,
+    pub blocks_until_cancel: Option<i64>,
+    pub awaiting_funding: bool,
+}
+
+impl SwapSummary {
+    pub fn new(
+        report: &StateReport,
+        deal_parameters: &DealParameters,
+        syncer_state: &SyncerState,
+    ) -> SwapSummary {
+        SwapSummary {
+            swap_id: syncer_state.swap_id,
+            state: report.state.clone(),
+            role: syncer_state.local_swap_role,
+            btc_amount: deal_parameters.arbitrating_amount,
+            xmr_amount: deal_parameters.accordant_amount,
+            lock_confirmations: report.arb_lock_confirmations,
+            cancel_confirmations: report.cancel_confirmations,
+            blocks_until_cancel: report.blocks_until_cancel_possible,
+            awaiting_funding: syncer_state.awaiting_funding,
+        }
+    }
+
+    /// Whether the swap ended, the end state is displayed as `Swap End: <outcome>`
+    pub fn is_terminal(&self) -> bool {
+        self.state.starts_with("Swap End")
+    }
+}
+
+impl fmt::Display for SwapSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Swap {} as {}: {}", self.swap_id, self.role, self.state)?;
+        writeln!(f, "  Trading {} for {}", self.btc_amount, self.xmr_amount)?;
+        if self.awaiting_funding {
+            writeln!(f, "  Awaiting funding")?;
+        }
+        if let Some(confs) = self.lock_confirmations {
+            writeln!(f, "  Lock confirmations: {}", confs)?;
+        }
+        if let Some(blocks) = self.blocks_until_cancel {
+            writeln!(f, "  Blocks until cancel: {}", blocks)?;
+        }
+        if let Some(confs) = self.cancel_confirmations {
+            writeln!(f, "  Cancel confirmations: {}", confs)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Outcome;
+    use crate::swapd::SwapStateMachine;
+    use farcaster_core::Uuid;
+    use strict_encoding::{StrictDecode, StrictEncode};
+
+    #[test]
+    fn swap_summary_terminal_state() {
+        let mut summary = SwapSummary {
+            swap_id: SwapId(Uuid::new()),
+            state: SwapStateMachine::BobBuySweeping.to_string(),
+            role: SwapRole::Bob,
+            btc_amount: bitcoin::Amount::from_sat(100_000),
+            xmr_amount: monero::Amount::from_pico(1_000_000_000),
+            lock_confirmations: Some(3),
+            cancel_confirmations: None,
+            blocks_until_cancel: Some(-2),
+            awaiting_funding: false,
+        };
+        assert!(!summary.is_terminal());
+        summary.state = SwapStateMachine::SwapEnd(Outcome::SuccessSwap).to_string();
+        assert!(summary.is_terminal());
+
+        let mut encoded = vec![];
+        summary.strict_encode(&mut encoded).unwrap();
+        let decoded = SwapSummary::strict_decode(std::io::Cursor::new(encoded)).unwrap();
+        assert_eq!(decoded, summary);
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::time::SystemTime;
+
+use strict_encoding::{NetworkDecode, NetworkEncode};
+
+use crate::bus::sync::{BridgeEvent, SyncMsg};
+use crate::bus::BusMsg;
+
+use super::swap_state::SwapStateMachine;
+
+/// A swap state transition recorded in the swap history
+#[derive(Clone, PartialEq, Eq, Debug, Display, NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display("{timestamp}: {old_state} -> {new_state} on {trigger}")]
+pub struct SwapEvent {
+    /// Seconds since the unix epoch at which the transition happened
+    pub timestamp: u64,
+    pub old_state: String,
+    pub new_state: String,
+    /// Type of the message that triggered the transition
+    pub trigger: String,
+}
+
+impl SwapEvent {
+    pub fn new(old_state: &SwapStateMachine, new_state: &SwapStateMachine, trigger: &str) -> Self {
+        SwapEvent {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+            old_state: old_state.to_string(),
+            new_state: new_state.to_string(),
+            trigger: trigger.to_string(),
+        }
+    }
+}
+
+/// Name of the type of a message, e.g. `Ctl::TakeSwap` or `Sync::Event::TransactionConfirmations`,
+/// built from the names of the nested message variants
+pub fn trigger_name(msg: &BusMsg) -> String {
+    match msg {
+        BusMsg::P2p(msg) => format!("P2p::{}", msg.name()),
+        BusMsg::Ctl(msg) => format!("Ctl::{}", msg.name()),
+        BusMsg::Info(msg) => format!("Info::{}", msg.name()),
+        BusMsg::Sync(msg) => {
+            let inner = match msg {
+                SyncMsg::Task(task) => task.name(),
+                SyncMsg::Event(event) | SyncMsg::BridgeEvent(BridgeEvent { event, .. }) => {
+                    event.name()
+                }
+            };
+            format!("Sync::{}::{}", msg.name(), inner)
+        }
+        BusMsg::Bridge(_) => "Bridge".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::ctl::CtlMsg;
+    use crate::bus::Outcome;
+    use crate::fixtures;
+    use crate::service::{Endpoints, ServiceId};
+    use crate::swapd::runtime::Runtime;
+    use crate::swapd::syncer_client::{SyncerState, SyncerTasks};
+    use crate::swapd::{CheckpointSwapd, ConfirmationBounds, StateReport, TemporalSafety};
+    use crate::syncerd::{Event, HeightChanged, TaskId, TransactionConfirmations};
+    use farcaster_core::bitcoin::fee::SatPerKvB;
+    use farcaster_core::blockchain::{Blockchain, FeeStrategy};
+    use farcaster_core::role::{SwapRole, TradeRole};
+    use farcaster_core::swap::btcxmr::Deal;
+    use farcaster_core::transaction::TxLabel;
+    use internet2::addr::{NodeAddr, NodeId};
+    use std::time::{Duration, SystemTime};
+    use strict_encoding::{StrictDecode, StrictEncode};
+
+    // Bob maker swapd waiting for its refund transaction to be final
+    fn runtime(deal: Deal) -> Runtime {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        };
+        let swap_id = deal.id().into();
+        let network = deal.parameters.network;
+        let tasks = SyncerTasks {
+            counter: 0,
+            watched_addrs: none!(),
+            watched_mempool: none!(),
+            watched_txs: none!(),
+            retrieving_txs: none!(),
+            sweeping_addr: none!(),
+            broadcasting_txs: none!(),
+            txids: none!(),
+            final_txs: none!(),
+            tasks: none!(),
+            watched_heights: none!(),
+            registered_at: none!(),
+            broadcast_times: none!(),
+        };
+        let syncer_state = SyncerState {
+            swap_id,
+            local_swap_role: SwapRole::Bob,
+            local_trade_role: TradeRole::Maker,
+            tasks,
+            monero_height: 0,
+            bitcoin_height: 0,
+            confirmation_bounds: ConfirmationBounds {
+                lock: 1,
+                cancel: 1,
+                buy: 1,
+                refund: 1,
+                punish: 1,
+            },
+            last_tx_event: none!(),
+            scan_progress: none!(),
+            btc_fee_estimate_sat_per_kvb: None,
+            mempool_congestion: None,
+            task_lifetime_refresh_threshold: 100,
+            rebroadcast_threshold: 6,
+            broadcast_heights: none!(),
+            propagation_warning_threshold: Duration::from_secs(60),
+            network,
+            bitcoin_syncer: ServiceId::Syncer(Blockchain::Bitcoin, network),
+            monero_syncer: ServiceId::Syncer(Blockchain::Monero, network),
+            awaiting_funding: false,
+            expected_funding_amounts: none!(),
+            xmr_addr_addendum: None,
+            confirmations: none!(),
+            broadcasted_txs: none!(),
+            failed_broadcasted_txs: none!(),
+            from_height_overrides: none!(),
+        };
+        let latest_state_report =
+            StateReport::new("Start".to_string(), &temporal_safety, &syncer_state);
+        Runtime {
+            swap_id,
+            identity: ServiceId::Swap(swap_id),
+            peer_service: ServiceId::dummy_peer_service_id(NodeAddr {
+                id: NodeId::from(deal.node_id),
+                addr: deal.peer_address,
+            }),
+            connected: false,
+            started: SystemTime::now(),
+            syncer_state,
+            temporal_safety,
+            enquirer: None,
+            pending_peer_request: none!(),
+            fee_strategy: deal.parameters.fee_strategy,
+            deal,
+            local_trade_role: TradeRole::Maker,
+            local_swap_role: SwapRole::Bob,
+            latest_state_report,
+            swap_state_machine: SwapStateMachine::BobCancelFinal,
+            unhandled_peer_message: None,
+            abort_reason: None,
+            history: vec![],
+            trigger: String::new(),
+        }
+    }
+
+    #[test]
+    fn swap_history_records_transitions() {
+        let height_changed = BusMsg::Sync(SyncMsg::Event(Event::HeightChanged(HeightChanged {
+            id: TaskId(1),
+            block: vec![0],
+            height: 1,
+        })));
+        assert_eq!(trigger_name(&BusMsg::Ctl(CtlMsg::Hello)), "Ctl::Hello");
+        assert_eq!(trigger_name(&height_changed), "Sync::Event::HeightChanged");
+
+        let deal = fixtures::deal();
+        let mut runtime = runtime(deal.clone());
+        let refund_task = TaskId(2);
+        runtime
+            .syncer_state
+            .tasks
+            .watched_txs
+            .insert(refund_task, TxLabel::Refund);
+        let mut endpoints = Endpoints::new();
+        let source = ServiceId::Syncer(Blockchain::Bitcoin, deal.parameters.network);
+
+        // a message not handled in the current state is not recorded
+        runtime
+            .execute_state_machine(&mut endpoints, height_changed, source.clone())
+            .unwrap();
+        assert!(runtime.history.is_empty());
+
+        let refund_final = BusMsg::Sync(SyncMsg::Event(Event::TransactionConfirmations(
+            TransactionConfirmations {
+                id: refund_task,
+                block: vec![0],
+                confirmations: Some(1),
+                tx: vec![],
+                amount: None,
+            },
+        )));
+        // no bus is connected in the test, reporting the swap outcome fails once the transition
+        // is recorded
+        assert!(runtime
+            .execute_state_machine(&mut endpoints, refund_final, source)
+            .is_err());
+        assert!(matches!(
+            runtime.swap_state_machine,
+            SwapStateMachine::SwapEnd(Outcome::FailureRefund)
+        ));
+        let history = runtime.history.clone();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].old_state, "Bob Cancel Final");
+        assert_eq!(history[0].new_state, "Swap End: Failure Refund");
+        assert_eq!(history[0].trigger, "Sync::Event::TransactionConfirmations");
+
+        // the history survives a checkpoint save and restore
+        let mut checkpoint = fixtures::checkpoint(deal);
+        checkpoint.state = runtime.swap_state_machine.clone();
+        checkpoint.temporal_safety = runtime.temporal_safety.clone();
+        checkpoint.history = history.clone();
+        checkpoint.fee_strategy = FeeStrategy::Fixed(SatPerKvB::from_sat(2500));
+        let mut encoded = vec![];
+        checkpoint.strict_encode(&mut encoded).unwrap();
+        let restored = CheckpointSwapd::strict_decode(std::io::Cursor::new(encoded)).unwrap();
+        assert_eq!(restored.history, history);
+        assert_eq!(
+            restored.fee_strategy,
+            FeeStrategy::Fixed(SatPerKvB::from_sat(2500))
+        );
+    }
+}
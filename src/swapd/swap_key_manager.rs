@@ -6,7 +6,8 @@
 
 use std::convert::TryInto;
 
-use bitcoin::secp256k1::ecdsa::Signature;
+use bitcoin::secp256k1::{ecdsa::Signature, Message, PublicKey, SECP256K1};
+use bitcoin::util::misc::{signed_msg_hash, MessageSignature};
 use farcaster_core::{
     bitcoin::{
         segwitv0::{BuyTx, CancelTx, FundingTx, LockTx, PunishTx, RefundTx},
@@ -325,7 +326,7 @@ impl AliceSwapKeyManager {
             local_params,
             bob_parameters,
             &core_arbitrating_txs,
-            runtime.deal.to_arbitrating_params(),
+            runtime.punish_arbitrating_params(),
         )?;
         let mut punish_tx = PunishTx::from_partial(punish);
         punish_tx.add_witness(
@@ -758,3 +759,52 @@ fn aggregate_xmr_spend_view(
         .elem();
     (alice_params.spend + bob_params.spend, alice_view + bob_view)
 }
+
+/// Signs the message with the Bitcoin key used to sign the lock transaction, returns the base64
+/// encoded Bitcoin message signature
+pub fn sign_message(key_manager: &KeyManager, message: &str) -> Result<String, Error> {
+    // derivation caches the key, work on a copy to keep the swap key manager untouched
+    let secret_key = key_manager
+        .clone()
+        .get_or_derive_bitcoin_key(ArbitratingKeyId::Lock)?;
+    let msg = Message::from_slice(&signed_msg_hash(message))?;
+    let signature = SECP256K1.sign_ecdsa_recoverable(&msg, &secret_key);
+    Ok(base64::encode(
+        MessageSignature::new(signature, true).serialize(),
+    ))
+}
+
+/// Checks that the base64 encoded Bitcoin message signature was made with the lock key
+pub fn verify_message(
+    key_manager: &KeyManager,
+    message: &str,
+    signature: &str,
+) -> Result<bool, Error> {
+    let secret_key = key_manager
+        .clone()
+        .get_or_derive_bitcoin_key(ArbitratingKeyId::Lock)?;
+    let lock_pubkey = PublicKey::from_secret_key(SECP256K1, &secret_key);
+    let signature = base64::decode(signature.trim())
+        .map_err(|err| Error::Farcaster(format!("Invalid signature encoding: {}", err)))?;
+    let signature = MessageSignature::from_slice(&signature)
+        .map_err(|err| Error::Farcaster(format!("Invalid message signature: {}", err)))?;
+    let signer = signature
+        .recover_pubkey(SECP256K1, signed_msg_hash(message))
+        .map_err(|err| Error::Farcaster(format!("Invalid message signature: {}", err)))?;
+    Ok(signer.inner == lock_pubkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_message_verifies_against_lock_key() {
+        let key_manager = KeyManager::new([1u8; 32], 0).unwrap();
+        let signature = sign_message(&key_manager, "swap participation").unwrap();
+        assert!(verify_message(&key_manager, "swap participation", &signature).unwrap());
+        assert!(!verify_message(&key_manager, "another message", &signature).unwrap());
+        let other_key_manager = KeyManager::new([1u8; 32], 1).unwrap();
+        assert!(!verify_message(&other_key_manager, "swap participation", &signature).unwrap());
+    }
+}
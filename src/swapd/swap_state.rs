@@ -4,8 +4,6 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
-use std::cmp::Ordering;
-
 use bitcoin::{psbt::serialize::Deserialize, secp256k1::ecdsa::Signature};
 use farcaster_core::{
     blockchain::Blockchain,
@@ -14,7 +12,7 @@ use farcaster_core::{
         message::{
             BuyProcedureSignature, CommitAliceParameters, CommitBobParameters, CoreArbitratingSetup,
         },
-        Parameters,
+        KeyManager, Parameters,
     },
     transaction::TxLabel,
 };
@@ -27,7 +25,10 @@ use crate::{
     syncerd::{bitcoin_syncer::p2wpkh_signed_tx_fee, AddressTransaction},
 };
 use crate::{bus::ctl::MoneroFundingInfo, service::SwapLogging};
-use crate::{bus::p2p::Reveal, swapd::temporal_safety::SWEEP_MONERO_THRESHOLD};
+use crate::{
+    bus::p2p::Reveal,
+    swapd::temporal_safety::{TemporalSafety, SWEEP_MONERO_THRESHOLD},
+};
 use crate::{
     bus::{
         ctl::{CtlMsg, InitMakerSwap, InitTakerSwap},
@@ -61,6 +62,7 @@ use super::{
     swap_key_manager::{
         AliceSwapKeyManager, AliceTxs, BobSwapKeyManager, BobTxs, WrappedEncryptedSignature,
     },
+    syncer_client::FundingValidation,
 };
 
 /// State machine for running a swap.
@@ -310,6 +312,100 @@ pub enum SwapStateMachine {
     SwapEnd(Outcome),
 }
 
+impl SwapStateMachine {
+    /// Whether the swap can still be force aborted, i.e. no funds are locked yet and Bob's
+    /// funding is not seen. A funded Bob must abort normally to sweep the funding address.
+    pub fn abortable(&self) -> bool {
+        matches!(
+            self,
+            SwapStateMachine::StartTaker(_)
+                | SwapStateMachine::StartMaker(_)
+                | SwapStateMachine::BobInitMaker(_)
+                | SwapStateMachine::AliceInitMaker(_)
+                | SwapStateMachine::BobInitTaker(_)
+                | SwapStateMachine::AliceInitTaker(_)
+                | SwapStateMachine::BobTakerMakerCommit(_)
+                | SwapStateMachine::AliceTakerMakerCommit(_)
+                | SwapStateMachine::BobReveal(_)
+                | SwapStateMachine::BobFeeEstimated(_)
+                | SwapStateMachine::AliceReveal(_)
+        )
+    }
+
+    /// Whether Alice is yet to sign the punish transaction, the only transaction built with the
+    /// fee strategy set by the user. It is signed when processing the core arbitrating setup.
+    pub fn punish_unsigned(&self) -> bool {
+        matches!(
+            self,
+            SwapStateMachine::StartTaker(SwapRole::Alice)
+                | SwapStateMachine::StartMaker(SwapRole::Alice)
+                | SwapStateMachine::AliceInitMaker(_)
+                | SwapStateMachine::AliceInitTaker(_)
+                | SwapStateMachine::AliceTakerMakerCommit(_)
+                | SwapStateMachine::AliceReveal(_)
+        )
+    }
+
+    /// The key manager of the swap, none once the swap moved past the states holding the keys
+    pub fn key_manager(&self) -> Option<&KeyManager> {
+        match self {
+            SwapStateMachine::BobInitMaker(BobInitMaker {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::BobInitTaker(BobInitTaker { swap_key_manager })
+            | SwapStateMachine::BobTakerMakerCommit(BobTakerMakerCommit {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::BobReveal(BobReveal {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::BobFeeEstimated(BobFeeEstimated {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::BobFunded(BobFunded {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::BobRefundProcedureSignatures(BobRefundProcedureSignatures {
+                swap_key_manager,
+                ..
+            })
+            | SwapStateMachine::BobAccordantLock(BobAccordantLock {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::BobAccordantLockFinal(BobAccordantLockFinal {
+                swap_key_manager,
+                ..
+            }) => Some(&swap_key_manager.key_manager),
+            SwapStateMachine::AliceInitMaker(AliceInitMaker {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::AliceInitTaker(AliceInitTaker { swap_key_manager })
+            | SwapStateMachine::AliceTakerMakerCommit(AliceTakerMakerCommit {
+                swap_key_manager,
+                ..
+            })
+            | SwapStateMachine::AliceReveal(AliceReveal {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::AliceCoreArbitratingSetup(AliceCoreArbitratingSetup {
+                swap_key_manager,
+                ..
+            })
+            | SwapStateMachine::AliceArbitratingLockFinal(AliceArbitratingLockFinal {
+                swap_key_manager,
+                ..
+            })
+            | SwapStateMachine::AliceAccordantLock(AliceAccordantLock {
+                swap_key_manager, ..
+            })
+            | SwapStateMachine::AliceCanceled(AliceCanceled {
+                swap_key_manager, ..
+            }) => Some(&swap_key_manager.key_manager),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, StrictEncode, StrictDecode)]
 pub struct BobInitMaker {
     remote_commit: CommitAliceParameters,
@@ -731,7 +827,7 @@ fn attempt_transition_to_init_taker(
                 }
             }
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_swap(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_swap(event, runtime),
         _ => Ok(None),
     }
 }
@@ -865,7 +961,7 @@ fn attempt_transition_to_init_maker(
                 }
             }
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_swap(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_swap(event, runtime),
         _ => Ok(None),
     }
 }
@@ -898,7 +994,9 @@ fn try_bob_init_taker_to_bob_taker_maker_commit(
                 },
             )))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_bob_abort_swap(event, runtime, swap_key_manager),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => {
+            handle_bob_abort_swap(event, runtime, swap_key_manager)
+        }
         _ => Ok(None),
     }
 }
@@ -931,7 +1029,7 @@ fn try_alice_init_taker_to_alice_taker_maker_commit(
                 },
             )))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_swap(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_swap(event, runtime),
         _ => Ok(None),
     }
 }
@@ -1025,6 +1123,16 @@ fn try_bob_reveal_to_bob_fee_estimated(
                 total_fees.label(),
             ));
             runtime.syncer_state.awaiting_funding = true;
+            runtime
+                .syncer_state
+                .set_expected_funding_amount(Blockchain::Bitcoin, required_funding_amount.as_sat());
+            // the funding deadline is unknown until the syncer reported the height
+            let bitcoin_height = runtime.syncer_state.bitcoin_height;
+            let max_safe_lock_mined_block = (bitcoin_height > 0).then(|| {
+                runtime
+                    .temporal_safety
+                    .max_safe_lock_mined_block(bitcoin_height)
+            });
             if let Some(enquirer) = runtime.enquirer.clone() {
                 event.send_ctl_service(
                     enquirer,
@@ -1032,11 +1140,20 @@ fn try_bob_reveal_to_bob_fee_estimated(
                         swap_id: runtime.swap_id,
                         address: funding_address.clone(),
                         amount: required_funding_amount,
+                        max_safe_lock_mined_block,
                     })),
                 )?;
             }
 
             runtime.log_debug(format!("Watch arbitrating funding {}", funding_address));
+            // detect the funding transaction before its first confirmation when supported
+            let watch_mempool_task = runtime
+                .syncer_state
+                .watch_mempool_btc(funding_address.clone(), TxLabel::Funding);
+            event.send_sync_service(
+                runtime.syncer_state.bitcoin_syncer(),
+                SyncMsg::Task(watch_mempool_task),
+            )?;
             let watch_addr_task = runtime
                 .syncer_state
                 .watch_addr_btc(funding_address, TxLabel::Funding);
@@ -1050,7 +1167,9 @@ fn try_bob_reveal_to_bob_fee_estimated(
                 required_funding_amount,
             })))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_bob_abort_swap(event, runtime, swap_key_manager),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => {
+            handle_bob_abort_swap(event, runtime, swap_key_manager)
+        }
         _ => Ok(None),
     }
 }
@@ -1082,12 +1201,15 @@ fn try_bob_fee_estimated_to_bob_funded(
                 &tx.txid().tx_hash()
             ));
             runtime.syncer_state.awaiting_funding = false;
-            // If the bitcoin amount does not match the expected funding amount, abort the swap
-            let amount = bitcoin::Amount::from_sat(*amount);
-            // Abort the swap in case of bad funding amount
-            if amount != required_funding_amount {
+            // If the bitcoin amount does not match the expected funding amount, abort the swap:
+            // the funding output is spent as a whole by the lock transaction, so neither an
+            // overpayment nor a later top-up can be used
+            let validation = runtime
+                .syncer_state
+                .validate_funding(Blockchain::Bitcoin, *amount);
+            if validation != FundingValidation::Correct {
                 // incorrect funding, start aborting procedure
-                let msg = format!("Incorrect amount funded. Required: {}, Funded: {}. Do not fund this swap anymore, will abort and atttempt to sweep the Bitcoin to the provided address.", amount, required_funding_amount);
+                let msg = format!("Incorrect amount funded, {} sat. Required: {}, Funded: {}. Do not fund this swap anymore, will abort and atttempt to sweep the Bitcoin to the provided address.", validation, required_funding_amount, bitcoin::Amount::from_sat(*amount));
                 runtime.log_error(&msg);
                 runtime.report_progress_message(event.endpoints, msg)?;
                 return handle_bob_abort_swap(event, runtime, swap_key_manager);
@@ -1154,7 +1276,9 @@ fn try_bob_fee_estimated_to_bob_funded(
             )?;
             Ok(Some(new_ssm))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_bob_abort_swap(event, runtime, swap_key_manager),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => {
+            handle_bob_abort_swap(event, runtime, swap_key_manager)
+        }
         _ => Ok(None),
     }
 }
@@ -1195,7 +1319,7 @@ fn try_bob_funded_to_bob_refund_procedure_signature(
                 view,
                 txlabel,
                 acc_lock_height_lower_bound,
-            );
+            )?;
 
             event.send_sync_service(runtime.syncer_state.monero_syncer(), SyncMsg::Task(task))?;
             // register a watch task for buy tx.
@@ -1217,12 +1341,21 @@ fn try_bob_funded_to_bob_refund_procedure_signature(
                 });
             runtime.log_debug("Checkpointing bob refund signature swapd state.");
             // manually add lock_tx to pending broadcasts to ensure it's checkpointed
-            runtime.syncer_state.broadcast(&lock_tx, TxLabel::Lock);
+            let task = runtime.syncer_state.broadcast(&lock_tx, TxLabel::Lock);
             runtime.checkpoint_state(event.endpoints, None, new_ssm.clone())?;
-            runtime.broadcast(&lock_tx, TxLabel::Lock, event.endpoints)?;
+            runtime.syncer_state.log_tx_created(
+                TxLabel::Lock,
+                lock_tx.txid(),
+                Some(bitcoin::Amount::from_sat(
+                    lock_tx.output.iter().map(|out| out.value).sum(),
+                )),
+            );
+            event.send_sync_service(runtime.syncer_state.bitcoin_syncer(), SyncMsg::Task(task))?;
             Ok(Some(new_ssm))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_bob_abort_swap(event, runtime, swap_key_manager),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => {
+            handle_bob_abort_swap(event, runtime, swap_key_manager)
+        }
         _ => Ok(None),
     }
 }
@@ -1420,10 +1553,7 @@ fn try_bob_cancel_final_to_swap_end(
                 confirmations: Some(confirmations),
                 ..
             },
-        ))) if runtime
-            .temporal_safety
-            .final_tx(confirmations, Blockchain::Bitcoin) =>
-        {
+        ))) if swap_end_txs_final(runtime, confirmations) => {
             match runtime.syncer_state.tasks.watched_txs.get(&id) {
                 Some(&TxLabel::Refund) => {
                     Ok(Some(SwapStateMachine::SwapEnd(Outcome::FailureRefund)))
@@ -1591,7 +1721,7 @@ fn try_alice_reveal_to_alice_core_arbitrating_setup(
             )?;
             Ok(Some(new_ssm))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_swap(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_swap(event, runtime),
         _ => Ok(None),
     }
 }
@@ -1639,7 +1769,7 @@ fn try_alice_core_arbitrating_setup_to_alice_arbitrating_lock_final(
                 view,
                 txlabel,
                 acc_lock_height_lower_bound,
-            );
+            )?;
             event.send_sync_service(
                 runtime.syncer_state.monero_syncer(),
                 SyncMsg::Task(watch_addr_task),
@@ -1729,9 +1859,14 @@ fn try_alice_arbitrating_lock_final_to_alice_accordant_lock(
             .temporal_safety
             .final_tx(confirmations, Blockchain::Bitcoin)
             && runtime.syncer_state.tasks.watched_txs.get(&id) == Some(&TxLabel::Lock)
-            && runtime
-                .temporal_safety
-                .stop_funding_before_cancel(confirmations)
+            && TemporalSafety {
+                // stop funding earlier if the mempool is congested
+                safety: runtime
+                    .syncer_state
+                    .effective_race_thr(runtime.temporal_safety.safety),
+                ..runtime.temporal_safety.clone()
+            }
+            .stop_funding_before_cancel(confirmations)
             && runtime.syncer_state.awaiting_funding =>
         {
             runtime.log_warn("Alice, the swap may be cancelled soon. Do not fund anymore");
@@ -1793,9 +1928,15 @@ fn try_alice_arbitrating_lock_final_to_alice_accordant_lock(
                 )?;
             }
 
-            match amount.cmp(&required_funding_amount.as_pico()) {
-                // Underfunding
-                Ordering::Less => {
+            // the required amount is part of the state, also when restored from a checkpoint
+            runtime
+                .syncer_state
+                .set_expected_funding_amount(Blockchain::Monero, required_funding_amount.as_pico());
+            match runtime
+                .syncer_state
+                .validate_funding(Blockchain::Monero, amount)
+            {
+                FundingValidation::Underpaid { .. } => {
                     // Alice still views underfunding as valid in the hope that Bob still passes her BuyProcSig
                     let msg = format!(
                                     "Too small amount funded. Required: {}, Funded: {}. Do not fund this swap anymore, will attempt to refund.",
@@ -1805,8 +1946,7 @@ fn try_alice_arbitrating_lock_final_to_alice_accordant_lock(
                     runtime.log_error(&msg);
                     runtime.report_progress_message(event.endpoints, msg)?;
                 }
-                // Overfunding
-                Ordering::Greater => {
+                FundingValidation::Overpaid { .. } => {
                     // Alice overfunded. To ensure that she does not publish the buy transaction
                     // if Bob gives her the BuySig, go straight to AliceCanceled
                     let msg = format!(
@@ -1825,8 +1965,7 @@ fn try_alice_arbitrating_lock_final_to_alice_accordant_lock(
                         acc_lock_height_lower_bound,
                     })));
                 }
-                // Funding Exact
-                Ordering::Equal => {}
+                FundingValidation::Correct => {}
             }
 
             Ok(Some(SwapStateMachine::AliceAccordantLock(
@@ -1938,9 +2077,7 @@ fn try_alice_buy_procedure_signature_to_swap_end(
                 confirmations: Some(confirmations),
                 ..
             },
-        ))) if runtime
-            .temporal_safety
-            .final_tx(confirmations, Blockchain::Bitcoin)
+        ))) if swap_end_txs_final(runtime, confirmations)
             && runtime.syncer_state.tasks.watched_txs.get(&id) == Some(&TxLabel::Buy) =>
         {
             Ok(Some(SwapStateMachine::SwapEnd(Outcome::SuccessSwap)))
@@ -1998,11 +2135,7 @@ fn try_alice_canceled_to_alice_refund_or_alice_punish(
                 }
 
                 // When Alice's Punish transaction is final, end the swap
-                Some(&TxLabel::Punish)
-                    if runtime
-                        .temporal_safety
-                        .final_tx(confirmations, Blockchain::Bitcoin) =>
-                {
+                Some(&TxLabel::Punish) if swap_end_txs_final(runtime, confirmations) => {
                     Ok(Some(SwapStateMachine::SwapEnd(Outcome::FailurePunish)))
                 }
 
@@ -2209,7 +2342,9 @@ fn attempt_transition_to_bob_reveal(
                 swap_key_manager,
             })))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_bob_abort_swap(event, runtime, swap_key_manager),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => {
+            handle_bob_abort_swap(event, runtime, swap_key_manager)
+        }
         _ => Ok(None),
     }
 }
@@ -2235,7 +2370,7 @@ fn attempt_transition_to_alice_reveal(
                 swap_key_manager,
             })))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_swap(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_swap(event, runtime),
         _ => Ok(None),
     }
 }
@@ -2248,7 +2383,7 @@ fn handle_bob_swap_interrupt_after_lock(
     bob_txs: BobTxs,
 ) -> Result<Option<SwapStateMachine>, Error> {
     match event.request {
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_impossible(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_impossible(event, runtime),
 
         BusMsg::Sync(SyncMsg::Event(SyncEvent::TransactionConfirmations(
             TransactionConfirmations {
@@ -2347,7 +2482,7 @@ fn handle_alice_swap_interrupt_after_lock(
                 acc_lock_height_lower_bound,
             })))
         }
-        BusMsg::Ctl(CtlMsg::AbortSwap) => handle_abort_impossible(event, runtime),
+        BusMsg::Ctl(CtlMsg::AbortSwap(_)) => handle_abort_impossible(event, runtime),
 
         _ => Ok(None),
     }
@@ -2357,6 +2492,9 @@ fn handle_abort_swap(
     event: Event,
     runtime: &mut Runtime,
 ) -> Result<Option<SwapStateMachine>, Error> {
+    if let BusMsg::Ctl(CtlMsg::AbortSwap(reason)) = &event.request {
+        runtime.abort_reason = reason.clone();
+    }
     event.complete_client_info(InfoMsg::String("Aborted swap".to_string()))?;
     runtime.log_info("Aborted swap.");
     Ok(Some(SwapStateMachine::SwapEnd(Outcome::FailureAbort)))
@@ -2380,6 +2518,9 @@ fn handle_bob_abort_swap(
     runtime: &mut Runtime,
     mut swap_key_manager: BobSwapKeyManager,
 ) -> Result<Option<SwapStateMachine>, Error> {
+    if let BusMsg::Ctl(CtlMsg::AbortSwap(reason)) = &event.request {
+        runtime.abort_reason = reason.clone();
+    }
     let funding_address = swap_key_manager
         .funding_address()
         .expect("Am Bob, so have funding address");
@@ -2397,6 +2538,17 @@ fn handle_bob_abort_swap(
     Ok(Some(SwapStateMachine::BobAbortAwaitingBitcoinSweep))
 }
 
+/// Whether the Bitcoin transaction ending the swap, with `confirmations`, and every other mined
+/// Bitcoin transaction of the swap are final
+fn swap_end_txs_final(runtime: &Runtime, confirmations: u32) -> bool {
+    runtime
+        .temporal_safety
+        .final_tx(confirmations, Blockchain::Bitcoin)
+        && runtime
+            .syncer_state
+            .all_final(Blockchain::Bitcoin, runtime.temporal_safety.arb_finality)
+}
+
 fn watch_cancel_address(
     runtime: &mut Runtime,
     event: &mut Event,
@@ -2419,3 +2571,88 @@ fn watch_cancel_address(
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{
+        psbt::PartiallySignedTransaction,
+        secp256k1::{Message, SecretKey, SECP256K1},
+    };
+    use farcaster_core::{
+        bitcoin::{segwitv0::FundingTx, BitcoinSegwitV0},
+        blockchain::FeePriority,
+        crypto::{ArbitratingKeyId, GenerateKey},
+        monero::Monero,
+        swap::btcxmr::Bob,
+        transaction::Fundable,
+    };
+
+    use super::*;
+    use crate::fixtures;
+
+    fn bob_funded() -> SwapStateMachine {
+        let deal = fixtures::deal();
+        let target_bitcoin_address =
+            bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let target_monero_address = monero::Address::from_str(
+            "51qzspbPiQ9Z9Wq3hR8HRhPmVcE3URCK8b8A9ypHHzyvhigWTefCapoG1MXVZQQi7B5t4DpJYrHZyaFjHSb5QqLe8YEaBpo",
+        )
+        .unwrap();
+        let mut key_manager = KeyManager::new([1u8; 32], 0).unwrap();
+        let bob = Bob::new(
+            BitcoinSegwitV0::new(),
+            Monero,
+            target_bitcoin_address.clone(),
+            FeePriority::Low,
+        );
+        let local_params = bob.generate_parameters(&mut key_manager, &deal).unwrap();
+        let funding_tx = FundingTx::initialize(
+            key_manager.get_pubkey(ArbitratingKeyId::Lock).unwrap(),
+            deal.parameters.network,
+        )
+        .unwrap();
+        let psbt = PartiallySignedTransaction::from_unsigned_tx(bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![],
+        })
+        .unwrap();
+        let cancel_sig = SECP256K1.sign_ecdsa(
+            &Message::from_slice(&[1u8; 32]).unwrap(),
+            &SecretKey::from_slice(&[1u8; 32]).unwrap(),
+        );
+        SwapStateMachine::BobFunded(BobFunded {
+            remote_params: local_params.clone(),
+            core_arbitrating_setup: CoreArbitratingSetup {
+                swap_id: deal.id().into(),
+                lock: psbt.clone(),
+                cancel: psbt.clone(),
+                refund: psbt,
+                cancel_sig,
+            },
+            swap_key_manager: BobSwapKeyManager {
+                bob,
+                local_params,
+                key_manager,
+                funding_tx,
+                target_bitcoin_address,
+                target_monero_address,
+            },
+            acc_lock_height_lower_bound: 0,
+        })
+    }
+
+    #[test]
+    fn force_abort_is_refused_once_bob_funding_is_seen() {
+        assert!(SwapStateMachine::StartMaker(SwapRole::Bob).abortable());
+        assert!(SwapStateMachine::StartTaker(SwapRole::Alice).abortable());
+        // the funding would be left on the funding address, a regular abort sweeps it
+        assert!(!bob_funded().abortable());
+        assert!(!SwapStateMachine::BobAbortAwaitingBitcoinSweep.abortable());
+        assert!(!SwapStateMachine::BobBuySweeping.abortable());
+        assert!(!SwapStateMachine::SwapEnd(Outcome::FailureAbort).abortable());
+    }
+}
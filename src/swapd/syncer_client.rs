@@ -8,10 +8,11 @@ use crate::{
     bus::ServiceBus,
     service::{Endpoints, LogStyle, SwapDetails, SwapLogging},
     syncerd::{
-        Abort, AddressAddendum, BroadcastTransaction, BtcAddressAddendum, GetTx, SweepAddress,
-        SweepAddressAddendum, SweepBitcoinAddress, SweepMoneroAddress, TaskTarget,
-        TransactionBroadcasted, TxFilter, Txid, WatchAddress, WatchEstimateFee, WatchHeight,
-        WatchTransaction, XmrAddressAddendum,
+        Abort, AddressAddendum, BroadcastTransaction, BtcAddressAddendum, GetMempoolInfo, GetTx,
+        GetXmrTx, MempoolInfo, ScanProgress, SweepAddress, SweepAddressAddendum,
+        SweepBitcoinAddress, SweepMoneroAddress, TaskTarget, TransactionBroadcasted, TxFilter,
+        Txid, WatchAddress, WatchEstimateFee, WatchHeight, WatchMempool, WatchTransaction,
+        WatchXmrTransaction, XmrAddressAddendum,
     },
     Error,
 };
@@ -22,34 +23,369 @@ use farcaster_core::{
     swap::SwapId,
     transaction::TxLabel,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::panic::Location;
+use std::time::{Duration, Instant};
+use strict_encoding::{StrictDecode, StrictEncode};
 
 use crate::{
     bus::sync::SyncMsg,
     bus::BusMsg,
-    syncerd::{Task, TaskId},
+    swapd::{ReorgImpact, TemporalSafety},
+    syncerd::{count_task_kinds, Task, TaskId, TaskKind},
     ServiceId,
 };
 
+/// Counter value from which new task ids are recycled from the unused ones instead of
+/// incrementing the counter, keeps task ids within `u32` range
+pub const TASK_COUNTER_RECYCLE_THRESHOLD: u64 = u32::MAX as u64 - 1;
+
 pub struct SyncerTasks {
-    pub counter: u32,
+    pub counter: u64,
     pub watched_txs: HashMap<TaskId, TxLabel>,
     pub final_txs: HashMap<TxLabel, bool>,
     pub watched_addrs: HashMap<TaskId, TxLabel>,
+    pub watched_mempool: HashMap<TaskId, TxLabel>,
     pub retrieving_txs: HashMap<TaskId, TxLabel>,
     pub broadcasting_txs: HashMap<TaskId, TxLabel>,
     pub sweeping_addr: Option<TaskId>,
     pub txids: HashMap<TxLabel, bitcoin::Txid>,
     pub tasks: HashMap<TaskId, Task>,
+    pub watched_heights: HashMap<Blockchain, TaskId>,
+    /// When each task id was handed out, to measure how long tasks stay active
+    pub registered_at: HashMap<TaskId, Instant>,
+    /// When each pending broadcast task was created, to measure the transaction propagation
+    pub broadcast_times: HashMap<TaskId, Instant>,
+}
+
+/// Age of the tasks still active in a swap, in seconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, StrictEncode, StrictDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct TaskStats {
+    pub active_count: usize,
+    pub oldest_active_age_secs: Option<u64>,
+    pub avg_active_age_secs: Option<u64>,
+}
+
+/// Tracking entry of a task removed from the syncer tasks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemovedTask {
+    WatchedTx(TxLabel),
+    WatchedAddr(TxLabel),
+    WatchedMempool(TxLabel),
+    RetrievingTx(TxLabel),
+    BroadcastingTx(TxLabel),
+    WatchedHeight(Blockchain),
+    SweepingAddr,
+    /// The task was registered but not tracked by any label
+    Untracked,
+    /// The task id is unknown
+    None,
+}
+
+/// Outcome of a transaction confirmations event for a watched transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxConfsUpdate {
+    pub label: TxLabel,
+    pub confs: Option<u32>,
+    /// Whether the transaction reached finality with this event
+    pub became_final: bool,
+}
+
+/// Last known state of the Bitcoin mempool reported by the syncer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MempoolCongestion {
+    pub mempool_bytes: u64,
+    pub min_relay_fee_sat_per_kvb: u64,
+    pub mempool_tx_count: u32,
+}
+
+impl std::fmt::Display for MempoolCongestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Mempool: {} MB, min relay: {} sat/kvB",
+            self.mempool_bytes / 1_000_000,
+            self.min_relay_fee_sat_per_kvb
+        )
+    }
+}
+
+/// Virtual size of a full block, used to express the mempool backlog in blocks
+const BLOCK_VSIZE: u64 = 1_000_000;
+
+/// A pending broadcast transaction that cannot be decoded, the swap state is corrupted
+#[derive(Clone, Debug, PartialEq, Eq, Display, Error)]
+#[display("Failed to decode pending broadcast transaction of task {task_id}: {error_desc}")]
+pub struct PendingBroadcastDecodeError {
+    pub task_id: TaskId,
+    pub error_desc: String,
+}
+
+/// A monero address that cannot be watched by the syncer
+#[derive(Clone, Debug, PartialEq, Eq, Display, Error)]
+#[display(doc_comments)]
+pub enum XmrAddressError {
+    /// Monero address is on network {found:?}, expected {expected:?}
+    NetworkMismatch {
+        expected: monero::Network,
+        found: monero::Network,
+    },
+
+    /// Integrated monero addresses are not supported
+    IntegratedAddress,
+
+    /// Monero address {0} key is the identity point
+    IdentityKey(&'static str),
+}
+
+/// Compressed Edwards Y encoding of the identity point
+const ED25519_IDENTITY: [u8; 32] = {
+    let mut bytes = [0; 32];
+    bytes[0] = 1;
+    bytes
+};
+
+/// Checks that a monero address can be watched on the given network
+pub fn validate_xmr_address(
+    addr: &monero::Address,
+    network: farcaster_core::blockchain::Network,
+    allow_integrated: bool,
+) -> Result<(), XmrAddressError> {
+    let expected = monero::Network::from(network);
+    if addr.network != expected {
+        return Err(XmrAddressError::NetworkMismatch {
+            expected,
+            found: addr.network,
+        });
+    }
+    if !allow_integrated && matches!(addr.addr_type, monero::AddressType::Integrated(_)) {
+        return Err(XmrAddressError::IntegratedAddress);
+    }
+    if addr.public_spend.point.as_bytes() == &ED25519_IDENTITY {
+        return Err(XmrAddressError::IdentityKey("spend"));
+    }
+    if addr.public_view.point.as_bytes() == &ED25519_IDENTITY {
+        return Err(XmrAddressError::IdentityKey("view"));
+    }
+    Ok(())
 }
 
 impl SyncerTasks {
     pub fn new_taskid(&mut self) -> TaskId {
+        self.assert_consistent();
+        if self.counter >= TASK_COUNTER_RECYCLE_THRESHOLD {
+            return self.recycle_taskid();
+        }
         self.counter += 1;
-        TaskId(self.counter)
+        let id = TaskId(
+            u32::try_from(self.counter).expect("task counter is below the recycle threshold"),
+        );
+        self.registered_at.insert(id, Instant::now());
+        id
+    }
+
+    /// Returns the smallest id above the lowest active task id that is not in use, wrapping
+    /// around below it if necessary. Only used once the counter reached the recycle threshold.
+    fn recycle_taskid(&mut self) -> TaskId {
+        let active: HashSet<u32> = self.active_taskids().map(|id| id.0).collect();
+        let min_active = active.iter().copied().min().unwrap_or_default();
+        let max_id =
+            u32::try_from(TASK_COUNTER_RECYCLE_THRESHOLD).expect("threshold fits in a task id");
+        let id = (min_active.saturating_add(1)..=max_id)
+            .chain(1..min_active)
+            .find(|id| !active.contains(id))
+            .expect("less active tasks than available task ids");
+        warn!(
+            "Syncer task counter reached {}, recycling task id {}",
+            self.counter, id
+        );
+        self.registered_at.insert(TaskId(id), Instant::now());
+        TaskId(id)
+    }
+
+    fn active_taskids(&self) -> impl Iterator<Item = &TaskId> {
+        self.tasks
+            .keys()
+            .chain(self.watched_addrs.keys())
+            .chain(self.watched_mempool.keys())
+            .chain(self.watched_txs.keys())
+            .chain(self.retrieving_txs.keys())
+            .chain(self.broadcasting_txs.keys())
+            .chain(self.sweeping_addr.iter())
+            .chain(self.watched_heights.values())
+    }
+
+    /// Checks the invariants between the tracking maps, compiles to nothing in release builds
+    pub fn assert_consistent(&self) {
+        for label in self.final_txs.keys() {
+            debug_assert!(
+                self.watched_txs.values().any(|watched| watched == label),
+                "final tx {} is not watched",
+                label
+            );
+        }
+        for id in self.broadcasting_txs.keys() {
+            debug_assert!(
+                self.tasks.contains_key(id),
+                "broadcasting task {} is not registered",
+                id
+            );
+        }
+        if let Some(id) = self.sweeping_addr {
+            debug_assert!(
+                self.tasks.contains_key(&id),
+                "sweeping task {} is not registered",
+                id
+            );
+        }
+        debug_assert!(
+            self.active_taskids()
+                .all(|id| u64::from(id.0) <= self.counter),
+            "task counter {} is behind a tracked task id",
+            self.counter
+        );
+    }
+
+    /// Removes the task from every tracking map and returns what it was tracking
+    pub fn remove_task(&mut self, id: TaskId) -> RemovedTask {
+        let registered = self.tasks.remove(&id).is_some();
+        self.registered_at.remove(&id);
+        self.broadcast_times.remove(&id);
+        let mut removed = if let Some(label) = self.watched_txs.remove(&id) {
+            RemovedTask::WatchedTx(label)
+        } else if let Some(label) = self.watched_addrs.remove(&id) {
+            RemovedTask::WatchedAddr(label)
+        } else if let Some(label) = self.watched_mempool.remove(&id) {
+            RemovedTask::WatchedMempool(label)
+        } else if let Some(label) = self.retrieving_txs.remove(&id) {
+            RemovedTask::RetrievingTx(label)
+        } else if let Some(label) = self.broadcasting_txs.remove(&id) {
+            RemovedTask::BroadcastingTx(label)
+        } else if registered {
+            RemovedTask::Untracked
+        } else {
+            RemovedTask::None
+        };
+        // a task id is only tracked once, clean the remaining maps in case of stale entries
+        self.watched_addrs.remove(&id);
+        self.watched_mempool.remove(&id);
+        self.retrieving_txs.remove(&id);
+        self.broadcasting_txs.remove(&id);
+        if let Some(blockchain) = self
+            .watched_heights
+            .iter()
+            .find_map(|(blockchain, task_id)| (*task_id == id).then(|| *blockchain))
+        {
+            self.watched_heights.remove(&blockchain);
+            removed = RemovedTask::WatchedHeight(blockchain);
+        }
+        if self.sweeping_addr == Some(id) {
+            self.sweeping_addr = None;
+            removed = RemovedTask::SweepingAddr;
+        }
+        removed
+    }
+
+    /// Number of registered tasks per task type, to detect tasks leaking
+    pub fn task_count_by_type(&self) -> HashMap<TaskKind, usize> {
+        count_task_kinds(self.tasks.values())
+    }
+
+    pub fn total_active_tasks(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Age statistics of the registered tasks, since their id was handed out
+    pub fn stats(&self) -> TaskStats {
+        let ages: Vec<u64> = self
+            .tasks
+            .keys()
+            .filter_map(|id| self.registered_at.get(id))
+            .map(|registered_at| registered_at.elapsed().as_secs())
+            .collect();
+        TaskStats {
+            active_count: self.tasks.len(),
+            oldest_active_age_secs: ages.iter().copied().max(),
+            avg_active_age_secs: (!ages.is_empty())
+                .then(|| ages.iter().sum::<u64>() / ages.len() as u64),
+        }
+    }
+
+    /// Returns the id of the task watching the address for the transaction label
+    pub fn watching_address_for_label(&self, label: &TxLabel) -> Option<TaskId> {
+        self.watched_addrs
+            .iter()
+            .find_map(|(id, watched)| if watched == label { Some(*id) } else { None })
+    }
+}
+
+/// Blockchain on which the transaction labeled `label` is published, only the accordant lock is
+/// on Monero
+fn tx_label_blockchain(label: TxLabel) -> Blockchain {
+    match label {
+        TxLabel::AccLock => Blockchain::Monero,
+        _ => Blockchain::Bitcoin,
+    }
+}
+
+/// Number of confirmations after which the syncer stops reporting on a watched transaction,
+/// configurable per transaction type
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, StrictEncode, StrictDecode)]
+#[display("lock: {lock}, cancel: {cancel}, buy: {buy}, refund: {refund}, punish: {punish}")]
+pub struct ConfirmationBounds {
+    pub lock: u32,
+    pub cancel: u32,
+    pub buy: u32,
+    pub refund: u32,
+    pub punish: u32,
+}
+
+impl ConfirmationBounds {
+    pub const DEFAULT_BOUND: u32 = 50000;
+
+    /// Confirmation bound to use when watching the transaction labeled `tx_label`. The funding
+    /// and accordant lock transactions use the lock bound.
+    pub fn bound(&self, tx_label: TxLabel) -> u32 {
+        match tx_label {
+            TxLabel::Funding | TxLabel::Lock | TxLabel::AccLock => self.lock,
+            TxLabel::Cancel => self.cancel,
+            TxLabel::Buy => self.buy,
+            TxLabel::Refund => self.refund,
+            TxLabel::Punish => self.punish,
+        }
+    }
+}
+
+impl Default for ConfirmationBounds {
+    fn default() -> Self {
+        ConfirmationBounds {
+            lock: Self::DEFAULT_BOUND,
+            cancel: Self::DEFAULT_BOUND,
+            buy: Self::DEFAULT_BOUND,
+            refund: Self::DEFAULT_BOUND,
+            punish: Self::DEFAULT_BOUND,
+        }
     }
 }
 
+/// Amount received on a funding address compared with the amount expected by the swap, in
+/// satoshis or piconeros depending on the blockchain
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+pub enum FundingValidation {
+    #[display("correct")]
+    Correct,
+    #[display("overpaid by {excess}")]
+    Overpaid { excess: u64 },
+    #[display("underpaid by {shortfall}")]
+    Underpaid { shortfall: u64 },
+}
+
 pub struct SyncerState {
     pub swap_id: SwapId,
     pub local_swap_role: SwapRole,
@@ -57,7 +393,7 @@ pub struct SyncerState {
     pub tasks: SyncerTasks,
     pub bitcoin_height: u64,
     pub monero_height: u64,
-    pub confirmation_bound: u32,
+    pub confirmation_bounds: ConfirmationBounds,
     pub last_tx_event: HashMap<TxLabel, SyncMsg>,
     pub network: farcaster_core::blockchain::Network,
     pub bitcoin_syncer: ServiceId,
@@ -65,8 +401,25 @@ pub struct SyncerState {
     pub xmr_addr_addendum: Option<XmrAddressAddendum>,
     pub confirmations: HashMap<TxLabel, Option<u32>>,
     pub awaiting_funding: bool,
+    /// Amount the funding address of each blockchain must receive, in satoshis or piconeros
+    pub expected_funding_amounts: HashMap<Blockchain, u64>,
     pub broadcasted_txs: HashMap<TxLabel, bitcoin::Transaction>,
     pub failed_broadcasted_txs: HashMap<TxLabel, bitcoin::Transaction>,
+    pub scan_progress: HashMap<TaskId, ScanProgress>,
+    pub btc_fee_estimate_sat_per_kvb: Option<u64>,
+    pub mempool_congestion: Option<MempoolCongestion>,
+    /// Remaining lifetime, in blocks, below which a watch task lifetime is refreshed
+    pub task_lifetime_refresh_threshold: u64,
+    /// Number of blocks without a broadcast result after which the pending broadcasts are sent
+    /// again to the syncer
+    pub rebroadcast_threshold: u64,
+    /// Bitcoin height at which each pending broadcast task was sent
+    pub broadcast_heights: HashMap<TaskId, u64>,
+    /// Delay between a broadcast and its result above which a possible network issue is reported
+    pub propagation_warning_threshold: Duration,
+    /// Height from which the address watched for a transaction label is scanned, takes
+    /// precedence over the height inferred when the watch task is created
+    pub from_height_overrides: HashMap<TxLabel, u64>,
 }
 
 impl SwapLogging for SyncerState {
@@ -88,6 +441,88 @@ impl SyncerState {
             u64::MAX
         }
     }
+    /// Replaces the registered task with the same task with a fresh lifetime and returns it. Only
+    /// transaction and height watches are refreshed: re-registering an address watch makes the
+    /// syncer scan the address history again. The syncer registers a task id sent twice as two
+    /// distinct tasks, the previous one must be aborted before sending the refreshed task.
+    pub fn refresh_task_lifetime(&mut self, id: TaskId, blockchain: Blockchain) -> Option<Task> {
+        let lifetime = self.task_lifetime(blockchain);
+        let mut task = self.tasks.tasks.get(&id)?.clone();
+        match &mut task {
+            Task::WatchTransaction(WatchTransaction { lifetime: l, .. })
+            | Task::WatchXmrTransaction(WatchXmrTransaction { lifetime: l, .. })
+            | Task::WatchHeight(WatchHeight { lifetime: l, .. }) => *l = lifetime,
+            _ => return None,
+        }
+        self.tasks.tasks.insert(id, task.clone());
+        Some(task)
+    }
+
+    /// Ids of the refreshable watch tasks on the blockchain whose remaining lifetime fell below
+    /// the refresh threshold
+    fn expiring_tasks(&self, blockchain: Blockchain) -> Vec<TaskId> {
+        let height = self.height(blockchain);
+        self.tasks
+            .tasks
+            .iter()
+            .filter_map(|(id, task)| {
+                let (task_blockchain, lifetime) = match task {
+                    Task::WatchTransaction(WatchTransaction { lifetime, .. }) => {
+                        // accordant lock is the only transaction watched on monero without
+                        // decoding its amount
+                        match self.tasks.watched_txs.get(id) {
+                            Some(TxLabel::AccLock) => (Blockchain::Monero, lifetime),
+                            _ => (Blockchain::Bitcoin, lifetime),
+                        }
+                    }
+                    Task::WatchXmrTransaction(WatchXmrTransaction { lifetime, .. }) => {
+                        (Blockchain::Monero, lifetime)
+                    }
+                    Task::WatchHeight(WatchHeight { lifetime, .. }) => (
+                        *self
+                            .tasks
+                            .watched_heights
+                            .iter()
+                            .find(|(_, task_id)| *task_id == id)?
+                            .0,
+                        lifetime,
+                    ),
+                    _ => return None,
+                };
+                (task_blockchain == blockchain
+                    && lifetime.saturating_sub(height) < self.task_lifetime_refresh_threshold)
+                    .then(|| *id)
+            })
+            .collect()
+    }
+
+    /// Labeled tasks whose lifetime ends within `warn_within` blocks of the current height of
+    /// their blockchain, with the remaining blocks, sorted by task id
+    pub fn tasks_near_expiry(&self, warn_within: u64) -> Vec<(TaskId, TxLabel, u64)> {
+        let mut tasks: Vec<(TaskId, TxLabel, u64)> = self
+            .tasks
+            .tasks
+            .iter()
+            .filter_map(|(id, task)| {
+                let label = [
+                    &self.tasks.watched_txs,
+                    &self.tasks.watched_addrs,
+                    &self.tasks.watched_mempool,
+                    &self.tasks.retrieving_txs,
+                    &self.tasks.broadcasting_txs,
+                ]
+                .iter()
+                .find_map(|labels| labels.get(id))?;
+                let remaining = task
+                    .lifetime()?
+                    .saturating_sub(self.height(tx_label_blockchain(*label)));
+                (remaining < warn_within).then(|| (*id, *label, remaining))
+            })
+            .collect();
+        tasks.sort_by_key(|(id, _, _)| *id);
+        tasks
+    }
+
     pub fn bitcoin_syncer(&self) -> ServiceId {
         self.bitcoin_syncer.clone()
     }
@@ -100,12 +535,16 @@ impl SyncerState {
             Blockchain::Monero => self.monero_height,
         }
     }
+    /// Records the new height and refreshes the tasks close to their lifetime end. A Bitcoin
+    /// height lower than the previous one is a reorg: the lock confirmations are lowered by the
+    /// reorg depth and the impact on the swap is returned.
     pub fn handle_height_change(
         &mut self,
         new_height: u64,
         blockchain: Blockchain,
+        temporal_safety: &TemporalSafety,
         endpoints: &mut Endpoints,
-    ) {
+    ) -> ReorgImpact {
         let height = match blockchain {
             Blockchain::Bitcoin => {
                 // Upon block height change attempt to re-broadcast transactions that previously failed to broadcast
@@ -130,23 +569,209 @@ impl SyncerState {
         if &new_height > height {
             *height = new_height;
             self.log_debug(format!("{} new height {}", blockchain, &new_height));
+        } else if &new_height < height {
+            let depth = *height - new_height;
+            *height = new_height;
+            self.log_warn(format!(
+                "{} reorg of {} blocks, new height {}",
+                blockchain, depth, new_height
+            ));
+            return match blockchain {
+                Blockchain::Bitcoin => self.lock_reorg_impact(depth, temporal_safety),
+                Blockchain::Monero => ReorgImpact::NoImpact,
+            };
         } else {
             self.log_warn("block height did not increment, maybe syncer sends multiple events");
+            return ReorgImpact::NoImpact;
+        }
+        if blockchain == Blockchain::Bitcoin && !self.stalled_broadcasts().is_empty() {
+            self.log_warn(format!(
+                "No broadcast result received after {} blocks, re-broadcasting pending transactions",
+                self.rebroadcast_threshold
+            ));
+            if let Err(err) = self.rebroadcast_pending(endpoints) {
+                self.log_error(format!(
+                    "Failed to send task for re-broadcasting pending transactions: {}",
+                    err
+                ));
+            }
+        }
+        if blockchain == Blockchain::Monero {
+            if let Err(err) = self.on_sweep_expired(endpoints) {
+                self.log_error(format!("Failed to send task re-issuing the sweep: {}", err));
+            }
+        }
+        let syncer = match blockchain {
+            Blockchain::Bitcoin => self.bitcoin_syncer(),
+            Blockchain::Monero => self.monero_syncer(),
+        };
+        for id in self.expiring_tasks(blockchain) {
+            if let Some(task) = self.refresh_task_lifetime(id, blockchain) {
+                self.log_debug(format!("Refreshing lifetime of task {}", id));
+                let abort = Task::Abort(Abort {
+                    task_target: TaskTarget::TaskId(id),
+                    respond: false,
+                });
+                for task in [abort, task] {
+                    if let Err(err) = endpoints.send_to(
+                        ServiceBus::Sync,
+                        ServiceId::Swap(self.swap_id),
+                        syncer.clone(),
+                        BusMsg::Sync(SyncMsg::Task(task)),
+                    ) {
+                        self.log_error(format!(
+                            "Failed to send task refreshing the lifetime of task {}: {}",
+                            id, err
+                        ));
+                    }
+                }
+            }
         }
+        // the tasks left near expiry cannot be refreshed and are about to be dropped by the syncer
+        for (id, label, remaining) in self.tasks_near_expiry(self.task_lifetime_refresh_threshold) {
+            if tx_label_blockchain(label) == blockchain {
+                self.log_warn(format!(
+                    "Task {} for {} expires in {} blocks",
+                    id, label, remaining
+                ));
+            }
+        }
+        ReorgImpact::NoImpact
+    }
+
+    /// Lowers the lock confirmations by the reorg depth until the syncer reports them again and
+    /// returns the impact on the swap
+    fn lock_reorg_impact(&mut self, depth: u64, temporal_safety: &TemporalSafety) -> ReorgImpact {
+        let prev_confs = match self.get_confs(TxLabel::Lock) {
+            Some(confs) => confs,
+            None => return ReorgImpact::NoImpact,
+        };
+        let new_confs = prev_confs.saturating_sub(u32::try_from(depth).unwrap_or(u32::MAX));
+        self.confirmations.insert(TxLabel::Lock, Some(new_confs));
+        temporal_safety.adjust_for_reorg(prev_confs, new_confs)
     }
+    /// Tracks the scan progress of an address watch task, returns true if the progress changed
+    /// and should be reported. The entry is cleared once the scan reached its target height.
+    pub fn handle_scan_progress(&mut self, progress: &ScanProgress) -> bool {
+        if !self.tasks.watched_addrs.contains_key(&progress.id) {
+            return false;
+        }
+        let changed = self
+            .scan_progress
+            .get(&progress.id)
+            .map_or(true, |last| last.percent != progress.percent);
+        if progress.scanned_height >= progress.target_height {
+            self.scan_progress.remove(&progress.id);
+        } else {
+            self.scan_progress.insert(progress.id, progress.clone());
+        }
+        changed
+    }
+    /// Unregisters the task and returns the task aborting it on the syncer
     pub fn abort_task(&mut self, id: TaskId) -> Task {
+        self.tasks.remove_task(id);
+        self.scan_progress.remove(&id);
         Task::Abort(Abort {
             task_target: TaskTarget::TaskId(id),
             respond: false,
         })
     }
 
+    /// Syncer on which the registered task runs
+    fn task_syncer(&self, id: &TaskId, task: &Task) -> ServiceId {
+        let blockchain = match task {
+            Task::WatchHeight(_) => self
+                .tasks
+                .watched_heights
+                .iter()
+                .find_map(|(blockchain, task_id)| (task_id == id).then(|| *blockchain))
+                .unwrap_or(Blockchain::Bitcoin),
+            // accordant lock is the only transaction watched on monero without decoding its
+            // amount
+            Task::WatchTransaction(_) => match self.tasks.watched_txs.get(id) {
+                Some(TxLabel::AccLock) => Blockchain::Monero,
+                _ => Blockchain::Bitcoin,
+            },
+            Task::WatchXmrTransaction(_)
+            | Task::GetXmrTx(_)
+            | Task::WatchAddress(WatchAddress {
+                addendum: AddressAddendum::Monero(_),
+                ..
+            })
+            | Task::SweepAddress(SweepAddress {
+                addendum: SweepAddressAddendum::Monero(_),
+                ..
+            }) => Blockchain::Monero,
+            _ => Blockchain::Bitcoin,
+        };
+        match blockchain {
+            Blockchain::Bitcoin => self.bitcoin_syncer(),
+            Blockchain::Monero => self.monero_syncer(),
+        }
+    }
+    /// Unregisters every task and returns the tasks aborting them with the syncer each must be
+    /// sent to. The transaction ids and finality are kept for the final swap report.
+    fn abort_all_tasks(&mut self) -> Vec<(ServiceId, Task)> {
+        let mut ids: Vec<TaskId> = self.tasks.tasks.keys().copied().collect();
+        ids.sort_by_key(|id| id.0);
+        let aborts = ids
+            .into_iter()
+            .map(|id| {
+                (
+                    self.task_syncer(&id, &self.tasks.tasks[&id]),
+                    Task::Abort(Abort {
+                        task_target: TaskTarget::TaskId(id),
+                        respond: false,
+                    }),
+                )
+            })
+            .collect();
+        self.tasks.tasks.clear();
+        self.tasks.watched_txs.clear();
+        self.tasks.watched_addrs.clear();
+        self.tasks.watched_mempool.clear();
+        self.tasks.retrieving_txs.clear();
+        self.tasks.broadcasting_txs.clear();
+        self.tasks.watched_heights.clear();
+        self.tasks.sweeping_addr = None;
+        self.scan_progress.clear();
+        self.broadcast_heights.clear();
+        aborts
+    }
+    /// Aborts every registered task on its syncer, used when the swap terminates so that no
+    /// task lingers in the syncers until it expires
+    pub fn cancel_all_tasks(&mut self, endpoints: &mut Endpoints) -> Result<(), Error> {
+        for (syncer, task) in self.abort_all_tasks() {
+            endpoints.send_to(
+                ServiceBus::Sync,
+                ServiceId::Swap(self.swap_id),
+                syncer,
+                BusMsg::Sync(SyncMsg::Task(task)),
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn broadcasted_tx(&self, tx_label: &TxLabel) -> bool {
         self.broadcasted_txs.contains_key(tx_label)
             || self.tasks.broadcasting_txs.values().any(|l| l == tx_label)
     }
 
+    /// The registered fee estimation task and whether its lifetime expired
+    fn fee_estimate_task(&self) -> Option<(TaskId, bool)> {
+        self.tasks.tasks.iter().find_map(|(id, task)| match task {
+            Task::WatchEstimateFee(WatchEstimateFee { lifetime, .. }) => {
+                Some((*id, *lifetime < self.bitcoin_height))
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the registered fee estimation task if any, otherwise registers a new one
     pub fn estimate_fee_btc(&mut self) -> Task {
+        if let Some((id, _)) = self.fee_estimate_task() {
+            return self.tasks.tasks[&id].clone();
+        }
         let id = self.tasks.new_taskid();
         let task = Task::WatchEstimateFee(WatchEstimateFee {
             id,
@@ -175,13 +800,34 @@ impl SyncerState {
             id,
             lifetime: self.task_lifetime(Blockchain::Bitcoin),
             hash: txid.into(),
-            confirmation_bound: self.confirmation_bound,
+            confirmation_bound: self.confirmation_bounds.bound(tx_label),
         });
         self.tasks.tasks.insert(id, task.clone());
         task
     }
+    /// Ids of the tasks watching the transaction or the address, or retrieving the transaction
+    /// with the label, sorted
+    pub fn pending_tasks_by_label(&self, label: TxLabel) -> Vec<TaskId> {
+        let mut ids: Vec<TaskId> = [
+            &self.tasks.watched_txs,
+            &self.tasks.watched_addrs,
+            &self.tasks.retrieving_txs,
+        ]
+        .iter()
+        .flat_map(|tasks| {
+            tasks
+                .iter()
+                .filter(|(_, task_label)| **task_label == label)
+                .map(|(id, _)| *id)
+        })
+        .collect();
+        ids.sort();
+        ids
+    }
     pub fn is_watched_tx(&self, tx_label: &TxLabel) -> bool {
-        self.tasks.watched_txs.values().any(|tx| tx == tx_label)
+        self.pending_tasks_by_label(*tx_label)
+            .iter()
+            .any(|id| self.tasks.watched_txs.contains_key(id))
     }
     pub fn watch_tx_xmr(&mut self, hash: Txid, tx_label: TxLabel) -> Task {
         if self.is_watched_tx(&tx_label) {
@@ -199,12 +845,25 @@ impl SyncerState {
             hash,
         ));
         self.log_debug(format!("Watching transaction {} with {}", hash, id));
-        let task = Task::WatchTransaction(WatchTransaction {
-            id,
-            lifetime: self.task_lifetime(Blockchain::Monero),
-            hash,
-            confirmation_bound: self.confirmation_bound,
-        });
+        // decode the received amount when the keys of the watched address are known
+        let task = match &self.xmr_addr_addendum {
+            Some(XmrAddressAddendum {
+                address, view_key, ..
+            }) => Task::WatchXmrTransaction(WatchXmrTransaction {
+                id,
+                lifetime: self.task_lifetime(Blockchain::Monero),
+                hash,
+                confirmation_bound: self.confirmation_bounds.bound(tx_label),
+                spend_key: address.public_spend,
+                view_key: *view_key,
+            }),
+            None => Task::WatchTransaction(WatchTransaction {
+                id,
+                lifetime: self.task_lifetime(Blockchain::Monero),
+                hash,
+                confirmation_bound: self.confirmation_bounds.bound(tx_label),
+            }),
+        };
         self.tasks.tasks.insert(id, task.clone());
         task
     }
@@ -215,12 +874,38 @@ impl SyncerState {
         self.tasks.tasks.insert(id, task.clone());
         task
     }
+    pub fn retrieve_tx_xmr(&mut self, hash: Vec<u8>, tx_label: TxLabel) -> Task {
+        let id = self.tasks.new_taskid();
+        let task = Task::GetXmrTx(GetXmrTx { id, hash });
+        self.tasks.retrieving_txs.insert(id, tx_label);
+        self.tasks.tasks.insert(id, task.clone());
+        task
+    }
+    /// Scan the address watched for the transaction label from this height, e.g. when restoring
+    /// a swap whose address may have received transactions long before the current height.
+    pub fn set_from_height_override(&mut self, label: TxLabel, height: u64) {
+        self.from_height_overrides.insert(label, height);
+    }
+
+    /// Watches a btc address, the syncer scans the whole address history unless a from height
+    /// override is set for the transaction label. Returns the task already watching an address
+    /// for the transaction label so the syncer does not report the same transactions twice.
+    #[track_caller]
     pub fn watch_addr_btc(&mut self, address: bitcoin::Address, tx_label: TxLabel) -> Task {
         if self.is_watched_addr(&tx_label) {
-            self.log_warn(format!(
-                "Address already watched for {} - notifications will be repeated",
-                tx_label.label()
-            ));
+            let existing = self
+                .pending_tasks_by_label(tx_label)
+                .into_iter()
+                .filter(|id| self.tasks.watched_addrs.contains_key(id))
+                .find_map(|id| self.tasks.tasks.get(&id).cloned());
+            if let Some(task) = existing {
+                self.log_warn(format!(
+                    "Address already watched for {}, reusing the watch task for call from {}",
+                    tx_label.label(),
+                    Location::caller()
+                ));
+                return task;
+            }
         }
         let id = self.tasks.new_taskid();
         self.tasks.watched_addrs.insert(id, tx_label);
@@ -229,7 +914,10 @@ impl SyncerState {
             tx_label.label(),
             address.addr(),
         ));
-        let addendum = BtcAddressAddendum { address };
+        let addendum = BtcAddressAddendum {
+            address,
+            from_height: self.from_height_overrides.get(&tx_label).copied(),
+        };
         let filter = if TxLabel::Cancel == tx_label {
             // If this is the cancel transaction, only look for outgoing transactions
             TxFilter::Outgoing
@@ -247,18 +935,42 @@ impl SyncerState {
         task
     }
 
+    /// Watches the mempool for unconfirmed transactions paying to a btc address, requires the
+    /// Bitcoin syncer to be connected to the node ZMQ rawtx notifications.
+    pub fn watch_mempool_btc(&mut self, address: bitcoin::Address, tx_label: TxLabel) -> Task {
+        let id = self.tasks.new_taskid();
+        self.tasks.watched_mempool.insert(id, tx_label);
+        self.log_info(format!(
+            "Watching mempool for {} on address {}",
+            tx_label.label(),
+            address.addr(),
+        ));
+        let task = Task::WatchMempool(WatchMempool {
+            id,
+            lifetime: self.task_lifetime(Blockchain::Bitcoin),
+            address,
+        });
+        self.tasks.tasks.insert(id, task.clone());
+        task
+    }
+
     pub fn is_watched_addr(&self, tx_label: &TxLabel) -> bool {
-        self.tasks.watched_addrs.values().any(|tx| tx == tx_label)
+        self.pending_tasks_by_label(*tx_label)
+            .iter()
+            .any(|id| self.tasks.watched_addrs.contains_key(id))
     }
 
-    /// Watches an xmr address from provided height.
+    /// Watches an xmr address from provided height, or from the from height override set for the
+    /// transaction label.
     pub fn watch_addr_xmr(
         &mut self,
         address: monero::Address,
         view: monero::PrivateKey,
         tx_label: TxLabel,
         from_height: u64,
-    ) -> Task {
+    ) -> Result<Task, Error> {
+        validate_xmr_address(&address, self.network, false)
+            .map_err(|err| Error::Farcaster(format!("Cannot watch {}: {}", address, err)))?;
         if self.is_watched_addr(&tx_label) {
             self.log_warn(format!(
                 "Address {} already watched for {} - notifications will be repeated",
@@ -272,6 +984,11 @@ impl SyncerState {
             tx_label.bright_white_bold(),
             view.bright_white_italic()
         ));
+        let from_height = self
+            .from_height_overrides
+            .get(&tx_label)
+            .copied()
+            .unwrap_or(from_height);
         let addendum = XmrAddressAddendum {
             address,
             view_key: view,
@@ -300,14 +1017,21 @@ impl SyncerState {
         };
         let task = Task::WatchAddress(watch_addr);
         self.tasks.tasks.insert(id, task.clone());
-        task
+        Ok(task)
     }
 
     pub fn watch_height(
         &mut self,
         endpoints: &mut Endpoints,
         blockchain: Blockchain,
-    ) -> Result<(), Error> {
+    ) -> Result<TaskId, Error> {
+        // a single height task per blockchain, otherwise every height change is reported twice
+        if let Some(task_id) = self.tasks.watched_heights.get(&blockchain) {
+            if self.tasks.tasks.contains_key(task_id) {
+                self.log_trace(format!("Already watching height {}", blockchain));
+                return Ok(*task_id);
+            }
+        }
         let swap_id = ServiceId::Swap(self.swap_id);
         let task_id = self.tasks.new_taskid();
         self.log_trace(format!("Watch height {}", blockchain));
@@ -316,6 +1040,7 @@ impl SyncerState {
             lifetime: self.task_lifetime(blockchain),
         });
         self.tasks.tasks.insert(task_id, task.clone());
+        self.tasks.watched_heights.insert(blockchain, task_id);
         endpoints.send_to(
             ServiceBus::Sync,
             swap_id,
@@ -325,7 +1050,81 @@ impl SyncerState {
             },
             BusMsg::Sync(SyncMsg::Task(task)),
         )?;
-        Ok(())
+        // the mempool congestion is tracked for as long as the bitcoin height is
+        if blockchain == Blockchain::Bitcoin {
+            let task = self.get_mempool_info();
+            endpoints.send_to(
+                ServiceBus::Sync,
+                ServiceId::Swap(self.swap_id),
+                self.bitcoin_syncer(),
+                BusMsg::Sync(SyncMsg::Task(task)),
+            )?;
+        }
+        Ok(task_id)
+    }
+    pub fn get_mempool_info(&mut self) -> Task {
+        let id = self.tasks.new_taskid();
+        let task = Task::GetMempoolInfo(GetMempoolInfo {
+            id,
+            lifetime: self.task_lifetime(Blockchain::Bitcoin),
+        });
+        self.tasks.tasks.insert(id, task.clone());
+        task
+    }
+    /// Records the mempool state reported by the syncer, returns the congestion to report
+    pub fn handle_mempool_info(&mut self, info: &MempoolInfo) -> &MempoolCongestion {
+        self.mempool_congestion.insert(MempoolCongestion {
+            mempool_bytes: info.mempool_bytes,
+            min_relay_fee_sat_per_kvb: info.min_relay_fee_sat_per_kvb,
+            mempool_tx_count: info.mempool_tx_count,
+        })
+    }
+
+    /// The latest Bitcoin fee estimate in satoshi per kvB, as reported by the syncer
+    pub fn bitcoin_fee_sat_per_kvb(&self) -> Option<u64> {
+        self.btc_fee_estimate_sat_per_kvb
+    }
+
+    /// The latest Bitcoin fee estimate in satoshi per vbyte, rounded up so that the fee is never
+    /// underestimated
+    pub fn bitcoin_fee_sat_per_vbyte(&self) -> Option<u64> {
+        self.btc_fee_estimate_sat_per_kvb
+            .map(|kvb| (kvb + 999) / 1000)
+    }
+    /// Amount the funding address of the blockchain must receive, known once the swap asked for
+    /// funding
+    pub fn expected_funding_amount(&self, blockchain: Blockchain) -> Option<u64> {
+        self.expected_funding_amounts.get(&blockchain).copied()
+    }
+
+    pub fn set_expected_funding_amount(&mut self, blockchain: Blockchain, amount: u64) {
+        self.expected_funding_amounts.insert(blockchain, amount);
+    }
+
+    /// Compares the amount received on the funding address with the expected funding amount,
+    /// any amount is correct while no funding amount is expected
+    pub fn validate_funding(&self, blockchain: Blockchain, received: u64) -> FundingValidation {
+        match self.expected_funding_amount(blockchain) {
+            Some(expected) if received > expected => FundingValidation::Overpaid {
+                excess: received - expected,
+            },
+            Some(expected) if received < expected => FundingValidation::Underpaid {
+                shortfall: expected - received,
+            },
+            _ => FundingValidation::Correct,
+        }
+    }
+
+    /// Safety window widened by the number of full blocks currently waiting in the mempool, a
+    /// congested mempool delays the confirmation of our transactions. The window is never more
+    /// than doubled.
+    pub fn effective_race_thr(&self, safety: u32) -> u32 {
+        let backlog = self
+            .mempool_congestion
+            .as_ref()
+            .map(|congestion| congestion.mempool_bytes / BLOCK_VSIZE)
+            .unwrap_or(0);
+        safety.saturating_add(backlog.min(safety as u64) as u32)
     }
 
     pub fn sweep_btc(&mut self, addendum: SweepBitcoinAddress, retry: bool) -> Task {
@@ -358,20 +1157,176 @@ impl SyncerState {
         task
     }
 
+    /// Replaces the Monero sweep task with the same sweep if its lifetime passed before it
+    /// succeeded, the syncer drops expired tasks without notice. Returns the new sweep task.
+    fn reissue_expired_sweep(&mut self) -> Option<Task> {
+        let id = self.tasks.sweeping_addr?;
+        let (addendum, retry) = match self.tasks.tasks.get(&id)? {
+            Task::SweepAddress(SweepAddress {
+                lifetime,
+                addendum: SweepAddressAddendum::Monero(addendum),
+                retry,
+                ..
+            }) if *lifetime < self.monero_height => (addendum.clone(), *retry),
+            _ => return None,
+        };
+        self.tasks.remove_task(id);
+        Some(self.sweep_xmr(addendum, retry))
+    }
+
+    /// Sends the Monero sweep again to the syncer if its task expired before the sweep succeeded
+    pub fn on_sweep_expired(&mut self, endpoints: &mut Endpoints) -> Result<(), Error> {
+        let expired = self.tasks.sweeping_addr;
+        if let Some(task) = self.reissue_expired_sweep() {
+            self.log_warn(format!(
+                "Sweep task {} expired before sweeping, re-issuing it as task {}",
+                expired.expect("a sweep was pending"),
+                self.tasks.sweeping_addr.expect("set by sweep_xmr"),
+            ));
+            endpoints.send_to(
+                ServiceBus::Sync,
+                ServiceId::Swap(self.swap_id),
+                self.monero_syncer(),
+                BusMsg::Sync(SyncMsg::Task(task)),
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn broadcast(&mut self, tx: &bitcoin::Transaction, label: TxLabel) -> Task {
+        self.broadcast_task(tx, label, None)
+    }
+
+    /// Same as [`SyncerState::broadcast`], the syncer holds the transaction until the Bitcoin
+    /// chain reaches `after_height`
+    pub fn broadcast_after(
+        &mut self,
+        tx: &bitcoin::Transaction,
+        label: TxLabel,
+        after_height: u64,
+    ) -> Task {
+        self.broadcast_task(tx, label, Some(after_height))
+    }
+
+    fn broadcast_task(
+        &mut self,
+        tx: &bitcoin::Transaction,
+        label: TxLabel,
+        broadcast_after_height: Option<u64>,
+    ) -> Task {
+        // message redelivery or a checkpoint restore may ask twice for the same broadcast
+        let txid = tx.txid();
+        let pending =
+            self.tasks
+                .broadcasting_txs
+                .keys()
+                .find_map(|id| match self.tasks.tasks.get(id) {
+                    Some(task @ Task::BroadcastTransaction(BroadcastTransaction { tx, .. }))
+                        if bitcoin::consensus::deserialize::<bitcoin::Transaction>(tx)
+                            .map(|tx| tx.txid())
+                            .ok()
+                            == Some(txid) =>
+                    {
+                        Some(task.clone())
+                    }
+                    _ => None,
+                });
+        if let Some(task) = pending {
+            self.log_warn(format!("duplicate broadcast attempt for txid {}", txid));
+            return task;
+        }
         let id = self.tasks.new_taskid();
         let task = Task::BroadcastTransaction(BroadcastTransaction {
             id,
             tx: bitcoin::consensus::serialize(tx),
-            broadcast_after_height: None,
+            broadcast_after_height,
         });
         self.tasks.tasks.insert(id, task.clone());
         self.tasks.broadcasting_txs.insert(id, label);
+        self.tasks.broadcast_times.insert(id, Instant::now());
+        // a delayed broadcast only stalls once the syncer had to send it
+        let sent_height = broadcast_after_height.map_or(self.bitcoin_height, |height| {
+            height.max(self.bitcoin_height)
+        });
+        self.broadcast_heights.insert(id, sent_height);
         task
     }
+    /// Ids of the pending broadcast tasks without a result since the rebroadcast threshold
+    fn stalled_broadcasts(&self) -> Vec<TaskId> {
+        self.tasks
+            .broadcasting_txs
+            .keys()
+            .filter(|id| {
+                self.broadcast_heights.get(id).map_or(false, |height| {
+                    self.bitcoin_height.saturating_sub(*height) >= self.rebroadcast_threshold
+                })
+            })
+            .copied()
+            .collect()
+    }
+    /// Replaces every pending broadcast task with a new task for the same transaction and returns
+    /// the new tasks. The result of a replaced task is ignored if it arrives later.
+    fn rebroadcast_tasks(&mut self) -> Vec<Task> {
+        let pending = self.pending_broadcast_txs();
+        // the pending tasks are dropped first, broadcast would return them as duplicates
+        let ids: Vec<TaskId> = self.tasks.broadcasting_txs.keys().copied().collect();
+        for id in ids {
+            self.tasks.remove_task(id);
+            self.broadcast_heights.remove(&id);
+        }
+        pending
+            .into_iter()
+            .filter_map(|pending| match pending {
+                Ok((tx, label)) => Some(self.broadcast(&tx, label)),
+                Err(err) => {
+                    self.log_warn(err.to_string());
+                    None
+                }
+            })
+            .collect()
+    }
+    /// Sends again every pending broadcast to the Bitcoin syncer, returns the number of
+    /// transactions re-broadcasted
+    pub fn rebroadcast_pending(&mut self, endpoints: &mut Endpoints) -> Result<usize, Error> {
+        let tasks = self.rebroadcast_tasks();
+        for task in tasks.iter() {
+            endpoints.send_to(
+                ServiceBus::Sync,
+                ServiceId::Swap(self.swap_id),
+                self.bitcoin_syncer(),
+                BusMsg::Sync(SyncMsg::Task(task.clone())),
+            )?;
+        }
+        Ok(tasks.len())
+    }
+    /// Time elapsed since the broadcast task was created, the delay until its
+    /// `TransactionBroadcasted` event when called on reception of the event
+    pub fn propagation_delay(&self, id: TaskId) -> Option<Duration> {
+        self.tasks
+            .broadcast_times
+            .get(&id)
+            .map(|broadcast_time| broadcast_time.elapsed())
+    }
     pub fn transaction_broadcasted(&mut self, event: &TransactionBroadcasted) {
+        self.broadcast_heights.remove(&event.id);
+        let delay = self.propagation_delay(event.id);
         if let Some(txlabel) = self.tasks.broadcasting_txs.remove(&event.id) {
-            self.tasks.tasks.remove(&event.id);
+            if let Some(delay) = delay {
+                self.log_info(format!(
+                    "{} transaction broadcast result received after {:.1}s",
+                    txlabel,
+                    delay.as_secs_f32()
+                ));
+                if delay > self.propagation_warning_threshold {
+                    self.log_warn(format!(
+                        "{} transaction took more than {}s to broadcast, the syncer may have a \
+                         network issue",
+                        txlabel,
+                        self.propagation_warning_threshold.as_secs()
+                    ));
+                }
+            }
+            self.tasks.remove_task(event.id);
             if let Some(ref err) = event.error {
                 self.log_warn(format!(
                     "Error broadcasting {} transaction: {}",
@@ -396,19 +1351,24 @@ impl SyncerState {
             }
         }
     }
-    pub fn pending_broadcast_txs(&self) -> Vec<(bitcoin::Transaction, TxLabel)> {
+    pub fn pending_broadcast_txs(
+        &self,
+    ) -> Vec<Result<(bitcoin::Transaction, TxLabel), PendingBroadcastDecodeError>> {
         self.tasks
             .broadcasting_txs
             .iter()
             .filter_map(|(id, label)| {
                 if let Task::BroadcastTransaction(broadcast_tx) = self.tasks.tasks.get(id)? {
-                    Some((
+                    Some(
                         bitcoin::Transaction::consensus_decode(std::io::Cursor::new(
                             broadcast_tx.tx.clone(),
                         ))
-                        .ok()?,
-                        *label,
-                    ))
+                        .map(|tx| (tx, *label))
+                        .map_err(|err| PendingBroadcastDecodeError {
+                            task_id: *id,
+                            error_desc: err.to_string(),
+                        }),
+                    )
                 } else {
                     None
                 }
@@ -418,9 +1378,8 @@ impl SyncerState {
 
     pub fn acc_lock_watched(&self) -> bool {
         self.tasks
-            .watched_addrs
-            .values()
-            .any(|&x| x == TxLabel::AccLock)
+            .watching_address_for_label(&TxLabel::AccLock)
+            .is_some()
     }
     pub fn handle_tx_confs(
         &mut self,
@@ -429,77 +1388,127 @@ impl SyncerState {
         swapid: SwapId,
         finality_thr: u32,
         endpoints: &mut Endpoints,
-    ) {
-        if let Some(txlabel) = self.tasks.watched_txs.get(id).cloned() {
-            if !self.tasks.final_txs.contains_key(&txlabel)
-                && confirmations.is_some()
-                && confirmations.unwrap() >= finality_thr
-            {
-                self.log_info(format!(
-                    "Tx {} {} with {} {}",
-                    txlabel.label(),
-                    "final".bright_green_bold(),
-                    confirmations.unwrap().bright_green_bold(),
-                    "confirmations".bright_green_bold()
-                ));
-                self.tasks.final_txs.insert(txlabel, true);
-            } else if let Some(finality) = self.tasks.final_txs.get(&txlabel) {
-                self.log_info(format!(
-                    "Tx {} {}",
-                    txlabel.label(),
-                    if *finality {
-                        "final".bright_green_bold()
-                    } else {
-                        "non-final".red_bold()
-                    },
-                ));
-            } else {
-                match confirmations {
-                    Some(0) => {
-                        self.log_info(format!(
-                            "Tx {} on mempool but hasn't been mined",
+    ) -> Option<TxConfsUpdate> {
+        // events for tasks cleaned up already are expected and ignored
+        let txlabel = self.tasks.watched_txs.get(id).cloned()?;
+        let mut became_final = false;
+        if !self.tasks.final_txs.contains_key(&txlabel)
+            && confirmations.is_some()
+            && confirmations.unwrap() >= finality_thr
+        {
+            became_final = true;
+            self.log_info(format!(
+                "Tx {} {} with {} {}",
+                txlabel.label(),
+                "final".bright_green_bold(),
+                confirmations.unwrap().bright_green_bold(),
+                "confirmations".bright_green_bold()
+            ));
+            self.tasks.final_txs.insert(txlabel, true);
+        } else if let Some(finality) = self.tasks.final_txs.get(&txlabel) {
+            self.log_info(format!(
+                "Tx {} {}",
+                txlabel.label(),
+                if *finality {
+                    "final".bright_green_bold()
+                } else {
+                    "non-final".red_bold()
+                },
+            ));
+        } else {
+            match confirmations {
+                Some(0) => {
+                    let txid = self.tasks.txids.get(&txlabel).copied();
+                    let fee_rate = txid.and(self.bitcoin_fee_sat_per_vbyte());
+                    self.log_tx_seen(txlabel, txid, fee_rate);
+                }
+                Some(confs) => {
+                    self.log_info(format!(
+                        "Tx {} mined with {} {}",
+                        txlabel.label(),
+                        confs.bright_green_bold(),
+                        "confirmations".bright_green_bold(),
+                    ));
+                }
+                None => {
+                    if let Some(tx) = self.broadcasted_txs.get(&txlabel) {
+                        let tx = tx.clone();
+                        self.log_warn(format!(
+                            "Tx {} was re-orged or dropped from the mempool. Re-broadcasting tx",
                             txlabel.label()
                         ));
-                    }
-                    Some(confs) => {
-                        self.log_info(format!(
-                            "Tx {} mined with {} {}",
-                            txlabel.label(),
-                            confs.bright_green_bold(),
-                            "confirmations".bright_green_bold(),
-                        ));
-                    }
-                    None => {
-                        if let Some(tx) = self.broadcasted_txs.get(&txlabel) {
-                            let tx = tx.clone();
-                            self.log_warn(format!("Tx {} was re-orged or dropped from the mempool. Re-broadcasting tx", txlabel.label()));
-                            let task = self.broadcast(&tx, txlabel);
-                            if let Err(err) = endpoints.send_to(
-                                ServiceBus::Sync,
-                                ServiceId::Swap(swapid),
-                                self.bitcoin_syncer(),
-                                BusMsg::Sync(SyncMsg::Task(task)),
-                            ) {
-                                self.log_error(format!(
-                                    "Failed to send task for re-broadcasting {} transaction: {}",
-                                    txlabel, err
-                                ));
-                            }
+                        let task = self.broadcast(&tx, txlabel);
+                        if let Err(err) = endpoints.send_to(
+                            ServiceBus::Sync,
+                            ServiceId::Swap(swapid),
+                            self.bitcoin_syncer(),
+                            BusMsg::Sync(SyncMsg::Task(task)),
+                        ) {
+                            self.log_error(format!(
+                                "Failed to send task for re-broadcasting {} transaction: {}",
+                                txlabel, err
+                            ));
                         }
-                        self.log_info(format!("Tx {} not on the mempool", txlabel.label()));
                     }
+                    self.log_info(format!("Tx {} not on the mempool", txlabel.label()));
                 }
             }
-            self.confirmations.insert(txlabel, *confirmations);
-        } else {
-            self.log_error(format!(
-                "Received event with unknown transaction and task id {}",
-                &id
-            ));
         }
+        self.confirmations.insert(txlabel, *confirmations);
+        Some(TxConfsUpdate {
+            label: txlabel,
+            confs: *confirmations,
+            became_final,
+        })
+    }
+    /// Logs a transaction seen on the mempool, with the fee rate it was estimated with if known
+    pub fn log_tx_seen(
+        &self,
+        txlabel: TxLabel,
+        txid: Option<bitcoin::Txid>,
+        fee_rate_sat_per_vbyte: Option<u64>,
+    ) {
+        let mut msg = format!("Tx {} on mempool but hasn't been mined", txlabel.label());
+        if let Some(txid) = txid {
+            msg.push_str(&format!(" ({})", txid.tx_hash()));
+        }
+        if let Some(fee_rate) = fee_rate_sat_per_vbyte {
+            msg.push_str(&format!(", fee: {} sat/vByte", fee_rate));
+        }
+        self.log_info(msg);
+    }
+    /// Logs a transaction created for broadcast, with the amount it transfers if known
+    pub fn log_tx_created(
+        &self,
+        txlabel: TxLabel,
+        txid: bitcoin::Txid,
+        btc_amount: Option<bitcoin::Amount>,
+    ) {
+        let mut msg = format!("Broadcasting {} tx({})", txlabel.label(), txid.tx_hash());
+        if let Some(amount) = btc_amount {
+            msg.push_str(&format!(", amount: {}", amount.bright_green_bold()));
+        }
+        self.log_info(msg);
     }
     pub fn watch_bitcoin_fee(&mut self, endpoints: &mut Endpoints) -> Result<(), Error> {
         let identity = ServiceId::Swap(self.swap_id);
+        match self.fee_estimate_task() {
+            Some((_, false)) => {
+                self.log_trace("Already watching bitcoin fee estimate");
+                return Ok(());
+            }
+            // replace an expired task with a fresh one
+            Some((id, true)) => {
+                let abort = self.abort_task(id);
+                endpoints.send_to(
+                    ServiceBus::Sync,
+                    identity.clone(),
+                    self.bitcoin_syncer(),
+                    BusMsg::Sync(SyncMsg::Task(abort)),
+                )?;
+            }
+            None => {}
+        }
         let task = self.estimate_fee_btc();
         endpoints.send_to(
             ServiceBus::Sync,
@@ -513,4 +1522,740 @@ impl SyncerState {
     pub fn get_confs(&self, label: TxLabel) -> Option<u32> {
         self.confirmations.get(&label).copied().flatten()
     }
+
+    /// Snapshot of the confirmations of every transaction seen so far, None until a transaction
+    /// is mined
+    pub fn get_all_confirmations(&self) -> HashMap<TxLabel, Option<u32>> {
+        self.confirmations.clone()
+    }
+
+    /// Whether every mined transaction of the blockchain reached the finality threshold, true if
+    /// none of its transactions is mined yet. Transactions only seen on the mempool are left out,
+    /// they may conflict with a mined one and never confirm.
+    pub fn all_final(&self, blockchain: Blockchain, finality_thr: u32) -> bool {
+        self.confirmations
+            .iter()
+            .filter(|(label, _)| tx_label_blockchain(**label) == blockchain)
+            .filter_map(|(_, confs)| confs.filter(|confs| *confs > 0))
+            .all(|confs| confs >= finality_thr)
+    }
+
+    /// Whether the Monero lock reached the accordant finality threshold, false until it is mined
+    pub fn xmr_lock_final(&self, temporal_safety: &TemporalSafety) -> bool {
+        self.get_confs(TxLabel::AccLock).map_or(false, |confs| {
+            temporal_safety.final_tx(confs, Blockchain::Monero)
+        })
+    }
+
+    /// Whether the Bitcoin lock reached the arbitrating finality threshold, false until it is
+    /// mined
+    pub fn btc_lock_final(&self, temporal_safety: &TemporalSafety) -> bool {
+        self.get_confs(TxLabel::Lock).map_or(false, |confs| {
+            temporal_safety.final_tx(confs, Blockchain::Bitcoin)
+        })
+    }
+
+    /// Number of watched transactions that reached their finality threshold
+    pub fn final_tx_count(&self) -> u32 {
+        self.required_final_txs()
+            .iter()
+            .filter(|label| self.tasks.final_txs.get(label) == Some(&true))
+            .count() as u32
+    }
+
+    /// Number of transactions that must be final to complete the current swap path
+    pub fn required_final_tx_count(&self) -> u32 {
+        self.required_final_txs().len() as u32
+    }
+
+    /// Transactions that must be final to complete the current swap path: lock and accordant
+    /// lock on the happy path, lock and cancel (and accordant lock if seen) once the cancel
+    /// transaction has been seen
+    fn required_final_txs(&self) -> Vec<TxLabel> {
+        if self.get_confs(TxLabel::Cancel).is_some() {
+            let mut labels = vec![TxLabel::Lock, TxLabel::Cancel];
+            if self.get_confs(TxLabel::AccLock).is_some() {
+                labels.push(TxLabel::AccLock);
+            }
+            labels
+        } else {
+            vec![TxLabel::Lock, TxLabel::AccLock]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncerd::BroadcastTransaction;
+    use bitcoin::consensus::encode::serialize;
+    use farcaster_core::blockchain::Network;
+    use std::str::FromStr;
+
+    fn syncer_state(tasks: SyncerTasks) -> SyncerState {
+        SyncerState {
+            swap_id: SwapId(farcaster_core::Uuid::random()),
+            local_swap_role: SwapRole::Alice,
+            local_trade_role: TradeRole::Maker,
+            tasks,
+            monero_height: 0,
+            bitcoin_height: 0,
+            confirmation_bounds: ConfirmationBounds::default(),
+            last_tx_event: none!(),
+            scan_progress: none!(),
+            btc_fee_estimate_sat_per_kvb: None,
+            mempool_congestion: None,
+            task_lifetime_refresh_threshold: 100,
+            rebroadcast_threshold: 6,
+            broadcast_heights: none!(),
+            propagation_warning_threshold: Duration::from_secs(60),
+            network: Network::Local,
+            bitcoin_syncer: ServiceId::Syncer(Blockchain::Bitcoin, Network::Local),
+            monero_syncer: ServiceId::Syncer(Blockchain::Monero, Network::Local),
+            awaiting_funding: false,
+            expected_funding_amounts: none!(),
+            xmr_addr_addendum: None,
+            confirmations: none!(),
+            broadcasted_txs: none!(),
+            failed_broadcasted_txs: none!(),
+            from_height_overrides: none!(),
+        }
+    }
+
+    fn syncer_tasks() -> SyncerTasks {
+        SyncerTasks {
+            counter: 0,
+            watched_addrs: none!(),
+            watched_mempool: none!(),
+            watched_txs: none!(),
+            retrieving_txs: none!(),
+            sweeping_addr: none!(),
+            broadcasting_txs: none!(),
+            txids: none!(),
+            final_txs: none!(),
+            tasks: none!(),
+            watched_heights: none!(),
+            registered_at: none!(),
+            broadcast_times: none!(),
+        }
+    }
+
+    #[test]
+    fn pending_broadcast_txs_reports_decode_failures() {
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![bitcoin::TxOut {
+                value: 1000,
+                script_pubkey: bitcoin::Script::new(),
+            }],
+        };
+        let mut tasks = syncer_tasks();
+        for (id, label, raw) in [
+            (TaskId(1), TxLabel::Lock, serialize(&tx)),
+            (TaskId(2), TxLabel::Cancel, vec![0xde, 0xad, 0xbe, 0xef]),
+        ] {
+            tasks.broadcasting_txs.insert(id, label);
+            tasks.tasks.insert(
+                id,
+                Task::BroadcastTransaction(BroadcastTransaction {
+                    id,
+                    tx: raw,
+                    broadcast_after_height: None,
+                }),
+            );
+        }
+        let syncer_state = syncer_state(tasks);
+
+        let mut pending = syncer_state.pending_broadcast_txs();
+        pending.sort_by_key(|res| res.is_err());
+        assert_eq!(pending[0], Ok((tx, TxLabel::Lock)));
+        assert_eq!(pending[1].as_ref().unwrap_err().task_id, TaskId(2));
+    }
+
+    #[test]
+    fn broadcast_reuses_the_pending_task_of_a_duplicate() {
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![bitcoin::TxOut {
+                value: 1000,
+                script_pubkey: bitcoin::Script::new(),
+            }],
+        };
+        let mut syncer_state = syncer_state(syncer_tasks());
+        let first = syncer_state.broadcast(&tx, TxLabel::Lock);
+        let second = syncer_state.broadcast(&tx, TxLabel::Lock);
+        assert_eq!(first, second);
+        assert_eq!(syncer_state.tasks.broadcasting_txs.len(), 1);
+    }
+
+    #[test]
+    fn propagation_delay_is_tracked_until_the_broadcast_result() {
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![bitcoin::TxOut {
+                value: 1000,
+                script_pubkey: bitcoin::Script::new(),
+            }],
+        };
+        let mut syncer_state = syncer_state(syncer_tasks());
+        let id = match syncer_state.broadcast(&tx, TxLabel::Lock) {
+            Task::BroadcastTransaction(BroadcastTransaction { id, .. }) => id,
+            _ => panic!("expected a broadcast transaction task"),
+        };
+        assert!(syncer_state.propagation_delay(id).is_some());
+        assert!(syncer_state.propagation_delay(TaskId(id.0 + 1)).is_none());
+
+        syncer_state.transaction_broadcasted(&TransactionBroadcasted {
+            id,
+            tx: serialize(&tx),
+            error: None,
+        });
+        assert!(syncer_state.propagation_delay(id).is_none());
+        assert!(syncer_state.tasks.broadcast_times.is_empty());
+    }
+
+    #[test]
+    fn stalled_broadcasts_are_rebroadcasted() {
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![bitcoin::TxOut {
+                value: 1000,
+                script_pubkey: bitcoin::Script::new(),
+            }],
+        };
+        let broadcast_id = |task: &Task| match task {
+            Task::BroadcastTransaction(BroadcastTransaction { id, .. }) => *id,
+            _ => panic!("expected a broadcast transaction task"),
+        };
+        let mut syncer_state = syncer_state(syncer_tasks());
+        syncer_state.bitcoin_height = 100;
+        let id = broadcast_id(&syncer_state.broadcast(&tx, TxLabel::Cancel));
+        syncer_state.bitcoin_height = 105;
+        assert!(syncer_state.stalled_broadcasts().is_empty());
+
+        // the TransactionBroadcasted event of the task is missed
+        syncer_state.bitcoin_height = 106;
+        assert_eq!(syncer_state.stalled_broadcasts(), vec![id]);
+        let tasks = syncer_state.rebroadcast_tasks();
+        assert_eq!(tasks.len(), 1);
+        let new_id = broadcast_id(&tasks[0]);
+        assert_ne!(new_id, id);
+        assert_eq!(
+            syncer_state.pending_broadcast_txs(),
+            vec![Ok((tx.clone(), TxLabel::Cancel))]
+        );
+        assert!(syncer_state.stalled_broadcasts().is_empty());
+
+        // a late result of the replaced task is ignored
+        syncer_state.transaction_broadcasted(&TransactionBroadcasted {
+            id,
+            tx: serialize(&tx),
+            error: None,
+        });
+        assert!(syncer_state.broadcasted_txs.is_empty());
+        syncer_state.transaction_broadcasted(&TransactionBroadcasted {
+            id: new_id,
+            tx: serialize(&tx),
+            error: None,
+        });
+        assert_eq!(
+            syncer_state.broadcasted_txs.get(&TxLabel::Cancel),
+            Some(&tx)
+        );
+        assert!(syncer_state.broadcast_heights.is_empty());
+    }
+
+    #[test]
+    fn expired_sweep_is_reissued() {
+        let secret = monero::PrivateKey::from_str(
+            "77916d0cd56ed1920aef6ca56d8a41bac915b68e4c46a589e0956e27a7b77404",
+        )
+        .unwrap();
+        let addendum = SweepMoneroAddress {
+            source_spend_key: secret,
+            source_view_key: secret,
+            destination_address: monero::Address::from_str("43qHP7gSJJf8HZw1G3ZmpWVyYnbxkKdfta34Qj2nuRENjAsXBtj9JcMWcYMeT3n4NyTZqxhUkKgsTS6P2TNgM6ksM32czSp").unwrap(),
+            minimum_balance: monero::Amount::from_pico(0),
+            from_height: Some(90),
+        };
+        let mut syncer_state = syncer_state(syncer_tasks());
+        assert_eq!(syncer_state.reissue_expired_sweep(), None);
+        syncer_state.monero_height = 100;
+        syncer_state.sweep_xmr(addendum.clone(), true);
+        let id = syncer_state.tasks.sweeping_addr.unwrap();
+
+        syncer_state.monero_height = 600;
+        assert_eq!(syncer_state.reissue_expired_sweep(), None);
+
+        syncer_state.monero_height = 601;
+        let reissued = syncer_state.reissue_expired_sweep().unwrap();
+        let new_id = syncer_state.tasks.sweeping_addr.unwrap();
+        assert_ne!(new_id, id);
+        assert_eq!(
+            reissued,
+            Task::SweepAddress(SweepAddress {
+                id: new_id,
+                lifetime: 1101,
+                addendum: SweepAddressAddendum::Monero(addendum),
+                retry: true,
+            })
+        );
+        assert!(!syncer_state.tasks.tasks.contains_key(&id));
+        assert_eq!(syncer_state.reissue_expired_sweep(), None);
+    }
+
+    #[test]
+    fn abort_task_unregisters_the_task() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        let task = syncer_state.watch_tx_btc(bitcoin::Txid::default(), TxLabel::Lock);
+        let id = match task {
+            Task::WatchTransaction(WatchTransaction { id, .. }) => id,
+            _ => panic!("expected a watch transaction task"),
+        };
+        assert!(syncer_state.tasks.tasks.contains_key(&id));
+
+        syncer_state.abort_task(id);
+        assert!(syncer_state.tasks.tasks.is_empty());
+        assert!(syncer_state.tasks.watched_txs.is_empty());
+    }
+
+    #[test]
+    fn abort_all_tasks_clears_every_tracking_map() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        let lock = syncer_state.watch_tx_btc(bitcoin::Txid::default(), TxLabel::Lock);
+        let acc_lock =
+            syncer_state.watch_tx_xmr(Txid::Monero(monero::Hash::new([0u8])), TxLabel::AccLock);
+        let retrieve =
+            syncer_state.retrieve_tx_btc(Txid::Bitcoin(bitcoin::Txid::default()), TxLabel::Buy);
+        syncer_state.tasks.sweeping_addr = Some(TaskId(42));
+        syncer_state
+            .tasks
+            .txids
+            .insert(TxLabel::Lock, bitcoin::Txid::default());
+        let id = |task: &Task| match task {
+            Task::WatchTransaction(WatchTransaction { id, .. }) | Task::GetTx(GetTx { id, .. }) => {
+                *id
+            }
+            _ => panic!("unexpected task"),
+        };
+
+        let aborts = syncer_state.abort_all_tasks();
+        let expected: Vec<(ServiceId, TaskId)> = vec![
+            (syncer_state.bitcoin_syncer(), id(&lock)),
+            (syncer_state.monero_syncer(), id(&acc_lock)),
+            (syncer_state.bitcoin_syncer(), id(&retrieve)),
+        ];
+        let aborted: Vec<(ServiceId, TaskId)> = aborts
+            .into_iter()
+            .map(|(syncer, task)| match task {
+                Task::Abort(Abort {
+                    task_target: TaskTarget::TaskId(id),
+                    ..
+                }) => (syncer, id),
+                _ => panic!("expected an abort task"),
+            })
+            .collect();
+        assert_eq!(aborted, expected);
+        assert!(syncer_state.tasks.tasks.is_empty());
+        assert!(syncer_state.tasks.watched_txs.is_empty());
+        assert!(syncer_state.tasks.retrieving_txs.is_empty());
+        assert!(syncer_state.tasks.watched_addrs.is_empty());
+        assert!(syncer_state.tasks.watched_mempool.is_empty());
+        assert!(syncer_state.tasks.broadcasting_txs.is_empty());
+        assert!(syncer_state.tasks.watched_heights.is_empty());
+        assert_eq!(syncer_state.tasks.sweeping_addr, None);
+        assert_eq!(syncer_state.tasks.txids.len(), 1);
+    }
+
+    #[test]
+    fn pending_tasks_by_label_collects_every_tracking_map() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        let watched = syncer_state.tasks.new_taskid();
+        let addr = syncer_state.tasks.new_taskid();
+        let retrieving = syncer_state.tasks.new_taskid();
+        let other = syncer_state.tasks.new_taskid();
+        syncer_state
+            .tasks
+            .watched_txs
+            .insert(watched, TxLabel::Lock);
+        syncer_state.tasks.watched_addrs.insert(addr, TxLabel::Lock);
+        syncer_state
+            .tasks
+            .retrieving_txs
+            .insert(retrieving, TxLabel::Lock);
+        syncer_state
+            .tasks
+            .watched_txs
+            .insert(other, TxLabel::Cancel);
+
+        assert_eq!(
+            syncer_state.pending_tasks_by_label(TxLabel::Lock),
+            vec![watched, addr, retrieving]
+        );
+        assert_eq!(
+            syncer_state.pending_tasks_by_label(TxLabel::Cancel),
+            vec![other]
+        );
+        assert!(syncer_state.pending_tasks_by_label(TxLabel::Buy).is_empty());
+        assert!(syncer_state.is_watched_tx(&TxLabel::Lock));
+        assert!(syncer_state.is_watched_addr(&TxLabel::Lock));
+        assert!(!syncer_state.is_watched_addr(&TxLabel::Cancel));
+
+        syncer_state.tasks.watched_txs.remove(&watched);
+        assert!(!syncer_state.is_watched_tx(&TxLabel::Lock));
+    }
+
+    #[test]
+    fn final_tx_count_counts_only_required_txs() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        for label in [TxLabel::Lock, TxLabel::Buy, TxLabel::Funding] {
+            syncer_state.tasks.final_txs.insert(label, true);
+        }
+        syncer_state.tasks.final_txs.insert(TxLabel::AccLock, false);
+        assert_eq!(syncer_state.final_tx_count(), 1);
+        assert_eq!(syncer_state.required_final_tx_count(), 2);
+
+        // once the cancel is seen, the accordant lock is only required if it was seen too
+        syncer_state.confirmations.insert(TxLabel::Cancel, Some(1));
+        syncer_state.tasks.final_txs.insert(TxLabel::Cancel, true);
+        assert_eq!(syncer_state.final_tx_count(), 2);
+        assert_eq!(syncer_state.required_final_tx_count(), 2);
+    }
+
+    #[test]
+    fn assert_consistent_accepts_tracked_tasks() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        syncer_state.watch_tx_btc(bitcoin::Txid::default(), TxLabel::Lock);
+        syncer_state.tasks.final_txs.insert(TxLabel::Lock, true);
+        syncer_state.retrieve_tx_btc(Txid::Bitcoin(bitcoin::Txid::default()), TxLabel::Buy);
+        syncer_state.tasks.assert_consistent();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is not watched")]
+    fn assert_consistent_detects_unwatched_final_tx() {
+        let mut tasks = syncer_tasks();
+        tasks.final_txs.insert(TxLabel::Cancel, false);
+        tasks.new_taskid();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is behind")]
+    fn assert_consistent_detects_stale_counter() {
+        let mut tasks = syncer_tasks();
+        tasks.watched_txs.insert(TaskId(3), TxLabel::Lock);
+        tasks.assert_consistent();
+    }
+
+    #[test]
+    fn new_taskid_recycles_ids_past_u32_range() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        syncer_state.tasks.counter = TASK_COUNTER_RECYCLE_THRESHOLD - 10;
+        let mut ids = HashSet::new();
+        for i in 0..1000 {
+            let id = syncer_state.get_mempool_info().id().unwrap();
+            assert!(ids.insert(id), "duplicate task id {}", id);
+            // keep a sliding window of active tasks to exercise the wrap around
+            if i >= 100 {
+                let oldest = *ids.iter().min().unwrap();
+                ids.remove(&oldest);
+                syncer_state.tasks.remove_task(oldest);
+            }
+        }
+        assert_eq!(syncer_state.tasks.counter, TASK_COUNTER_RECYCLE_THRESHOLD);
+        assert!(ids
+            .iter()
+            .all(|id| u64::from(id.0) <= TASK_COUNTER_RECYCLE_THRESHOLD));
+    }
+
+    #[test]
+    fn remove_task_cleans_every_tracking_map() {
+        let mut tasks = syncer_tasks();
+        let id = tasks.new_taskid();
+        let other = tasks.new_taskid();
+        tasks.watched_txs.insert(id, TxLabel::Lock);
+        tasks.watched_addrs.insert(id, TxLabel::AccLock);
+        tasks.retrieving_txs.insert(id, TxLabel::Buy);
+        tasks.broadcasting_txs.insert(id, TxLabel::Cancel);
+        tasks.watched_txs.insert(other, TxLabel::Refund);
+        tasks.tasks.insert(
+            id,
+            Task::BroadcastTransaction(BroadcastTransaction {
+                id,
+                tx: vec![],
+                broadcast_after_height: None,
+            }),
+        );
+
+        assert_eq!(tasks.remove_task(id), RemovedTask::WatchedTx(TxLabel::Lock));
+        assert!(tasks.tasks.is_empty());
+        assert!(tasks.watched_addrs.is_empty());
+        assert!(tasks.retrieving_txs.is_empty());
+        assert!(tasks.broadcasting_txs.is_empty());
+        assert_eq!(tasks.watched_txs.len(), 1);
+        assert_eq!(tasks.remove_task(id), RemovedTask::None);
+
+        tasks.sweeping_addr = Some(other);
+        assert_eq!(tasks.remove_task(other), RemovedTask::SweepingAddr);
+        assert!(tasks.watched_txs.is_empty());
+        assert_eq!(tasks.sweeping_addr, None);
+    }
+
+    #[test]
+    fn refresh_task_lifetime_replaces_the_stored_task() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        syncer_state.bitcoin_height = 100;
+        let task = syncer_state.watch_tx_btc(bitcoin::Txid::default(), TxLabel::Lock);
+        let id = match task {
+            Task::WatchTransaction(WatchTransaction { id, .. }) => id,
+            _ => panic!("expected a watch transaction task"),
+        };
+        assert!(syncer_state.expiring_tasks(Blockchain::Bitcoin).is_empty());
+
+        syncer_state.bitcoin_height = 550;
+        assert_eq!(syncer_state.expiring_tasks(Blockchain::Bitcoin), vec![id]);
+        assert!(syncer_state.expiring_tasks(Blockchain::Monero).is_empty());
+        let refreshed = syncer_state.refresh_task_lifetime(id, Blockchain::Bitcoin);
+        assert!(matches!(
+            refreshed,
+            Some(Task::WatchTransaction(WatchTransaction {
+                lifetime: 1050,
+                ..
+            }))
+        ));
+        assert_eq!(syncer_state.tasks.tasks.get(&id), refreshed.as_ref());
+        assert_eq!(
+            syncer_state.tasks.watched_txs.get(&id),
+            Some(&TxLabel::Lock)
+        );
+        assert!(syncer_state.expiring_tasks(Blockchain::Bitcoin).is_empty());
+    }
+
+    #[test]
+    fn task_count_by_type_matches_registered_tasks() {
+        let tx = bitcoin::Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![],
+        };
+        let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let mut syncer_state = syncer_state(syncer_tasks());
+        assert!(syncer_state.tasks.task_count_by_type().is_empty());
+        assert_eq!(syncer_state.tasks.total_active_tasks(), 0);
+
+        syncer_state.watch_tx_btc(bitcoin::Txid::default(), TxLabel::Lock);
+        syncer_state.watch_tx_btc(bitcoin::Txid::default(), TxLabel::Cancel);
+        syncer_state.watch_addr_btc(address.clone(), TxLabel::Funding);
+        syncer_state.watch_mempool_btc(address, TxLabel::Funding);
+        syncer_state.retrieve_tx_btc(Txid::Bitcoin(bitcoin::Txid::default()), TxLabel::Buy);
+        syncer_state.retrieve_tx_xmr(vec![0; 32], TxLabel::AccLock);
+        syncer_state.broadcast(&tx, TxLabel::Lock);
+        syncer_state.estimate_fee_btc();
+        syncer_state.get_mempool_info();
+
+        let counts = syncer_state.tasks.task_count_by_type();
+        assert_eq!(counts.get(&TaskKind::WatchTransaction), Some(&2));
+        assert_eq!(counts.get(&TaskKind::WatchAddress), Some(&1));
+        assert_eq!(counts.get(&TaskKind::WatchMempool), Some(&1));
+        assert_eq!(counts.get(&TaskKind::GetTx), Some(&1));
+        assert_eq!(counts.get(&TaskKind::GetXmrTx), Some(&1));
+        assert_eq!(counts.get(&TaskKind::BroadcastTransaction), Some(&1));
+        assert_eq!(counts.get(&TaskKind::WatchEstimateFee), Some(&1));
+        assert_eq!(counts.get(&TaskKind::GetMempoolInfo), Some(&1));
+        assert_eq!(counts.get(&TaskKind::SweepAddress), None);
+        assert_eq!(counts.values().sum::<usize>(), 9);
+        assert_eq!(syncer_state.tasks.total_active_tasks(), 9);
+
+        // removed tasks are no longer counted
+        let id = syncer_state
+            .tasks
+            .watching_address_for_label(&TxLabel::Funding)
+            .unwrap();
+        syncer_state.abort_task(id);
+        let counts = syncer_state.tasks.task_count_by_type();
+        assert_eq!(counts.get(&TaskKind::WatchAddress), None);
+        assert_eq!(syncer_state.tasks.total_active_tasks(), 8);
+    }
+
+    #[test]
+    fn watch_addr_btc_uses_from_height_override() {
+        let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let mut syncer_state = syncer_state(syncer_tasks());
+        syncer_state.set_from_height_override(TxLabel::Cancel, 1200);
+        for (label, expected) in [(TxLabel::Funding, None), (TxLabel::Cancel, Some(1200))] {
+            match syncer_state.watch_addr_btc(address.clone(), label) {
+                Task::WatchAddress(WatchAddress {
+                    addendum: AddressAddendum::Bitcoin(addendum),
+                    ..
+                }) => assert_eq!(addendum.from_height, expected),
+                _ => panic!("expected a bitcoin watch address task"),
+            }
+        }
+    }
+
+    #[test]
+    fn lock_finality_follows_the_threshold_of_each_blockchain() {
+        for threshold in 0..5 {
+            let temporal_safety = TemporalSafety {
+                cancel_timelock: 10,
+                punish_timelock: 20,
+                safety: 2,
+                arb_finality: threshold,
+                acc_finality: threshold + 5,
+            };
+            let mut syncer_state = syncer_state(syncer_tasks());
+            assert!(!syncer_state.btc_lock_final(&temporal_safety));
+            assert!(!syncer_state.xmr_lock_final(&temporal_safety));
+
+            syncer_state.confirmations.insert(TxLabel::Lock, None);
+            syncer_state.confirmations.insert(TxLabel::AccLock, None);
+            assert!(!syncer_state.btc_lock_final(&temporal_safety));
+            assert!(!syncer_state.xmr_lock_final(&temporal_safety));
+
+            for confs in 0..12 {
+                syncer_state
+                    .confirmations
+                    .insert(TxLabel::Lock, Some(confs));
+                syncer_state
+                    .confirmations
+                    .insert(TxLabel::AccLock, Some(confs));
+                assert_eq!(
+                    syncer_state.btc_lock_final(&temporal_safety),
+                    confs >= threshold
+                );
+                assert_eq!(
+                    syncer_state.xmr_lock_final(&temporal_safety),
+                    confs >= threshold + 5
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn all_final_checks_the_confirmations_of_the_blockchain() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        assert!(syncer_state.all_final(Blockchain::Bitcoin, 3));
+
+        syncer_state.confirmations.insert(TxLabel::Lock, Some(3));
+        syncer_state.confirmations.insert(TxLabel::Cancel, Some(2));
+        syncer_state
+            .confirmations
+            .insert(TxLabel::AccLock, Some(10));
+        assert!(!syncer_state.all_final(Blockchain::Bitcoin, 3));
+        assert!(syncer_state.all_final(Blockchain::Monero, 10));
+        assert!(!syncer_state.all_final(Blockchain::Monero, 11));
+
+        syncer_state.confirmations.insert(TxLabel::Cancel, Some(4));
+        assert!(syncer_state.all_final(Blockchain::Bitcoin, 3));
+        // transactions not mined are left out, e.g. a refund conflicting with a mined punish
+        syncer_state.confirmations.insert(TxLabel::Refund, Some(0));
+        syncer_state.confirmations.insert(TxLabel::Buy, None);
+        assert!(syncer_state.all_final(Blockchain::Bitcoin, 3));
+        assert_eq!(
+            syncer_state.get_all_confirmations(),
+            syncer_state.confirmations
+        );
+    }
+
+    #[test]
+    fn bitcoin_fee_sat_per_vbyte_rounds_up() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        assert_eq!(syncer_state.bitcoin_fee_sat_per_vbyte(), None);
+        for (kvb, vbyte) in [(0, 0), (1, 1), (999, 1), (1000, 1), (1001, 2), (12_500, 13)] {
+            syncer_state.btc_fee_estimate_sat_per_kvb = Some(kvb);
+            assert_eq!(syncer_state.bitcoin_fee_sat_per_kvb(), Some(kvb));
+            assert_eq!(syncer_state.bitcoin_fee_sat_per_vbyte(), Some(vbyte));
+        }
+    }
+
+    #[test]
+    fn watch_addr_btc_reuses_task_for_watched_label() {
+        let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let mut syncer_state = syncer_state(syncer_tasks());
+        let first = syncer_state.watch_addr_btc(address.clone(), TxLabel::Funding);
+        let second = syncer_state.watch_addr_btc(address, TxLabel::Funding);
+        assert_eq!(first, second);
+        assert_eq!(syncer_state.tasks.watched_addrs.len(), 1);
+        assert_eq!(syncer_state.tasks.tasks.len(), 1);
+    }
+
+    #[test]
+    fn tasks_near_expiry_reports_remaining_blocks() {
+        let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let mut syncer_state = syncer_state(syncer_tasks());
+        syncer_state.bitcoin_height = 1000;
+        // registered with the default lifetime, 500 blocks from now
+        syncer_state.watch_mempool_btc(address.clone(), TxLabel::Cancel);
+        let mut task = syncer_state.watch_addr_btc(address, TxLabel::Funding);
+        if let Task::WatchAddress(WatchAddress { lifetime, .. }) = &mut task {
+            *lifetime = 1010;
+        }
+        let id = task.id().unwrap();
+        syncer_state.tasks.tasks.insert(id, task);
+        assert_eq!(
+            syncer_state.tasks_near_expiry(100),
+            vec![(id, TxLabel::Funding, 10)]
+        );
+        assert!(syncer_state.tasks_near_expiry(10).is_empty());
+        assert_eq!(syncer_state.tasks_near_expiry(501).len(), 2);
+    }
+
+    #[test]
+    fn validate_funding_compares_with_expected_amount() {
+        let mut syncer_state = syncer_state(syncer_tasks());
+        assert_eq!(
+            syncer_state.validate_funding(Blockchain::Bitcoin, 1000),
+            FundingValidation::Correct
+        );
+        syncer_state.set_expected_funding_amount(Blockchain::Bitcoin, 1000);
+        assert_eq!(
+            syncer_state.validate_funding(Blockchain::Bitcoin, 1000),
+            FundingValidation::Correct
+        );
+        assert_eq!(
+            syncer_state.validate_funding(Blockchain::Bitcoin, 1200),
+            FundingValidation::Overpaid { excess: 200 }
+        );
+        assert_eq!(
+            syncer_state.validate_funding(Blockchain::Bitcoin, 700),
+            FundingValidation::Underpaid { shortfall: 300 }
+        );
+        // the expected amount is tracked per blockchain
+        assert_eq!(
+            syncer_state.validate_funding(Blockchain::Monero, 700),
+            FundingValidation::Correct
+        );
+    }
+
+    #[test]
+    fn task_stats_report_active_task_ages() {
+        let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+        let mut syncer_state = syncer_state(syncer_tasks());
+        assert_eq!(syncer_state.tasks.stats(), TaskStats::default());
+
+        let first = syncer_state.watch_addr_btc(address.clone(), TxLabel::Funding);
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        syncer_state.watch_mempool_btc(address, TxLabel::Funding);
+        let stats = syncer_state.tasks.stats();
+        assert_eq!(stats.active_count, 2);
+        assert!(stats.oldest_active_age_secs.unwrap() >= 1);
+        assert!(stats.avg_active_age_secs.is_some());
+
+        syncer_state.tasks.remove_task(first.id().unwrap());
+        let stats = syncer_state.tasks.stats();
+        assert_eq!(stats.active_count, 1);
+        assert_eq!(stats.oldest_active_age_secs, Some(0));
+        assert!(syncer_state.tasks.registered_at.len() == 1);
+    }
 }
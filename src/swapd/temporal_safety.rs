@@ -5,7 +5,9 @@
 // https://opensource.org/licenses/MIT.
 
 use crate::Error;
+use farcaster_core::bitcoin::timelock::CSVTimelock;
 use farcaster_core::blockchain::Blockchain;
+use farcaster_core::swap::btcxmr::{Deal, DealParameters};
 use strict_encoding::{StrictDecode, StrictEncode};
 
 /// Represent a blockchain height
@@ -17,10 +19,37 @@ pub type BlockSpan = u32;
 /// The minimum number of block confirmations required before sweeping
 pub const SWEEP_MONERO_THRESHOLD: u32 = 10;
 
+/// Average time between two Bitcoin blocks, used to estimate durations expressed in blocks
+pub const BITCOIN_BLOCK_TIME_SECS: u64 = 600;
+
+/// Estimated distance to the cancel and punish deadlines of a swap, in blocks and in seconds.
+/// Negative values mean the deadline is already past.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineReport {
+    pub blocks_until_cancel: i64,
+    pub blocks_until_punish: i64,
+    pub seconds_until_cancel: i64,
+    pub seconds_until_punish: i64,
+}
+
+/// Change in what the swap can safely broadcast after a block reorganization lowered the lock
+/// confirmations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReorgImpact {
+    /// The reorg does not change what can be broadcast
+    NoImpact,
+    /// Buy was past its safety margin and can be broadcast again without racing cancel
+    BuySafetyRestored,
+    /// Cancel was valid and its timelock is not reached anymore
+    CancelValidityLost { was_valid: bool, now_valid: bool },
+    /// Punish was valid and its timelock is not reached anymore
+    PunishValidityLost,
+}
+
 /// List of parameters used to determined if a transaction should be considered final or not and if
 /// it is safe to broadcast a transaction given the timelocks and confirmations of other
 /// transactions.
-#[derive(Debug, Clone, StrictEncode, StrictDecode)]
+#[derive(Debug, Clone, PartialEq, Eq, StrictEncode, StrictDecode)]
 pub struct TemporalSafety {
     /// First timelock in the protocol, used to abort the swap instead of buying tx
     pub cancel_timelock: BlockHeight,
@@ -37,6 +66,55 @@ pub struct TemporalSafety {
 }
 
 impl TemporalSafety {
+    /// Create the temporal safety parameters of a deal, with the timelocks read from the deal and
+    /// the finality and safety thresholds from the local configuration. Fails if the parameters
+    /// are not coherent.
+    pub fn from_deal(
+        deal: &Deal,
+        arb_finality: BlockSpan,
+        acc_finality: BlockSpan,
+        safety: BlockSpan,
+    ) -> Result<Self, Error> {
+        Self::from_deal_parameters(&deal.parameters, arb_finality, acc_finality, safety)
+    }
+
+    /// Same as [`TemporalSafety::from_deal`] for the parameters of a deal not yet created, e.g.
+    /// when validating the parameters of a make request
+    pub fn from_deal_parameters(
+        parameters: &DealParameters,
+        arb_finality: BlockSpan,
+        acc_finality: BlockSpan,
+        safety: BlockSpan,
+    ) -> Result<Self, Error> {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: parameters.cancel_timelock.as_u32(),
+            punish_timelock: parameters.punish_timelock.as_u32(),
+            safety,
+            arb_finality,
+            acc_finality,
+        };
+        temporal_safety.valid_params()?;
+        Ok(temporal_safety)
+    }
+
+    /// Timelocks to set in the deal parameters, `(cancel_timelock, punish_timelock)`, the inverse
+    /// of [`TemporalSafety::from_deal`]
+    pub fn to_deal_params(&self) -> (CSVTimelock, CSVTimelock) {
+        (
+            CSVTimelock::new(self.cancel_timelock),
+            CSVTimelock::new(self.punish_timelock),
+        )
+    }
+
+    /// Whether the timelocks are the ones of the deal
+    pub fn matches_deal(&self, deal: &Deal) -> bool {
+        self.to_deal_params()
+            == (
+                deal.parameters.cancel_timelock,
+                deal.parameters.punish_timelock,
+            )
+    }
+
     /// Validate if temporal parameters are coherent
     pub fn valid_params(&self) -> Result<(), Error> {
         let finality = self.arb_finality;
@@ -65,7 +143,7 @@ impl TemporalSafety {
     /// Lock must be final, cancel cannot be raced, add + 1 to offset initial lock confirmation
     pub fn stop_funding_before_cancel(&self, lock_confirmations: u32) -> bool {
         self.final_tx(lock_confirmations, Blockchain::Bitcoin)
-            && lock_confirmations > (self.cancel_timelock - self.safety + 1)
+            && lock_confirmations as i64 > (self.cancel_timelock as i64 - self.safety as i64 + 1)
     }
 
     // Blocks remaining until funding will be stopped for safety, because it is too close to
@@ -74,6 +152,14 @@ impl TemporalSafety {
         self.cancel_timelock as i64 - (self.safety as i64 + 1 + lock_confirmations as i64)
     }
 
+    /// Last Bitcoin block in which the lock transaction can be mined, funding at
+    /// `current_height`, and still leave time for the lock to become final and for buy to be
+    /// broadcast without racing cancel. Saturates at zero.
+    pub fn max_safe_lock_mined_block(&self, current_height: u64) -> u64 {
+        (current_height + self.cancel_timelock as u64)
+            .saturating_sub(self.safety as u64 + self.arb_finality as u64 + 1)
+    }
+
     /// Lock must be final, valid after lock_minedblock + cancel_timelock
     pub fn valid_cancel(&self, lock_confirmations: u32) -> bool {
         self.final_tx(lock_confirmations, Blockchain::Bitcoin)
@@ -85,16 +171,51 @@ impl TemporalSafety {
         self.cancel_timelock as i64 - lock_confirmations as i64
     }
 
-    /// Lock must be final, but buy shall not be raced with cancel
+    /// Estimate how long until cancel and punish become valid given the lock confirmations and
+    /// an average block time. Punish is estimated assuming cancel is mined as soon as it is
+    /// valid.
+    pub fn time_to_deadline(
+        &self,
+        lock_confirmations: u32,
+        avg_block_time_secs: u64,
+    ) -> DeadlineReport {
+        let blocks_until_cancel = self.blocks_until_cancel(lock_confirmations);
+        let blocks_until_punish = blocks_until_cancel + self.punish_timelock as i64;
+        DeadlineReport {
+            blocks_until_cancel,
+            blocks_until_punish,
+            seconds_until_cancel: blocks_until_cancel * avg_block_time_secs as i64,
+            seconds_until_punish: blocks_until_punish * avg_block_time_secs as i64,
+        }
+    }
+
+    /// Inclusive range of lock confirmations in which broadcasting cancel is valid and cannot be
+    /// raced with punish
+    pub fn safe_cancel_window(&self) -> (BlockHeight, BlockHeight) {
+        (
+            self.cancel_timelock,
+            self.punish_timelock.saturating_sub(self.safety),
+        )
+    }
+
+    /// Returns whether the lock confirmations are within the safe cancel window
+    pub fn is_in_safe_cancel_window(&self, lock_confirmations: u32) -> bool {
+        let (start, end) = self.safe_cancel_window();
+        start <= lock_confirmations && lock_confirmations <= end
+    }
+
+    /// Lock must be final, but buy shall not be raced with cancel. Never safe if the cancel
+    /// timelock is shorter than the safety margin.
     pub fn safe_buy(&self, lock_confirmations: u32) -> bool {
         self.final_tx(lock_confirmations, Blockchain::Bitcoin)
-            && lock_confirmations <= (self.cancel_timelock - self.safety)
+            && lock_confirmations as i64 <= (self.cancel_timelock as i64 - self.safety as i64)
     }
 
-    /// Cancel must be final, but refund shall not be raced with punish
+    /// Cancel must be final, but refund shall not be raced with punish. Never safe if the punish
+    /// timelock is shorter than the safety margin.
     pub fn safe_refund(&self, cancel_confirmations: u32) -> bool {
         self.final_tx(cancel_confirmations, Blockchain::Bitcoin)
-            && cancel_confirmations <= (self.punish_timelock - self.safety)
+            && cancel_confirmations as i64 <= (self.punish_timelock as i64 - self.safety as i64)
     }
 
     /// Cancel must be final, valid after cancel_confirmations > punish_timelock
@@ -108,6 +229,32 @@ impl TemporalSafety {
         self.punish_timelock as i64 - cancel_confirmations as i64
     }
 
+    /// Impact of a reorg lowering the lock confirmations from `prev_confs` to `new_confs`. Punish
+    /// validity is estimated assuming cancel was mined as soon as it was valid. Losing cancel
+    /// validity takes precedence over losing punish validity, which takes precedence over buy
+    /// becoming safe again.
+    pub fn adjust_for_reorg(&self, prev_confs: u32, new_confs: u32) -> ReorgImpact {
+        if new_confs >= prev_confs {
+            return ReorgImpact::NoImpact;
+        }
+        let was_valid = self.valid_cancel(prev_confs);
+        let now_valid = self.valid_cancel(new_confs);
+        let punish_valid =
+            |lock_confs: u32| self.valid_punish(lock_confs.saturating_sub(self.cancel_timelock));
+        if was_valid && !now_valid {
+            ReorgImpact::CancelValidityLost {
+                was_valid,
+                now_valid,
+            }
+        } else if punish_valid(prev_confs) && !punish_valid(new_confs) {
+            ReorgImpact::PunishValidityLost
+        } else if !self.safe_buy(prev_confs) && self.safe_buy(new_confs) {
+            ReorgImpact::BuySafetyRestored
+        } else {
+            ReorgImpact::NoImpact
+        }
+    }
+
     pub fn block_height_reorg_lower_bound(
         &self,
         blockchain: Blockchain,
@@ -120,3 +267,203 @@ impl TemporalSafety {
         current_height.saturating_sub(finality_thr as u64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn timelocks_shorter_than_safety_are_never_safe() {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: 2,
+            punish_timelock: 3,
+            safety: 4,
+            arb_finality: 1,
+            acc_finality: 1,
+        };
+        assert!(temporal_safety.valid_params().is_err());
+        for confs in [1, 2, 3, 100] {
+            assert!(!temporal_safety.safe_buy(confs));
+            assert!(!temporal_safety.safe_refund(confs));
+            assert!(temporal_safety.stop_funding_before_cancel(confs));
+        }
+    }
+
+    #[test]
+    fn safe_cancel_window_bounds() {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        };
+        assert_eq!(temporal_safety.safe_cancel_window(), (10, 27));
+        assert!(!temporal_safety.is_in_safe_cancel_window(9));
+        assert!(temporal_safety.is_in_safe_cancel_window(10));
+        assert!(temporal_safety.is_in_safe_cancel_window(27));
+        assert!(!temporal_safety.is_in_safe_cancel_window(28));
+    }
+
+    #[test]
+    fn time_to_deadline_estimates() {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        };
+        assert_eq!(
+            temporal_safety.time_to_deadline(4, 600),
+            DeadlineReport {
+                blocks_until_cancel: 6,
+                blocks_until_punish: 36,
+                seconds_until_cancel: 3600,
+                seconds_until_punish: 21600,
+            }
+        );
+        assert_eq!(
+            temporal_safety.time_to_deadline(15, 120),
+            DeadlineReport {
+                blocks_until_cancel: -5,
+                blocks_until_punish: 25,
+                seconds_until_cancel: -600,
+                seconds_until_punish: 3000,
+            }
+        );
+    }
+
+    #[test]
+    fn adjust_for_reorg_impacts() {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        };
+        assert_eq!(
+            temporal_safety.adjust_for_reorg(5, 8),
+            ReorgImpact::NoImpact
+        );
+        assert_eq!(
+            temporal_safety.adjust_for_reorg(6, 4),
+            ReorgImpact::NoImpact
+        );
+        assert_eq!(
+            temporal_safety.adjust_for_reorg(8, 6),
+            ReorgImpact::BuySafetyRestored
+        );
+        assert_eq!(
+            temporal_safety.adjust_for_reorg(11, 9),
+            ReorgImpact::CancelValidityLost {
+                was_valid: true,
+                now_valid: false
+            }
+        );
+        assert_eq!(
+            temporal_safety.adjust_for_reorg(41, 39),
+            ReorgImpact::PunishValidityLost
+        );
+        // a reorg deep enough to invalidate cancel invalidates punish too
+        assert!(matches!(
+            temporal_safety.adjust_for_reorg(41, 5),
+            ReorgImpact::CancelValidityLost { .. }
+        ));
+    }
+
+    #[test]
+    fn max_safe_lock_mined_block_bounds() {
+        let temporal_safety = TemporalSafety {
+            cancel_timelock: 10,
+            punish_timelock: 30,
+            safety: 3,
+            arb_finality: 1,
+            acc_finality: 1,
+        };
+        assert_eq!(temporal_safety.max_safe_lock_mined_block(100), 105);
+        assert_eq!(temporal_safety.max_safe_lock_mined_block(0), 5);
+        // the margins exactly consume the cancel timelock
+        let tight = TemporalSafety {
+            cancel_timelock: 5,
+            ..temporal_safety.clone()
+        };
+        assert_eq!(tight.max_safe_lock_mined_block(100), 100);
+        // the margins exceed the cancel timelock, funding is already too late
+        let unsafe_params = TemporalSafety {
+            cancel_timelock: 4,
+            ..temporal_safety
+        };
+        assert_eq!(unsafe_params.max_safe_lock_mined_block(100), 99);
+        assert_eq!(unsafe_params.max_safe_lock_mined_block(0), 0);
+    }
+
+    #[test]
+    fn from_deal_rejects_unordered_parameters() {
+        let mut deal = fixtures::deal();
+        // every combination of small parameters, including equal and inverted values
+        for cancel in 0..8 {
+            for punish in 0..8 {
+                for safety in 0..8 {
+                    for finality in 0..8 {
+                        deal.parameters.cancel_timelock = CSVTimelock::new(cancel);
+                        deal.parameters.punish_timelock = CSVTimelock::new(punish);
+                        let ordered = finality < cancel
+                            && cancel < punish
+                            && finality < safety
+                            && safety < cancel;
+                        let temporal_safety =
+                            TemporalSafety::from_deal(&deal, finality, finality, safety);
+                        assert_eq!(
+                            temporal_safety.is_ok(),
+                            ordered,
+                            "cancel: {}, punish: {}, safety: {}, finality: {}",
+                            cancel,
+                            punish,
+                            safety,
+                            finality
+                        );
+                        if let Ok(temporal_safety) = temporal_safety {
+                            assert_eq!(temporal_safety.cancel_timelock, cancel);
+                            assert_eq!(temporal_safety.punish_timelock, punish);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn to_deal_params_round_trip() {
+        let mut deal = fixtures::deal();
+        for cancel in 0..12 {
+            for punish in 0..12 {
+                for safety in 0..6 {
+                    let temporal_safety = TemporalSafety {
+                        cancel_timelock: cancel,
+                        punish_timelock: punish,
+                        safety,
+                        arb_finality: 1,
+                        acc_finality: 2,
+                    };
+                    if temporal_safety.valid_params().is_err() {
+                        continue;
+                    }
+                    let (cancel_timelock, punish_timelock) = temporal_safety.to_deal_params();
+                    deal.parameters.cancel_timelock = cancel_timelock;
+                    deal.parameters.punish_timelock = punish_timelock;
+                    assert!(temporal_safety.matches_deal(&deal));
+                    assert_eq!(
+                        TemporalSafety::from_deal(&deal, 1, 2, safety).unwrap(),
+                        temporal_safety
+                    );
+
+                    deal.parameters.punish_timelock = CSVTimelock::new(punish + 1);
+                    assert!(!temporal_safety.matches_deal(&deal));
+                }
+            }
+        }
+    }
+}
@@ -19,6 +19,8 @@ use crate::syncerd::Event;
 use crate::syncerd::FeeEstimations;
 use crate::syncerd::GetTx;
 use crate::syncerd::Health;
+use crate::syncerd::MempoolInfo;
+use crate::syncerd::TaskId;
 use crate::syncerd::TaskTarget;
 use crate::syncerd::TransactionBroadcasted;
 use crate::syncerd::TransactionRetrieved;
@@ -39,7 +41,7 @@ use internet2::session::LocalSession;
 use internet2::zeromq::ZmqSocketType;
 use internet2::SendRecvMessage;
 use internet2::TypedEnum;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::sync::mpsc::{Receiver, TryRecvError};
 use std::sync::Arc;
 use std::time::Duration;
@@ -370,6 +372,12 @@ fn query_addr_history(
 
     let mut addr_txs = vec![];
     for hist in tx_hist {
+        // mempool transactions have a height <= 0 and are always reported
+        if let Some(from_height) = address.from_height {
+            if hist.height > 0 && (hist.height as u64) < from_height {
+                continue;
+            }
+        }
         let txid = hist.tx_hash;
         let tx = client.transaction_get(&txid)?;
         let mut output_found = false;
@@ -633,6 +641,13 @@ async fn run_syncerd_task_receiver(
                             state_guard.estimate_fee(task, syncerd_task.source).await;
                             drop(state_guard);
                         }
+                        Task::GetMempoolInfo(task) => {
+                            let mut state_guard = state.lock().await;
+                            state_guard
+                                .watch_mempool_info(task, syncerd_task.source)
+                                .await;
+                            drop(state_guard);
+                        }
                         Task::SweepAddress(task) => match task.addendum.clone() {
                             SweepAddressAddendum::Bitcoin(sweep) => {
                                 let addr = sweep.source_address;
@@ -657,24 +672,14 @@ async fn run_syncerd_task_receiver(
                         }
                         Task::BroadcastTransaction(task) => {
                             debug!("trying to broadcast tx: {}", task.tx.to_hex());
-                            if let Some(height) = task.broadcast_after_height {
-                                let mut state_guard = state.lock().await;
-                                // If we already surpassed the height, immediately broadcast it. Otherwise queue the broadcast
-                                if height <= state_guard.block_height() {
-                                    drop(state_guard);
-                                    transaction_broadcast_tx
-                                        .send((task, syncerd_task.source))
-                                        .await
-                                        .expect("failed on transaction_broadcast_tx sender");
-                                } else {
-                                    state_guard
-                                        .pending_broadcasts
-                                        .insert((task, syncerd_task.source));
-                                    drop(state_guard);
-                                }
-                            } else {
+                            // If we already reached the height, immediately broadcast it.
+                            // Otherwise queue the broadcast
+                            let mut state_guard = state.lock().await;
+                            let ready = state_guard.queue_broadcast(task, syncerd_task.source);
+                            drop(state_guard);
+                            if let Some(ready) = ready {
                                 transaction_broadcast_tx
-                                    .send((task, syncerd_task.source))
+                                    .send(ready)
                                     .await
                                     .expect("failed on transaction_broadcast_tx sender");
                             }
@@ -705,6 +710,21 @@ async fn run_syncerd_task_receiver(
                             state_guard.watch_transaction(task, syncerd_task.source);
                             drop(state_guard);
                         }
+                        Task::WatchMempool(task) => {
+                            debug!(
+                                "received new watch mempool task for address: {}",
+                                task.address
+                            );
+                            let mut state_guard = state.lock().await;
+                            state_guard.watch_mempool(task, syncerd_task.source);
+                            drop(state_guard);
+                        }
+                        Task::WatchXmrTransaction(_) => {
+                            error!("watch xmr transaction not supported by bitcoin syncer");
+                        }
+                        Task::GetXmrTx(_) => {
+                            error!("get xmr tx not supported by bitcoin syncer");
+                        }
                         Task::Terminate => {
                             debug!("terminating async syncer runtime");
                             terminate_tx
@@ -714,6 +734,7 @@ async fn run_syncerd_task_receiver(
                         }
                         Task::HealthCheck(HealthCheck { id }) => {
                             debug!("performing health check");
+                            state.lock().await.health_check_started(id);
                             let health =
                                 match ElectrumRpc::new(&electrum_server, proxy_address.clone())
                                     .and_then(|client| {
@@ -884,30 +905,14 @@ fn height_polling(
 
                 // if the blocks changed, check pending broadcasts and query transactions
                 if block_change {
-                    let state_guard = state.lock().await;
-                    let height = state_guard.block_height();
-                    let pending_broadcasts: HashSet<(BroadcastTransaction, ServiceId)> =
-                        state_guard
-                            .pending_broadcasts
-                            .iter()
-                            .filter(|(task, _)| {
-                                if let Some(after_height) = task.broadcast_after_height {
-                                    after_height < height
-                                } else {
-                                    false
-                                }
-                            })
-                            .cloned()
-                            .collect();
+                    let mut state_guard = state.lock().await;
+                    let ready_broadcasts = state_guard.take_ready_broadcasts();
                     drop(state_guard);
-                    for pending in pending_broadcasts {
+                    for pending in ready_broadcasts {
                         // Do not re-try sending pending broadcasts
-                        if let Err(err) = transaction_broadcast_tx.send(pending.clone()).await {
+                        if let Err(err) = transaction_broadcast_tx.send(pending).await {
                             error!("error sending through transaction_broadcast_tx {}", err);
                         }
-                        let mut state_guard = state.lock().await;
-                        state_guard.pending_broadcasts.remove(&pending);
-                        drop(state_guard);
                     }
                     rpc.query_transactions(Arc::clone(&state), false).await;
                 }
@@ -1044,6 +1049,117 @@ impl GenericEstimateFee for Client {
     }
 }
 
+/// Query electrum for the virtual size of the node's mempool, summing its fee histogram, and for
+/// the node's minimum relay fee in sat/kvB.
+fn query_mempool_info(client: &Client) -> Result<(u64, u64), electrum_client::Error> {
+    let histogram = client.raw_call("mempool.get_fee_histogram", vec![])?;
+    let mempool_bytes = histogram
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| entry.get(1).and_then(|vsize| vsize.as_u64()))
+                .sum()
+        })
+        .unwrap_or(0);
+    let min_relay_fee_sat_per_kvb = (client.relay_fee()? * 1.0e8).ceil() as u64;
+    Ok((mempool_bytes, min_relay_fee_sat_per_kvb))
+}
+
+fn mempool_info_polling(
+    electrum_server: String,
+    proxy_address: Option<String>,
+    state: Arc<Mutex<SyncerState>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::task::spawn(async move {
+        loop {
+            debug!("creating mempool info polling electrum client");
+            if let Ok(client) = create_electrum_client(&electrum_server, proxy_address.clone()) {
+                loop {
+                    match query_mempool_info(&client) {
+                        Ok((mempool_bytes, min_relay_fee_sat_per_kvb)) => {
+                            let mut state_guard = state.lock().await;
+                            state_guard
+                                .mempool_info_updated(MempoolInfo {
+                                    id: TaskId(0),
+                                    mempool_bytes,
+                                    min_relay_fee_sat_per_kvb,
+                                    // electrum does not expose the number of mempool transactions
+                                    mempool_tx_count: 0,
+                                })
+                                .await;
+                            drop(state_guard);
+                        }
+                        Err(err) => {
+                            error!("Failed to retrieve mempool info: {}", err);
+                            break;
+                        }
+                    }
+                    tokio::time::sleep(std::time::Duration::from_secs(600)).await;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(20)).await;
+        }
+    })
+}
+
+/// Subscribe to the `rawtx` ZMQ notifications of the Bitcoin node and feed every transaction
+/// entering the mempool to the syncer state. Electrum does not notify unconfirmed transactions
+/// in time, so zero-confirmation detection is only available with a configured ZMQ endpoint.
+fn mempool_watching(
+    state: Arc<Mutex<SyncerState>>,
+    zmq_rawtx: Option<String>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let zmq_rawtx = match zmq_rawtx {
+            Some(zmq_rawtx) => zmq_rawtx,
+            None => {
+                debug!("No bitcoin zmq rawtx endpoint configured, mempool watching disabled");
+                return;
+            }
+        };
+        loop {
+            debug!("subscribing to bitcoin zmq rawtx endpoint {}", zmq_rawtx);
+            let subscriber = microservices::ZMQ_CONTEXT
+                .socket(zmq::SUB)
+                .and_then(|socket| {
+                    socket.connect(&zmq_rawtx)?;
+                    socket.set_subscribe(b"rawtx")?;
+                    Ok(socket)
+                });
+            match subscriber {
+                Ok(subscriber) => loop {
+                    match subscriber.recv_multipart(zmq::DONTWAIT) {
+                        Ok(parts) => {
+                            let tx = parts.get(1).and_then(|raw_tx| {
+                                bitcoin::consensus::deserialize::<bitcoin::Transaction>(raw_tx).ok()
+                            });
+                            if let Some(tx) = tx {
+                                let mut state_guard = state.lock().await;
+                                state_guard.mempool_transaction(&tx).await;
+                                drop(state_guard);
+                            } else {
+                                warn!("Failed to decode bitcoin zmq rawtx notification");
+                            }
+                        }
+                        Err(zmq::Error::EAGAIN) => {
+                            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                        }
+                        Err(err) => {
+                            error!("Bitcoin zmq rawtx subscription failed: {}", err);
+                            break;
+                        }
+                    }
+                },
+                Err(err) => {
+                    error!("Failed to subscribe to bitcoin zmq rawtx endpoint: {}", err);
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(20)).await;
+        }
+    })
+}
+
 fn estimate_fee_polling(
     electrum_server: String,
     proxy_address: Option<String>,
@@ -1293,6 +1409,7 @@ impl Synclet for BitcoinSyncer {
 
         if let Some(electrum_server) = &opts.electrum_server {
             let electrum_server = electrum_server.clone();
+            let bitcoin_zmq_rawtx = opts.bitcoin_zmq_rawtx.clone();
             std::thread::spawn(move || {
                 use tokio::runtime::Builder;
                 trace!("building tokio syncer runtime");
@@ -1385,6 +1502,15 @@ impl Synclet for BitcoinSyncer {
                         Arc::clone(&state),
                     );
 
+                    let mempool_info_handle = mempool_info_polling(
+                        electrum_server.clone(),
+                        proxy_address.clone(),
+                        Arc::clone(&state),
+                    );
+
+                    let mempool_watch_handle =
+                        mempool_watching(Arc::clone(&state), bitcoin_zmq_rawtx);
+
                     let sweep_handle = sweep_polling(
                         Arc::clone(&state),
                         electrum_server.clone(),
@@ -1402,6 +1528,8 @@ impl Synclet for BitcoinSyncer {
                         transaction_get_handle,
                         balance_get_handle,
                         estimate_fee_handle,
+                        mempool_info_handle,
+                        mempool_watch_handle,
                         sweep_handle,
                         terminate_handle,
                     );
@@ -7,6 +7,7 @@
 pub mod bitcoin_syncer;
 pub mod monero_syncer;
 pub mod syncer_state;
+pub mod task_store;
 pub mod types;
 
 #[cfg(feature = "shell")]
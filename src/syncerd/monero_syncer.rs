@@ -15,12 +15,14 @@ use crate::syncerd::runtime::Synclet;
 use crate::syncerd::syncer_state::create_set;
 use crate::syncerd::syncer_state::AddressTx;
 use crate::syncerd::syncer_state::SyncerState;
+use crate::syncerd::syncer_state::WatchedTransaction;
 use crate::syncerd::types::{AddressAddendum, SweepAddressAddendum, Task};
 use crate::syncerd::TaskTarget;
 use crate::syncerd::TransactionBroadcasted;
 use crate::syncerd::XmrAddressAddendum;
 use crate::syncerd::{AddressBalance, TxFilter};
 use crate::syncerd::{Event, Health};
+use crate::syncerd::{GetXmrTx, XmrTransactionRetrieved};
 use farcaster_core::blockchain::{Blockchain, Network};
 use internet2::session::LocalSession;
 use internet2::zeromq::ZmqSocketType;
@@ -134,6 +136,42 @@ impl MoneroRpc {
         Ok(transactions)
     }
 
+    /// Retrieve the raw transaction blob, None if the transaction is unknown to the daemon.
+    async fn get_raw_transaction(&mut self, txid: monero::Hash) -> Result<Option<Vec<u8>>, Error> {
+        let txs = self
+            .daemon_rpc
+            .get_transactions(vec![txid], Some(false), Some(false))
+            .await?;
+        match txs.txs.into_iter().flatten().next() {
+            Some(tx) => Ok(Some(
+                hex::decode(tx.as_hex).map_err(|err| SyncerError::MoneroRpc(err.into()))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Decode the total amount a transaction sends to the address of the view pair, None if the
+    /// transaction is unknown to the daemon.
+    async fn get_received_amount(
+        &mut self,
+        txid: monero::Hash,
+        view_pair: &monero::ViewPair,
+    ) -> Result<Option<u64>, Error> {
+        let tx_bytes = match self.get_raw_transaction(txid).await? {
+            Some(tx_bytes) => tx_bytes,
+            None => return Ok(None),
+        };
+        let tx: monero::Transaction = monero::consensus::deserialize(&tx_bytes)
+            .map_err(|err| SyncerError::MoneroRpc(err.into()))?;
+        let amount = tx
+            .check_outputs(view_pair, 0..1, 0..1)
+            .map_err(|err| SyncerError::MoneroRpc(err.into()))?
+            .iter()
+            .filter_map(|out| out.amount())
+            .sum();
+        Ok(Some(amount))
+    }
+
     async fn check_block(&mut self) -> Result<Block, Error> {
         let height = self.get_height().await?;
 
@@ -459,6 +497,34 @@ async fn run_syncerd_task_receiver(
                         Task::GetTx(_) => {
                             error!("get tx not implemented for monero syncer");
                         }
+                        Task::GetXmrTx(GetXmrTx { id, hash }) => {
+                            let tx = if hash.len() == 32 {
+                                let txid = monero::Hash::from_slice(&hash);
+                                let mut rpc = MoneroRpc::new(
+                                    syncer_servers.monero_daemon.clone(),
+                                    proxy_address.clone(),
+                                );
+                                match rpc.get_raw_transaction(txid).await {
+                                    Ok(tx) => tx,
+                                    Err(err) => {
+                                        debug!("Error while retrieving tx {}: {}", txid, err);
+                                        None
+                                    }
+                                }
+                            } else {
+                                error!("Malformed Monero transaction hash: {}", hex::encode(&hash));
+                                None
+                            };
+                            tx_event
+                                .send(BridgeEvent {
+                                    event: Event::XmrTransactionRetrieved(
+                                        XmrTransactionRetrieved { id, tx },
+                                    ),
+                                    source: syncerd_task.source,
+                                })
+                                .await
+                                .expect("error sending xmr transaction retrieved event");
+                        }
                         Task::GetAddressBalance(task) => {
                             balance_get_tx
                                 .send((task, syncerd_task.source))
@@ -468,6 +534,12 @@ async fn run_syncerd_task_receiver(
                         Task::WatchEstimateFee(_) => {
                             error!("estimate fee not implemented for monero syncer");
                         }
+                        Task::GetMempoolInfo(_) => {
+                            error!("mempool info not implemented for monero syncer");
+                        }
+                        Task::WatchMempool(_) => {
+                            error!("watch mempool not supported by monero syncer");
+                        }
                         Task::SweepAddress(task) => match task.addendum.clone() {
                             SweepAddressAddendum::Monero(sweep) => {
                                 let addr = sweep.destination_address;
@@ -523,11 +595,17 @@ async fn run_syncerd_task_receiver(
                             let mut state_guard = state.lock().await;
                             state_guard.watch_transaction(task, syncerd_task.source);
                         }
+                        Task::WatchXmrTransaction(task) => {
+                            debug!("received new watch xmr tx task: {}", task.hash);
+                            let mut state_guard = state.lock().await;
+                            state_guard.watch_xmr_transaction(task, syncerd_task.source);
+                        }
                         Task::Terminate => {
                             debug!("unimplemented");
                         }
                         Task::HealthCheck(HealthCheck { id }) => {
                             debug!("performing health check");
+                            state.lock().await.health_check_started(id);
                             let mut health = match create_rpc_client(
                                 syncer_servers.monero_daemon.clone(),
                                 proxy_address.clone(),
@@ -663,7 +741,14 @@ fn address_polling(
                     } else {
                         // we cannot parallelize polling here, since we have to open and close the
                         // wallet
-                        match rpc
+                        if !watched_address.initial_check_done {
+                            let mut state_guard = state.lock().await;
+                            state_guard
+                                .scan_progress(id, address_addendum.from_height)
+                                .await;
+                            drop(state_guard);
+                        }
+                        let address_transactions = match rpc
                             .check_address(
                                 address_addendum.clone(),
                                 Arc::clone(&wallet_mutex),
@@ -677,7 +762,14 @@ fn address_polling(
                                 error!("error polling addresses: {}", err);
                                 None
                             }
+                        };
+                        if !watched_address.initial_check_done && address_transactions.is_some() {
+                            let mut state_guard = state.lock().await;
+                            let height = state_guard.block_height();
+                            state_guard.scan_progress(id, height).await;
+                            drop(state_guard);
                         }
+                        address_transactions
                     };
                 if let Some(address_transactions) = address_transactions {
                     let mut state_guard = state.lock().await;
@@ -719,6 +811,7 @@ fn height_polling(
                 drop(state_guard);
 
                 if !transactions.is_empty() {
+                    let view_pairs = undecoded_view_pairs(transactions.values());
                     let tx_ids: Vec<monero::Hash> = transactions
                         .drain()
                         .filter_map(|(_, tx)| {
@@ -738,7 +831,12 @@ fn height_polling(
                             error!("polling transactions error: {}", err);
                         }
                     }
+                    let amounts =
+                        decode_received_amounts(&mut rpc, &view_pairs, &polled_transactions).await;
                     let mut state_guard = state.lock().await;
+                    for (tx_id, amount) in amounts {
+                        state_guard.set_transaction_amount(tx_id.into(), amount);
+                    }
                     for tx in polled_transactions.drain(..) {
                         state_guard
                             .change_transaction(
@@ -802,6 +900,44 @@ fn sweep_polling(
     })
 }
 
+/// View pairs of the watched transactions whose received amount still has to be decoded
+fn undecoded_view_pairs<'a>(
+    transactions: impl Iterator<Item = &'a WatchedTransaction>,
+) -> HashMap<monero::Hash, monero::ViewPair> {
+    transactions
+        .filter_map(|tx| match (tx.task.hash, tx.view_pair) {
+            (Txid::Monero(txid), Some(view_pair))
+                if tx.transaction_confirmations.amount.is_none() =>
+            {
+                Some((txid, view_pair))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decode the received amounts of the polled transactions seen by the daemon
+async fn decode_received_amounts(
+    rpc: &mut MoneroRpc,
+    view_pairs: &HashMap<monero::Hash, monero::ViewPair>,
+    polled_transactions: &[Transaction],
+) -> Vec<(monero::Hash, u64)> {
+    let mut amounts = vec![];
+    for tx in polled_transactions.iter() {
+        if tx.confirmations.is_none() {
+            continue;
+        }
+        if let Some(view_pair) = view_pairs.get(&tx.tx_id) {
+            match rpc.get_received_amount(tx.tx_id, view_pair).await {
+                Ok(Some(amount)) => amounts.push((tx.tx_id, amount)),
+                Ok(None) => {}
+                Err(err) => error!("error decoding received amount: {}", err),
+            }
+        }
+    }
+    amounts
+}
+
 fn unseen_transaction_polling(
     state: Arc<Mutex<SyncerState>>,
     syncer_servers: MoneroSyncerServers,
@@ -825,6 +961,11 @@ fn unseen_transaction_polling(
                         }
                     })
                     .collect();
+                let view_pairs = undecoded_view_pairs(
+                    unseen_transactions
+                        .iter()
+                        .filter_map(|id| transactions.get(id)),
+                );
                 drop(state_guard);
 
                 let mut polled_transactions = vec![];
@@ -836,7 +977,12 @@ fn unseen_transaction_polling(
                         error!("polling unseen transactions error: {}", err);
                     }
                 }
+                let amounts =
+                    decode_received_amounts(&mut rpc, &view_pairs, &polled_transactions).await;
                 let mut state_guard = state.lock().await;
+                for (tx_id, amount) in amounts {
+                    state_guard.set_transaction_amount(tx_id.into(), amount);
+                }
                 for tx in polled_transactions.drain(..) {
                     state_guard
                         .change_transaction(
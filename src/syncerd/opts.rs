@@ -5,6 +5,7 @@
 // https://opensource.org/licenses/MIT.
 
 use farcaster_core::blockchain::{Blockchain, Network};
+use std::path::PathBuf;
 use std::str::FromStr;
 
 /// Syncer blockchain management daemon; part of Farcaster Node
@@ -38,6 +39,10 @@ pub struct Opts {
     #[clap(long)]
     pub electrum_server: Option<String>,
 
+    /// Bitcoin node ZMQ rawtx endpoint used by Bitcoin syncers to watch the mempool
+    #[clap(long)]
+    pub bitcoin_zmq_rawtx: Option<String>,
+
     /// Monero daemon to use for Monero syncers
     #[clap(long)]
     pub monero_daemon: Option<String>,
@@ -53,6 +58,15 @@ pub struct Opts {
     /// Wallet directory use by the monero-wallet-rpc
     #[clap(long)]
     pub monero_wallet_dir_path: Option<String>,
+
+    /// High-water mark of the internal ZMQ sockets, 0 for unlimited
+    #[clap(long, default_value = "0")]
+    pub zmq_hwm: i32,
+
+    /// Database directory where the syncer persists its tasks across restarts, tasks are only
+    /// held in memory if not set
+    #[clap(long)]
+    pub task_store: Option<PathBuf>,
 }
 
 impl Opts {
@@ -6,7 +6,7 @@
 
 use crate::bus::{
     ctl::CtlMsg,
-    info::{InfoMsg, SyncerInfo},
+    info::{InfoMsg, SyncerInfo, TaskInfo},
     sync::SyncMsg,
     BusMsg, ServiceBus,
 };
@@ -14,11 +14,12 @@ use crate::service::Endpoints;
 use crate::syncerd::bitcoin_syncer::BitcoinSyncer;
 use crate::syncerd::monero_syncer::MoneroSyncer;
 use crate::syncerd::opts::Opts;
+use crate::syncerd::task_store::{InMemoryTaskStore, SledTaskStore, TaskStore};
 use crate::syncerd::*;
 use crate::CtlServer;
 use crate::{Error, LogStyle, Service, ServiceConfig, ServiceId};
 
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
 use std::time::{Duration, SystemTime};
@@ -58,20 +59,36 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
     info!("Creating new {} ({}) syncer", &blockchain, &network);
     let (tx, rx): (Sender<SyncerdTask>, Receiver<SyncerdTask>) = std::sync::mpsc::channel();
 
-    let tx_event = ZMQ_CONTEXT.socket(zmq::PAIR)?;
-    let rx_event = ZMQ_CONTEXT.socket(zmq::PAIR)?;
-    rx_event.bind("inproc://syncerdbridge")?;
-    tx_event.connect("inproc://syncerdbridge")?;
+    let (tx_event, rx_event) = bridge_sockets(opts.zmq_hwm)?;
 
     let syncer: Box<dyn Synclet> = match blockchain {
         Blockchain::Monero => Box::new(MoneroSyncer::new()),
         Blockchain::Bitcoin => Box::new(BitcoinSyncer::new()),
     };
 
+    let tasks: Box<dyn TaskStore> = match &opts.task_store {
+        Some(path) => Box::new(SledTaskStore::open(path)?),
+        None => Box::new(InMemoryTaskStore::default()),
+    };
+    // the sources of the tasks received before a restart may be gone, their tasks are only
+    // re-subscribed once they contact the syncer again
+    let mut restored: HashMap<ServiceId, Vec<SyncerdTask>> = none!();
+    for (task, _) in tasks.iter() {
+        restored.entry(task.source.clone()).or_default().push(task);
+    }
+    if !restored.is_empty() {
+        info!(
+            "Restored the tasks of {} services from the task store",
+            restored.len()
+        );
+    }
+
     let mut runtime = Runtime {
         identity: ServiceId::Syncer(blockchain, network),
         started: SystemTime::now(),
-        tasks: none!(),
+        tasks,
+        restored,
+        height: 0,
         syncer,
         tx,
     };
@@ -84,11 +101,30 @@ pub fn run(config: ServiceConfig, opts: Opts) -> Result<(), Error> {
     unreachable!()
 }
 
+/// Pair of sockets bridging the events of the syncer thread to the service, with the high-water
+/// mark set before they are connected
+fn bridge_sockets(hwm: i32) -> Result<(zmq::Socket, zmq::Socket), Error> {
+    let tx_event = ZMQ_CONTEXT.socket(zmq::PAIR)?;
+    let rx_event = ZMQ_CONTEXT.socket(zmq::PAIR)?;
+    for socket in [&tx_event, &rx_event] {
+        socket.set_rcvhwm(hwm)?;
+        socket.set_sndhwm(hwm)?;
+    }
+    rx_event.bind("inproc://syncerdbridge")?;
+    tx_event.connect("inproc://syncerdbridge")?;
+    Ok((tx_event, rx_event))
+}
+
 pub struct Runtime {
     identity: ServiceId,
     syncer: Box<dyn Synclet>,
     started: SystemTime,
-    tasks: HashSet<SyncerdTask>,
+    /// Tasks with the height of the blockchain when each task was received
+    tasks: Box<dyn TaskStore>,
+    /// Tasks restored from the store, by source, not yet re-subscribed
+    restored: HashMap<ServiceId, Vec<SyncerdTask>>,
+    /// Latest height reported by the syncer to its clients
+    height: u64,
     tx: Sender<SyncerdTask>,
 }
 
@@ -176,6 +212,7 @@ impl Runtime {
     ) -> Result<(), Error> {
         match request {
             InfoMsg::GetInfo => {
+                let tasks: Vec<SyncerdTask> = self.tasks.iter().map(|(t, _)| t).collect();
                 self.send_client_info(
                     endpoints,
                     source,
@@ -189,16 +226,38 @@ impl Runtime {
                             .duration_since(SystemTime::UNIX_EPOCH)
                             .unwrap_or_else(|_| Duration::from_secs(0))
                             .as_secs(),
-                        tasks: self.tasks.iter().cloned().collect(),
+                        task_counts: count_task_kinds(tasks.iter().map(|t| &t.task))
+                            .into_iter()
+                            .collect(),
+                        total_tasks: tasks.len(),
+                        tasks,
                     }),
                 )?;
             }
 
             InfoMsg::ListTasks => {
+                let blockchain = match self.identity {
+                    ServiceId::Syncer(blockchain, _) => blockchain,
+                    _ => unreachable!("a syncer has a syncer identity"),
+                };
+                let mut tasks: Vec<TaskInfo> = self
+                    .tasks
+                    .iter()
+                    .filter_map(|(t, created_at_height)| {
+                        Some(TaskInfo {
+                            id: t.task.id()?,
+                            kind: TaskKind::from(&t.task),
+                            blockchain,
+                            created_at_height,
+                            expires_at_height: t.task.lifetime().unwrap_or(u64::MAX),
+                        })
+                    })
+                    .collect();
+                tasks.sort_by_key(|info| info.id);
                 self.send_client_info(
                     endpoints,
                     source,
-                    InfoMsg::TaskList(self.tasks.iter().cloned().collect()),
+                    InfoMsg::TaskList(tasks.into_iter().collect()),
                 )?;
             }
 
@@ -219,7 +278,13 @@ impl Runtime {
         match request {
             SyncMsg::Task(task) => {
                 let t = SyncerdTask { task, source };
-                self.tasks.insert(t.clone());
+                if let Task::Abort(Abort { task_target, .. }) = &t.task {
+                    self.remove_aborted_tasks(&t.source, task_target)?;
+                }
+                self.resubscribe_restored_tasks(&t);
+                if !matches!(t.task, Task::Abort(_)) && t.task.id().is_some() {
+                    self.tasks.insert(t.clone(), self.height)?;
+                }
                 match self.tx.send(t) {
                     Ok(()) => trace!("Task successfully sent to syncer runtime"),
                     Err(e) => error!("Failed to send task with error: {}", e.to_string()),
@@ -234,6 +299,93 @@ impl Runtime {
         Ok(())
     }
 
+    /// Removes from the store the tasks of the source targeted by an abort task
+    fn remove_aborted_tasks(
+        &mut self,
+        source: &ServiceId,
+        task_target: &TaskTarget,
+    ) -> Result<(), Error> {
+        let aborted: Vec<SyncerdTask> = self
+            .tasks
+            .iter()
+            .map(|(t, _)| t)
+            .filter(|t| {
+                &t.source == source
+                    && match task_target {
+                        TaskTarget::TaskId(id) => t.task.id() == Some(*id),
+                        TaskTarget::AllTasks => true,
+                    }
+            })
+            .collect();
+        for t in aborted.iter() {
+            self.tasks.remove(t)?;
+        }
+        if let Some(restored) = self.restored.get_mut(source) {
+            restored.retain(|t| !aborted.contains(t));
+        }
+        Ok(())
+    }
+
+    /// Re-subscribes the synclet to the restored tasks of the source of the task, the source is
+    /// alive again. A restored task with the id of the task is not re-subscribed, the source sends
+    /// it anew.
+    fn resubscribe_restored_tasks(&mut self, task: &SyncerdTask) {
+        let restored = match self.restored.remove(&task.source) {
+            Some(restored) => restored,
+            None => return,
+        };
+        for t in restored {
+            let expired = t
+                .task
+                .lifetime()
+                .map_or(false, |lifetime| lifetime < self.height);
+            if expired || (task.task.id().is_some() && t.task.id() == task.task.id()) {
+                continue;
+            }
+            match self.tx.send(t) {
+                Ok(()) => trace!("Restored task successfully sent to syncer runtime"),
+                Err(e) => error!("Failed to send restored task with error: {}", e.to_string()),
+            }
+        }
+    }
+
+    /// Removes from the store the tasks whose lifetime expired at the height, like the synclet
+    /// drops them
+    fn remove_expired_tasks(&mut self, height: u64) -> Result<(), Error> {
+        let expired: Vec<SyncerdTask> = self
+            .tasks
+            .iter()
+            .map(|(t, _)| t)
+            .filter(|t| {
+                t.task
+                    .lifetime()
+                    .map_or(false, |lifetime| lifetime < height)
+            })
+            .collect();
+        for t in expired.iter() {
+            self.tasks.remove(t)?;
+        }
+        Ok(())
+    }
+
+    /// Removes from the store the one-shot task of the source completed by the event
+    fn remove_completed_task(&mut self, source: &ServiceId, event: &Event) -> Result<(), Error> {
+        let id = match completed_task_id(event) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let completed: Vec<SyncerdTask> = self
+            .tasks
+            .iter()
+            .map(|(t, _)| t)
+            .filter(|t| &t.source == source && t.task.id() == Some(id))
+            .collect();
+        for t in completed.iter() {
+            self.tasks.remove(t)?;
+        }
+        Ok(())
+    }
+
     fn handle_bridge(
         &mut self,
         endpoints: &mut Endpoints,
@@ -243,6 +395,16 @@ impl Runtime {
         debug!("Syncerd BRIDGE RPC request: {}", request);
         match request {
             SyncMsg::BridgeEvent(syncerd_bridge_event) => {
+                if let Event::HeightChanged(HeightChanged { height, .. }) =
+                    syncerd_bridge_event.event
+                {
+                    self.height = std::cmp::max(self.height, height);
+                    self.remove_expired_tasks(self.height)?;
+                }
+                self.remove_completed_task(
+                    &syncerd_bridge_event.source,
+                    &syncerd_bridge_event.event,
+                )?;
                 endpoints.send_to(
                     ServiceBus::Sync,
                     self.identity(),
@@ -258,3 +420,46 @@ impl Runtime {
         Ok(())
     }
 }
+
+/// Id of the one-shot task whose result is reported by the event, the task is then done
+fn completed_task_id(event: &Event) -> Option<TaskId> {
+    match event {
+        Event::TransactionBroadcasted(TransactionBroadcasted { id, .. })
+        | Event::SweepSuccess(SweepSuccess { id, .. })
+        | Event::TransactionRetrieved(TransactionRetrieved { id, .. })
+        | Event::XmrTransactionRetrieved(XmrTransactionRetrieved { id, .. })
+        | Event::HealthResult(HealthResult { id, .. })
+        | Event::AddressBalance(AddressBalance { id, .. }) => Some(*id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bridge_sockets_use_the_high_water_mark() {
+        let (tx_event, rx_event) = bridge_sockets(2).unwrap();
+        for socket in [&tx_event, &rx_event] {
+            assert_eq!(socket.get_rcvhwm().unwrap(), 2);
+            assert_eq!(socket.get_sndhwm().unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn one_shot_task_results_complete_their_task() {
+        let broadcasted = Event::TransactionBroadcasted(TransactionBroadcasted {
+            id: TaskId(3),
+            tx: vec![],
+            error: None,
+        });
+        assert_eq!(completed_task_id(&broadcasted), Some(TaskId(3)));
+        let height_changed = Event::HeightChanged(HeightChanged {
+            id: TaskId(4),
+            block: vec![],
+            height: 10,
+        });
+        assert_eq!(completed_task_id(&height_changed), None);
+    }
+}
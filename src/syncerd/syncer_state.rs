@@ -10,6 +10,7 @@ use crate::Error;
 use crate::ServiceId;
 use farcaster_core::blockchain::Blockchain;
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use tokio::sync::mpsc::Sender as TokioSender;
 
 use crate::service::LogStyle;
@@ -47,6 +48,8 @@ pub struct SyncerState {
     tasks_sources: HashMap<InternalId, ServiceId>,
     watch_height: HashMap<InternalId, WatchHeight>,
     watch_fee_estimation: HashMap<InternalId, WatchEstimateFee>,
+    watch_mempool_info: HashMap<InternalId, GetMempoolInfo>,
+    pub mempool_watches: HashMap<InternalId, MempoolWatch>,
     lifetimes: HashMap<u64, HashSet<InternalId>>,
     pub addresses: HashMap<InternalId, AddressTransactions>,
     pub transactions: HashMap<InternalId, WatchedTransaction>,
@@ -56,13 +59,27 @@ pub struct SyncerState {
     task_count: TaskCounter,
     pub subscribed_addresses: HashSet<AddressAddendum>,
     pub fee_estimation: Option<FeeEstimations>,
+    pub mempool_info: Option<MempoolInfo>,
     pub pending_broadcasts: HashSet<(BroadcastTransaction, ServiceId)>,
+    /// When the last block was received, to report its age on health checks
+    last_block_at: Option<Instant>,
+    /// When the health checks in progress were started, to measure the backend latency
+    health_checks: HashMap<TaskId, Instant>,
 }
 
 #[derive(Clone, Debug)]
 pub struct WatchedTransaction {
     pub task: WatchTransaction,
     pub transaction_confirmations: TransactionConfirmations,
+    /// Keys used to decode the amount received by a watched Monero transaction
+    pub view_pair: Option<monero::ViewPair>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MempoolWatch {
+    pub task: WatchMempool,
+    /// Mempool transactions already reported for this watch
+    seen_txs: HashSet<bitcoin::Txid>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -93,6 +110,8 @@ impl SyncerState {
             tasks_sources: HashMap::new(),
             watch_height: HashMap::new(),
             watch_fee_estimation: HashMap::new(),
+            watch_mempool_info: HashMap::new(),
+            mempool_watches: HashMap::new(),
             lifetimes: HashMap::new(),
             addresses: HashMap::new(),
             transactions: HashMap::new(),
@@ -103,7 +122,10 @@ impl SyncerState {
             blockchain,
             subscribed_addresses: HashSet::new(),
             fee_estimation: None,
+            mempool_info: None,
             pending_broadcasts: HashSet::new(),
+            last_block_at: None,
+            health_checks: HashMap::new(),
         }
     }
 
@@ -111,6 +133,40 @@ impl SyncerState {
         self.block_height
     }
 
+    /// Returns the broadcast if it can be sent now, otherwise queues it until the chain reaches
+    /// its `broadcast_after_height`
+    pub fn queue_broadcast(
+        &mut self,
+        task: BroadcastTransaction,
+        source: ServiceId,
+    ) -> Option<(BroadcastTransaction, ServiceId)> {
+        match task.broadcast_after_height {
+            Some(height) if height > self.block_height => {
+                self.pending_broadcasts.insert((task, source));
+                None
+            }
+            _ => Some((task, source)),
+        }
+    }
+
+    /// Removes and returns the queued broadcasts whose height has been reached
+    pub fn take_ready_broadcasts(&mut self) -> Vec<(BroadcastTransaction, ServiceId)> {
+        let height = self.block_height;
+        let ready: Vec<(BroadcastTransaction, ServiceId)> = self
+            .pending_broadcasts
+            .iter()
+            .filter(|(task, _)| {
+                task.broadcast_after_height
+                    .map_or(true, |after_height| after_height <= height)
+            })
+            .cloned()
+            .collect();
+        for pending in ready.iter() {
+            self.pending_broadcasts.remove(pending);
+        }
+        ready
+    }
+
     pub async fn abort(
         &mut self,
         task_task_id_or_all_tasks: TaskTarget,
@@ -205,6 +261,61 @@ impl SyncerState {
                 .collect(),
         );
 
+        // check mempool watch tasks
+        let ids: Vec<(InternalId, TaskId)> = self
+            .mempool_watches
+            .iter()
+            .filter_map(|(id, mempool_watch)| {
+                if task_id.is_none() || mempool_watch.task.id == task_id.unwrap() {
+                    Some((*id, mempool_watch.task.id))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        aborted_ids.append(
+            &mut ids
+                .iter()
+                .filter_map(|(internal_id, found_task_id)| {
+                    if let Some(source_id) = self.tasks_sources.get(internal_id) {
+                        if *source_id == source {
+                            self.remove_mempool_watch(internal_id);
+                            return Some(*found_task_id);
+                        }
+                    }
+                    None
+                })
+                .collect(),
+        );
+
+        // check fee estimation tasks
+        let ids: Vec<(InternalId, TaskId)> = self
+            .watch_fee_estimation
+            .iter()
+            .filter_map(|(id, watch_fee_estimation)| {
+                if task_id.is_none() || watch_fee_estimation.id == task_id.unwrap() {
+                    Some((*id, watch_fee_estimation.id))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        aborted_ids.append(
+            &mut ids
+                .iter()
+                .filter_map(|(internal_id, found_task_id)| {
+                    if let Some(source_id) = self.tasks_sources.get(internal_id) {
+                        if *source_id == source {
+                            self.watch_fee_estimation.remove(internal_id);
+                            self.tasks_sources.remove(internal_id);
+                            return Some(*found_task_id);
+                        }
+                    }
+                    None
+                })
+                .collect(),
+        );
+
         // check sweep address tasks
         let ids: Vec<(InternalId, TaskId)> = self
             .sweep_addresses
@@ -327,6 +438,29 @@ impl SyncerState {
     }
 
     pub fn watch_transaction(&mut self, task: WatchTransaction, source: ServiceId) {
+        self.insert_watched_transaction(task, None, source);
+    }
+
+    pub fn watch_xmr_transaction(&mut self, task: WatchXmrTransaction, source: ServiceId) {
+        let view_pair = monero::ViewPair {
+            view: task.view_key,
+            spend: task.spend_key,
+        };
+        let task = WatchTransaction {
+            id: task.id,
+            lifetime: task.lifetime,
+            hash: task.hash,
+            confirmation_bound: task.confirmation_bound,
+        };
+        self.insert_watched_transaction(task, Some(view_pair), source);
+    }
+
+    fn insert_watched_transaction(
+        &mut self,
+        task: WatchTransaction,
+        view_pair: Option<monero::ViewPair>,
+        source: ServiceId,
+    ) {
         // increment the count to use it as a unique internal id
         self.task_count.increment();
 
@@ -344,7 +478,9 @@ impl SyncerState {
                     block: none!(),
                     confirmations: None,
                     tx: vec![],
+                    amount: None,
                 },
+                view_pair,
             },
         );
         self.unseen_transactions.insert(self.task_count.into());
@@ -374,6 +510,81 @@ impl SyncerState {
         }
     }
 
+    pub async fn watch_mempool_info(&mut self, task: GetMempoolInfo, source: ServiceId) {
+        // increment the count to use it as a unique internal id
+        self.task_count.increment();
+        if let Err(e) = self.add_lifetime(task.lifetime, self.task_count.into()) {
+            error!("{}", e);
+            return;
+        }
+        self.watch_mempool_info
+            .insert(self.task_count.into(), task.clone());
+        self.tasks_sources
+            .insert(self.task_count.into(), source.clone());
+
+        // try to emit an event immediately from the cached values
+        if let Some(ref mempool_info) = self.mempool_info {
+            send_event(
+                &self.tx_event,
+                &mut vec![(
+                    Event::MempoolInfo(MempoolInfo {
+                        id: task.id,
+                        ..mempool_info.clone()
+                    }),
+                    source,
+                )],
+            )
+            .await;
+        }
+    }
+
+    pub fn watch_mempool(&mut self, task: WatchMempool, source: ServiceId) {
+        // increment the count to use it as a unique internal id
+        self.task_count.increment();
+        if let Err(e) = self.add_lifetime(task.lifetime, self.task_count.into()) {
+            error!("{}", e);
+            return;
+        }
+        self.tasks_sources.insert(self.task_count.into(), source);
+        self.mempool_watches.insert(
+            self.task_count.into(),
+            MempoolWatch {
+                task,
+                seen_txs: none!(),
+            },
+        );
+    }
+
+    /// Match a transaction entering the mempool against the watched addresses and notify the
+    /// sources of the watches it pays to, each transaction is reported once per watch.
+    pub async fn mempool_transaction(&mut self, tx: &bitcoin::Transaction) {
+        let txid = tx.txid();
+        let mut events: Vec<(Event, ServiceId)> = vec![];
+        for (id, mempool_watch) in self.mempool_watches.iter_mut() {
+            let script_pubkey = mempool_watch.task.address.script_pubkey();
+            let amount: u64 = tx
+                .output
+                .iter()
+                .filter(|output| output.script_pubkey == script_pubkey)
+                .map(|output| output.value)
+                .sum();
+            if amount == 0 || !mempool_watch.seen_txs.insert(txid) {
+                continue;
+            }
+            if let Some(source) = self.tasks_sources.get(id) {
+                events.push((
+                    Event::TransactionSeen(TransactionSeen {
+                        id: mempool_watch.task.id,
+                        hash: Txid::Bitcoin(txid),
+                        amount,
+                    }),
+                    source.clone(),
+                ));
+            }
+        }
+        send_event(&self.tx_event, &mut events).await;
+    }
+
     pub fn sweep_address(&mut self, task: SweepAddress, source: ServiceId) {
         self.task_count.increment();
         if let Some(lifetimes) = self.lifetimes.get_mut(&task.lifetime) {
@@ -388,6 +599,7 @@ impl SyncerState {
     }
     pub async fn change_height(&mut self, new_height: u64, block: Vec<u8>) -> bool {
         if self.block_height != new_height || self.block_hash != block {
+            self.last_block_at = Some(Instant::now());
             self.handle_change_height(new_height, block.clone());
             self.drop_lifetimes();
 
@@ -596,6 +808,7 @@ impl SyncerState {
                                 .chunks(STRICT_ENCODE_MAX_ITEMS.into())
                                 .map(|c| c.to_vec())
                                 .collect(), // chunk as a workaround for the strict encoding length limit
+                            amount: watched_tx.transaction_confirmations.amount,
                         };
                         events.push((
                             Event::TransactionConfirmations(tx_confs.clone()),
@@ -614,6 +827,7 @@ impl SyncerState {
                             WatchedTransaction {
                                 task: watched_tx.task.clone(),
                                 transaction_confirmations,
+                                view_pair: watched_tx.view_pair,
                             },
                         ))
                     }
@@ -624,6 +838,16 @@ impl SyncerState {
         send_event(&self.tx_event, &mut events).await;
     }
 
+    /// Set the decoded amount received by a watched transaction, reported with its next
+    /// confirmations event
+    pub fn set_transaction_amount(&mut self, tx_id: Txid, amount: u64) {
+        for watched_tx in self.transactions.values_mut() {
+            if watched_tx.task.hash == tx_id {
+                watched_tx.transaction_confirmations.amount = Some(amount);
+            }
+        }
+    }
+
     pub async fn success_sweep(&mut self, id: &InternalId, txids: Vec<Txid>) {
         if let Some(sweep_address) = self.sweep_addresses.get(id) {
             send_event(
@@ -673,6 +897,26 @@ impl SyncerState {
         self.drop_lifetimes();
     }
 
+    pub async fn mempool_info_updated(&mut self, mempool_info: MempoolInfo) {
+        self.drop_lifetimes();
+        let mut events: Vec<(Event, ServiceId)> = self
+            .watch_mempool_info
+            .iter()
+            .filter_map(|(id, task)| {
+                let source = self.tasks_sources.get(id)?.clone();
+                Some((
+                    Event::MempoolInfo(MempoolInfo {
+                        id: task.id,
+                        ..mempool_info.clone()
+                    }),
+                    source,
+                ))
+            })
+            .collect();
+        send_event(&self.tx_event, &mut events).await;
+        self.mempool_info = Some(mempool_info);
+    }
+
     pub async fn fail_sweep(&mut self, id: &InternalId) {
         if let Some(sweep_address) = self.sweep_addresses.get(id) {
             send_event(
@@ -736,6 +980,8 @@ impl SyncerState {
                 self.unseen_transactions.remove(task);
                 self.watch_height.remove(task);
                 self.watch_fee_estimation.remove(task);
+                self.watch_mempool_info.remove(task);
+                self.mempool_watches.remove(task);
                 self.sweep_addresses.remove(task);
                 self.tasks_sources.remove(task);
             }
@@ -784,6 +1030,19 @@ impl SyncerState {
         self.tasks_sources.remove(id);
     }
 
+    fn remove_mempool_watch(&mut self, id: &InternalId) {
+        if let Some(mempool_watch) = self.mempool_watches.get(id) {
+            if let Some(ids) = self.lifetimes.get_mut(&mempool_watch.task.lifetime) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.lifetimes.remove(&mempool_watch.task.lifetime);
+                }
+            }
+        }
+        self.mempool_watches.remove(id);
+        self.tasks_sources.remove(id);
+    }
+
     fn remove_sweep_address(&mut self, id: &InternalId) {
         if let Some(sweep_address) = self.sweep_addresses.get(id) {
             if let Some(ids) = self.lifetimes.get_mut(&sweep_address.lifetime) {
@@ -797,7 +1056,62 @@ impl SyncerState {
         self.tasks_sources.remove(id);
     }
 
+    /// Report the progress of the initial blockchain scan of a watched Monero address, from its
+    /// restore height up to the current chain tip.
+    pub async fn scan_progress(&mut self, id: InternalId, scanned_height: u64) {
+        let (task_id, from_height) = match self.addresses.get(&id) {
+            Some(AddressTransactions {
+                task:
+                    WatchAddress {
+                        id,
+                        addendum: AddressAddendum::Monero(addendum),
+                        ..
+                    },
+                ..
+            }) => (*id, addendum.from_height),
+            _ => return,
+        };
+        let target_height = self.block_height.max(scanned_height);
+        let percent = if target_height <= from_height {
+            100
+        } else {
+            (scanned_height.saturating_sub(from_height) * 100 / (target_height - from_height)) as u8
+        };
+        if let Some(source) = self.tasks_sources.get(&id).cloned() {
+            send_event(
+                &self.tx_event,
+                &mut vec![(
+                    Event::ScanProgress(ScanProgress {
+                        id: task_id,
+                        scanned_height,
+                        target_height,
+                        percent,
+                    }),
+                    source,
+                )],
+            )
+            .await;
+        }
+    }
+
+    /// Records the start of a health check, its latency is measured until the result is sent
+    pub fn health_check_started(&mut self, id: TaskId) {
+        self.health_checks.insert(id, Instant::now());
+    }
+
     pub async fn health_result(&mut self, id: TaskId, health: Health, source: ServiceId) {
+        let latency_ms = self
+            .health_checks
+            .remove(&id)
+            .map(|started| started.elapsed().as_millis() as u64)
+            .unwrap_or_default();
+        let health = SyncerHealth {
+            health,
+            latency_ms,
+            last_block_age_secs: self.last_block_at.map(|at| at.elapsed().as_secs()),
+            // neither electrum nor monerod report their peers to the syncer
+            peer_count: None,
+        };
         send_event(
             &self.tx_event,
             &mut vec![(Event::HealthResult(HealthResult { id, health }), source)],
@@ -950,7 +1264,10 @@ async fn syncer_state_addresses() {
         tokio::sync::mpsc::channel(120);
     let mut state = SyncerState::new(event_tx.clone(), Blockchain::Bitcoin);
     let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
-    let addendum = AddressAddendum::Bitcoin(BtcAddressAddendum { address });
+    let addendum = AddressAddendum::Bitcoin(BtcAddressAddendum {
+        address,
+        from_height: None,
+    });
     let address_task = WatchAddress {
         id: TaskId(0),
         lifetime: 1,
@@ -1243,3 +1560,129 @@ async fn syncer_state_height() {
     assert_eq!(state.watch_height.len(), 0);
     assert!(event_rx.try_recv().is_err());
 }
+
+#[tokio::test]
+async fn syncer_state_broadcast_after_height() {
+    use farcaster_core::blockchain::Network;
+    use tokio::sync::mpsc::Receiver as TokioReceiver;
+
+    let (event_tx, _event_rx): (TokioSender<BridgeEvent>, TokioReceiver<BridgeEvent>) =
+        tokio::sync::mpsc::channel(120);
+    let mut state = SyncerState::new(event_tx, Blockchain::Bitcoin);
+    let source = ServiceId::Syncer(Blockchain::Bitcoin, Network::Mainnet);
+    state.change_height(100, vec![1]).await;
+
+    let now = BroadcastTransaction {
+        id: TaskId(0),
+        tx: vec![0],
+        broadcast_after_height: Some(100),
+    };
+    assert_eq!(
+        state.queue_broadcast(now.clone(), source.clone()),
+        Some((now, source.clone()))
+    );
+
+    let later = BroadcastTransaction {
+        id: TaskId(1),
+        tx: vec![1],
+        broadcast_after_height: Some(102),
+    };
+    assert_eq!(state.queue_broadcast(later.clone(), source.clone()), None);
+    assert!(state.take_ready_broadcasts().is_empty());
+    state.change_height(101, vec![2]).await;
+    assert!(state.take_ready_broadcasts().is_empty());
+    state.change_height(102, vec![3]).await;
+    assert_eq!(state.take_ready_broadcasts(), vec![(later, source)]);
+    assert!(state.pending_broadcasts.is_empty());
+}
+
+#[tokio::test]
+async fn syncer_state_mempool() {
+    use farcaster_core::blockchain::Network;
+    use std::str::FromStr;
+    use tokio::sync::mpsc::Receiver as TokioReceiver;
+
+    let (event_tx, mut event_rx): (TokioSender<BridgeEvent>, TokioReceiver<BridgeEvent>) =
+        tokio::sync::mpsc::channel(120);
+    let mut state = SyncerState::new(event_tx.clone(), Blockchain::Bitcoin);
+    let address = bitcoin::Address::from_str("32BkaQeAVcd65Vn7pjEziohf5bCiryNQov").unwrap();
+    let mempool_task = WatchMempool {
+        id: TaskId(0),
+        lifetime: 1,
+        address: address.clone(),
+    };
+    let source1 = ServiceId::Syncer(Blockchain::Bitcoin, Network::Mainnet);
+    let mut tx = bitcoin::Transaction {
+        version: 2,
+        lock_time: 0,
+        input: vec![],
+        output: vec![bitcoin::TxOut {
+            value: 1000,
+            script_pubkey: bitcoin::Script::new(),
+        }],
+    };
+
+    state.watch_mempool(mempool_task, source1.clone());
+    assert_eq!(state.lifetimes.len(), 1);
+    assert_eq!(state.tasks_sources.len(), 1);
+    assert_eq!(state.mempool_watches.len(), 1);
+
+    // transactions not paying to the watched address are ignored
+    state.mempool_transaction(&tx).await;
+    assert!(event_rx.try_recv().is_err());
+
+    tx.output.push(bitcoin::TxOut {
+        value: 5000,
+        script_pubkey: address.script_pubkey(),
+    });
+    state.mempool_transaction(&tx).await;
+    let event = event_rx.try_recv().unwrap().event;
+    assert_eq!(
+        event,
+        Event::TransactionSeen(TransactionSeen {
+            id: TaskId(0),
+            hash: Txid::Bitcoin(tx.txid()),
+            amount: 5000,
+        })
+    );
+
+    // a transaction is only reported once
+    state.mempool_transaction(&tx).await;
+    assert!(event_rx.try_recv().is_err());
+
+    state
+        .abort(TaskTarget::TaskId(TaskId(0)), source1, false)
+        .await;
+    assert_eq!(state.lifetimes.len(), 0);
+    assert_eq!(state.tasks_sources.len(), 0);
+    assert_eq!(state.mempool_watches.len(), 0);
+}
+
+#[tokio::test]
+async fn syncer_state_health_latency() {
+    use farcaster_core::blockchain::Network;
+    use tokio::sync::mpsc::Receiver as TokioReceiver;
+
+    let (event_tx, mut event_rx): (TokioSender<BridgeEvent>, TokioReceiver<BridgeEvent>) =
+        tokio::sync::mpsc::channel(120);
+    let mut state = SyncerState::new(event_tx, Blockchain::Bitcoin);
+    let source = ServiceId::Syncer(Blockchain::Bitcoin, Network::Mainnet);
+    state.change_height(1, vec![1]).await;
+
+    // a slow backend answers the health check after 50ms
+    state.health_check_started(TaskId(0));
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    state
+        .health_result(TaskId(0), Health::Healthy, source)
+        .await;
+    match event_rx.try_recv().map(|bridge_event| bridge_event.event) {
+        Ok(Event::HealthResult(HealthResult { id, health })) => {
+            assert_eq!(id, TaskId(0));
+            assert_eq!(health.health, Health::Healthy);
+            assert!(health.latency_ms >= 50);
+            assert_eq!(health.last_block_age_secs, Some(0));
+            assert_eq!(health.peer_count, None);
+        }
+        other => panic!("expected a health result, got {:?}", other),
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2020-2022 Farcaster Devs & LNP/BP Standards Association
+//
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::Cursor;
+use std::path::Path;
+
+use strict_encoding::{StrictDecode, StrictEncode};
+
+use crate::syncerd::runtime::SyncerdTask;
+use crate::Error;
+
+/// Storage of the tasks received by a syncer, with the height of the blockchain when each task
+/// was received
+pub trait TaskStore {
+    /// Stores the task, keeps the height of a task already stored
+    fn insert(&mut self, task: SyncerdTask, height: u64) -> Result<(), Error>;
+
+    /// Removes the task, does nothing if the task is not stored
+    fn remove(&mut self, task: &SyncerdTask) -> Result<(), Error>;
+
+    /// Iterates over the stored tasks and their reception heights
+    fn iter(&self) -> Box<dyn Iterator<Item = (SyncerdTask, u64)> + '_>;
+}
+
+/// Task store held in memory, the tasks are lost when the syncer stops
+#[derive(Debug, Default)]
+pub struct InMemoryTaskStore(HashMap<SyncerdTask, u64>);
+
+impl TaskStore for InMemoryTaskStore {
+    fn insert(&mut self, task: SyncerdTask, height: u64) -> Result<(), Error> {
+        self.0.entry(task).or_insert(height);
+        Ok(())
+    }
+
+    fn remove(&mut self, task: &SyncerdTask) -> Result<(), Error> {
+        self.0.remove(task);
+        Ok(())
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (SyncerdTask, u64)> + '_> {
+        Box::new(self.0.iter().map(|(task, height)| (task.clone(), *height)))
+    }
+}
+
+/// Task store persisted in a sled database, the tasks survive a restart of the syncer
+pub struct SledTaskStore(sled::Db);
+
+impl SledTaskStore {
+    pub fn open(path: &Path) -> Result<SledTaskStore, Error> {
+        Ok(SledTaskStore(sled::open(path)?))
+    }
+}
+
+impl TaskStore for SledTaskStore {
+    fn insert(&mut self, task: SyncerdTask, height: u64) -> Result<(), Error> {
+        let mut key = vec![];
+        task.strict_encode(&mut key)?;
+        if !self.0.contains_key(&key)? {
+            self.0.insert(key, &height.to_be_bytes())?;
+            self.0.flush()?;
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, task: &SyncerdTask) -> Result<(), Error> {
+        let mut key = vec![];
+        task.strict_encode(&mut key)?;
+        if self.0.remove(key)?.is_some() {
+            self.0.flush()?;
+        }
+        Ok(())
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (SyncerdTask, u64)> + '_> {
+        Box::new(self.0.iter().filter_map(|entry| {
+            let (key, val) = entry
+                .map_err(|err| warn!("Failed to read a stored task: {}", err))
+                .ok()?;
+            let task = SyncerdTask::strict_decode(Cursor::new(key.to_vec()))
+                .map_err(|err| warn!("Failed to decode a stored task: {}", err))
+                .ok()?;
+            let height = u64::from_be_bytes(val.as_ref().try_into().ok()?);
+            Some((task, height))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncerd::{Task, TaskId, WatchHeight};
+    use crate::ServiceId;
+    use farcaster_core::blockchain::{Blockchain, Network};
+    use farcaster_core::Uuid;
+
+    fn watch_height(id: u32) -> SyncerdTask {
+        SyncerdTask {
+            task: Task::WatchHeight(WatchHeight {
+                id: TaskId(id),
+                lifetime: 100,
+            }),
+            source: ServiceId::Syncer(Blockchain::Bitcoin, Network::Local),
+        }
+    }
+
+    #[test]
+    fn sled_task_store_restores_tasks_after_restart() {
+        let path = std::env::temp_dir().join(format!("farcaster-syncer-tasks-{}", Uuid::new()));
+        {
+            let mut store = SledTaskStore::open(&path).unwrap();
+            store.insert(watch_height(0), 10).unwrap();
+            store.insert(watch_height(1), 11).unwrap();
+            store.insert(watch_height(2), 12).unwrap();
+            store.insert(watch_height(1), 15).unwrap();
+            store.remove(&watch_height(2)).unwrap();
+        }
+
+        let store = SledTaskStore::open(&path).unwrap();
+        let mut tasks: Vec<_> = store.iter().collect();
+        tasks.sort_by_key(|(_, height)| *height);
+        assert_eq!(tasks, vec![(watch_height(0), 10), (watch_height(1), 11)]);
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -4,6 +4,7 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use std::collections::HashMap;
 use std::fmt;
 
 use bitcoin::consensus::Decodable;
@@ -50,6 +51,9 @@ pub enum AddressAddendum {
 pub struct BtcAddressAddendum {
     /// The address to be watched.
     pub address: bitcoin::Address,
+    /// Ignore transactions confirmed below this height, the whole address history is scanned if
+    /// None.
+    pub from_height: Option<u64>,
 }
 
 #[cfg_attr(feature = "serde", serde_as)]
@@ -177,6 +181,20 @@ pub struct WatchAddress {
     pub filter: TxFilter,
 }
 
+/// Watch the Bitcoin node mempool for unconfirmed transactions paying to an address
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display("WatchMempool(id: {id}, lifetime: {lifetime}, address: {address})")]
+pub struct WatchMempool {
+    pub id: TaskId,
+    pub lifetime: u64,
+    pub address: bitcoin::Address,
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[cfg_attr(
     feature = "serde",
@@ -204,6 +222,27 @@ pub struct WatchTransaction {
     pub confirmation_bound: u32,
 }
 
+/// Watch a Monero transaction and decode the amount it sends to the address made of the spend
+/// public key and the view private key.
+#[cfg_attr(feature = "serde", serde_as)]
+#[derive(Clone, Display, Debug, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display("WatchXmrTransaction(id: {id}, lifetime: {lifetime}, hash: {hash}, confirmation_bound: {confirmation_bound})")]
+pub struct WatchXmrTransaction {
+    pub id: TaskId,
+    pub lifetime: u64,
+    pub hash: Txid,
+    pub confirmation_bound: u32,
+    #[serde_as(as = "DisplayFromStr")]
+    pub spend_key: monero::PublicKey,
+    #[serde_as(as = "DisplayFromStr")]
+    pub view_key: monero::PrivateKey,
+}
+
 #[derive(Clone, Debug, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[cfg_attr(
     feature = "serde",
@@ -244,6 +283,18 @@ pub struct GetTx {
     pub hash: Txid,
 }
 
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(Debug)]
+pub struct GetXmrTx {
+    pub id: TaskId,
+    pub hash: Vec<u8>,
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[cfg_attr(
     feature = "serde",
@@ -256,6 +307,18 @@ pub struct WatchEstimateFee {
     pub lifetime: u64,
 }
 
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(Debug)]
+pub struct GetMempoolInfo {
+    pub id: TaskId,
+    pub lifetime: u64,
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[cfg_attr(
     feature = "serde",
@@ -295,23 +358,148 @@ pub enum Task {
     #[display("{0}")]
     WatchAddress(WatchAddress),
     #[display("{0}")]
+    WatchMempool(WatchMempool),
+    #[display("{0}")]
     WatchTransaction(WatchTransaction),
     #[display("{0}")]
+    WatchXmrTransaction(WatchXmrTransaction),
+    #[display("{0}")]
     BroadcastTransaction(BroadcastTransaction),
     #[display("{0}")]
     SweepAddress(SweepAddress),
     #[display("{0}")]
     GetTx(GetTx),
     #[display("{0}")]
+    GetXmrTx(GetXmrTx),
+    #[display("{0}")]
     GetAddressBalance(GetAddressBalance),
     #[display("{0}")]
     WatchEstimateFee(WatchEstimateFee),
     #[display("{0}")]
+    GetMempoolInfo(GetMempoolInfo),
+    #[display("{0}")]
     HealthCheck(HealthCheck),
     #[display("Terminate")]
     Terminate,
 }
 
+impl Task {
+    /// Name of the message variant
+    pub fn name(&self) -> &'static str {
+        match self {
+            Task::Abort(..) => "Abort",
+            Task::WatchHeight(..) => "WatchHeight",
+            Task::WatchAddress(..) => "WatchAddress",
+            Task::WatchMempool(..) => "WatchMempool",
+            Task::WatchTransaction(..) => "WatchTransaction",
+            Task::WatchXmrTransaction(..) => "WatchXmrTransaction",
+            Task::BroadcastTransaction(..) => "BroadcastTransaction",
+            Task::SweepAddress(..) => "SweepAddress",
+            Task::GetTx(..) => "GetTx",
+            Task::GetXmrTx(..) => "GetXmrTx",
+            Task::GetAddressBalance(..) => "GetAddressBalance",
+            Task::WatchEstimateFee(..) => "WatchEstimateFee",
+            Task::GetMempoolInfo(..) => "GetMempoolInfo",
+            Task::HealthCheck(..) => "HealthCheck",
+            Task::Terminate => "Terminate",
+        }
+    }
+
+    /// Id of the task, None for the tasks acting on other tasks or on the syncer itself
+    pub fn id(&self) -> Option<TaskId> {
+        match self {
+            Task::WatchHeight(WatchHeight { id, .. })
+            | Task::WatchAddress(WatchAddress { id, .. })
+            | Task::WatchMempool(WatchMempool { id, .. })
+            | Task::WatchTransaction(WatchTransaction { id, .. })
+            | Task::WatchXmrTransaction(WatchXmrTransaction { id, .. })
+            | Task::BroadcastTransaction(BroadcastTransaction { id, .. })
+            | Task::SweepAddress(SweepAddress { id, .. })
+            | Task::GetTx(GetTx { id, .. })
+            | Task::GetXmrTx(GetXmrTx { id, .. })
+            | Task::GetAddressBalance(GetAddressBalance { id, .. })
+            | Task::WatchEstimateFee(WatchEstimateFee { id, .. })
+            | Task::GetMempoolInfo(GetMempoolInfo { id, .. })
+            | Task::HealthCheck(HealthCheck { id }) => Some(*id),
+            Task::Abort(_) | Task::Terminate => None,
+        }
+    }
+
+    /// Height after which the syncer drops the task, None for the tasks handled once
+    pub fn lifetime(&self) -> Option<u64> {
+        match self {
+            Task::WatchHeight(WatchHeight { lifetime, .. })
+            | Task::WatchAddress(WatchAddress { lifetime, .. })
+            | Task::WatchMempool(WatchMempool { lifetime, .. })
+            | Task::WatchTransaction(WatchTransaction { lifetime, .. })
+            | Task::WatchXmrTransaction(WatchXmrTransaction { lifetime, .. })
+            | Task::SweepAddress(SweepAddress { lifetime, .. })
+            | Task::WatchEstimateFee(WatchEstimateFee { lifetime, .. })
+            | Task::GetMempoolInfo(GetMempoolInfo { lifetime, .. }) => Some(*lifetime),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of a [`Task`], without its parameters, used to report the syncer load per task type
+#[derive(
+    Clone, Copy, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Ord, PartialOrd, Hash,
+)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(Debug)]
+pub enum TaskKind {
+    Abort,
+    WatchHeight,
+    WatchAddress,
+    WatchMempool,
+    WatchTransaction,
+    WatchXmrTransaction,
+    BroadcastTransaction,
+    SweepAddress,
+    GetTx,
+    GetXmrTx,
+    GetAddressBalance,
+    WatchEstimateFee,
+    GetMempoolInfo,
+    HealthCheck,
+    Terminate,
+}
+
+impl From<&Task> for TaskKind {
+    fn from(task: &Task) -> Self {
+        match task {
+            Task::Abort(_) => TaskKind::Abort,
+            Task::WatchHeight(_) => TaskKind::WatchHeight,
+            Task::WatchAddress(_) => TaskKind::WatchAddress,
+            Task::WatchMempool(_) => TaskKind::WatchMempool,
+            Task::WatchTransaction(_) => TaskKind::WatchTransaction,
+            Task::WatchXmrTransaction(_) => TaskKind::WatchXmrTransaction,
+            Task::BroadcastTransaction(_) => TaskKind::BroadcastTransaction,
+            Task::SweepAddress(_) => TaskKind::SweepAddress,
+            Task::GetTx(_) => TaskKind::GetTx,
+            Task::GetXmrTx(_) => TaskKind::GetXmrTx,
+            Task::GetAddressBalance(_) => TaskKind::GetAddressBalance,
+            Task::WatchEstimateFee(_) => TaskKind::WatchEstimateFee,
+            Task::GetMempoolInfo(_) => TaskKind::GetMempoolInfo,
+            Task::HealthCheck(_) => TaskKind::HealthCheck,
+            Task::Terminate => TaskKind::Terminate,
+        }
+    }
+}
+
+/// Count the tasks per kind, kinds without any task are omitted
+pub fn count_task_kinds<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> HashMap<TaskKind, usize> {
+    let mut counts = HashMap::new();
+    for task in tasks {
+        *counts.entry(TaskKind::from(task)).or_insert(0) += 1;
+    }
+    counts
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[display(Debug)]
 pub struct TaskAborted {
@@ -388,6 +576,15 @@ impl fmt::Display for AddressTransaction {
     }
 }
 
+/// Unconfirmed transaction paying to a mempool watched address
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[display("TransactionSeen(id: {id}, hash: {hash}, amount: {amount})")]
+pub struct TransactionSeen {
+    pub id: TaskId,
+    pub hash: Txid,
+    pub amount: u64,
+}
+
 #[derive(Clone, Debug, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 pub struct TransactionConfirmations {
     pub id: TaskId,
@@ -396,16 +593,20 @@ pub struct TransactionConfirmations {
     // for bitcoin with bitcoin::consensus encoding, chunked into chunks with
     // length < 2^16 as a workaround for the strict encoding length limit
     pub tx: Vec<Vec<u8>>,
+    /// Amount received by the watched address, only decoded for Monero transactions watched
+    /// with a view key
+    pub amount: Option<u64>,
 }
 
 impl fmt::Display for TransactionConfirmations {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "TransactionConfirmations(id: {}, block: {}, confirmations: {:?})",
+            "TransactionConfirmations(id: {}, block: {}, confirmations: {:?}, amount: {:?})",
             self.id,
             hex::encode(&self.block),
             self.confirmations,
+            self.amount,
         )
     }
 }
@@ -459,6 +660,15 @@ pub struct TransactionRetrieved {
     pub tx: Option<bitcoin::Transaction>,
 }
 
+/// Raw Monero transaction blob retrieved by the Monero syncer, None if the daemon does not know
+/// the transaction yet
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[display(Debug)]
+pub struct XmrTransactionRetrieved {
+    pub id: TaskId,
+    pub tx: Option<Vec<u8>>,
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[display(Debug)]
 pub struct FeeEstimation {
@@ -476,11 +686,20 @@ pub enum FeeEstimations {
     },
 }
 
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[display(Debug)]
+pub struct MempoolInfo {
+    pub id: TaskId,
+    pub mempool_bytes: u64,
+    pub min_relay_fee_sat_per_kvb: u64,
+    pub mempool_tx_count: u32,
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[display(Debug)]
 pub struct HealthResult {
     pub id: TaskId,
-    pub health: Health,
+    pub health: SyncerHealth,
 }
 
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
@@ -498,6 +717,36 @@ pub enum Health {
     ConfigUnavailable(String),
 }
 
+/// Health of a syncer along with the measurements of its backend
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display(Debug)]
+pub struct SyncerHealth {
+    pub health: Health,
+    /// Round trip time of the health check to the syncer backend
+    pub latency_ms: u64,
+    /// Time since the syncer received the last block, none before the first block
+    pub last_block_age_secs: Option<u64>,
+    /// Number of peers connected to the syncer backend, if the backend reports it
+    pub peer_count: Option<u32>,
+}
+
+impl SyncerHealth {
+    /// Health without measurements, when the syncer could not be reached
+    pub fn unmeasured(health: Health) -> Self {
+        SyncerHealth {
+            health,
+            latency_ms: 0,
+            last_block_age_secs: None,
+            peer_count: None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
 #[display(Debug)]
 // the sats per kvB is because we need u64 for Eq, PartialEq and Hash
@@ -508,6 +757,15 @@ pub struct AddressBalance {
     pub err: Option<String>,
 }
 
+#[derive(Clone, Debug, Display, StrictEncode, StrictDecode, Eq, PartialEq, Hash)]
+#[display(Debug)]
+pub struct ScanProgress {
+    pub id: TaskId,
+    pub scanned_height: u64,
+    pub target_height: u64,
+    pub percent: u8,
+}
+
 /// Events returned by syncers to the daemon to update the blockchain states.
 /// Events are identified with a unique 32-bits integer that match the [`Task`]
 /// id.
@@ -520,6 +778,9 @@ pub enum Event {
     AddressTransaction(AddressTransaction),
     #[display("{0}")]
     TransactionConfirmations(TransactionConfirmations),
+    /// Notify the daemon a transaction paying to a watched address entered the mempool.
+    #[display("{0}")]
+    TransactionSeen(TransactionSeen),
     #[display("{0}")]
     TransactionBroadcasted(TransactionBroadcasted),
     #[display("{0}")]
@@ -532,7 +793,11 @@ pub enum Event {
     #[display("{0}")]
     TransactionRetrieved(TransactionRetrieved),
     #[display("{0}")]
+    XmrTransactionRetrieved(XmrTransactionRetrieved),
+    #[display("{0}")]
     FeeEstimation(FeeEstimation),
+    #[display("{0}")]
+    MempoolInfo(MempoolInfo),
     /// Empty event to signify that a task with a certain id has not produced an initial result
     #[display("{0}")]
     Empty(TaskId),
@@ -540,4 +805,31 @@ pub enum Event {
     HealthResult(HealthResult),
     #[display("{0}")]
     AddressBalance(AddressBalance),
+    /// Notify the daemon about the progress of a blockchain scan started by an address watch
+    /// task with a past `from_height`.
+    #[display("{0}")]
+    ScanProgress(ScanProgress),
+}
+
+impl Event {
+    /// Name of the message variant
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::HeightChanged(..) => "HeightChanged",
+            Event::AddressTransaction(..) => "AddressTransaction",
+            Event::TransactionConfirmations(..) => "TransactionConfirmations",
+            Event::TransactionSeen(..) => "TransactionSeen",
+            Event::TransactionBroadcasted(..) => "TransactionBroadcasted",
+            Event::SweepSuccess(..) => "SweepSuccess",
+            Event::TaskAborted(..) => "TaskAborted",
+            Event::TransactionRetrieved(..) => "TransactionRetrieved",
+            Event::XmrTransactionRetrieved(..) => "XmrTransactionRetrieved",
+            Event::FeeEstimation(..) => "FeeEstimation",
+            Event::MempoolInfo(..) => "MempoolInfo",
+            Event::Empty(..) => "Empty",
+            Event::HealthResult(..) => "HealthResult",
+            Event::AddressBalance(..) => "AddressBalance",
+            Event::ScanProgress(..) => "ScanProgress",
+        }
+    }
 }
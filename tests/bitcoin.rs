@@ -231,9 +231,11 @@ fn bitcoin_syncer_address_test() {
 
     let addendum_1 = AddressAddendum::Bitcoin(BtcAddressAddendum {
         address: address1.clone(),
+        from_height: None,
     });
     let addendum_2 = AddressAddendum::Bitcoin(BtcAddressAddendum {
         address: address2.clone(),
+        from_height: None,
     });
     let watch_address_task_1 = SyncerdTask {
         task: Task::WatchAddress(WatchAddress {
@@ -338,6 +340,7 @@ fn bitcoin_syncer_address_test() {
     let address4 = bitcoin_rpc.get_new_address(None, None).unwrap();
     let addendum_4 = AddressAddendum::Bitcoin(BtcAddressAddendum {
         address: address4.clone(),
+        from_height: None,
     });
     for i in 0..5 {
         tx.send(SyncerdTask {
@@ -1,7 +1,9 @@
+use bitcoincore_rpc::RpcApi;
 use std::time;
 use utils::fc::*;
 
 use farcaster_node::bus::info::MadeDeal;
+use farcaster_node::bus::DealInfo;
 
 #[macro_use]
 extern crate log;
@@ -49,3 +51,65 @@ async fn cli_make_deal() {
     // clean up processes
     kill_all();
 }
+
+#[tokio::test]
+#[ignore]
+async fn cli_make_deal_with_expiry() {
+    let bitcoin_rpc = bitcoin_setup();
+    let (_, data_dir_maker, _, _) = launch_farcasterd_pair().await;
+
+    // Allow some time for the microservices to start and register each other
+    tokio::time::sleep(time::Duration::from_secs(10)).await;
+
+    let mut args = vec![
+        "make",
+        "--btc-addr",
+        "bcrt1q3rc4sm3w9fr6a46n08znfjt7eu2yhhel6j8rsa",
+        "--xmr-addr",
+        "44CpGC77Kn6exUWYCUwfaUYmDeKn7MyRcNPikgeHBCz8M6LXUC3fGCWNMW7UACHyTL6QxzqKxvJbu5o2VESLzCaeNHNUkwv",
+        "--btc-amount",
+        "1 BTC",
+        "--xmr-amount",
+        "100 XMR",
+        "--network",
+        "Local",
+        "--arb-blockchain",
+        "Bitcoin",
+        "--acc-blockchain",
+        "Monero",
+        "--maker-role",
+        "Alice",
+        "--cancel-timelock",
+        "10",
+        "--punish-timelock",
+        "30",
+        "--fee-strategy",
+        "1000 satoshi/kvB",
+        "--deal-expiry",
+        "2",
+    ];
+    args.append(&mut data_dir_maker.iter().map(std::ops::Deref::deref).collect());
+    let _: MadeDeal = cli(args).unwrap();
+
+    // Allow some time for the syncer to report the current height
+    tokio::time::sleep(time::Duration::from_secs(5)).await;
+    let height = bitcoin_rpc.get_block_count().unwrap();
+
+    let mut list_args = vec!["list-deals"];
+    list_args.append(&mut data_dir_maker.iter().map(std::ops::Deref::deref).collect());
+    let deals: Vec<DealInfo> = cli(list_args.clone()).unwrap();
+    assert_eq!(deals.len(), 1);
+    assert_eq!(deals[0].expires_at_block, Some(height + 2));
+    assert!(deals[0].expires_in.is_some());
+
+    // mine past the expiry height, the deal gets revoked
+    bitcoin_rpc
+        .generate_to_address(2, &reusable_btc_address())
+        .unwrap();
+    tokio::time::sleep(time::Duration::from_secs(15)).await;
+    let deals: Vec<DealInfo> = cli(list_args).unwrap();
+    assert!(deals.is_empty());
+
+    // clean up processes
+    kill_all();
+}
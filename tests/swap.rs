@@ -177,6 +177,62 @@ async fn swap_bob_maker_manual_bitcoin_sweep() {
     .await;
 }
 
+#[tokio::test]
+#[timeout(600000)]
+#[ignore]
+async fn swap_cli_json_output() {
+    setup_logging();
+    let bitcoin_rpc = Arc::new(bitcoin_setup());
+    let (_, monero_wallet) = monero_setup().await;
+
+    let (_, data_dir_maker, _, data_dir_taker) = launch_farcasterd_pair().await;
+
+    let (_, _, swap_id) = make_and_take_deal(
+        data_dir_maker.clone(),
+        data_dir_taker.clone(),
+        "Bob".to_string(),
+        Arc::clone(&bitcoin_rpc),
+        Arc::clone(&monero_wallet),
+        bitcoin::Amount::from_str("1 BTC").unwrap(),
+        monero::Amount::from_str_with_denomination("1 XMR").unwrap(),
+    )
+    .await;
+
+    let json_output = |args: Vec<String>| -> serde_json::Value {
+        let args = args
+            .into_iter()
+            .chain(vec!["--output-format".to_string(), "json".to_string()]);
+        let (stdout, stderr) = run("../swap-cli", args).unwrap();
+        serde_json::from_str(&stdout.join("\n"))
+            .unwrap_or_else(|err| panic!("invalid json output {:?}: {}", stderr, err))
+    };
+
+    let swaps = json_output(
+        data_dir_maker
+            .clone()
+            .into_iter()
+            .chain(vec!["list-swaps".to_string()])
+            .collect(),
+    );
+    assert!(swaps
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!(swap_id.to_string())));
+
+    let progress = json_output(progress_args(data_dir_maker.clone(), swap_id));
+    assert_eq!(progress["swap_id"], serde_json::json!(swap_id.to_string()));
+    assert!(progress.get("state").is_some());
+    assert!(progress["confirmations"].is_object());
+
+    let funding = json_output(needs_funding_args(
+        data_dir_maker.clone(),
+        "bitcoin".to_string(),
+    ));
+    assert!(funding["swaps_need_funding"].is_array());
+
+    kill_all();
+}
+
 #[tokio::test]
 #[timeout(600000)]
 #[ignore]